@@ -0,0 +1,932 @@
+//! One builder per program entrypoint. Signers are the first argument;
+//! every derivable account comes from [`PoolKeys`], [`ClmmPoolKeys`] or
+//! the `pda` helpers.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+use amm_capstone::instructions::{CurveParams, UpdateConfigParams};
+use amm_capstone::{accounts, instruction, ID};
+
+use crate::pda::*;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn init_treasury(payer: &Pubkey) -> Instruction {
+    build(
+        accounts::InitTreasury {
+            payer: *payer,
+            program: ID,
+            program_data: find_program_data_address().0,
+            treasury: find_treasury_address().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitTreasury {},
+    )
+}
+
+pub fn set_treasury_authority(authority: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    build(
+        accounts::SetTreasuryAuthority {
+            authority: *authority,
+            treasury: find_treasury_address().0,
+        },
+        instruction::SetTreasuryAuthority {
+            new_authority: *new_authority,
+        },
+    )
+}
+
+pub fn withdraw_treasury(authority: &Pubkey, destination: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::WithdrawTreasury {
+            authority: *authority,
+            treasury: find_treasury_address().0,
+            destination: *destination,
+        },
+        instruction::WithdrawTreasury { amount },
+    )
+}
+
+pub fn init_config(
+    payer: &Pubkey,
+    fee_tiers: Vec<u16>,
+    protocol_fee_rate: u16,
+    fund_fee_rate: u16,
+    create_pool_fee: u64,
+) -> Instruction {
+    build(
+        accounts::InitConfig {
+            payer: *payer,
+            program: ID,
+            program_data: find_program_data_address().0,
+            amm_config: find_amm_config_address().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitConfig {
+            fee_tiers,
+            protocol_fee_rate,
+            fund_fee_rate,
+            create_pool_fee,
+        },
+    )
+}
+
+pub fn update_config(admin: &Pubkey, params: UpdateConfigParams) -> Instruction {
+    build(
+        accounts::UpdateConfig {
+            admin: *admin,
+            amm_config: find_amm_config_address().0,
+        },
+        instruction::UpdateConfig { params },
+    )
+}
+
+/// Creates the pool. `fee_tier_index` must point at `keys.fee_bps` in
+/// the config, and `curve` must be of `keys.curve_type`; the pool's
+/// address is derived from both.
+pub fn create_pool(
+    payer: &Pubkey,
+    keys: &PoolKeys,
+    fee_tier_index: u8,
+    curve: CurveParams,
+) -> Instruction {
+    build(
+        accounts::CreatePool {
+            payer: *payer,
+            amm_config: find_amm_config_address().0,
+            treasury: find_treasury_address().0,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            lp_mint: keys.lp_mint,
+            locked_lp_authority: keys.locked_lp_authority,
+            locked_lp: keys.locked_lp,
+            observation_state: keys.observation_state,
+            vault_authority: keys.vault_authority,
+            token_program: anchor_spl::token::ID,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreatePool {
+            fee_tier_index,
+            curve,
+        },
+    )
+}
+
+/// User-owned token accounts for one pool: both pool tokens and LP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserTokenAccounts {
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp: Pubkey,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn add_liquidity(
+    user: &Pubkey,
+    keys: &PoolKeys,
+    user_accounts: &UserTokenAccounts,
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+    amount_a_min: u64,
+    amount_b_min: u64,
+    min_lp_out: u64,
+) -> Instruction {
+    build(
+        accounts::AddLiquidity {
+            user: *user,
+            pool: keys.pool,
+            user_token_a: user_accounts.token_a,
+            user_token_b: user_accounts.token_b,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            lp_mint: keys.lp_mint,
+            user_lp: user_accounts.lp,
+            locked_lp: keys.locked_lp,
+            vault_authority: keys.vault_authority,
+            token_program: anchor_spl::token::ID,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::AddLiquidity {
+            amount_a_desired,
+            amount_b_desired,
+            amount_a_min,
+            amount_b_min,
+            min_lp_out,
+        },
+    )
+}
+
+/// Single-token deposit; the side follows from `user_token_in`'s mint.
+pub fn add_liquidity_single(
+    user: &Pubkey,
+    keys: &PoolKeys,
+    user_token_in: &Pubkey,
+    user_lp: &Pubkey,
+    amount_in: u64,
+    min_lp_out: u64,
+) -> Instruction {
+    build(
+        accounts::AddLiquiditySingle {
+            user: *user,
+            pool: keys.pool,
+            amm_config: find_amm_config_address().0,
+            user_token_in: *user_token_in,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            lp_mint: keys.lp_mint,
+            user_lp: *user_lp,
+            vault_authority: keys.vault_authority,
+            token_program: anchor_spl::token::ID,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::AddLiquiditySingle {
+            amount_in,
+            min_lp_out,
+        },
+    )
+}
+
+pub fn remove_liquidity(
+    user: &Pubkey,
+    keys: &PoolKeys,
+    user_accounts: &UserTokenAccounts,
+    lp_amount: u64,
+) -> Instruction {
+    build(
+        accounts::RemoveLiquidity {
+            user: *user,
+            pool: keys.pool,
+            user_lp: user_accounts.lp,
+            lp_mint: keys.lp_mint,
+            user_token_a: user_accounts.token_a,
+            user_token_b: user_accounts.token_b,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            vault_authority: keys.vault_authority,
+            token_program: anchor_spl::token::ID,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::RemoveLiquidity { lp_amount },
+    )
+}
+
+fn swap_accounts(
+    user: &Pubkey,
+    keys: &PoolKeys,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+) -> accounts::Swap {
+    accounts::Swap {
+        user: *user,
+        pool: keys.pool,
+        amm_config: find_amm_config_address().0,
+        user_input: *user_input,
+        user_output: *user_output,
+        observation_state: keys.observation_state,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    }
+}
+
+/// Exact-input swap. The direction follows from `user_input`'s mint.
+pub fn swap(
+    user: &Pubkey,
+    keys: &PoolKeys,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    amount_in: u64,
+    min_out: u64,
+) -> Instruction {
+    build(
+        swap_accounts(user, keys, user_input, user_output),
+        instruction::Swap { amount_in, min_out },
+    )
+}
+
+/// Exact-output swap. The direction follows from `user_input`'s mint.
+pub fn swap_base_out(
+    user: &Pubkey,
+    keys: &PoolKeys,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    max_amount_in: u64,
+    amount_out: u64,
+) -> Instruction {
+    build(
+        swap_accounts(user, keys, user_input, user_output),
+        instruction::SwapBaseOut {
+            max_amount_in,
+            amount_out,
+        },
+    )
+}
+
+/// `treasury_token_a` / `_b` must be owned by the treasury authority.
+pub fn collect_fees(
+    authority: &Pubkey,
+    keys: &PoolKeys,
+    treasury_token_a: &Pubkey,
+    treasury_token_b: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CollectFees {
+            authority: *authority,
+            treasury: find_treasury_address().0,
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            treasury_token_a: *treasury_token_a,
+            treasury_token_b: *treasury_token_b,
+            vault_authority: keys.vault_authority,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::CollectFees {},
+    )
+}
+
+/// `fund_token_a` / `_b` must be owned by the config admin.
+pub fn collect_fund_fees(
+    admin: &Pubkey,
+    keys: &PoolKeys,
+    fund_token_a: &Pubkey,
+    fund_token_b: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CollectFundFees {
+            admin: *admin,
+            amm_config: find_amm_config_address().0,
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            fund_token_a: *fund_token_a,
+            fund_token_b: *fund_token_b,
+            vault_authority: keys.vault_authority,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::CollectFundFees {},
+    )
+}
+
+/// Permissionless; sets reserves to what the vaults back.
+pub fn sync(keys: &PoolKeys) -> Instruction {
+    build(
+        accounts::SyncReserves {
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+        },
+        instruction::Sync {},
+    )
+}
+
+/// Permissionless; sends vault excess to `recipient_token_a` / `_b`.
+pub fn skim(
+    keys: &PoolKeys,
+    recipient_token_a: &Pubkey,
+    recipient_token_b: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::Skim {
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            recipient_token_a: *recipient_token_a,
+            recipient_token_b: *recipient_token_b,
+            vault_authority: keys.vault_authority,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::Skim {},
+    )
+}
+
+pub fn set_pool_status(admin: &Pubkey, pool: &Pubkey, status: u8) -> Instruction {
+    build(
+        accounts::SetPoolStatus {
+            admin: *admin,
+            amm_config: find_amm_config_address().0,
+            pool: *pool,
+        },
+        instruction::SetPoolStatus { status },
+    )
+}
+
+pub fn ramp_amp(admin: &Pubkey, pool: &Pubkey, target_amp: u64, stop_ts: i64) -> Instruction {
+    build(
+        accounts::RampAmp {
+            admin: *admin,
+            amm_config: find_amm_config_address().0,
+            pool: *pool,
+        },
+        instruction::RampAmp {
+            target_amp,
+            stop_ts,
+        },
+    )
+}
+
+pub fn stop_ramp_amp(admin: &Pubkey, pool: &Pubkey) -> Instruction {
+    build(
+        accounts::RampAmp {
+            admin: *admin,
+            amm_config: find_amm_config_address().0,
+            pool: *pool,
+        },
+        instruction::StopRampAmp {},
+    )
+}
+
+/// Signed by the pool's creator, who owns a liquidity bootstrapping
+/// pool's weight schedule.
+pub fn update_weights_gradually(
+    authority: &Pubkey,
+    pool: &Pubkey,
+    start_ts: i64,
+    end_ts: i64,
+    end_weight_a: u64,
+    end_weight_b: u64,
+) -> Instruction {
+    build(
+        accounts::UpdateWeightsGradually {
+            authority: *authority,
+            pool: *pool,
+        },
+        instruction::UpdateWeightsGradually {
+            start_ts,
+            end_ts,
+            end_weight_a,
+            end_weight_b,
+        },
+    )
+}
+
+/// Creates the pool. `fee_tier_index` must point at `keys.fee_bps` in
+/// the config.
+pub fn create_clmm_pool(
+    payer: &Pubkey,
+    keys: &ClmmPoolKeys,
+    fee_tier_index: u8,
+    tick_spacing: u16,
+    sqrt_price_x64: u128,
+) -> Instruction {
+    build(
+        accounts::CreateClmmPool {
+            payer: *payer,
+            amm_config: find_amm_config_address().0,
+            treasury: find_treasury_address().0,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            vault_authority: keys.vault_authority,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateClmmPool {
+            fee_tier_index,
+            tick_spacing,
+            sqrt_price_x64,
+        },
+    )
+}
+
+/// `start_tick_index` must be an array start, see
+/// [`ClmmPoolKeys::tick_array`].
+pub fn init_tick_array(payer: &Pubkey, pool: &Pubkey, start_tick_index: i32) -> Instruction {
+    build(
+        accounts::InitTickArray {
+            payer: *payer,
+            pool: *pool,
+            tick_array: find_tick_array_address(pool, start_tick_index).0,
+            system_program: system_program::ID,
+        },
+        instruction::InitTickArray { start_tick_index },
+    )
+}
+
+pub fn open_position(
+    owner: &Pubkey,
+    pool: &Pubkey,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Instruction {
+    build(
+        accounts::OpenPosition {
+            owner: *owner,
+            pool: *pool,
+            position: find_position_address(pool, owner, tick_lower, tick_upper).0,
+            system_program: system_program::ID,
+        },
+        instruction::OpenPosition {
+            tick_lower,
+            tick_upper,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn increase_liquidity(
+    owner: &Pubkey,
+    keys: &ClmmPoolKeys,
+    position: &PositionKeys,
+    user_token_a: &Pubkey,
+    user_token_b: &Pubkey,
+    liquidity: u128,
+    amount_a_max: u64,
+    amount_b_max: u64,
+) -> Instruction {
+    build(
+        accounts::IncreaseLiquidity {
+            owner: *owner,
+            pool: keys.pool,
+            position: position.position,
+            tick_array_lower: position.tick_array_lower,
+            tick_array_upper: position.tick_array_upper,
+            user_token_a: *user_token_a,
+            user_token_b: *user_token_b,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::IncreaseLiquidity {
+            liquidity,
+            amount_a_max,
+            amount_b_max,
+        },
+    )
+}
+
+/// Credits the withdrawn tokens to the position; [`collect`] sends them.
+pub fn decrease_liquidity(
+    owner: &Pubkey,
+    pool: &Pubkey,
+    position: &PositionKeys,
+    liquidity: u128,
+    amount_a_min: u64,
+    amount_b_min: u64,
+) -> Instruction {
+    build(
+        accounts::DecreaseLiquidity {
+            owner: *owner,
+            pool: *pool,
+            position: position.position,
+            tick_array_lower: position.tick_array_lower,
+            tick_array_upper: position.tick_array_upper,
+        },
+        instruction::DecreaseLiquidity {
+            liquidity,
+            amount_a_min,
+            amount_b_min,
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn collect(
+    owner: &Pubkey,
+    keys: &ClmmPoolKeys,
+    position: &PositionKeys,
+    recipient_token_a: &Pubkey,
+    recipient_token_b: &Pubkey,
+    amount_a_max: u64,
+    amount_b_max: u64,
+) -> Instruction {
+    build(
+        accounts::Collect {
+            owner: *owner,
+            pool: keys.pool,
+            position: position.position,
+            tick_array_lower: position.tick_array_lower,
+            tick_array_upper: position.tick_array_upper,
+            recipient_token_a: *recipient_token_a,
+            recipient_token_b: *recipient_token_b,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            vault_authority: keys.vault_authority,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::Collect {
+            amount_a_max,
+            amount_b_max,
+        },
+    )
+}
+
+fn clmm_swap_accounts(
+    user: &Pubkey,
+    keys: &ClmmPoolKeys,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+) -> accounts::ClmmSwap {
+    accounts::ClmmSwap {
+        user: *user,
+        pool: keys.pool,
+        amm_config: find_amm_config_address().0,
+        user_input: *user_input,
+        user_output: *user_output,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    }
+}
+
+fn with_tick_arrays(mut ix: Instruction, tick_arrays: &[Pubkey]) -> Instruction {
+    ix.accounts
+        .extend(tick_arrays.iter().map(|array| AccountMeta::new(*array, false)));
+    ix
+}
+
+/// Exact-input swap on a concentrated liquidity pool. `tick_arrays` are
+/// the arrays the price may cross, in swap order, starting with the one
+/// holding the current tick. A `sqrt_price_limit_x64` of zero means no
+/// limit.
+#[allow(clippy::too_many_arguments)]
+pub fn clmm_swap(
+    user: &Pubkey,
+    keys: &ClmmPoolKeys,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    tick_arrays: &[Pubkey],
+    amount_in: u64,
+    min_out: u64,
+    sqrt_price_limit_x64: u128,
+) -> Instruction {
+    with_tick_arrays(
+        build(
+            clmm_swap_accounts(user, keys, user_input, user_output),
+            instruction::ClmmSwap {
+                amount_in,
+                min_out,
+                sqrt_price_limit_x64,
+            },
+        ),
+        tick_arrays,
+    )
+}
+
+/// Exact-output counterpart of [`clmm_swap`].
+#[allow(clippy::too_many_arguments)]
+pub fn clmm_swap_base_out(
+    user: &Pubkey,
+    keys: &ClmmPoolKeys,
+    user_input: &Pubkey,
+    user_output: &Pubkey,
+    tick_arrays: &[Pubkey],
+    max_amount_in: u64,
+    amount_out: u64,
+    sqrt_price_limit_x64: u128,
+) -> Instruction {
+    with_tick_arrays(
+        build(
+            clmm_swap_accounts(user, keys, user_input, user_output),
+            instruction::ClmmSwapBaseOut {
+                max_amount_in,
+                amount_out,
+                sqrt_price_limit_x64,
+            },
+        ),
+        tick_arrays,
+    )
+}
+
+/// As [`collect_fees`], for a concentrated liquidity pool.
+pub fn collect_clmm_fees(
+    authority: &Pubkey,
+    keys: &ClmmPoolKeys,
+    treasury_token_a: &Pubkey,
+    treasury_token_b: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CollectClmmFees {
+            authority: *authority,
+            treasury: find_treasury_address().0,
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            treasury_token_a: *treasury_token_a,
+            treasury_token_b: *treasury_token_b,
+            vault_authority: keys.vault_authority,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::CollectClmmFees {},
+    )
+}
+
+/// As [`collect_fund_fees`], for a concentrated liquidity pool.
+pub fn collect_clmm_fund_fees(
+    admin: &Pubkey,
+    keys: &ClmmPoolKeys,
+    fund_token_a: &Pubkey,
+    fund_token_b: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CollectClmmFundFees {
+            admin: *admin,
+            amm_config: find_amm_config_address().0,
+            pool: keys.pool,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            fund_token_a: *fund_token_a,
+            fund_token_b: *fund_token_b,
+            vault_authority: keys.vault_authority,
+            token_a_program: keys.token_a_program,
+            token_b_program: keys.token_b_program,
+        },
+        instruction::CollectClmmFundFees {},
+    )
+}
+
+/// As [`set_pool_status`], for a concentrated liquidity pool.
+pub fn set_clmm_pool_status(admin: &Pubkey, pool: &Pubkey, status: u8) -> Instruction {
+    build(
+        accounts::SetClmmPoolStatus {
+            admin: *admin,
+            amm_config: find_amm_config_address().0,
+            pool: *pool,
+        },
+        instruction::SetClmmPoolStatus { status },
+    )
+}
+
+/// Read-only; simulate it and decode the `Twap` from return data.
+pub fn observe(keys: &PoolKeys, seconds_ago: u32) -> Instruction {
+    build(
+        accounts::Observe {
+            pool: keys.pool,
+            observation_state: keys.observation_state,
+        },
+        instruction::Observe { seconds_ago },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amm_capstone::state::CurveType;
+    use anchor_lang::Discriminator;
+
+    fn keys() -> PoolKeys {
+        PoolKeys::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            anchor_spl::token::ID,
+            anchor_spl::token_2022::ID,
+            30,
+            CurveType::ConstantProduct,
+        )
+    }
+
+    #[test]
+    fn pool_keys_match_pda_helpers() {
+        let keys = keys();
+        let (pool, _) = find_pool_address(
+            &keys.token_a_mint,
+            &keys.token_b_mint,
+            30,
+            CurveType::ConstantProduct,
+        );
+
+        assert_eq!(keys.pool, pool);
+        assert_eq!(keys.vault_authority, find_vault_authority_address(&pool).0);
+        assert_ne!(keys.vault_a, keys.vault_b);
+
+        // One pool per fee rate, each with its own vaults
+        let other = PoolKeys::new(
+            keys.token_a_mint,
+            keys.token_b_mint,
+            keys.token_a_program,
+            keys.token_b_program,
+            100,
+            keys.curve_type,
+        );
+        assert_ne!(other.pool, keys.pool);
+        assert_ne!(other.vault_a, keys.vault_a);
+
+        // And per curve
+        let stable = PoolKeys::new(
+            keys.token_a_mint,
+            keys.token_b_mint,
+            keys.token_a_program,
+            keys.token_b_program,
+            30,
+            CurveType::Stable,
+        );
+        assert_ne!(stable.pool, keys.pool);
+    }
+
+    #[test]
+    fn swap_encodes_arguments_after_discriminator() {
+        let keys = keys();
+        let user = Pubkey::new_unique();
+        let ix = swap(
+            &user,
+            &keys,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            7,
+            5,
+        );
+
+        assert_eq!(ix.program_id, ID);
+        assert_eq!(&ix.data[..8], instruction::Swap::DISCRIMINATOR);
+        assert_eq!(&ix.data[8..16], &7u64.to_le_bytes());
+        assert_eq!(&ix.data[16..], &5u64.to_le_bytes());
+
+        // Signer first, writable where the program mutates
+        assert_eq!(ix.accounts[0].pubkey, user);
+        assert!(ix.accounts[0].is_signer);
+        assert!(ix.accounts[1].is_writable);
+    }
+
+    #[test]
+    fn create_pool_uses_derived_accounts() {
+        let keys = keys();
+        let ix = create_pool(&Pubkey::new_unique(), &keys, 2, CurveParams::ConstantProduct);
+        let addresses: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+
+        for derived in [
+            keys.pool,
+            keys.vault_a,
+            keys.vault_b,
+            keys.lp_mint,
+            keys.locked_lp,
+            keys.observation_state,
+            find_treasury_address().0,
+            find_amm_config_address().0,
+        ] {
+            assert!(addresses.contains(&derived));
+        }
+        assert_eq!(ix.data[8], 2);
+        assert_eq!(ix.data[9], 0);
+
+        let stable = create_pool(&Pubkey::new_unique(), &keys, 2, CurveParams::Stable { amp: 100 });
+        assert_eq!(stable.data[9], 1);
+        assert_eq!(stable.data[10..18], 100u64.to_le_bytes());
+
+        let weighted = create_pool(
+            &Pubkey::new_unique(),
+            &keys,
+            2,
+            CurveParams::Weighted {
+                weight_a: 8,
+                weight_b: 2,
+            },
+        );
+        assert_eq!(weighted.data[9], 2);
+        assert_eq!(weighted.data[10..18], 8u64.to_le_bytes());
+        assert_eq!(weighted.data[18..26], 2u64.to_le_bytes());
+
+        let bootstrapping = create_pool(
+            &Pubkey::new_unique(),
+            &keys,
+            2,
+            CurveParams::LiquidityBootstrapping {
+                start_weight_a: 9,
+                start_weight_b: 1,
+                end_weight_a: 5,
+                end_weight_b: 5,
+                start_ts: 100,
+                end_ts: -1,
+            },
+        );
+        assert_eq!(bootstrapping.data[9], 3);
+        assert_eq!(bootstrapping.data[26..34], 5u64.to_le_bytes());
+        assert_eq!(bootstrapping.data[50..], (-1i64).to_le_bytes());
+    }
+
+    #[test]
+    fn clmm_swap_appends_tick_arrays_as_writable() {
+        let keys = ClmmPoolKeys::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            anchor_spl::token::ID,
+            anchor_spl::token::ID,
+            30,
+        );
+        let arrays = [keys.tick_array(0, 10), keys.tick_array(-1, 10)];
+        assert_ne!(arrays[0], arrays[1]);
+        assert_eq!(keys.tick_array(599, 10), arrays[0]);
+
+        let ix = clmm_swap(
+            &Pubkey::new_unique(),
+            &keys,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &arrays,
+            7,
+            5,
+            0,
+        );
+        let fixed = clmm_swap_accounts(
+            &Pubkey::new_unique(),
+            &keys,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+        )
+        .to_account_metas(None)
+        .len();
+
+        assert_eq!(ix.accounts.len(), fixed + 2);
+        for (meta, array) in ix.accounts[fixed..].iter().zip(arrays) {
+            assert_eq!(meta.pubkey, array);
+            assert!(meta.is_writable && !meta.is_signer);
+        }
+        assert_eq!(&ix.data[..8], instruction::ClmmSwap::DISCRIMINATOR);
+        assert_eq!(&ix.data[24..], &0u128.to_le_bytes());
+    }
+}
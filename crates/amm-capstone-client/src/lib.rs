@@ -0,0 +1,20 @@
+//! Off-chain client for the `amm-capstone` program.
+//!
+//! - [`pda`] derives every program address from the same seed constants
+//!   the program uses.
+//! - [`instructions`] builds a typed `Instruction` for each entrypoint,
+//!   filling in derived accounts from [`PoolKeys`] or [`ClmmPoolKeys`].
+//! - [`state`] decodes (and, given any RPC, fetches) `Pool`, `ClmmPool`,
+//!   `Position`, `Treasury` and `AmmConfig` accounts.
+//!
+//! Quotes come from `amm-capstone-math`, re-exported as [`math`].
+
+pub mod instructions;
+pub mod pda;
+pub mod state;
+
+pub use amm_capstone::math;
+pub use amm_capstone::ID as PROGRAM_ID;
+pub use instructions::*;
+pub use pda::*;
+pub use state::*;
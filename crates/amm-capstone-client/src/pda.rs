@@ -0,0 +1,246 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+
+use amm_capstone::constants::*;
+use amm_capstone::state::{CurveType, TickArrayState};
+use amm_capstone::ID;
+
+pub fn find_treasury_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED], &ID)
+}
+
+pub fn find_amm_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AMM_CONFIG_SEED], &ID)
+}
+
+/// The program's `ProgramData` account, holding its upgrade authority.
+pub fn find_program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID)
+}
+
+/// The pool for a pair in canonical order (`token_a_mint <
+/// token_b_mint`) charging `fee_bps`, one of the config's fee tiers, on
+/// a `curve_type` curve.
+pub fn find_pool_address(
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    fee_bps: u16,
+    curve_type: CurveType,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            POOL_SEED,
+            find_amm_config_address().0.as_ref(),
+            token_a_mint.as_ref(),
+            token_b_mint.as_ref(),
+            &fee_bps.to_le_bytes(),
+            &[curve_type as u8],
+        ],
+        &ID,
+    )
+}
+
+/// Vault A of either kind of pool.
+pub fn find_vault_a_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_A_SEED, pool.as_ref()], &ID)
+}
+
+pub fn find_vault_b_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_B_SEED, pool.as_ref()], &ID)
+}
+
+pub fn find_vault_authority_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_AUTH_SEED, pool.as_ref()], &ID)
+}
+
+pub fn find_lp_mint_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LP_MINT_SEED, pool.as_ref()], &ID)
+}
+
+pub fn find_locked_lp_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LOCKED_LP_SEED, pool.as_ref()], &ID)
+}
+
+pub fn find_locked_lp_authority_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LOCKED_LP_AUTH_SEED, pool.as_ref()], &ID)
+}
+
+pub fn find_observation_address(pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[OBSERVATION_SEED, pool.as_ref()], &ID)
+}
+
+/// As [`find_pool_address`], for a concentrated liquidity pool.
+pub fn find_clmm_pool_address(
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    fee_bps: u16,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            CLMM_POOL_SEED,
+            find_amm_config_address().0.as_ref(),
+            token_a_mint.as_ref(),
+            token_b_mint.as_ref(),
+            &fee_bps.to_le_bytes(),
+        ],
+        &ID,
+    )
+}
+
+/// The tick array of `pool` starting at `start_tick_index` (see
+/// `TickArrayState::start_index`).
+pub fn find_tick_array_address(pool: &Pubkey, start_tick_index: i32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[TICK_ARRAY_SEED, pool.as_ref(), &start_tick_index.to_le_bytes()],
+        &ID,
+    )
+}
+
+pub fn find_position_address(
+    pool: &Pubkey,
+    owner: &Pubkey,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            POSITION_SEED,
+            pool.as_ref(),
+            owner.as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes(),
+        ],
+        &ID,
+    )
+}
+
+/// Every address belonging to one pool, derived from its mints, fee
+/// rate and curve. The token programs are not derivable and must match
+/// each mint's owner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub fee_bps: u16,
+    pub curve_type: CurveType,
+    pub token_a_program: Pubkey,
+    pub token_b_program: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub vault_authority: Pubkey,
+    pub lp_mint: Pubkey,
+    pub locked_lp: Pubkey,
+    pub locked_lp_authority: Pubkey,
+    pub observation_state: Pubkey,
+}
+
+impl PoolKeys {
+    pub fn new(
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        token_a_program: Pubkey,
+        token_b_program: Pubkey,
+        fee_bps: u16,
+        curve_type: CurveType,
+    ) -> Self {
+        let (pool, _) = find_pool_address(&token_a_mint, &token_b_mint, fee_bps, curve_type);
+
+        Self {
+            pool,
+            token_a_mint,
+            token_b_mint,
+            fee_bps,
+            curve_type,
+            token_a_program,
+            token_b_program,
+            vault_a: find_vault_a_address(&pool).0,
+            vault_b: find_vault_b_address(&pool).0,
+            vault_authority: find_vault_authority_address(&pool).0,
+            lp_mint: find_lp_mint_address(&pool).0,
+            locked_lp: find_locked_lp_address(&pool).0,
+            locked_lp_authority: find_locked_lp_authority_address(&pool).0,
+            observation_state: find_observation_address(&pool).0,
+        }
+    }
+
+    /// (input mint, output mint) for the given direction.
+    pub fn mints(&self, a_to_b: bool) -> (Pubkey, Pubkey) {
+        if a_to_b {
+            (self.token_a_mint, self.token_b_mint)
+        } else {
+            (self.token_b_mint, self.token_a_mint)
+        }
+    }
+}
+
+/// Every address belonging to one concentrated liquidity pool, derived
+/// from its mints and fee rate. Tick arrays and positions are per range,
+/// see [`find_tick_array_address`] and [`find_position_address`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClmmPoolKeys {
+    pub pool: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub fee_bps: u16,
+    pub token_a_program: Pubkey,
+    pub token_b_program: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub vault_authority: Pubkey,
+}
+
+impl ClmmPoolKeys {
+    pub fn new(
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        token_a_program: Pubkey,
+        token_b_program: Pubkey,
+        fee_bps: u16,
+    ) -> Self {
+        let (pool, _) = find_clmm_pool_address(&token_a_mint, &token_b_mint, fee_bps);
+
+        Self {
+            pool,
+            token_a_mint,
+            token_b_mint,
+            fee_bps,
+            token_a_program,
+            token_b_program,
+            vault_a: find_vault_a_address(&pool).0,
+            vault_b: find_vault_b_address(&pool).0,
+            vault_authority: find_vault_authority_address(&pool).0,
+        }
+    }
+
+    /// The tick array holding `tick` at `tick_spacing`.
+    pub fn tick_array(&self, tick: i32, tick_spacing: u16) -> Pubkey {
+        let start = TickArrayState::start_index(tick, tick_spacing);
+        find_tick_array_address(&self.pool, start).0
+    }
+
+    /// The position of `owner` over `tick_lower..tick_upper` and the tick
+    /// arrays holding its bounds.
+    pub fn position(
+        &self,
+        owner: &Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+        tick_spacing: u16,
+    ) -> PositionKeys {
+        PositionKeys {
+            position: find_position_address(&self.pool, owner, tick_lower, tick_upper).0,
+            tick_array_lower: self.tick_array(tick_lower, tick_spacing),
+            tick_array_upper: self.tick_array(tick_upper, tick_spacing),
+        }
+    }
+}
+
+/// A position and the tick arrays of its bounds, which may be the same
+/// account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionKeys {
+    pub position: Pubkey,
+    pub tick_array_lower: Pubkey,
+    pub tick_array_upper: Pubkey,
+}
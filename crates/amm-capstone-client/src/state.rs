@@ -0,0 +1,96 @@
+//! Account decoding. Fetching is left to the caller's RPC client through
+//! [`AccountFetcher`], so this crate does not pin a client version.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Result};
+
+pub use amm_capstone::state::{AmmConfig, ClmmPool, Pool, Position, Treasury};
+
+/// Anything that can return an account's raw data, e.g. a thin wrapper
+/// around `RpcClient::get_account_data`.
+pub trait AccountFetcher {
+    type Error: From<anchor_lang::error::Error>;
+
+    fn account_data(&self, address: &Pubkey) -> core::result::Result<Vec<u8>, Self::Error>;
+}
+
+/// Decodes any program account, checking its discriminator.
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_pool(data: &[u8]) -> Result<Pool> {
+    decode_account(data)
+}
+
+pub fn decode_treasury(data: &[u8]) -> Result<Treasury> {
+    decode_account(data)
+}
+
+pub fn decode_amm_config(data: &[u8]) -> Result<AmmConfig> {
+    decode_account(data)
+}
+
+pub fn decode_clmm_pool(data: &[u8]) -> Result<ClmmPool> {
+    decode_account(data)
+}
+
+pub fn decode_position(data: &[u8]) -> Result<Position> {
+    decode_account(data)
+}
+
+pub fn fetch_pool<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> core::result::Result<Pool, F::Error> {
+    Ok(decode_pool(&fetcher.account_data(address)?)?)
+}
+
+pub fn fetch_treasury<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> core::result::Result<Treasury, F::Error> {
+    Ok(decode_treasury(&fetcher.account_data(address)?)?)
+}
+
+pub fn fetch_amm_config<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> core::result::Result<AmmConfig, F::Error> {
+    Ok(decode_amm_config(&fetcher.account_data(address)?)?)
+}
+
+pub fn fetch_clmm_pool<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> core::result::Result<ClmmPool, F::Error> {
+    Ok(decode_clmm_pool(&fetcher.account_data(address)?)?)
+}
+
+pub fn fetch_position<F: AccountFetcher>(
+    fetcher: &F,
+    address: &Pubkey,
+) -> core::result::Result<Position, F::Error> {
+    Ok(decode_position(&fetcher.account_data(address)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+
+    #[test]
+    fn treasury_round_trips_and_checks_discriminator() {
+        let treasury = Treasury {
+            authority: Pubkey::new_unique(),
+        };
+        let mut data = Vec::new();
+        treasury.try_serialize(&mut data).unwrap();
+
+        let decoded = decode_treasury(&data).unwrap();
+        assert_eq!(decoded.authority, treasury.authority);
+
+        // A Treasury is not a Pool
+        assert!(decode_pool(&data).is_err());
+    }
+}
@@ -0,0 +1,514 @@
+//! Concentrated liquidity, as in Uniswap v3. Liquidity `L` placed
+//! between the square-root prices `√P_lower` and `√P_upper` holds, at the
+//! current `√P` inside that range,
+//!
+//! ```text
+//! a = L·(1/√P - 1/√P_upper),   b = L·(√P - √P_lower)
+//! ```
+//!
+//! Below the range it is all token A, above it all token B. Prices are
+//! of A in B, with square roots in Q64.64 (see `tick_math`). A swap of
+//! A for B moves the price down, B for A up. Amounts the pool takes in
+//! round up and amounts it pays out round down.
+
+use crate::curve::{mul_div_ceil, mul_div_floor};
+use crate::fixed_point::Q64;
+use crate::full_math::{div_wide, full_mul, mul_div_u128, mul_div_u128_ceil};
+use crate::tick_math::{MAX_TICK, MIN_TICK};
+use crate::FEE_RATE_DENOMINATOR;
+
+/// Most liquidity a pool can have in range. Keeps `L·2^64/√P` within
+/// u128 at every price, since `√P` is never below 2^32 in Q64.64.
+pub const MAX_LIQUIDITY: u128 = 1 << 96;
+
+/// One step of a swap, within a range of constant liquidity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    // Input net of the fee, and output
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Token A held by `liquidity` between two square-root prices, in either
+/// order. `None` when it does not fit in u64 or a price is zero.
+pub fn amount_a_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<u64> {
+    u64::try_from(delta_a(sqrt_price_a, sqrt_price_b, liquidity, round_up)?).ok()
+}
+
+/// Token B held by `liquidity` between two square-root prices, in either
+/// order. `None` when it does not fit in u64.
+pub fn amount_b_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<u64> {
+    u64::try_from(delta_b(sqrt_price_a, sqrt_price_b, liquidity, round_up)?).ok()
+}
+
+/// Tokens (A, B) behind `liquidity` placed between `sqrt_price_lower` and
+/// `sqrt_price_upper` at the current `sqrt_price`. Round up for what a
+/// depositor owes, down for what a withdrawal pays.
+pub fn position_amounts(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<(u64, u64)> {
+    if sqrt_price <= sqrt_price_lower {
+        Some((
+            amount_a_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+            0,
+        ))
+    } else if sqrt_price >= sqrt_price_upper {
+        Some((
+            0,
+            amount_b_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+        ))
+    } else {
+        Some((
+            amount_a_delta(sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+            amount_b_delta(sqrt_price_lower, sqrt_price, liquidity, round_up)?,
+        ))
+    }
+}
+
+/// Most liquidity that `amount_a` and `amount_b` can back between
+/// `sqrt_price_lower` and `sqrt_price_upper` at the current `sqrt_price`.
+/// Rounded down, so `position_amounts` of it never asks for more.
+pub fn liquidity_for_amounts(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    amount_a: u64,
+    amount_b: u64,
+) -> Option<u128> {
+    if sqrt_price_lower >= sqrt_price_upper {
+        return None;
+    }
+
+    // L = a·√P_lo·√P_hi / (√P_hi - √P_lo) and L = b / (√P_hi - √P_lo)
+    let for_a = |lower: u128, upper: u128| {
+        let product = mul_div_u128(lower, upper, Q64)?;
+        mul_div_u128(amount_a as u128, product, upper - lower)
+    };
+    let for_b = |lower: u128, upper: u128| mul_div_u128(amount_b as u128, Q64, upper - lower);
+
+    if sqrt_price <= sqrt_price_lower {
+        for_a(sqrt_price_lower, sqrt_price_upper)
+    } else if sqrt_price >= sqrt_price_upper {
+        for_b(sqrt_price_lower, sqrt_price_upper)
+    } else {
+        Some(for_a(sqrt_price, sqrt_price_upper)?.min(for_b(sqrt_price_lower, sqrt_price)?))
+    }
+}
+
+/// Square-root price after `amount_in` (net of the fee) enters a range
+/// of `liquidity`: token A when `a_to_b`, moving the price down, else
+/// token B, moving it up. Rounded so the price moves less.
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u64,
+    a_to_b: bool,
+) -> Option<u128> {
+    if sqrt_price == 0 || liquidity == 0 {
+        return None;
+    }
+
+    if a_to_b {
+        // L / (L/√P + a), with L/√P rounded down
+        let virtual_a = mul_div_u128(liquidity, Q64, sqrt_price)?;
+        let denominator = virtual_a.checked_add(amount_in as u128)?;
+        mul_div_u128_ceil(liquidity, Q64, denominator)
+    } else {
+        // √P + b/L
+        sqrt_price.checked_add(mul_div_u128(amount_in as u128, Q64, liquidity)?)
+    }
+}
+
+/// Square-root price after `amount_out` leaves a range of `liquidity`:
+/// token B when `a_to_b`, moving the price down, else token A, moving
+/// it up. Rounded so the price moves more. `None` when the range cannot
+/// pay it.
+pub fn next_sqrt_price_from_output(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_out: u64,
+    a_to_b: bool,
+) -> Option<u128> {
+    if sqrt_price == 0 || liquidity == 0 {
+        return None;
+    }
+
+    if a_to_b {
+        // √P - b/L
+        let change = mul_div_u128_ceil(amount_out as u128, Q64, liquidity)?;
+        sqrt_price.checked_sub(change).filter(|price| *price > 0)
+    } else {
+        // L / (L/√P - a), with L/√P rounded down
+        let virtual_a = mul_div_u128(liquidity, Q64, sqrt_price)?;
+        let denominator = virtual_a
+            .checked_sub(amount_out as u128)
+            .filter(|denominator| *denominator > 0)?;
+        mul_div_u128_ceil(liquidity, Q64, denominator)
+    }
+}
+
+/// Swaps as much of `amount_remaining` as a range of `liquidity` allows
+/// on the way from `sqrt_price` to `sqrt_price_target`, the next tick
+/// or the swap's price limit. `amount_remaining` is input, fee included,
+/// when `exact_in`, else output. The fee is `fee_bps` of the input,
+/// rounded up; when the step uses up an exact input, the fee takes
+/// whatever the price movement leaves of it.
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_bps: u16,
+    exact_in: bool,
+) -> Option<SwapStep> {
+    let net_rate = FEE_RATE_DENOMINATOR
+        .checked_sub(fee_bps as u64)
+        .filter(|rate| *rate > 0)?;
+    let a_to_b = sqrt_price >= sqrt_price_target;
+
+    // (input, output) between the current price and `to`
+    let deltas = |to: u128| -> Option<(u128, u128)> {
+        Some(if a_to_b {
+            (
+                delta_a(to, sqrt_price, liquidity, true)?,
+                delta_b(to, sqrt_price, liquidity, false)?,
+            )
+        } else {
+            (
+                delta_b(sqrt_price, to, liquidity, true)?,
+                delta_a(sqrt_price, to, liquidity, false)?,
+            )
+        })
+    };
+    let (to_target_in, to_target_out) = deltas(sqrt_price_target)?;
+
+    let sqrt_price_next = if exact_in {
+        let remaining_less_fee = mul_div_floor(amount_remaining, net_rate, FEE_RATE_DENOMINATOR)?;
+        if remaining_less_fee as u128 >= to_target_in {
+            sqrt_price_target
+        } else {
+            next_sqrt_price_from_input(sqrt_price, liquidity, remaining_less_fee, a_to_b)?
+        }
+    } else if amount_remaining as u128 >= to_target_out {
+        sqrt_price_target
+    } else {
+        next_sqrt_price_from_output(sqrt_price, liquidity, amount_remaining, a_to_b)?
+    };
+
+    let (amount_in, amount_out) = deltas(sqrt_price_next)?;
+    let amount_in = u64::try_from(amount_in).ok()?;
+    let mut amount_out = u64::try_from(amount_out).ok()?;
+    if !exact_in {
+        amount_out = amount_out.min(amount_remaining);
+    }
+
+    let fee_amount = if exact_in && sqrt_price_next != sqrt_price_target {
+        amount_remaining.checked_sub(amount_in)?
+    } else {
+        mul_div_ceil(amount_in, fee_bps as u64, net_rate)?
+    };
+
+    Some(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// `liquidity` changed by a signed `delta`. `None` below zero or on
+/// overflow.
+pub fn add_liquidity_delta(liquidity: u128, delta: i128) -> Option<u128> {
+    if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    }
+}
+
+/// Most liquidity that may reference one tick, so that even with every
+/// usable tick at the cap the pool stays within `MAX_LIQUIDITY`.
+pub fn max_liquidity_per_tick(tick_spacing: u16) -> u128 {
+    let spacing = tick_spacing.max(1) as i32;
+    let ticks = (MAX_TICK / spacing - MIN_TICK / spacing) as u128 + 1;
+    MAX_LIQUIDITY / ticks
+}
+
+/// Fee growth per unit of liquidity (Q64.64) earned inside
+/// `tick_lower..tick_upper`, from the global growth and the growth
+/// recorded on the far side of each boundary tick. Wraps, as the
+/// accumulators do; consumers take differences.
+pub fn fee_growth_inside(
+    tick_current: i32,
+    tick_lower: i32,
+    tick_upper: i32,
+    fee_growth_global: u128,
+    fee_growth_outside_lower: u128,
+    fee_growth_outside_upper: u128,
+) -> u128 {
+    let below = if tick_current >= tick_lower {
+        fee_growth_outside_lower
+    } else {
+        fee_growth_global.wrapping_sub(fee_growth_outside_lower)
+    };
+    let above = if tick_current < tick_upper {
+        fee_growth_outside_upper
+    } else {
+        fee_growth_global.wrapping_sub(fee_growth_outside_upper)
+    };
+
+    fee_growth_global.wrapping_sub(below).wrapping_sub(above)
+}
+
+/// Fees `liquidity` earned while the growth inside its range went from
+/// `fee_growth_inside_last` to `fee_growth_inside`. Rounded down.
+pub fn fees_earned(
+    liquidity: u128,
+    fee_growth_inside: u128,
+    fee_growth_inside_last: u128,
+) -> Option<u64> {
+    let growth = fee_growth_inside.wrapping_sub(fee_growth_inside_last);
+    u64::try_from(mul_div_u128(growth, liquidity, Q64)?).ok()
+}
+
+// L·(√P_hi - √P_lo)·2^64 / (√P_lo·√P_hi), rounded once. L·(√P_hi - √P_lo)
+// / √P_hi is split into quotient and remainder so the numerator over
+// √P_lo fits in 256 bits without giving up precision.
+fn delta_a(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<u128> {
+    let (lower, upper) = (
+        sqrt_price_a.min(sqrt_price_b),
+        sqrt_price_a.max(sqrt_price_b),
+    );
+    if lower == 0 {
+        return None;
+    }
+
+    let difference = upper - lower;
+    let quotient = mul_div_u128(liquidity, difference, upper)?;
+    // Below `upper`, so exact modulo 2^128
+    let remainder = liquidity
+        .wrapping_mul(difference)
+        .wrapping_sub(quotient.wrapping_mul(upper));
+    let fraction = if round_up {
+        mul_div_u128_ceil(remainder, Q64, upper)?
+    } else {
+        mul_div_u128(remainder, Q64, upper)?
+    };
+
+    // quotient·2^64 + fraction, with `fraction` at most 2^64
+    let (high, low) = full_mul(quotient, Q64);
+    let (low, carry) = low.overflowing_add(fraction);
+    let high = high + carry as u128;
+
+    let floor = div_wide(high, low, lower)?;
+    if round_up && full_mul(floor, lower) != (high, low) {
+        floor.checked_add(1)
+    } else {
+        Some(floor)
+    }
+}
+
+// L·(√P_hi - √P_lo)
+fn delta_b(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<u128> {
+    let difference = sqrt_price_a.abs_diff(sqrt_price_b);
+    if round_up {
+        mul_div_u128_ceil(liquidity, difference, Q64)
+    } else {
+        mul_div_u128(liquidity, difference, Q64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tick_math::{sqrt_price_at_tick, MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64};
+
+    fn at(tick: i32) -> u128 {
+        sqrt_price_at_tick(tick).unwrap()
+    }
+
+    #[test]
+    fn amounts_round_by_at_most_a_unit() {
+        // Price 1 to about 1.0202 (ticks 0 and 200)
+        let liquidity = 1_000_000_000;
+        let a_up = amount_a_delta(at(0), at(200), liquidity, true).unwrap();
+        let a_down = amount_a_delta(at(200), at(0), liquidity, false).unwrap();
+        let exact_a = 1e9 * (1.0 - 1.0001f64.powf(-100.0));
+        assert!(a_down as f64 <= exact_a && a_up as f64 >= exact_a);
+        assert!(a_up - a_down <= 1);
+
+        let b_up = amount_b_delta(at(0), at(200), liquidity, true).unwrap();
+        let b_down = amount_b_delta(at(0), at(200), liquidity, false).unwrap();
+        let exact_b = 1e9 * (1.0001f64.powf(100.0) - 1.0);
+        assert!(b_down as f64 <= exact_b && b_up as f64 >= exact_b);
+        assert!(b_up - b_down <= 1);
+
+        assert_eq!(amount_a_delta(at(5), at(5), liquidity, true), Some(0));
+        assert_eq!(amount_a_delta(0, at(5), liquidity, true), None);
+        assert_eq!(
+            amount_b_delta(MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64, MAX_LIQUIDITY, false),
+            None
+        );
+    }
+
+    #[test]
+    fn position_amounts_depend_on_where_the_price_is() {
+        let (lower, upper) = (at(-100), at(100));
+        let liquidity = 1_000_000;
+
+        let (a, b) = position_amounts(at(-200), lower, upper, liquidity, true).unwrap();
+        assert!(a > 0 && b == 0);
+        let (a, b) = position_amounts(at(200), lower, upper, liquidity, true).unwrap();
+        assert!(a == 0 && b > 0);
+
+        // Centred on price 1, it holds about as much of each
+        let (a, b) = position_amounts(at(0), lower, upper, liquidity, true).unwrap();
+        assert!(a.abs_diff(b) <= 1 && a > 0);
+    }
+
+    #[test]
+    fn liquidity_for_amounts_never_asks_for_more() {
+        let (lower, upper) = (at(-600), at(1_200));
+        for sqrt_price in [at(-1_000), at(-600), at(0), at(700), at(1_200), at(5_000)] {
+            let liquidity =
+                liquidity_for_amounts(sqrt_price, lower, upper, 1_000_000, 2_000_000).unwrap();
+            let (a, b) = position_amounts(sqrt_price, lower, upper, liquidity, true).unwrap();
+            assert!(a <= 1_000_000 && b <= 2_000_000);
+            // And uses up one side, to within rounding
+            assert!(a + 1_000 >= 1_000_000 || b + 1_000 >= 2_000_000);
+        }
+
+        assert_eq!(liquidity_for_amounts(at(0), upper, lower, 1, 1), None);
+    }
+
+    #[test]
+    fn next_price_rounds_against_the_trader() {
+        let liquidity = 1_000_000_000;
+        let sqrt_price = at(0);
+
+        // Selling A lowers the price by less than the exact amount would
+        let down = next_sqrt_price_from_input(sqrt_price, liquidity, 1_000_000, true).unwrap();
+        assert!(down < sqrt_price);
+        assert!(amount_a_delta(down, sqrt_price, liquidity, true).unwrap() <= 1_000_000);
+
+        // Selling B raises it by less
+        let up = next_sqrt_price_from_input(sqrt_price, liquidity, 1_000_000, false).unwrap();
+        assert!(up > sqrt_price);
+        assert!(amount_b_delta(sqrt_price, up, liquidity, true).unwrap() <= 1_000_000);
+
+        // Buying moves it at least far enough to pay out in full
+        let down = next_sqrt_price_from_output(sqrt_price, liquidity, 1_000_000, true).unwrap();
+        assert!(amount_b_delta(down, sqrt_price, liquidity, false).unwrap() >= 1_000_000);
+        let up = next_sqrt_price_from_output(sqrt_price, liquidity, 1_000_000, false).unwrap();
+        assert!(amount_a_delta(sqrt_price, up, liquidity, false).unwrap() >= 1_000_000);
+
+        // More A than the range's virtual reserve cannot be bought
+        assert_eq!(
+            next_sqrt_price_from_output(sqrt_price, liquidity, 1_000_000_000, false),
+            None
+        );
+        assert_eq!(next_sqrt_price_from_input(sqrt_price, 0, 1, true), None);
+    }
+
+    #[test]
+    fn swap_step_stops_at_the_target_or_the_amount() {
+        let liquidity = 1_000_000_000;
+        let (sqrt_price, target) = (at(0), at(-10));
+
+        // Enough to reach the target: the fee is on what was used
+        let step = compute_swap_step(sqrt_price, target, liquidity, 10_000_000, 30, true).unwrap();
+        assert_eq!(step.sqrt_price_next, target);
+        assert_eq!(
+            step.amount_in,
+            amount_a_delta(target, sqrt_price, liquidity, true).unwrap()
+        );
+        assert_eq!(
+            step.fee_amount,
+            mul_div_ceil(step.amount_in, 30, 9_970).unwrap()
+        );
+        assert!(step.amount_in + step.fee_amount <= 10_000_000);
+
+        // Not enough: all of it is used, the fee included
+        let step = compute_swap_step(sqrt_price, target, liquidity, 10_000, 30, true).unwrap();
+        assert!(step.sqrt_price_next < sqrt_price && step.sqrt_price_next > target);
+        assert_eq!(step.amount_in + step.fee_amount, 10_000);
+        assert!(step.fee_amount >= 30);
+        assert!(step.amount_out < step.amount_in);
+
+        // Exact output is paid in full, no more
+        let step = compute_swap_step(sqrt_price, target, liquidity, 10_000, 30, false).unwrap();
+        assert_eq!(step.amount_out, 10_000);
+        assert!(step.amount_in > 10_000);
+
+        // With no liquidity the price just moves to the target
+        let step = compute_swap_step(sqrt_price, target, 0, 10_000, 30, true).unwrap();
+        assert_eq!(
+            (step.sqrt_price_next, step.amount_in, step.amount_out),
+            (target, 0, 0)
+        );
+
+        assert_eq!(
+            compute_swap_step(sqrt_price, target, liquidity, 10, 10_000, true),
+            None
+        );
+    }
+
+    #[test]
+    fn liquidity_delta_and_tick_cap() {
+        assert_eq!(add_liquidity_delta(10, -4), Some(6));
+        assert_eq!(add_liquidity_delta(10, 4), Some(14));
+        assert_eq!(add_liquidity_delta(10, -11), None);
+        assert_eq!(add_liquidity_delta(u128::MAX, 1), None);
+
+        // Wider spacing leaves fewer ticks to share the cap
+        assert!(max_liquidity_per_tick(1) < max_liquidity_per_tick(60));
+        assert!(max_liquidity_per_tick(1) * 887_273 <= MAX_LIQUIDITY);
+    }
+
+    #[test]
+    fn fee_growth_inside_counts_only_in_range_growth() {
+        // 100 of growth below the range, 40 inside, 10 above; each tick
+        // records the growth on the side away from the current tick
+        let (lower, upper) = (-60, 60);
+        for (tick, outside_lower, outside_upper) in [(-100, 50, 10), (0, 100, 10), (100, 100, 140)]
+        {
+            assert_eq!(
+                fee_growth_inside(tick, lower, upper, 150, outside_lower, outside_upper),
+                40,
+                "{tick}"
+            );
+        }
+
+        // Growth wraps, and so does the difference
+        assert_eq!(fee_growth_inside(0, lower, upper, 5, 10, 0), u128::MAX - 4);
+        assert_eq!(fees_earned(1 << 64, 3, u128::MAX - 1), Some(5));
+        assert_eq!(fees_earned(1_000, 1 << 63, 0), Some(500));
+        assert_eq!(fees_earned(u128::MAX, u128::MAX, 0), None);
+    }
+}
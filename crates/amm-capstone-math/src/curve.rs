@@ -0,0 +1,362 @@
+use crate::full_math::mul_div_u128;
+use crate::FEE_RATE_DENOMINATOR;
+
+/// Output for exactly `amount_in`, net of a `fee_bps` input fee.
+///
+/// Rounded down so the pool never gives out more than the curve allows.
+/// Returns `None` on overflow, for a fee above 100%, or when both
+/// `reserve_in` and the input are zero.
+pub fn get_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+) -> Option<u64> {
+    let fee_denominator = FEE_RATE_DENOMINATOR as u128;
+    let fee_numerator = fee_denominator.checked_sub(fee_bps as u128)?;
+
+    // apply fee, with the ratio reduced so a fee-free quote (or one at a
+    // round fee) leaves more headroom before u128 overflows
+    let divisor = gcd(fee_numerator, fee_denominator).max(1);
+    let amount_in_with_fee = (amount_in as u128).checked_mul(fee_numerator / divisor)?;
+
+    let numerator = amount_in_with_fee.checked_mul(reserve_out as u128)?;
+    let denominator = (reserve_in as u128)
+        .checked_mul(fee_denominator / divisor)?
+        .checked_add(amount_in_with_fee)?;
+
+    // Below reserve_out whenever the denominator is non-zero
+    u64::try_from(numerator.checked_div(denominator)?).ok()
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Input needed to receive exactly `amount_out`, including the fee.
+///
+/// Rounded up so the pool never gives out more than the curve allows.
+/// Returns `None` when the pool cannot cover `amount_out` or on overflow.
+pub fn get_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+) -> Option<u64> {
+    let fee_denominator = FEE_RATE_DENOMINATOR as u128;
+
+    if amount_out >= reserve_out || fee_bps as u128 >= fee_denominator {
+        return None;
+    }
+
+    let numerator = (reserve_in as u128)
+        .checked_mul(amount_out as u128)?
+        .checked_mul(fee_denominator)?;
+    let denominator = (reserve_out as u128 - amount_out as u128)
+        .checked_mul(fee_denominator - fee_bps as u128)?;
+
+    u64::try_from(numerator.div_ceil(denominator)).ok()
+}
+
+/// `a * b / c`, rounded down. The product is taken in u128, so only the
+/// result can fail to fit; returns `None` then or when `c` is zero.
+pub fn mul_div_floor(a: u64, b: u64, c: u64) -> Option<u64> {
+    let product = a as u128 * b as u128;
+    u64::try_from(product.checked_div(c as u128)?).ok()
+}
+
+/// `a * b / c`, rounded up. Returns `None` when the result does not fit
+/// in u64 or `c` is zero.
+pub fn mul_div_ceil(a: u64, b: u64, c: u64) -> Option<u64> {
+    if c == 0 {
+        return None;
+    }
+    let product = a as u128 * b as u128;
+    u64::try_from(product.div_ceil(c as u128)).ok()
+}
+
+/// Integer square root (floor) using Newton's method.
+pub fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    // Start from a power of two above the root so the iteration
+    // decreases monotonically towards floor(sqrt(value)).
+    let bits = 128 - value.leading_zeros();
+    let mut x = 1u128 << bits.div_ceil(2);
+
+    loop {
+        let y = (x + value / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// LP tokens owed for a deposit of `amount_a` / `amount_b`.
+///
+/// The first deposit mints `sqrt(a * b)` (of which `MINIMUM_LIQUIDITY`
+/// is locked by the caller); later deposits mint the smaller of the two
+/// proportional shares so a lopsided deposit cannot dilute existing LPs.
+/// Returns `None` on overflow or when the pool has supply but no reserves.
+pub fn get_lp_amount(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Option<u64> {
+    if lp_supply == 0 {
+        // u64 * u64 always fits in u128, and so does its root in u64
+        let liquidity = integer_sqrt(amount_a as u128 * amount_b as u128);
+        return u64::try_from(liquidity).ok();
+    }
+
+    if reserve_a == 0 || reserve_b == 0 {
+        return None;
+    }
+
+    // Rounded down: the depositor never gets a fraction of an LP token
+    // they did not pay for
+    let share_a = mul_div_floor(amount_a, lp_supply, reserve_a)?;
+    let share_b = mul_div_floor(amount_b, lp_supply, reserve_b)?;
+
+    Some(share_a.min(share_b))
+}
+
+/// Amount of the other token matching `amount` at the current ratio
+/// (rounded down, as in Uniswap v2's `quote`). LP is then minted on the
+/// rounded amount, so the pool is never short for it.
+pub fn quote(amount: u64, reserve_from: u64, reserve_to: u64) -> Option<u64> {
+    mul_div_floor(amount, reserve_to, reserve_from)
+}
+
+/// Largest deposit within the desired amounts that matches the pool
+/// ratio, so neither side is over-charged. An empty pool takes the
+/// desired amounts as-is since they set the initial price.
+pub fn get_optimal_deposit(
+    amount_a_desired: u64,
+    amount_b_desired: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+) -> Option<(u64, u64)> {
+    if reserve_a == 0 && reserve_b == 0 {
+        return Some((amount_a_desired, amount_b_desired));
+    }
+
+    let amount_b_optimal = quote(amount_a_desired, reserve_a, reserve_b)?;
+    if amount_b_optimal <= amount_b_desired {
+        return Some((amount_a_desired, amount_b_optimal));
+    }
+
+    let amount_a_optimal = quote(amount_b_desired, reserve_b, reserve_a)?;
+    debug_assert!(amount_a_optimal <= amount_a_desired);
+
+    Some((amount_a_optimal, amount_b_desired))
+}
+
+/// Value at `now` on a linear ramp from `start_value` at `start_ts` to
+/// `end_value` at `end_ts`: `start_value` until the ramp starts,
+/// `end_value` from its end on, rounded toward `start_value` between.
+pub(crate) fn linear_ramp_at(
+    start_value: u64,
+    end_value: u64,
+    start_ts: i64,
+    end_ts: i64,
+    now: i64,
+) -> u64 {
+    if now >= end_ts {
+        return end_value;
+    }
+    if now <= start_ts {
+        return start_value;
+    }
+
+    // start_ts < now < end_ts, so the step is below the full change
+    let elapsed = now.abs_diff(start_ts) as u128;
+    let duration = end_ts.abs_diff(start_ts) as u128;
+    let step =
+        |change: u64| mul_div_u128(change as u128, elapsed, duration).unwrap_or_default() as u64;
+
+    if end_value >= start_value {
+        start_value + step(end_value - start_value)
+    } else {
+        start_value - step(start_value - end_value)
+    }
+}
+
+/// Smallest input from `start` up for which `covers` holds, for curves
+/// whose inverse is only an estimate. Doubles the step until it covers,
+/// then bisects, so a steep curve stays cheap. `covers` must be
+/// monotone; `None` from it, or no covering input, gives `None`.
+pub(crate) fn smallest_covering_input(
+    start: u64,
+    covers: impl Fn(u64) -> Option<bool>,
+) -> Option<u64> {
+    if covers(start)? {
+        return Some(start);
+    }
+
+    let (mut short, mut step) = (start, 1u64);
+    let mut enough = loop {
+        let candidate = start.checked_add(step)?;
+        if covers(candidate)? {
+            break candidate;
+        }
+        short = candidate;
+        step = step.checked_mul(2)?;
+    };
+
+    while enough - short > 1 {
+        let middle = short + (enough - short) / 2;
+        if covers(middle)? {
+            enough = middle;
+        } else {
+            short = middle;
+        }
+    }
+
+    Some(enough)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn integer_sqrt_small_values() {
+        let expected = [0u128, 1, 1, 1, 2, 2, 2, 2, 2, 3, 3];
+        for (value, root) in expected.iter().enumerate() {
+            assert_eq!(integer_sqrt(value as u128), *root);
+        }
+    }
+
+    #[test]
+    fn integer_sqrt_is_floor() {
+        for value in [15u128, 16, 17, 99, 100, 101, 1_000_000_007] {
+            let root = integer_sqrt(value);
+            assert!(root * root <= value);
+            assert!((root + 1) * (root + 1) > value);
+        }
+    }
+
+    #[test]
+    fn integer_sqrt_extremes() {
+        assert_eq!(integer_sqrt(u128::MAX), u64::MAX as u128);
+
+        let max_product = u64::MAX as u128 * u64::MAX as u128;
+        assert_eq!(integer_sqrt(max_product), u64::MAX as u128);
+        assert_eq!(integer_sqrt(max_product - 1), u64::MAX as u128 - 1);
+    }
+
+    #[test]
+    fn mul_div_rounds_as_named() {
+        assert_eq!(mul_div_floor(7, 3, 2), Some(10));
+        assert_eq!(mul_div_ceil(7, 3, 2), Some(11));
+        assert_eq!(mul_div_ceil(8, 3, 2), Some(12));
+        assert_eq!(mul_div_floor(1, 1, 0), None);
+        assert_eq!(mul_div_ceil(1, 1, 0), None);
+    }
+
+    #[test]
+    fn mul_div_near_u64_max() {
+        let max = u64::MAX;
+        // The product overflows u64 but the result fits
+        assert_eq!(mul_div_floor(max, max, max), Some(max));
+        assert_eq!(mul_div_ceil(max, max, max), Some(max));
+        assert_eq!(mul_div_floor(max, max - 1, max), Some(max - 1));
+        assert_eq!(mul_div_ceil(max, 3, 4), Some(max / 4 * 3 + 3));
+        // Results past u64::MAX are rejected rather than truncated
+        assert_eq!(mul_div_floor(max, 2, 1), None);
+        assert_eq!(mul_div_ceil(max, max, max - 1), None);
+    }
+
+    #[test]
+    fn first_deposit_mints_geometric_mean() {
+        assert_eq!(get_lp_amount(400, 100, 0, 0, 0), Some(200));
+        assert_eq!(get_lp_amount(u64::MAX, u64::MAX, 0, 0, 0), Some(u64::MAX));
+    }
+
+    #[test]
+    fn later_deposits_mint_smaller_share() {
+        // Pool at 1:2 with 1_000 LP outstanding.
+        assert_eq!(get_lp_amount(100, 200, 1_000, 2_000, 1_000), Some(100));
+        // Excess B earns nothing extra.
+        assert_eq!(get_lp_amount(100, 900, 1_000, 2_000, 1_000), Some(100));
+        // Excess A earns nothing extra.
+        assert_eq!(get_lp_amount(900, 200, 1_000, 2_000, 1_000), Some(100));
+    }
+
+    #[test]
+    fn deposits_near_u64_max_do_not_overflow() {
+        let max = u64::MAX;
+        assert_eq!(get_lp_amount(max, max, max, max, max), Some(max));
+        assert_eq!(get_lp_amount(max, max, 1, 1, 2), None);
+        assert_eq!(quote(max, max, max), Some(max));
+        assert_eq!(quote(max, 1, 2), None);
+    }
+
+    #[test]
+    fn supply_without_reserves_is_rejected() {
+        assert_eq!(get_lp_amount(100, 100, 0, 10, 1_000), None);
+    }
+
+    #[test]
+    fn quote_follows_pool_ratio() {
+        assert_eq!(quote(100, 1_000, 2_000), Some(200));
+        assert_eq!(quote(1, 3, 2), Some(0));
+        assert_eq!(quote(100, 0, 2_000), None);
+    }
+
+    #[test]
+    fn optimal_deposit_trims_excess_b() {
+        assert_eq!(
+            get_optimal_deposit(100, 500, 1_000, 2_000),
+            Some((100, 200))
+        );
+    }
+
+    #[test]
+    fn optimal_deposit_trims_excess_a() {
+        assert_eq!(
+            get_optimal_deposit(500, 200, 1_000, 2_000),
+            Some((100, 200))
+        );
+    }
+
+    #[test]
+    fn optimal_deposit_on_empty_pool_uses_desired() {
+        assert_eq!(get_optimal_deposit(7, 11, 0, 0), Some((7, 11)));
+    }
+
+    #[test]
+    fn amount_in_round_trips_through_amount_out() {
+        let (reserve_in, reserve_out) = (1_000_000u64, 2_000_000u64);
+        for fee_bps in [0u16, 1, 5, 30, 100] {
+            for amount_out in [1u64, 7, 1_000, 123_456, 1_999_999] {
+                let amount_in =
+                    get_amount_in(amount_out, reserve_in, reserve_out, fee_bps).unwrap();
+                // Enough to buy amount_out...
+                assert!(
+                    get_amount_out(amount_in, reserve_in, reserve_out, fee_bps).unwrap()
+                        >= amount_out
+                );
+                // ...and one unit less is not.
+                assert!(
+                    get_amount_out(amount_in - 1, reserve_in, reserve_out, fee_bps).unwrap()
+                        < amount_out
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn amount_in_rejects_draining_the_pool() {
+        assert_eq!(get_amount_in(2_000, 1_000, 2_000, 30), None);
+        assert_eq!(get_amount_in(3_000, 1_000, 2_000, 30), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Q64.64 logarithms and powers, for the weighted curve.
+//!
+//! `log2_q64` is exact to about a unit in the last place. `exp2_q64`
+//! sums its series with 127 fractional bits, so it is exact to a unit in
+//! the last place of its Q64.64 result. `pow_q64` is `exp2(e·log2 b)`;
+//! the log's error of 2^-64, scaled by an exponent of at most 128,
+//! keeps it within a relative 2^-57. `pow_up` / `pow_down` widen that to the
+//! documented `POW_ERROR_BITS` / `POW_ERROR_ULPS` bound, so callers can
+//! round a power in whichever direction favours the pool.
+
+use crate::full_math::{full_mul, mul_div_u128};
+
+/// 1.0 in Q64.64.
+pub const Q64: u128 = 1 << 64;
+
+/// `pow_q64` is within a relative 2^-`POW_ERROR_BITS` of the exact
+/// power, plus `POW_ERROR_ULPS` units in the last place for results too
+/// small for Q64.64 to hold to that precision.
+pub const POW_ERROR_BITS: u32 = 56;
+pub const POW_ERROR_ULPS: u128 = 4;
+
+/// Largest exponent `pow_q64` accepts (128, in Q64.64). The error bound
+/// holds up to here.
+pub const MAX_POW_EXPONENT: u128 = 128 << 64;
+
+// 1.0 in Q1.127, the working precision of `exp2_q64`
+const ONE_Q127: u128 = 1 << 127;
+
+// ln 2, rounded down
+const LN2_Q64: u128 = 12_786_308_645_202_655_659;
+const LN2_Q127: u128 = 0x58b9_0bfb_e8e7_bcd5_e4f1_d9cc_01f9_7b57;
+
+/// `log2(x)` for a Q64.64 `x`, as signed Q64.64. Rounded down; `None`
+/// for zero.
+pub fn log2_q64(x: u128) -> Option<i128> {
+    if x == 0 {
+        return None;
+    }
+
+    let msb = 127 - x.leading_zeros();
+    let integer = msb as i128 - 64;
+
+    // x / 2^msb, in [1, 2) as Q1.127. Squaring it doubles its log; each
+    // time the square reaches 2, halve it and that fractional bit is set.
+    let mut y = x << (127 - msb);
+    let mut fraction = 0i128;
+    for bit in (0..64).rev() {
+        let (high, low) = full_mul(y, y);
+        if high >> 127 == 1 {
+            y = high;
+            fraction |= 1 << bit;
+        } else {
+            y = (high << 1) | (low >> 127);
+        }
+    }
+
+    Some((integer << 64) + fraction)
+}
+
+/// `ln(x)` for a Q64.64 `x`, as signed Q64.64, rounded toward zero;
+/// `None` for zero.
+pub fn ln_q64(x: u128) -> Option<i128> {
+    let log2 = log2_q64(x)?;
+    // |log2| < 2^71, so the product fits
+    let magnitude = mul_div_u128(log2.unsigned_abs(), LN2_Q64, Q64)? as i128;
+
+    Some(if log2 < 0 { -magnitude } else { magnitude })
+}
+
+/// `2^y` for a signed Q64.64 `y`, as Q64.64 rounded down. `None` when the
+/// result does not fit (`y >= 64`).
+pub fn exp2_q64(y: i128) -> Option<u128> {
+    let integer = y >> 64;
+    let fraction = y as u128 & (Q64 - 1);
+
+    // 2^f = e^(f·ln 2) as a Taylor series in Q1.127. f·ln 2 < 0.7, so
+    // the terms drop below 2^-127 within 32 steps and the sum stays
+    // below 2.
+    let t = mul_div_u128(fraction, LN2_Q127, Q64)?;
+    let mut term = ONE_Q127;
+    let mut sum = ONE_Q127;
+    for n in 1..=32 {
+        term = mul_q127(term, t) / n;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+
+    // From Q1.127 to Q64.64, then scaled by 2^integer
+    let shift = integer - 63;
+    if shift >= 0 {
+        if shift > sum.leading_zeros() as i128 {
+            return None;
+        }
+        Some(sum << shift)
+    } else if shift <= -128 {
+        Some(0)
+    } else {
+        Some(sum >> -shift)
+    }
+}
+
+/// `base^exponent` for Q64.64 operands, within the error bound above.
+/// `None` when `exponent` exceeds `MAX_POW_EXPONENT` or the result does
+/// not fit.
+pub fn pow_q64(base: u128, exponent: u128) -> Option<u128> {
+    if exponent > MAX_POW_EXPONENT {
+        return None;
+    }
+    if exponent == 0 {
+        return Some(Q64);
+    }
+    if base == 0 {
+        return Some(0);
+    }
+
+    let log2 = log2_q64(base)?;
+    // |log2| <= 64 and exponent <= 128, so the product fits in i128
+    let product = mul_div_u128(log2.unsigned_abs(), exponent, Q64)? as i128;
+
+    exp2_q64(if log2 < 0 { -product } else { product })
+}
+
+/// `pow_q64`, rounded up past its error bound: never below the exact
+/// power.
+pub fn pow_up(base: u128, exponent: u128) -> Option<u128> {
+    let power = pow_q64(base, exponent)?;
+    power
+        .checked_add(power >> POW_ERROR_BITS)?
+        .checked_add(POW_ERROR_ULPS)
+}
+
+/// `pow_q64`, rounded down past its error bound: never above the exact
+/// power.
+pub fn pow_down(base: u128, exponent: u128) -> Option<u128> {
+    let power = pow_q64(base, exponent)?;
+    Some(power.saturating_sub((power >> POW_ERROR_BITS) + POW_ERROR_ULPS))
+}
+
+// Product of two Q1.127 values below 2, rounded down
+fn mul_q127(a: u128, b: u128) -> u128 {
+    let (high, low) = full_mul(a, b);
+    (high << 1) | (low >> 127)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_q64(value: f64) -> u128 {
+        (value * Q64 as f64) as u128
+    }
+
+    fn to_f64(value: u128) -> f64 {
+        value as f64 / Q64 as f64
+    }
+
+    #[test]
+    fn log2_of_powers_of_two_is_exact() {
+        assert_eq!(log2_q64(Q64), Some(0));
+        assert_eq!(log2_q64(Q64 << 3), Some(3 << 64));
+        assert_eq!(log2_q64(Q64 >> 5), Some(-5 << 64));
+        assert_eq!(log2_q64(1), Some(-64 << 64));
+        assert_eq!(log2_q64(0), None);
+
+        // Just below 2^64, the largest Q64.64 value
+        let top = log2_q64(u128::MAX).unwrap();
+        assert!(top < 64 << 64 && top > (64 << 64) - (1 << 10));
+    }
+
+    #[test]
+    fn ln_matches_f64() {
+        for value in [
+            1e-12,
+            0.001,
+            0.5,
+            0.999_999,
+            1.000_001,
+            core::f64::consts::E,
+            10.0,
+            1e9,
+            1.8e19,
+        ] {
+            let x = to_q64(value);
+            let ln = ln_q64(x).unwrap() as f64 / Q64 as f64;
+            assert!((ln - to_f64(x).ln()).abs() < 1e-12, "{value}: {ln}");
+        }
+    }
+
+    #[test]
+    fn exp2_edges() {
+        assert_eq!(exp2_q64(0), Some(Q64));
+        assert_eq!(exp2_q64(3 << 64), Some(8 * Q64));
+        assert_eq!(exp2_q64(-3 << 64), Some(Q64 / 8));
+        assert_eq!(exp2_q64(-64 << 64), Some(1));
+        assert_eq!(exp2_q64(-65 << 64), Some(0));
+        assert_eq!(exp2_q64(i128::MIN), Some(0));
+        assert!(exp2_q64((64 << 64) - 1).is_some());
+        assert_eq!(exp2_q64(64 << 64), None);
+        assert_eq!(exp2_q64(i128::MAX), None);
+
+        let sqrt2 = to_f64(exp2_q64(1 << 63).unwrap());
+        assert!((sqrt2 - core::f64::consts::SQRT_2).abs() < 1e-15);
+    }
+
+    #[test]
+    fn pow_is_within_its_error_bound() {
+        let bases = [
+            1e-9, 0.01, 0.3, 0.769, 0.999, 1.0, 1.001, 1.3, 4.0, 1e6, 1.8e19,
+        ];
+        let exponents = [0.0101, 0.2, 0.25, 0.5, 1.0, 2.0, 4.0, 9.0, 99.0];
+
+        for base in bases {
+            for exponent in exponents {
+                let (b, e) = (to_q64(base), to_q64(exponent));
+                if base.powf(exponent) > 1.85e19 {
+                    assert_eq!(pow_q64(b, e), None);
+                    continue;
+                }
+
+                // Exponent and base as converted, so f64's own rounding of
+                // the inputs does not count against the bound
+                let exact = to_f64(b).powf(to_f64(e)) * Q64 as f64;
+                let power = pow_q64(b, e).unwrap() as f64;
+                let bound = exact / (1u64 << POW_ERROR_BITS) as f64 + POW_ERROR_ULPS as f64;
+                assert!((power - exact).abs() <= bound, "{base}^{exponent}");
+
+                assert!(pow_up(b, e).unwrap() as f64 >= exact);
+                assert!(pow_down(b, e).unwrap() as f64 <= exact);
+            }
+        }
+    }
+
+    #[test]
+    fn pow_edges() {
+        assert_eq!(pow_q64(Q64, 99 * Q64), Some(Q64));
+        assert_eq!(pow_q64(12_345, 0), Some(Q64));
+        assert_eq!(pow_q64(0, Q64), Some(0));
+        assert_eq!(pow_q64(2 * Q64, 3 * Q64), Some(8 * Q64));
+        assert_eq!(pow_q64(2 * Q64, 64 * Q64), None);
+        assert_eq!(pow_q64(Q64 / 2, MAX_POW_EXPONENT + 1), None);
+        assert_eq!(pow_q64(Q64 / 2, MAX_POW_EXPONENT), Some(0));
+    }
+}
@@ -0,0 +1,115 @@
+//! u128 arithmetic with 256-bit intermediates, for the curves whose
+//! products of reserves outgrow u128.
+
+/// `a * b` as a 256-bit `(high, low)` pair.
+pub fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    // Each partial product of 64-bit halves fits in u128
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (middle << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+
+    (high, low)
+}
+
+/// The 256-bit `(high, low)` divided by `divisor`, rounded down.
+/// Returns `None` when `divisor` is zero or the quotient does not fit in
+/// u128.
+pub fn div_wide(high: u128, low: u128, divisor: u128) -> Option<u128> {
+    if divisor == 0 || high >= divisor {
+        return None;
+    }
+    if high == 0 {
+        return Some(low / divisor);
+    }
+
+    // Schoolbook long division, one bit of `low` at a time. `remainder`
+    // stays below `divisor`, so the only overflow is the bit shifted
+    // out, which means the remainder already exceeds `divisor`.
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1;
+        }
+    }
+
+    Some(quotient)
+}
+
+/// `a * b / c`, rounded down, without overflowing on the product.
+/// Returns `None` when `c` is zero or the result does not fit in u128.
+pub fn mul_div_u128(a: u128, b: u128, c: u128) -> Option<u128> {
+    let (high, low) = full_mul(a, b);
+    div_wide(high, low, c)
+}
+
+/// `a * b / c`, rounded up. Returns `None` when `c` is zero or the
+/// result does not fit in u128.
+pub fn mul_div_u128_ceil(a: u128, b: u128, c: u128) -> Option<u128> {
+    let floor = mul_div_u128(a, b, c)?;
+    if full_mul(floor, c) == full_mul(a, b) {
+        Some(floor)
+    } else {
+        floor.checked_add(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_mul_matches_u128_when_it_fits() {
+        for (a, b) in [(0u128, 5u128), (7, 9), (u64::MAX as u128, u64::MAX as u128)] {
+            assert_eq!(full_mul(a, b), (0, a * b));
+        }
+    }
+
+    #[test]
+    fn full_mul_extremes() {
+        // (2^128 - 1)^2 = 2^256 - 2^129 + 1
+        assert_eq!(full_mul(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+        assert_eq!(full_mul(1 << 64, 1 << 64), (1, 0));
+        assert_eq!(full_mul(u128::MAX, 2), (1, u128::MAX - 1));
+    }
+
+    #[test]
+    fn mul_div_keeps_wide_intermediates() {
+        assert_eq!(
+            mul_div_u128(u128::MAX, u128::MAX, u128::MAX),
+            Some(u128::MAX)
+        );
+        assert_eq!(mul_div_u128(1 << 100, 1 << 100, 1 << 90), Some(1 << 110));
+        assert_eq!(mul_div_u128(u128::MAX, 3, 4), Some(u128::MAX / 4 * 3 + 2));
+        assert_eq!(mul_div_u128(10, 10, 3), Some(33));
+        assert_eq!(mul_div_u128_ceil(10, 10, 3), Some(34));
+        assert_eq!(
+            mul_div_u128_ceil(1 << 100, 1 << 100, 1 << 90),
+            Some(1 << 110)
+        );
+        assert_eq!(mul_div_u128_ceil(u128::MAX, 3, 3), Some(u128::MAX));
+    }
+
+    #[test]
+    fn mul_div_rejects_overflow_and_zero() {
+        assert_eq!(mul_div_u128(u128::MAX, 2, 1), None);
+        assert_eq!(mul_div_u128(1 << 64, 1 << 64, 1), None);
+        assert_eq!(mul_div_u128(1, 1, 0), None);
+        assert_eq!(mul_div_u128_ceil(u128::MAX, 2, 1), None);
+        assert_eq!(mul_div_u128_ceil(1, 1, 0), None);
+    }
+}
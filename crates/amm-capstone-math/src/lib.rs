@@ -0,0 +1,37 @@
+//! Pure AMM math shared by the `amm-capstone` program and off-chain
+//! quoters.
+//!
+//! The program prices every swap and liquidity change through this
+//! crate, so a quote computed here from a deserialized pool matches what
+//! the program will do, down to the rounding. Token-2022 transfer fees
+//! are applied by the caller: amounts here are what reaches or leaves the
+//! vaults.
+#![cfg_attr(not(test), no_std)]
+
+pub mod clmm;
+pub mod curve;
+pub mod fixed_point;
+pub mod full_math;
+pub mod oracle;
+pub mod pool;
+pub mod stable;
+pub mod tick_math;
+pub mod weighted;
+
+pub use clmm::*;
+pub use curve::*;
+pub use fixed_point::*;
+pub use full_math::*;
+pub use oracle::*;
+pub use pool::*;
+pub use stable::*;
+pub use tick_math::*;
+pub use weighted::*;
+
+/// LP tokens permanently locked on the first deposit so the share
+/// price can never be manipulated from an empty supply.
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/// Denominator for fee tiers (bps of the input) and fee shares
+/// (bps of the fee).
+pub const FEE_RATE_DENOMINATOR: u64 = 10_000;
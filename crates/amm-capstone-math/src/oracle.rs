@@ -0,0 +1,80 @@
+/// Spot price of one unit of the base token in quote tokens, as Q64.64
+/// fixed point. Returns `None` when the base reserve is empty.
+pub fn price_q64(reserve_base: u64, reserve_quote: u64) -> Option<u128> {
+    if reserve_base == 0 {
+        return None;
+    }
+
+    // reserve_quote < 2^64, so the shift cannot overflow
+    Some(((reserve_quote as u128) << 64) / reserve_base as u128)
+}
+
+/// Time-weighted average price (Q64.64) between two readings of a
+/// cumulative price accumulator taken `elapsed` seconds apart.
+///
+/// Accumulators wrap on overflow, so the difference is taken with
+/// wrapping arithmetic as in Uniswap v2. Returns `None` for an empty
+/// window.
+pub fn twap(cumulative_start: u128, cumulative_end: u128, elapsed: u64) -> Option<u128> {
+    if elapsed == 0 {
+        return None;
+    }
+
+    Some(cumulative_end.wrapping_sub(cumulative_start) / elapsed as u128)
+}
+
+/// TWAP (Q64.64) between two stored observations of the same
+/// accumulator, in either order. Returns `None` when both were taken at
+/// the same time.
+pub fn twap_between_observations(
+    start_ts: i64,
+    start_cumulative: u128,
+    end_ts: i64,
+    end_cumulative: u128,
+) -> Option<u128> {
+    if start_ts > end_ts {
+        return twap_between_observations(end_ts, end_cumulative, start_ts, start_cumulative);
+    }
+
+    twap(start_cumulative, end_cumulative, end_ts.abs_diff(start_ts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn price_q64_is_quote_per_base() {
+        assert_eq!(price_q64(1_000, 2_000), Some(2 << 64));
+        assert_eq!(price_q64(2_000, 1_000), Some(1 << 63));
+        assert_eq!(price_q64(0, 1_000), None);
+        assert_eq!(price_q64(1, u64::MAX), Some((u64::MAX as u128) << 64));
+    }
+
+    #[test]
+    fn twap_averages_over_window() {
+        // 10s at price 1, then 30s at price 3 -> average 2.5
+        let one = 1u128 << 64;
+        let end = one * 10 + 3 * one * 30;
+        assert_eq!(twap(0, end, 40), Some(5 * one / 2));
+        assert_eq!(twap(end, end, 0), None);
+    }
+
+    #[test]
+    fn twap_survives_accumulator_wraparound() {
+        let price = 7u128 << 64;
+        let start = u128::MAX - price;
+        let end = start.wrapping_add(price * 3);
+        assert!(end < start);
+        assert_eq!(twap(start, end, 3), Some(price));
+    }
+
+    #[test]
+    fn twap_between_observations_ignores_order() {
+        let one = 1u128 << 64;
+        // Observations at t=100 (cum 0) and t=110 (10s at price 4)
+        let (t0, c0, t1, c1) = (100, 0, 110, 40 * one);
+        assert_eq!(twap_between_observations(t0, c0, t1, c1), Some(4 * one));
+        assert_eq!(twap_between_observations(t1, c1, t0, c0), Some(4 * one));
+        assert_eq!(twap_between_observations(t0, c0, t0, c0), None);
+    }
+}
@@ -0,0 +1,776 @@
+use crate::curve::{
+    get_amount_in, get_amount_out, get_lp_amount, get_optimal_deposit, mul_div_ceil, mul_div_floor,
+};
+use crate::stable::{compute_d, get_stable_amount_in, get_stable_amount_out};
+use crate::weighted::{
+    get_weighted_amount_in, get_weighted_amount_out, get_weighted_join_lp, WEIGHT_ONE,
+};
+use crate::{FEE_RATE_DENOMINATOR, MINIMUM_LIQUIDITY};
+
+/// Why a quote could not be produced. Each variant has a same-named
+/// program error.
+///
+/// Every quote rounds in the pool's favour: amounts the pool pays out
+/// round down, amounts it takes in (including the swap fee) round up.
+/// Any intermediate that would overflow u64 is `Overflow`, never a
+/// truncated or wrapped value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuoteError {
+    InvalidAmount,
+    PoolEmpty,
+    InsufficientLiquidity,
+    InvariantViolation,
+    Overflow,
+    UnsupportedCurve,
+}
+
+/// The invariant a pool prices swaps on. Proportional deposits and
+/// withdrawals are the same for every curve except for the LP minted by
+/// the first deposit; single-token deposits need a weighted invariant,
+/// which the constant product is at 50/50.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Curve {
+    /// `x·y = k`, as in Uniswap v2.
+    #[default]
+    ConstantProduct,
+    /// Curve's StableSwap with amplification `amp`, for pegged pairs.
+    Stable { amp: u64 },
+    /// Balancer's weighted product, with weights summing to
+    /// `WEIGHT_ONE`.
+    Weighted { weight_a: u64, weight_b: u64 },
+}
+
+impl Curve {
+    /// (input, output) weights for the given direction, or `None` for a
+    /// curve that is not a weighted product.
+    pub fn weights(&self, a_to_b: bool) -> Option<(u64, u64)> {
+        let (weight_a, weight_b) = match *self {
+            Curve::ConstantProduct => (WEIGHT_ONE / 2, WEIGHT_ONE / 2),
+            Curve::Weighted { weight_a, weight_b } => (weight_a, weight_b),
+            Curve::Stable { .. } => return None,
+        };
+
+        Some(if a_to_b {
+            (weight_a, weight_b)
+        } else {
+            (weight_b, weight_a)
+        })
+    }
+}
+
+/// The parts of a pool (and its config) that pricing depends on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolState {
+    pub curve: Curve,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    // Swap fee, in bps of the input
+    pub fee_bps: u16,
+    // Shares of the swap fee, in bps of the fee, kept out of reserves
+    pub protocol_fee_rate: u16,
+    pub fund_fee_rate: u16,
+}
+
+/// Outcome of a swap, seen from the vaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub total_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+    // Part of the fee left in reserves for LPs
+    pub lp_fee: u64,
+    pub new_reserve_in: u64,
+    pub new_reserve_out: u64,
+}
+
+/// Outcome of a deposit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepositQuote {
+    pub amount_a: u64,
+    pub amount_b: u64,
+    // LP minted to the depositor
+    pub liquidity: u64,
+    // LP minted to the locked account (first deposit only)
+    pub locked_liquidity: u64,
+}
+
+/// Outcome of a deposit of one token alone. The part of it that buys
+/// the other token's share of the pool pays the swap fee, split as on a
+/// swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SingleDepositQuote {
+    pub amount_in: u64,
+    pub liquidity: u64,
+    pub total_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+    pub new_reserve: u64,
+}
+
+/// Outcome of a withdrawal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawQuote {
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+impl PoolState {
+    /// A constant-product pool; see `with_curve` for the others.
+    pub fn new(reserve_a: u64, reserve_b: u64, fee_bps: u16) -> Self {
+        Self {
+            curve: Curve::ConstantProduct,
+            reserve_a,
+            reserve_b,
+            fee_bps,
+            protocol_fee_rate: 0,
+            fund_fee_rate: 0,
+        }
+    }
+
+    pub fn with_fee_shares(self, protocol_fee_rate: u16, fund_fee_rate: u16) -> Self {
+        Self {
+            protocol_fee_rate,
+            fund_fee_rate,
+            ..self
+        }
+    }
+
+    pub fn with_curve(self, curve: Curve) -> Self {
+        Self { curve, ..self }
+    }
+
+    /// (input, output) reserves for the given direction.
+    pub fn reserves(&self, a_to_b: bool) -> (u64, u64) {
+        if a_to_b {
+            (self.reserve_a, self.reserve_b)
+        } else {
+            (self.reserve_b, self.reserve_a)
+        }
+    }
+
+    /// Total swap fee on `amount_in`, rounded up so any non-zero input
+    /// pays at least one unit at a non-zero tier. Fails when the fee
+    /// tier is above 100% and the fee would exceed the input.
+    pub fn swap_fee(&self, amount_in: u64) -> Result<u64, QuoteError> {
+        let fee = mul_div_ceil(amount_in, self.fee_bps as u64, FEE_RATE_DENOMINATOR)
+            .ok_or(QuoteError::Overflow)?;
+        if fee > amount_in {
+            return Err(QuoteError::Overflow);
+        }
+        Ok(fee)
+    }
+
+    /// Smallest input that is still worth `effective_input` once the swap
+    /// fee is taken from it, i.e. the inverse of `amount - swap_fee`.
+    fn input_for_effective(&self, effective_input: u64) -> Result<u64, QuoteError> {
+        let net_rate = FEE_RATE_DENOMINATOR
+            .checked_sub(self.fee_bps as u64)
+            .filter(|rate| *rate > 0)
+            .ok_or(QuoteError::Overflow)?;
+
+        mul_div_ceil(effective_input, FEE_RATE_DENOMINATOR, net_rate).ok_or(QuoteError::Overflow)
+    }
+
+    /// Swap of exactly `amount_in`. The fee is taken from the input
+    /// first and the rest is priced on the constant-product curve.
+    pub fn quote_exact_in(&self, a_to_b: bool, amount_in: u64) -> Result<SwapQuote, QuoteError> {
+        let (reserve_in, reserve_out) = self.non_empty_reserves(a_to_b)?;
+
+        let effective_input = amount_in
+            .checked_sub(self.swap_fee(amount_in)?)
+            .ok_or(QuoteError::Overflow)?;
+        let amount_out = self.curve_amount_out(a_to_b, effective_input, reserve_in, reserve_out)?;
+
+        self.settle_swap(a_to_b, amount_in, amount_out)
+    }
+
+    /// Swap buying exactly `amount_out`, with the input rounded up. The
+    /// fee is grossed up onto the curve's input the same way exact-in
+    /// takes it off, so both modes agree on the price.
+    pub fn quote_exact_out(&self, a_to_b: bool, amount_out: u64) -> Result<SwapQuote, QuoteError> {
+        let (reserve_in, reserve_out) = self.non_empty_reserves(a_to_b)?;
+
+        if amount_out >= reserve_out {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+
+        let effective_input = match self.curve {
+            Curve::ConstantProduct => get_amount_in(amount_out, reserve_in, reserve_out, 0),
+            Curve::Stable { amp } => get_stable_amount_in(amount_out, reserve_in, reserve_out, amp),
+            Curve::Weighted { .. } => {
+                self.curve
+                    .weights(a_to_b)
+                    .and_then(|(weight_in, weight_out)| {
+                        get_weighted_amount_in(
+                            amount_out,
+                            reserve_in,
+                            weight_in,
+                            reserve_out,
+                            weight_out,
+                        )
+                    })
+            }
+        }
+        .ok_or(QuoteError::Overflow)?;
+        let amount_in = self.input_for_effective(effective_input)?;
+
+        self.settle_swap(a_to_b, amount_in, amount_out)
+    }
+
+    /// Splits the fee on `amount_in` and checks the resulting reserves
+    /// against the invariant. Both quote modes end here; the program also
+    /// calls it directly when a transfer fee makes the vault receive a
+    /// different input than was quoted.
+    pub fn settle_swap(
+        &self,
+        a_to_b: bool,
+        amount_in: u64,
+        amount_out: u64,
+    ) -> Result<SwapQuote, QuoteError> {
+        let (reserve_in, reserve_out) = self.non_empty_reserves(a_to_b)?;
+
+        let total_fee = self.swap_fee(amount_in)?;
+        let protocol_fee = bps_of(total_fee, self.protocol_fee_rate)?;
+        let fund_fee = bps_of(total_fee, self.fund_fee_rate)?;
+        let lp_fee = total_fee
+            .checked_sub(protocol_fee)
+            .and_then(|fee| fee.checked_sub(fund_fee))
+            .ok_or(QuoteError::Overflow)?;
+
+        // Protocol and fund fees stay in the input vault but are excluded
+        // from reserves, so only the effective input and LP fee grow `k`.
+        let effective_input = amount_in
+            .checked_sub(total_fee)
+            .ok_or(QuoteError::Overflow)?;
+        let new_reserve_in = reserve_in
+            .checked_add(effective_input)
+            .and_then(|reserve| reserve.checked_add(lp_fee))
+            .ok_or(QuoteError::Overflow)?;
+
+        let new_reserve_out = reserve_out
+            .checked_sub(amount_out)
+            .ok_or(QuoteError::InsufficientLiquidity)?;
+
+        match self.curve {
+            Curve::ConstantProduct => {
+                // u64 * u64 always fits in u128
+                let old_k = reserve_in as u128 * reserve_out as u128;
+                let new_k = new_reserve_in as u128 * new_reserve_out as u128;
+
+                if new_k < old_k {
+                    return Err(QuoteError::InvariantViolation);
+                }
+            }
+            Curve::Stable { amp } => {
+                // D has no closed form to compare, so hold the output to
+                // what the curve prices for the input net of the fee
+                if amount_out
+                    > self.curve_amount_out(a_to_b, effective_input, reserve_in, reserve_out)?
+                {
+                    return Err(QuoteError::InvariantViolation);
+                }
+                // Reserves so lopsided that D no longer converges would
+                // leave the pool unable to price its next swap
+                if compute_d(new_reserve_in, new_reserve_out, amp).is_none() {
+                    return Err(QuoteError::InsufficientLiquidity);
+                }
+            }
+            Curve::Weighted { .. } => {
+                // Comparing x^w_a·y^w_b directly would need the powers
+                // the curve already rounds in the pool's favour
+                if amount_out
+                    > self.curve_amount_out(a_to_b, effective_input, reserve_in, reserve_out)?
+                {
+                    return Err(QuoteError::InvariantViolation);
+                }
+            }
+        }
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            total_fee,
+            protocol_fee,
+            fund_fee,
+            lp_fee,
+            new_reserve_in,
+            new_reserve_out,
+        })
+    }
+
+    /// Deposit of up to the desired amounts, trimmed to the pool ratio.
+    pub fn quote_add_liquidity(
+        &self,
+        amount_a_desired: u64,
+        amount_b_desired: u64,
+        lp_supply: u64,
+    ) -> Result<DepositQuote, QuoteError> {
+        let (amount_a, amount_b) = get_optimal_deposit(
+            amount_a_desired,
+            amount_b_desired,
+            self.reserve_a,
+            self.reserve_b,
+        )
+        .ok_or(QuoteError::PoolEmpty)?;
+
+        self.quote_deposit(amount_a, amount_b, lp_supply)
+    }
+
+    /// LP owed for depositing exactly `amount_a` / `amount_b`. On the
+    /// first deposit `MINIMUM_LIQUIDITY` of it is locked.
+    pub fn quote_deposit(
+        &self,
+        amount_a: u64,
+        amount_b: u64,
+        lp_supply: u64,
+    ) -> Result<DepositQuote, QuoteError> {
+        if amount_a == 0 || amount_b == 0 {
+            return Err(QuoteError::InvalidAmount);
+        }
+
+        let liquidity = match self.curve {
+            // The first stable deposit mints D, its value at the peg
+            Curve::Stable { amp } if lp_supply == 0 => {
+                compute_d(amount_a, amount_b, amp).and_then(|d| u64::try_from(d).ok())
+            }
+            _ => get_lp_amount(
+                amount_a,
+                amount_b,
+                self.reserve_a,
+                self.reserve_b,
+                lp_supply,
+            ),
+        }
+        .ok_or(QuoteError::Overflow)?;
+
+        let locked_liquidity = if lp_supply == 0 { MINIMUM_LIQUIDITY } else { 0 };
+
+        let liquidity = liquidity
+            .checked_sub(locked_liquidity)
+            .filter(|liquidity| *liquidity > 0)
+            .ok_or(QuoteError::InsufficientLiquidity)?;
+
+        Ok(DepositQuote {
+            amount_a,
+            amount_b,
+            liquidity,
+            locked_liquidity,
+        })
+    }
+
+    /// Deposit of exactly `amount_in` of one token (A when `token_a`),
+    /// minting LP by the growth of the weighted invariant. Equivalent to
+    /// swapping the other token's weight of it and depositing both, so
+    /// that part pays the swap fee; the protocol and fund shares stay out of
+    /// reserves as on a swap. Needs a funded pool and a weighted curve.
+    pub fn quote_single_sided_deposit(
+        &self,
+        token_a: bool,
+        amount_in: u64,
+        lp_supply: u64,
+    ) -> Result<SingleDepositQuote, QuoteError> {
+        let (weight, _) = self
+            .curve
+            .weights(token_a)
+            .ok_or(QuoteError::UnsupportedCurve)?;
+        let (reserve, _) = self.non_empty_reserves(token_a)?;
+        if lp_supply == 0 {
+            return Err(QuoteError::PoolEmpty);
+        }
+        if amount_in == 0 {
+            return Err(QuoteError::InvalidAmount);
+        }
+
+        // Rounded up, and so is the fee on it
+        let taxable =
+            mul_div_ceil(amount_in, WEIGHT_ONE - weight, WEIGHT_ONE).ok_or(QuoteError::Overflow)?;
+        let total_fee = self.swap_fee(taxable)?;
+        let protocol_fee = bps_of(total_fee, self.protocol_fee_rate)?;
+        let fund_fee = bps_of(total_fee, self.fund_fee_rate)?;
+        // The LP share stays in reserves, as on a swap
+        let lp_fee = total_fee
+            .checked_sub(protocol_fee)
+            .and_then(|fee| fee.checked_sub(fund_fee))
+            .ok_or(QuoteError::Overflow)?;
+
+        let liquidity = get_weighted_join_lp(amount_in - total_fee, reserve, weight, lp_supply)
+            .ok_or(QuoteError::Overflow)?;
+        if liquidity == 0 {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+
+        let new_reserve = reserve
+            .checked_add(amount_in - total_fee + lp_fee)
+            .ok_or(QuoteError::Overflow)?;
+
+        Ok(SingleDepositQuote {
+            amount_in,
+            liquidity,
+            total_fee,
+            protocol_fee,
+            fund_fee,
+            new_reserve,
+        })
+    }
+
+    /// Burning `lp_amount` returns its proportional share of each
+    /// reserve, rounded down.
+    pub fn quote_remove_liquidity(
+        &self,
+        lp_amount: u64,
+        lp_supply: u64,
+    ) -> Result<WithdrawQuote, QuoteError> {
+        if lp_supply == 0 {
+            return Err(QuoteError::PoolEmpty);
+        }
+        if lp_amount > lp_supply {
+            return Err(QuoteError::InvalidAmount);
+        }
+
+        // Rounded down, so the dust stays with the remaining LPs.
+        // lp_amount <= lp_supply, so each share fits back into u64.
+        let share =
+            |reserve: u64| mul_div_floor(reserve, lp_amount, lp_supply).ok_or(QuoteError::Overflow);
+
+        Ok(WithdrawQuote {
+            amount_a: share(self.reserve_a)?,
+            amount_b: share(self.reserve_b)?,
+        })
+    }
+
+    // Curve output for an input already net of the swap fee
+    fn curve_amount_out(
+        &self,
+        a_to_b: bool,
+        effective_input: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+    ) -> Result<u64, QuoteError> {
+        match self.curve {
+            Curve::ConstantProduct => get_amount_out(effective_input, reserve_in, reserve_out, 0),
+            Curve::Stable { amp } => {
+                get_stable_amount_out(effective_input, reserve_in, reserve_out, amp)
+            }
+            Curve::Weighted { .. } => {
+                self.curve
+                    .weights(a_to_b)
+                    .and_then(|(weight_in, weight_out)| {
+                        get_weighted_amount_out(
+                            effective_input,
+                            reserve_in,
+                            weight_in,
+                            reserve_out,
+                            weight_out,
+                        )
+                    })
+            }
+        }
+        .ok_or(QuoteError::Overflow)
+    }
+
+    fn non_empty_reserves(&self, a_to_b: bool) -> Result<(u64, u64), QuoteError> {
+        if self.reserve_a == 0 || self.reserve_b == 0 {
+            return Err(QuoteError::PoolEmpty);
+        }
+        Ok(self.reserves(a_to_b))
+    }
+}
+
+/// `bps` basis points of `amount`, rounded down. Used for the protocol
+/// and fund shares, which leave the reserves, so the LPs keep the dust.
+fn bps_of(amount: u64, bps: u16) -> Result<u64, QuoteError> {
+    mul_div_floor(amount, bps as u64, FEE_RATE_DENOMINATOR).ok_or(QuoteError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> PoolState {
+        PoolState::new(1_000_000, 2_000_000, 30).with_fee_shares(2_000, 1_000)
+    }
+
+    #[test]
+    fn exact_in_splits_fee_and_grows_k() {
+        let quote = pool().quote_exact_in(true, 10_000).unwrap();
+
+        assert_eq!(quote.total_fee, 30);
+        assert_eq!(quote.protocol_fee, 6);
+        assert_eq!(quote.fund_fee, 3);
+        assert_eq!(quote.lp_fee, 21);
+        assert_eq!(
+            quote.amount_out,
+            get_amount_out(10_000 - 30, 1_000_000, 2_000_000, 0).unwrap()
+        );
+        assert_eq!(quote.new_reserve_in, 1_000_000 + 10_000 - 6 - 3);
+        assert_eq!(quote.new_reserve_out, 2_000_000 - quote.amount_out);
+        assert!(
+            quote.new_reserve_in as u128 * quote.new_reserve_out as u128
+                >= 1_000_000u128 * 2_000_000
+        );
+    }
+
+    #[test]
+    fn exact_out_delivers_requested_amount() {
+        for amount_out in [1u64, 999, 123_456] {
+            let quote = pool().quote_exact_out(false, amount_out).unwrap();
+            assert_eq!(quote.amount_out, amount_out);
+            // Exact-in with the quoted input buys at least as much
+            let check = pool().quote_exact_in(false, quote.amount_in).unwrap();
+            assert!(check.amount_out >= amount_out);
+        }
+    }
+
+    #[test]
+    fn swap_fee_rounds_up() {
+        assert_eq!(pool().swap_fee(1), Ok(1));
+        assert_eq!(pool().swap_fee(3_334), Ok(11));
+        assert_eq!(pool().swap_fee(10_000), Ok(30));
+        assert_eq!(PoolState::new(1, 1, 0).swap_fee(u64::MAX), Ok(0));
+
+        // A fee-free tier still pays nothing
+        let quote = PoolState::new(1_000_000, 2_000_000, 0)
+            .quote_exact_in(true, 1)
+            .unwrap();
+        assert_eq!(quote.total_fee, 0);
+    }
+
+    #[test]
+    fn exact_out_input_is_minimal() {
+        for amount_out in [1u64, 999, 123_456] {
+            let quote = pool().quote_exact_out(true, amount_out).unwrap();
+            let short = pool().quote_exact_in(true, quote.amount_in - 1).unwrap();
+            assert!(short.amount_out < amount_out);
+        }
+    }
+
+    #[test]
+    fn swaps_near_u64_max_fail_with_overflow() {
+        let max = u64::MAX;
+
+        // The input reserve cannot absorb more
+        let full = PoolState::new(max - 10, 2_000_000, 30);
+        assert_eq!(full.quote_exact_in(true, 1_000), Err(QuoteError::Overflow));
+        assert!(full.quote_exact_in(true, 10).is_ok());
+
+        // The required input does not fit in u64
+        let deep = PoolState::new(max, max, 30);
+        assert_eq!(
+            deep.quote_exact_out(true, max - 1),
+            Err(QuoteError::Overflow)
+        );
+
+        // Largest inputs still quote when the reserves have room
+        let quote = PoolState::new(1, max - 1, 30)
+            .quote_exact_in(true, max - 1)
+            .unwrap();
+        assert!(quote.amount_out < max - 1);
+        assert_eq!(
+            quote.total_fee,
+            mul_div_ceil(max - 1, 30, FEE_RATE_DENOMINATOR).unwrap()
+        );
+    }
+
+    #[test]
+    fn remove_liquidity_near_u64_max() {
+        let deep = PoolState::new(u64::MAX, u64::MAX - 1, 30);
+
+        let all = deep.quote_remove_liquidity(u64::MAX, u64::MAX).unwrap();
+        assert_eq!((all.amount_a, all.amount_b), (u64::MAX, u64::MAX - 1));
+
+        // The remainder rounds in the remaining LPs' favour
+        let half = deep.quote_remove_liquidity(u64::MAX / 2, u64::MAX).unwrap();
+        assert_eq!(
+            (half.amount_a, half.amount_b),
+            (u64::MAX / 2, u64::MAX / 2 - 1)
+        );
+    }
+
+    #[test]
+    fn swaps_reject_empty_pools_and_draining() {
+        let empty = PoolState::new(0, 2_000_000, 30);
+        assert_eq!(empty.quote_exact_in(true, 1), Err(QuoteError::PoolEmpty));
+        assert_eq!(
+            pool().quote_exact_out(true, 2_000_000),
+            Err(QuoteError::InsufficientLiquidity)
+        );
+        assert_eq!(
+            pool().settle_swap(true, 1, 1_000),
+            Err(QuoteError::InvariantViolation)
+        );
+    }
+
+    #[test]
+    fn first_deposit_locks_minimum_liquidity() {
+        let empty = PoolState::new(0, 0, 30);
+        let quote = empty.quote_add_liquidity(400_000, 100_000, 0).unwrap();
+
+        assert_eq!(quote.locked_liquidity, MINIMUM_LIQUIDITY);
+        assert_eq!(quote.liquidity, 200_000 - MINIMUM_LIQUIDITY);
+        assert_eq!(
+            empty.quote_add_liquidity(1_000, 1_000, 0),
+            Err(QuoteError::InsufficientLiquidity)
+        );
+    }
+
+    #[test]
+    fn later_deposits_follow_pool_ratio() {
+        let quote = pool().quote_add_liquidity(100, 900, 1_000_000).unwrap();
+        assert_eq!((quote.amount_a, quote.amount_b), (100, 200));
+        assert_eq!(quote.liquidity, 100);
+        assert_eq!(quote.locked_liquidity, 0);
+    }
+
+    #[test]
+    fn remove_liquidity_is_proportional() {
+        let quote = pool().quote_remove_liquidity(250_000, 1_000_000).unwrap();
+        assert_eq!((quote.amount_a, quote.amount_b), (250_000, 500_000));
+
+        assert_eq!(
+            pool().quote_remove_liquidity(2, 1),
+            Err(QuoteError::InvalidAmount)
+        );
+        assert_eq!(
+            pool().quote_remove_liquidity(1, 0),
+            Err(QuoteError::PoolEmpty)
+        );
+    }
+
+    fn stable_pool() -> PoolState {
+        PoolState::new(1_000_000, 1_000_000, 5)
+            .with_fee_shares(2_000, 1_000)
+            .with_curve(Curve::Stable { amp: 100 })
+    }
+
+    #[test]
+    fn stable_swaps_dispatch_on_the_curve() {
+        let stable = stable_pool().quote_exact_in(true, 100_000).unwrap();
+        let product = PoolState::new(1_000_000, 1_000_000, 5)
+            .quote_exact_in(true, 100_000)
+            .unwrap();
+
+        assert_eq!(stable.total_fee, product.total_fee);
+        assert_eq!(
+            stable.amount_out,
+            get_stable_amount_out(100_000 - 50, 1_000_000, 1_000_000, 100).unwrap()
+        );
+        assert!(stable.amount_out > product.amount_out);
+
+        for amount_out in [1u64, 999, 99_000] {
+            let quote = stable_pool().quote_exact_out(false, amount_out).unwrap();
+            assert_eq!(quote.amount_out, amount_out);
+            let check = stable_pool()
+                .quote_exact_in(false, quote.amount_in)
+                .unwrap();
+            assert!(check.amount_out >= amount_out);
+        }
+    }
+
+    #[test]
+    fn stable_settle_holds_output_to_the_curve() {
+        let priced = get_stable_amount_out(10_000 - 5, 1_000_000, 1_000_000, 100).unwrap();
+
+        assert!(stable_pool().settle_swap(true, 10_000, priced).is_ok());
+        assert_eq!(
+            stable_pool().settle_swap(true, 10_000, priced + 1),
+            Err(QuoteError::InvariantViolation)
+        );
+    }
+
+    #[test]
+    fn first_stable_deposit_mints_d() {
+        let empty = PoolState::new(0, 0, 5).with_curve(Curve::Stable { amp: 100 });
+        let quote = empty.quote_add_liquidity(400_000, 100_000, 0).unwrap();
+
+        let d = compute_d(400_000, 100_000, 100).unwrap() as u64;
+        assert_eq!(quote.liquidity + quote.locked_liquidity, d);
+        // Worth more than the constant-product geometric mean
+        assert!(d > 200_000);
+    }
+
+    fn weighted_pool() -> PoolState {
+        PoolState::new(4_000_000, 1_000_000, 30)
+            .with_fee_shares(2_000, 1_000)
+            .with_curve(Curve::Weighted {
+                weight_a: WEIGHT_ONE / 10 * 8,
+                weight_b: WEIGHT_ONE / 10 * 2,
+            })
+    }
+
+    #[test]
+    fn weighted_swaps_dispatch_on_the_curve() {
+        let quote = weighted_pool().quote_exact_in(false, 10_000).unwrap();
+        assert_eq!(
+            quote.amount_out,
+            get_weighted_amount_out(
+                10_000 - 30,
+                1_000_000,
+                WEIGHT_ONE / 10 * 2,
+                4_000_000,
+                WEIGHT_ONE / 10 * 8
+            )
+            .unwrap()
+        );
+
+        for a_to_b in [true, false] {
+            for amount_out in [1u64, 999, 500_000] {
+                let quote = weighted_pool().quote_exact_out(a_to_b, amount_out).unwrap();
+                let check = weighted_pool()
+                    .quote_exact_in(a_to_b, quote.amount_in)
+                    .unwrap();
+                assert!(check.amount_out >= amount_out);
+            }
+        }
+
+        assert_eq!(
+            weighted_pool().settle_swap(false, 10_000, quote.amount_out + 1),
+            Err(QuoteError::InvariantViolation)
+        );
+    }
+
+    #[test]
+    fn single_sided_deposit_taxes_the_swapped_part() {
+        // 20% of a B deposit stays B; the other 80% pays the fee
+        let quote = weighted_pool()
+            .quote_single_sided_deposit(false, 100_000, 1_000_000)
+            .unwrap();
+        assert_eq!(quote.total_fee, 240);
+        assert_eq!((quote.protocol_fee, quote.fund_fee), (48, 24));
+        assert_eq!(quote.new_reserve, 1_000_000 + 100_000 - 48 - 24);
+        assert_eq!(
+            quote.liquidity,
+            get_weighted_join_lp(100_000 - 240, 1_000_000, WEIGHT_ONE / 10 * 2, 1_000_000).unwrap()
+        );
+
+        // Never worth more than depositing both tokens in proportion
+        let both = weighted_pool()
+            .quote_deposit(400_000, 100_000, 1_000_000)
+            .unwrap();
+        let single_a = weighted_pool()
+            .quote_single_sided_deposit(true, 400_000, 1_000_000)
+            .unwrap();
+        assert!(quote.liquidity + single_a.liquidity <= both.liquidity);
+    }
+
+    #[test]
+    fn single_sided_deposit_needs_a_weighted_funded_pool() {
+        assert_eq!(
+            stable_pool().quote_single_sided_deposit(true, 1_000, 1_000_000),
+            Err(QuoteError::UnsupportedCurve)
+        );
+        assert_eq!(
+            weighted_pool().quote_single_sided_deposit(true, 1_000, 0),
+            Err(QuoteError::PoolEmpty)
+        );
+        assert_eq!(
+            weighted_pool().quote_single_sided_deposit(true, 0, 1_000_000),
+            Err(QuoteError::InvalidAmount)
+        );
+        // Constant product is the 50/50 weighted pool
+        assert!(pool()
+            .quote_single_sided_deposit(true, 1_000, 1_000_000)
+            .is_ok());
+    }
+}
@@ -0,0 +1,432 @@
+//! Curve's StableSwap invariant for two tokens:
+//!
+//! ```text
+//! A·n^n·(x + y) + D = A·D·n^n + D^(n+1) / (n^n·x·y),   n = 2
+//! ```
+//!
+//! Near balance it prices like a constant sum, so pegged pairs trade
+//! with little slippage; far from balance it bends into a constant
+//! product so neither side can be drained. `amp` is A; `ann` below is
+//! A·n, as in Curve's original contract.
+
+use crate::curve::{linear_ramp_at, smallest_covering_input};
+use crate::fixed_point::Q64;
+use crate::full_math::{div_wide, full_mul, mul_div_u128};
+
+const N_COINS: u128 = 2;
+
+/// Bounds on the amplification coefficient, as in Curve.
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+
+/// Amplification at `now` on a linear ramp from `initial_amp` at
+/// `start_ts` to `target_amp` at `stop_ts`, as in Curve's `_A`. Before
+/// the ramp starts it is `initial_amp`; from `stop_ts` on, `target_amp`.
+pub fn ramp_amp_at(
+    initial_amp: u64,
+    target_amp: u64,
+    start_ts: i64,
+    stop_ts: i64,
+    now: i64,
+) -> u64 {
+    linear_ramp_at(initial_amp, target_amp, start_ts, stop_ts, now)
+}
+
+// Newton converges in a handful of steps from any valid start; this only
+// bounds pathological inputs.
+const MAX_ITERATIONS: usize = 255;
+
+/// The invariant `D` for a pair of reserves. `Some(0)` for an empty
+/// pool; `None` when exactly one reserve is zero, `amp` is out of range,
+/// or Newton's method does not converge.
+pub fn compute_d(reserve_a: u64, reserve_b: u64, amp: u64) -> Option<u128> {
+    if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+        return None;
+    }
+
+    let (x, y) = (reserve_a as u128, reserve_b as u128);
+    let sum = x + y;
+    if sum == 0 {
+        return Some(0);
+    }
+    if x == 0 || y == 0 {
+        return None;
+    }
+
+    let ann = amp as u128 * N_COINS;
+    let (small, large) = (x.min(y), x.max(y));
+    let mut d = sum;
+
+    for _ in 0..MAX_ITERATIONS {
+        // D^3 / (n^n·x·y), one reserve at a time. The smaller one goes
+        // first: D >= 2·min(x, y), so the first quotient is at least D and
+        // keeps its precision however lopsided the pool is.
+        let d_p = mul_div_u128(d, d, small * N_COINS)?;
+        let d_p = mul_div_u128(d_p, d, large * N_COINS)?;
+
+        let previous = d;
+        let numerator = (ann * sum).checked_add(d_p.checked_mul(N_COINS)?)?;
+        let denominator = ((ann - 1) * d).checked_add(d_p.checked_mul(N_COINS + 1)?)?;
+        d = mul_div_u128(numerator, d, denominator)?;
+
+        if d.abs_diff(previous) <= 1 {
+            return Some(d);
+        }
+    }
+
+    None
+}
+
+/// The other reserve that keeps the invariant at `d` when one reserve
+/// is `x`.
+///
+/// Newton's method stops within a unit of the root, so the result is
+/// pinned to the smallest `y` that satisfies the invariant. That makes it
+/// never increase as `x` grows, which swap pricing relies on.
+pub fn compute_y(x: u64, d: u128, amp: u64) -> Option<u128> {
+    if !(MIN_AMP..=MAX_AMP).contains(&amp) || x == 0 {
+        return None;
+    }
+    // D never exceeds the sum of the reserves
+    if d > 2 * u64::MAX as u128 {
+        return None;
+    }
+
+    let x = x as u128;
+    let ann = amp as u128 * N_COINS;
+
+    // y^2 + (b - D)·y = c, with c = D^3 / (n^n·A·n·x). Dividing by the
+    // small A·n^2 first keeps the precision that dividing by a large x
+    // would lose.
+    let c = mul_div_u128(d, d, ann * N_COINS)?;
+    let c = mul_div_u128(c, d, x * N_COINS)?;
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+
+        let (high, low) = full_mul(y, y);
+        let (low, carry) = low.overflowing_add(c);
+        let denominator = (2 * y + b).checked_sub(d)?;
+        y = div_wide(high + carry as u128, low, denominator)?;
+
+        if y.abs_diff(previous) <= 1 {
+            return smallest_root(y, b, c, d);
+        }
+    }
+
+    None
+}
+
+// Steps from a root estimate to the smallest y with y·(y + b - D) >= c
+fn smallest_root(estimate: u128, b: u128, c: u128, d: u128) -> Option<u128> {
+    let covers = |y: u128| match (y + b).checked_sub(d) {
+        Some(slope) if slope > 0 => full_mul(y, slope) >= (0, c),
+        _ => false,
+    };
+
+    let mut y = estimate;
+    for _ in 0..MAX_ITERATIONS {
+        if y > 0 && covers(y - 1) {
+            y -= 1;
+        } else if !covers(y) {
+            y += 1;
+        } else {
+            return Some(y);
+        }
+    }
+
+    None
+}
+
+/// Output for exactly `amount_in` (already net of any swap fee), rounded
+/// down with one unit kept back, as in Curve's `exchange`.
+///
+/// Returns `None` on overflow, for an out-of-range `amp`, or when either
+/// reserve is empty.
+pub fn get_stable_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    amp: u64,
+) -> Option<u64> {
+    StableSwap::new(reserve_in, reserve_out, amp)?.amount_out(amount_in)
+}
+
+/// Input (net of any swap fee) for which `get_stable_amount_out` gives
+/// at least `amount_out`. Returns `None` when the pool cannot cover
+/// `amount_out` or on overflow.
+pub fn get_stable_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    amp: u64,
+) -> Option<u64> {
+    StableSwap::new(reserve_in, reserve_out, amp)?.amount_in(amount_out)
+}
+
+/// Marginal price of one unit of the base token in quote tokens, as
+/// Q64.64: the slope of the invariant at the current reserves,
+///
+/// ```text
+/// (y / x) · (A·n·x + D_P) / (A·n·y + D_P),   D_P = D^(n+1) / (n^n·x·y)
+/// ```
+///
+/// for base reserve `x` and quote reserve `y`. Exactly 1 when the pool is
+/// balanced; it approaches the reserve ratio as `amp` falls. Returns
+/// `None` when either reserve is empty or `amp` is out of range.
+pub fn stable_price_q64(reserve_base: u64, reserve_quote: u64, amp: u64) -> Option<u128> {
+    if reserve_base == 0 || reserve_quote == 0 {
+        return None;
+    }
+
+    let d = compute_d(reserve_base, reserve_quote, amp)?;
+    let (x, y) = (reserve_base as u128, reserve_quote as u128);
+    let ann = amp as u128 * N_COINS;
+    let d_p = mul_div_u128(d, d, x.min(y) * N_COINS)?;
+    let d_p = mul_div_u128(d_p, d, x.max(y) * N_COINS)?;
+
+    // y·(A·n·x + D_P) / (A·n·y + D_P) lies between the two reserves, so
+    // as Q64.64 it fits and keeps 64 fractional bits through the final
+    // division by x
+    let scaled = mul_div_u128(
+        (ann * x).checked_add(d_p)?,
+        y * Q64,
+        (ann * y).checked_add(d_p)?,
+    )?;
+    Some(scaled / x)
+}
+
+// One direction of a swap against fixed reserves, with `D` and the
+// starting point solved once.
+struct StableSwap {
+    reserve_in: u64,
+    amp: u64,
+    d: u128,
+    // The output reserve as the solver sees it at the current input
+    // reserve. Pricing against this rather than the stored reserve keeps
+    // the solver's rounding from paying out anything for a zero input.
+    base_out: u128,
+}
+
+impl StableSwap {
+    fn new(reserve_in: u64, reserve_out: u64, amp: u64) -> Option<Self> {
+        if reserve_in == 0 || reserve_out == 0 {
+            return None;
+        }
+
+        let d = compute_d(reserve_in, reserve_out, amp)?;
+        let base_out = compute_y(reserve_in, d, amp)?.min(reserve_out as u128);
+
+        Some(Self {
+            reserve_in,
+            amp,
+            d,
+            base_out,
+        })
+    }
+
+    fn amount_out(&self, amount_in: u64) -> Option<u64> {
+        let new_reserve_in = self.reserve_in.checked_add(amount_in)?;
+        let new_reserve_out = compute_y(new_reserve_in, self.d, self.amp)?;
+
+        // At most `base_out`, which fits in u64
+        let amount_out = self
+            .base_out
+            .saturating_sub(new_reserve_out)
+            .saturating_sub(1);
+
+        u64::try_from(amount_out).ok()
+    }
+
+    fn amount_in(&self, amount_out: u64) -> Option<u64> {
+        // The output reserve must stay above zero after the unit kept back
+        let target_out = self
+            .base_out
+            .checked_sub(amount_out as u128 + 1)
+            .filter(|reserve| *reserve > 0)?;
+
+        // Solving the invariant for the input reserve lands within a unit
+        // or two; walk up from there to an input that prices at least
+        // `amount_out`
+        let estimate = compute_y(u64::try_from(target_out).ok()?, self.d, self.amp)?;
+        let start = u64::try_from(estimate.saturating_sub(self.reserve_in as u128)).ok()?;
+
+        smallest_covering_input(start, |amount_in| {
+            Some(self.amount_out(amount_in)? >= amount_out)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::get_amount_out;
+
+    #[test]
+    fn ramp_interpolates_linearly() {
+        let at = |now| ramp_amp_at(100, 1_000, 1_000, 2_000, now);
+        assert_eq!(at(i64::MIN), 100);
+        assert_eq!(at(1_000), 100);
+        assert_eq!(at(1_500), 550);
+        assert_eq!(at(1_999), 999);
+        assert_eq!(at(2_000), 1_000);
+        assert_eq!(at(i64::MAX), 1_000);
+
+        // Rounds toward the starting amplification, so a ramp down never
+        // passes its target either
+        let down = |now| ramp_amp_at(MAX_AMP, MIN_AMP, i64::MIN, i64::MAX, now);
+        assert_eq!(down(0), (MAX_AMP + MIN_AMP) / 2 + 1);
+        assert_eq!(down(i64::MAX - 1), MIN_AMP + 1);
+
+        // A finished or stopped ramp holds its target
+        assert_eq!(ramp_amp_at(100, 200, 5, 5, 5), 200);
+    }
+
+    #[test]
+    fn balanced_pool_d_is_sum_of_reserves() {
+        for amp in [1, 100, MAX_AMP] {
+            let d = compute_d(1_000_000, 1_000_000, amp).unwrap();
+            assert!(d.abs_diff(2_000_000) <= 1);
+        }
+        assert_eq!(compute_d(0, 0, 100), Some(0));
+        assert_eq!(compute_d(0, 1_000, 100), None);
+        assert_eq!(compute_d(1_000, 1_000, 0), None);
+        assert_eq!(compute_d(1_000, 1_000, MAX_AMP + 1), None);
+    }
+
+    #[test]
+    fn imbalance_lowers_d_below_the_sum() {
+        let balanced = compute_d(1_000_000, 1_000_000, 100).unwrap();
+        let skewed = compute_d(1_900_000, 100_000, 100).unwrap();
+        assert!(skewed < balanced);
+
+        // Higher amplification keeps D closer to the constant sum
+        assert!(compute_d(1_900_000, 100_000, 1_000).unwrap() > skewed);
+    }
+
+    #[test]
+    fn y_recovers_the_other_reserve() {
+        for (x, y, amp) in [
+            (1_000_000u64, 1_000_000u64, 100u64),
+            (1_900_000, 100_000, 100),
+            (5, 7, 1),
+        ] {
+            let d = compute_d(x, y, amp).unwrap();
+            let solved = compute_y(x, d, amp).unwrap();
+            assert!(solved.abs_diff(y as u128) <= 2, "{x} {y} {amp}: {solved}");
+        }
+    }
+
+    #[test]
+    fn y_never_rises_with_x() {
+        let d = compute_d(1_000_000, 1_000_000, 50).unwrap();
+        let mut previous = u128::MAX;
+        for x in 999_990..1_000_010 {
+            let y = compute_y(x, d, 50).unwrap();
+            assert!(y <= previous);
+            previous = y;
+        }
+    }
+
+    #[test]
+    fn pegged_swap_beats_constant_product() {
+        let stable = get_stable_amount_out(100_000, 1_000_000, 1_000_000, 100).unwrap();
+        let product = get_amount_out(100_000, 1_000_000, 1_000_000, 0).unwrap();
+
+        assert!(stable > product);
+        assert!(stable < 100_000);
+        assert!(stable > 99_000);
+    }
+
+    #[test]
+    fn zero_input_buys_nothing() {
+        for (reserve_in, reserve_out) in
+            [(1_000_000u64, 1_000_000u64), (1_900_000, 100_000), (3, 11)]
+        {
+            assert_eq!(
+                get_stable_amount_out(0, reserve_in, reserve_out, 100),
+                Some(0)
+            );
+        }
+    }
+
+    #[test]
+    fn amount_in_covers_amount_out() {
+        for (reserve_in, reserve_out) in [
+            (1_000_000u64, 1_000_000u64),
+            (1_900_000, 100_000),
+            (100_000, 1_900_000),
+        ] {
+            for amount_out in [1u64, 17, 5_000, 90_000] {
+                let amount_in =
+                    get_stable_amount_in(amount_out, reserve_in, reserve_out, 100).unwrap();
+                let bought =
+                    get_stable_amount_out(amount_in, reserve_in, reserve_out, 100).unwrap();
+                assert!(bought >= amount_out);
+                // Within a unit of the cheapest input
+                let short = get_stable_amount_out(
+                    amount_in.saturating_sub(2),
+                    reserve_in,
+                    reserve_out,
+                    100,
+                )
+                .unwrap();
+                assert!(short < amount_out);
+            }
+        }
+    }
+
+    #[test]
+    fn amount_in_rejects_draining_the_pool() {
+        assert_eq!(
+            get_stable_amount_in(1_000_000, 1_000_000, 1_000_000, 100),
+            None
+        );
+        assert_eq!(
+            get_stable_amount_in(999_999, 1_000_000, 1_000_000, 100),
+            None
+        );
+    }
+
+    #[test]
+    fn stable_price_is_the_slope_of_the_curve() {
+        // Balanced pools trade at par whatever A is
+        for amp in [1, 100, MAX_AMP] {
+            assert_eq!(stable_price_q64(1_000_000, 1_000_000, amp), Some(Q64));
+        }
+
+        // A skewed pool's price matches a small trade's, each way, and
+        // sits between par and the reserve ratio
+        let (x, y) = (1_900_000_000u64, 100_000_000u64);
+        let price = stable_price_q64(x, y, 100).unwrap();
+        let inverse = stable_price_q64(y, x, 100).unwrap();
+        let traded = get_stable_amount_out(1_000, x, y, 100).unwrap() as u128;
+        assert!((traded * Q64 / 1_000).abs_diff(price) < price / 100);
+        assert!(price < Q64 && price > y as u128 * Q64 / x as u128);
+        assert!(mul_div_u128(price, inverse, Q64).unwrap().abs_diff(Q64) < Q64 / 1_000_000);
+
+        // Higher amplification holds it closer to par
+        assert!(stable_price_q64(x, y, 1_000).unwrap() > price);
+
+        assert_eq!(stable_price_q64(0, 1_000, 100), None);
+        assert_eq!(stable_price_q64(1_000, 0, 100), None);
+        assert_eq!(stable_price_q64(1_000, 1_000, 0), None);
+        assert!(stable_price_q64(1, u64::MAX, MAX_AMP).is_some());
+        assert!(stable_price_q64(u64::MAX, 1, MAX_AMP).is_some());
+    }
+
+    #[test]
+    fn reserves_near_u64_max_stay_exact() {
+        let big = u64::MAX / 2;
+        let d = compute_d(big, big, 100).unwrap();
+        assert!(d.abs_diff(2 * big as u128) <= 1);
+
+        let amount_out = get_stable_amount_out(1_000_000, big, big, 100).unwrap();
+        assert!(amount_out <= 1_000_000 && amount_out > 999_000);
+
+        // An input that would take the reserve past u64::MAX is rejected
+        assert_eq!(get_stable_amount_out(u64::MAX, big, big, 100), None);
+    }
+}
@@ -0,0 +1,156 @@
+//! Ticks and the Q64.64 square-root prices they stand for, as in
+//! Uniswap v3:
+//!
+//! ```text
+//! sqrt_price(tick) = 1.0001^(tick / 2)
+//! ```
+//!
+//! Each tick moves the price of token A in B by 0.01%. Prices are kept
+//! as their square roots so that a swap within one range of constant
+//! liquidity moves the price linearly in the amount of B traded.
+
+use crate::fixed_point::log2_q64;
+
+/// Tick bounds, chosen so every square-root price fits comfortably in
+/// Q64.64 (between about 2^-32 and 2^32).
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+
+/// `sqrt_price_at_tick` of `MIN_TICK` and `MAX_TICK`.
+pub const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79_226_673_521_066_979_257_578_248_091;
+
+// 2^64 / sqrt(1.0001)^(2^i), rounded down: the factor bit `i` of |tick|
+// contributes to `sqrt_price_at_tick`
+const TICK_FACTORS: [u128; 19] = [
+    0xfffc_b933_bd6f_ad37,
+    0xfff9_7272_373d_4132,
+    0xfff2_e50f_5f65_6932,
+    0xffe5_caca_7e10_e4e6,
+    0xffcb_9843_d60f_6159,
+    0xff97_3b41_fa98_c081,
+    0xff2e_a164_66c9_6a38,
+    0xfe5d_ee04_6a99_a2a8,
+    0xfcbe_86c7_900a_88ae,
+    0xf987_a725_3ac4_1317,
+    0xf339_2b08_22b7_0005,
+    0xe715_9475_a2c2_9b74,
+    0xd097_f3bd_fd20_22b8,
+    0xa9f7_4646_2d87_0fdf,
+    0x70d8_69a1_56d2_a1b8,
+    0x31be_135f_97d0_8fd9,
+    0x09aa_508b_5b7a_84e1,
+    0x005d_6af8_dedb_8119,
+    0x0000_2216_e584_f5fa,
+];
+
+// log2(sqrt(1.0001)) in Q64.64, rounded down
+const LOG2_TICK_Q64: i128 = 1_330_584_781_654_114;
+
+/// Square-root price at `tick`, as Q64.64. `None` outside
+/// `MIN_TICK..=MAX_TICK`.
+pub fn sqrt_price_at_tick(tick: i32) -> Option<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+
+    // sqrt(1.0001)^-|tick| from the factors of its set bits. Every
+    // factor is below 1, so the running product stays at or below 2^64
+    // and each multiplication fits in u128.
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = 1u128 << 64;
+    for (bit, factor) in TICK_FACTORS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+
+    // The reciprocal for positive ticks; `ratio` is at least 2^32 here
+    Some(if tick > 0 { u128::MAX / ratio } else { ratio })
+}
+
+/// Greatest tick whose square-root price is at or below `sqrt_price_x64`.
+/// `None` outside `MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64`.
+pub fn tick_at_sqrt_price(sqrt_price_x64: u128) -> Option<i32> {
+    if !(MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        return None;
+    }
+
+    // The log is close enough to land within a tick of the answer;
+    // `sqrt_price_at_tick` settles it exactly
+    let estimate = log2_q64(sqrt_price_x64)?.div_euclid(LOG2_TICK_Q64);
+    let mut tick = estimate.clamp(MIN_TICK as i128, MAX_TICK as i128) as i32;
+
+    while tick > MIN_TICK && sqrt_price_at_tick(tick)? > sqrt_price_x64 {
+        tick -= 1;
+    }
+    while tick < MAX_TICK && sqrt_price_at_tick(tick + 1)? <= sqrt_price_x64 {
+        tick += 1;
+    }
+
+    Some(tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_f64(value: u128) -> f64 {
+        value as f64 / (1u128 << 64) as f64
+    }
+
+    #[test]
+    fn sqrt_price_matches_f64() {
+        assert_eq!(sqrt_price_at_tick(0), Some(1 << 64));
+
+        for tick in [
+            1, -1, 7, 60, -600, 10_000, -123_456, 400_000, MIN_TICK, MAX_TICK,
+        ] {
+            let expected = 1.0001f64.powf(tick as f64 / 2.0);
+            let sqrt_price = to_f64(sqrt_price_at_tick(tick).unwrap());
+            assert!(
+                (sqrt_price / expected - 1.0).abs() < 1e-9,
+                "{tick}: {sqrt_price} vs {expected}"
+            );
+        }
+
+        assert_eq!(sqrt_price_at_tick(MIN_TICK), Some(MIN_SQRT_PRICE_X64));
+        assert_eq!(sqrt_price_at_tick(MAX_TICK), Some(MAX_SQRT_PRICE_X64));
+        assert_eq!(sqrt_price_at_tick(MIN_TICK - 1), None);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK + 1), None);
+    }
+
+    #[test]
+    fn sqrt_price_increases_with_every_tick() {
+        let ranges = [
+            MIN_TICK..MIN_TICK + 1_000,
+            -1_000..1_000,
+            MAX_TICK - 1_000..MAX_TICK,
+        ];
+        for range in ranges {
+            for tick in range {
+                assert!(
+                    sqrt_price_at_tick(tick) < sqrt_price_at_tick(tick + 1),
+                    "{tick}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_inverts_sqrt_price_at_tick() {
+        for tick in [MIN_TICK, -200_001, -60, -1, 0, 1, 59, 200_001, MAX_TICK - 1] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            let next = sqrt_price_at_tick(tick + 1).unwrap();
+
+            assert_eq!(tick_at_sqrt_price(sqrt_price), Some(tick));
+            assert_eq!(tick_at_sqrt_price(sqrt_price + 1), Some(tick));
+            assert_eq!(tick_at_sqrt_price(next - 1), Some(tick));
+            assert_eq!(tick_at_sqrt_price(next), Some(tick + 1));
+        }
+
+        assert_eq!(tick_at_sqrt_price(MAX_SQRT_PRICE_X64), Some(MAX_TICK));
+        assert_eq!(tick_at_sqrt_price(MIN_SQRT_PRICE_X64 - 1), None);
+        assert_eq!(tick_at_sqrt_price(MAX_SQRT_PRICE_X64 + 1), None);
+    }
+}
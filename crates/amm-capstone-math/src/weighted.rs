@@ -0,0 +1,297 @@
+//! Balancer's weighted-product invariant for two tokens:
+//!
+//! ```text
+//! V = x^w_a · y^w_b,   w_a + w_b = 1
+//! ```
+//!
+//! At 50/50 it is the constant product; an 80/20 pool holds 80% of its
+//! value in the first token and moves its price a quarter as much per
+//! trade. Weights are fractions of `WEIGHT_ONE`. Powers come from
+//! `pow_q64`, rounded past its error bound in the pool's favour.
+
+use crate::curve::{linear_ramp_at, smallest_covering_input};
+use crate::fixed_point::{pow_down, pow_up, Q64};
+use crate::full_math::{mul_div_u128, mul_div_u128_ceil};
+
+/// Weights are normalized to this: `weight_a + weight_b == WEIGHT_ONE`.
+pub const WEIGHT_ONE: u64 = 1_000_000_000_000_000_000;
+
+/// Lightest weight a token may have (1%), as in Balancer. It keeps the
+/// weight ratio, the exponent of every power, at most 99.
+pub const MIN_WEIGHT: u64 = WEIGHT_ONE / 100;
+
+/// Whether a pair of weights is normalized and each at least `MIN_WEIGHT`.
+pub fn valid_weights(weight_a: u64, weight_b: u64) -> bool {
+    weight_a >= MIN_WEIGHT
+        && weight_b >= MIN_WEIGHT
+        && weight_a.checked_add(weight_b) == Some(WEIGHT_ONE)
+}
+
+/// Weights at `now` while they move linearly from `start_weight_a` at
+/// `start_ts` to `end_weight_a` at `end_ts`, as in a liquidity
+/// bootstrapping pool. Token B takes the rest of `WEIGHT_ONE`, so the
+/// pair stays normalized; between two valid pairs it stays valid too.
+pub fn gradual_weights_at(
+    start_weight_a: u64,
+    end_weight_a: u64,
+    start_ts: i64,
+    end_ts: i64,
+    now: i64,
+) -> (u64, u64) {
+    let weight_a = linear_ramp_at(start_weight_a, end_weight_a, start_ts, end_ts, now);
+    (weight_a, WEIGHT_ONE.saturating_sub(weight_a))
+}
+
+/// Output for exactly `amount_in` (already net of any swap fee):
+///
+/// ```text
+/// out = reserve_out · (1 - (reserve_in / (reserve_in + in))^(w_in / w_out))
+/// ```
+///
+/// Rounded down. Returns `None` on overflow, for an empty reserve, or
+/// for weights outside `valid_weights`.
+pub fn get_weighted_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    weight_in: u64,
+    reserve_out: u64,
+    weight_out: u64,
+) -> Option<u64> {
+    if reserve_in == 0 || reserve_out == 0 || !valid_weights(weight_in, weight_out) {
+        return None;
+    }
+
+    // Base and exponent both round so the power rounds up, and the
+    // output down
+    let new_reserve_in = reserve_in.checked_add(amount_in)?;
+    let base = div_q64_ceil(reserve_in, new_reserve_in)?;
+    let exponent = mul_div_u128(weight_in as u128, Q64, weight_out as u128)?;
+    let power = pow_up(base, exponent)?.min(Q64);
+
+    u64::try_from(mul_div_u128(reserve_out as u128, Q64 - power, Q64)?).ok()
+}
+
+/// Input (net of any swap fee) for which `get_weighted_amount_out` gives
+/// at least `amount_out`:
+///
+/// ```text
+/// in = reserve_in · ((reserve_out / (reserve_out - out))^(w_out / w_in) - 1)
+/// ```
+///
+/// Rounded up. Returns `None` when the pool cannot cover `amount_out` or
+/// on overflow.
+pub fn get_weighted_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    weight_in: u64,
+    reserve_out: u64,
+    weight_out: u64,
+) -> Option<u64> {
+    if reserve_in == 0 || amount_out >= reserve_out || !valid_weights(weight_in, weight_out) {
+        return None;
+    }
+
+    let base = div_q64_ceil(reserve_out, reserve_out - amount_out)?;
+    let exponent = mul_div_u128(weight_out as u128, Q64, weight_in as u128)? + 1;
+    let growth = pow_up(base, exponent)?.saturating_sub(Q64);
+    let estimate = u64::try_from(mul_div_u128_ceil(reserve_in as u128, growth, Q64)?).ok()?;
+
+    // The estimate rounds the same way `get_weighted_amount_out` does, so
+    // it almost always covers; walk up in case its margin falls short
+    smallest_covering_input(estimate, |amount_in| {
+        Some(
+            get_weighted_amount_out(amount_in, reserve_in, weight_in, reserve_out, weight_out)?
+                >= amount_out,
+        )
+    })
+}
+
+/// LP minted for `amount_in` of one token alone (already net of any swap
+/// fee) into a pool with `lp_supply` outstanding:
+///
+/// ```text
+/// lp = lp_supply · (((reserve + in) / reserve)^weight - 1)
+/// ```
+///
+/// The invariant grows by that factor, so existing shares keep their
+/// value. Rounded down; `None` on overflow or an empty pool.
+pub fn get_weighted_join_lp(
+    amount_in: u64,
+    reserve: u64,
+    weight: u64,
+    lp_supply: u64,
+) -> Option<u64> {
+    if reserve == 0 || lp_supply == 0 || weight == 0 || weight >= WEIGHT_ONE {
+        return None;
+    }
+
+    let new_reserve = reserve.checked_add(amount_in)?;
+    let base = mul_div_u128(new_reserve as u128, Q64, reserve as u128)?;
+    let exponent = mul_div_u128(weight as u128, Q64, WEIGHT_ONE as u128)?;
+    let growth = pow_down(base, exponent)?.saturating_sub(Q64);
+
+    u64::try_from(mul_div_u128(lp_supply as u128, growth, Q64)?).ok()
+}
+
+/// Spot price of one unit of the base token in quote tokens, as Q64.64:
+/// `(reserve_quote / weight_quote) / (reserve_base / weight_base)`.
+/// Returns `None` for an empty base reserve, a zero weight, or a price
+/// that does not fit.
+pub fn weighted_price_q64(
+    reserve_base: u64,
+    weight_base: u64,
+    reserve_quote: u64,
+    weight_quote: u64,
+) -> Option<u128> {
+    // Each product of a u64 reserve and weight fits in u128
+    mul_div_u128(
+        reserve_quote as u128 * weight_base as u128,
+        Q64,
+        reserve_base as u128 * weight_quote as u128,
+    )
+}
+
+// `numerator / denominator` as Q64.64, rounded up
+fn div_q64_ceil(numerator: u64, denominator: u64) -> Option<u128> {
+    mul_div_u128_ceil(numerator as u128, Q64, denominator as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::get_amount_out;
+
+    const HALF: u64 = WEIGHT_ONE / 2;
+    const EIGHTY: u64 = WEIGHT_ONE / 10 * 8;
+    const TWENTY: u64 = WEIGHT_ONE / 10 * 2;
+
+    #[test]
+    fn weights_must_be_normalized() {
+        assert!(valid_weights(HALF, HALF));
+        assert!(valid_weights(EIGHTY, TWENTY));
+        assert!(valid_weights(WEIGHT_ONE - MIN_WEIGHT, MIN_WEIGHT));
+        assert!(!valid_weights(WEIGHT_ONE - MIN_WEIGHT + 1, MIN_WEIGHT - 1));
+        assert!(!valid_weights(HALF, HALF + 1));
+        assert!(!valid_weights(0, WEIGHT_ONE));
+        assert!(!valid_weights(u64::MAX, u64::MAX));
+    }
+
+    #[test]
+    fn gradual_weights_move_linearly() {
+        // A 99/1 sale moving to 50/50 over 100 seconds
+        let at = |now| gradual_weights_at(WEIGHT_ONE - MIN_WEIGHT, HALF, 1_000, 1_100, now);
+
+        assert_eq!(at(0), (WEIGHT_ONE - MIN_WEIGHT, MIN_WEIGHT));
+        assert_eq!(at(1_000), (WEIGHT_ONE - MIN_WEIGHT, MIN_WEIGHT));
+        assert_eq!(at(1_050).0, (WEIGHT_ONE - MIN_WEIGHT + HALF) / 2);
+        assert_eq!(at(1_100), (HALF, HALF));
+        assert_eq!(at(i64::MAX), (HALF, HALF));
+
+        for now in 1_000..=1_100 {
+            let (weight_a, weight_b) = at(now);
+            assert!(valid_weights(weight_a, weight_b), "{now}");
+            assert!(weight_a >= at(now + 1).0);
+        }
+
+        // Rising, and over the whole i64 range
+        let (weight_a, _) = gradual_weights_at(TWENTY, EIGHTY, i64::MIN, i64::MAX, 0);
+        assert!(weight_a.abs_diff(HALF) <= 1);
+    }
+
+    #[test]
+    fn equal_weights_price_as_constant_product() {
+        for amount_in in [1u64, 1_000, 250_000, 10_000_000] {
+            let weighted =
+                get_weighted_amount_out(amount_in, 1_000_000, HALF, 2_000_000, HALF).unwrap();
+            let product = get_amount_out(amount_in, 1_000_000, 2_000_000, 0).unwrap();
+            // The power's error margin costs at most a unit
+            assert!(
+                weighted <= product && weighted + 1 >= product,
+                "{amount_in}"
+            );
+        }
+    }
+
+    #[test]
+    fn heavier_input_weight_buys_more() {
+        // 80/20 holding four times as much A as B: they trade at par
+        let spot =
+            get_weighted_amount_out(1_000, 4_000_000_000, EIGHTY, 1_000_000_000, TWENTY).unwrap();
+        assert!(spot.abs_diff(1_000) <= 1);
+
+        let light = get_weighted_amount_out(100_000, 1_000_000, TWENTY, 1_000_000, EIGHTY).unwrap();
+        let even = get_weighted_amount_out(100_000, 1_000_000, HALF, 1_000_000, HALF).unwrap();
+        let heavy = get_weighted_amount_out(100_000, 1_000_000, EIGHTY, 1_000_000, TWENTY).unwrap();
+        assert!(light < even && even < heavy);
+        assert_eq!(
+            get_weighted_amount_out(0, 1_000_000, EIGHTY, 1_000_000, TWENTY),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn amount_in_covers_amount_out() {
+        for (weight_in, weight_out) in [(HALF, HALF), (EIGHTY, TWENTY), (TWENTY, EIGHTY)] {
+            for amount_out in [1u64, 17, 5_000, 900_000] {
+                let amount_in =
+                    get_weighted_amount_in(amount_out, 3_000_000, weight_in, 1_000_000, weight_out)
+                        .unwrap();
+                let bought =
+                    get_weighted_amount_out(amount_in, 3_000_000, weight_in, 1_000_000, weight_out)
+                        .unwrap();
+                assert!(bought >= amount_out);
+            }
+        }
+
+        assert_eq!(
+            get_weighted_amount_in(1_000_000, 1, HALF, 1_000_000, HALF),
+            None
+        );
+        assert_eq!(
+            get_weighted_amount_in(10, 1_000_000, HALF, 1_000_000, HALF + 1),
+            None
+        );
+    }
+
+    #[test]
+    fn single_token_join_grows_supply_by_weight() {
+        // Doubling the 80% side grows the invariant by 2^0.8
+        let lp = get_weighted_join_lp(1_000_000, 1_000_000, EIGHTY, 1_000_000_000).unwrap();
+        let expected = 1_000_000_000.0 * (2f64.powf(0.8) - 1.0);
+        assert!((lp as f64) <= expected && (lp as f64) > expected - 2.0);
+
+        // Rounded down, so dust mints nothing
+        assert_eq!(get_weighted_join_lp(1, u64::MAX / 2, HALF, 1_000), Some(0));
+        assert_eq!(get_weighted_join_lp(1, 0, HALF, 1_000), None);
+        assert_eq!(get_weighted_join_lp(1, 1_000, HALF, 0), None);
+    }
+
+    #[test]
+    fn spot_price_scales_by_weight() {
+        assert_eq!(weighted_price_q64(1_000, HALF, 2_000, HALF), Some(2 * Q64));
+        // Four times as much A at 80/20 is a price of one
+        assert_eq!(weighted_price_q64(4_000, EIGHTY, 1_000, TWENTY), Some(Q64));
+        assert_eq!(weighted_price_q64(1_000, TWENTY, 4_000, EIGHTY), Some(Q64));
+        assert_eq!(weighted_price_q64(0, HALF, 1_000, HALF), None);
+        assert_eq!(
+            weighted_price_q64(1, WEIGHT_ONE - MIN_WEIGHT, u64::MAX, MIN_WEIGHT),
+            None
+        );
+    }
+
+    #[test]
+    fn reserves_near_u64_max() {
+        let big = u64::MAX / 2;
+        let out = get_weighted_amount_out(1_000_000, big, EIGHTY, big, TWENTY).unwrap();
+        // The power's error margin is a few hundred units at this scale
+        assert!(out < 4_000_000 && out > 3_999_500, "{out}");
+
+        assert_eq!(
+            get_weighted_amount_out(u64::MAX, big, EIGHTY, big, TWENTY),
+            None
+        );
+        assert!(
+            get_weighted_amount_out(u64::MAX - big, big, HALF, u64::MAX, HALF).unwrap() < u64::MAX
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! Property tests driving random add / swap / remove sequences through
+//! `PoolState`, the same quoting path the program settles with.
+
+use amm_capstone_math::*;
+use proptest::prelude::*;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Add { amount_a: u64, amount_b: u64 },
+    SwapExactIn { a_to_b: bool, amount_in: u64 },
+    SwapExactOut { a_to_b: bool, amount_out: u64 },
+    AddSingle { token_a: bool, amount_in: u64 },
+    // Share of the trader's LP to burn, in bps
+    Remove { share_bps: u16 },
+}
+
+/// A pool seeded by a passive LP, and one trader's net token flows.
+#[derive(Debug)]
+struct Model {
+    pool: PoolState,
+    lp_supply: u64,
+    trader_lp: u64,
+    // Positive when the trader has received more than paid in
+    trader_a: i128,
+    trader_b: i128,
+    // Protocol and fund fees held in the vaults outside reserves
+    fees_a: u128,
+    fees_b: u128,
+}
+
+impl Model {
+    fn seed(pool: PoolState, amount_a: u64, amount_b: u64) -> Option<Self> {
+        let quote = pool.quote_add_liquidity(amount_a, amount_b, 0).ok()?;
+        Some(Self {
+            pool: PoolState {
+                reserve_a: amount_a,
+                reserve_b: amount_b,
+                ..pool
+            },
+            lp_supply: quote.liquidity + quote.locked_liquidity,
+            trader_lp: 0,
+            trader_a: 0,
+            trader_b: 0,
+            fees_a: 0,
+            fees_b: 0,
+        })
+    }
+
+    fn k(&self) -> u128 {
+        self.pool.reserve_a as u128 * self.pool.reserve_b as u128
+    }
+
+    fn d(&self, amp: u64) -> u128 {
+        compute_d(self.pool.reserve_a, self.pool.reserve_b, amp).unwrap()
+    }
+
+    /// `ln(x^w_a · y^w_b / lp_supply)`, the log of one LP token's share
+    /// of the weighted invariant.
+    fn ln_weighted_share(&self, weight_a: u64, weight_b: u64) -> f64 {
+        let weight = |w: u64| w as f64 / WEIGHT_ONE as f64;
+        weight(weight_a) * (self.pool.reserve_a as f64).ln()
+            + weight(weight_b) * (self.pool.reserve_b as f64).ln()
+            - (self.lp_supply as f64).ln()
+    }
+
+    /// Applies `op` if the math accepts it. Rejections are fine, except
+    /// for an invariant violation, which no quote should produce.
+    fn apply(&mut self, op: &Op) -> Result<(), TestCaseError> {
+        let result = match *op {
+            Op::Add { amount_a, amount_b } => self.add(amount_a, amount_b),
+            Op::SwapExactIn { a_to_b, amount_in } => self
+                .pool
+                .quote_exact_in(a_to_b, amount_in)
+                .map(|quote| self.settle(a_to_b, quote)),
+            Op::SwapExactOut { a_to_b, amount_out } => self
+                .pool
+                .quote_exact_out(a_to_b, amount_out)
+                .map(|quote| self.settle(a_to_b, quote)),
+            Op::AddSingle { token_a, amount_in } => self.add_single(token_a, amount_in),
+            Op::Remove { share_bps } => {
+                let lp = (self.trader_lp as u128 * share_bps as u128 / 10_000) as u64;
+                self.remove(lp)
+            }
+        };
+
+        prop_assert_ne!(result, Err(QuoteError::InvariantViolation));
+        Ok(())
+    }
+
+    fn add(&mut self, amount_a: u64, amount_b: u64) -> Result<(), QuoteError> {
+        let quote = self
+            .pool
+            .quote_add_liquidity(amount_a, amount_b, self.lp_supply)?;
+
+        // The program fails these with Overflow too
+        let reserve_a = self.pool.reserve_a.checked_add(quote.amount_a);
+        let reserve_b = self.pool.reserve_b.checked_add(quote.amount_b);
+        let lp_supply = self.lp_supply.checked_add(quote.liquidity);
+        let (Some(reserve_a), Some(reserve_b), Some(lp_supply)) = (reserve_a, reserve_b, lp_supply)
+        else {
+            return Err(QuoteError::Overflow);
+        };
+
+        self.pool.reserve_a = reserve_a;
+        self.pool.reserve_b = reserve_b;
+        self.lp_supply = lp_supply;
+        self.trader_lp += quote.liquidity;
+        self.trader_a -= quote.amount_a as i128;
+        self.trader_b -= quote.amount_b as i128;
+        Ok(())
+    }
+
+    fn add_single(&mut self, token_a: bool, amount_in: u64) -> Result<(), QuoteError> {
+        let quote = self
+            .pool
+            .quote_single_sided_deposit(token_a, amount_in, self.lp_supply)?;
+        let lp_supply = self
+            .lp_supply
+            .checked_add(quote.liquidity)
+            .ok_or(QuoteError::Overflow)?;
+
+        let kept = (quote.protocol_fee + quote.fund_fee) as u128;
+        if token_a {
+            self.pool.reserve_a = quote.new_reserve;
+            self.fees_a += kept;
+            self.trader_a -= amount_in as i128;
+        } else {
+            self.pool.reserve_b = quote.new_reserve;
+            self.fees_b += kept;
+            self.trader_b -= amount_in as i128;
+        }
+        self.lp_supply = lp_supply;
+        self.trader_lp += quote.liquidity;
+        Ok(())
+    }
+
+    fn settle(&mut self, a_to_b: bool, quote: SwapQuote) {
+        let kept = (quote.protocol_fee + quote.fund_fee) as u128;
+        let (paid, received) = (quote.amount_in as i128, quote.amount_out as i128);
+
+        if a_to_b {
+            self.pool.reserve_a = quote.new_reserve_in;
+            self.pool.reserve_b = quote.new_reserve_out;
+            self.fees_a += kept;
+            self.trader_a -= paid;
+            self.trader_b += received;
+        } else {
+            self.pool.reserve_b = quote.new_reserve_in;
+            self.pool.reserve_a = quote.new_reserve_out;
+            self.fees_b += kept;
+            self.trader_b -= paid;
+            self.trader_a += received;
+        }
+    }
+
+    fn remove(&mut self, lp_amount: u64) -> Result<(), QuoteError> {
+        let quote = self
+            .pool
+            .quote_remove_liquidity(lp_amount, self.lp_supply)?;
+
+        self.pool.reserve_a -= quote.amount_a;
+        self.pool.reserve_b -= quote.amount_b;
+        self.lp_supply -= lp_amount;
+        self.trader_lp -= lp_amount;
+        self.trader_a += quote.amount_a as i128;
+        self.trader_b += quote.amount_b as i128;
+        Ok(())
+    }
+}
+
+/// Full 256-bit product, as (high, low) halves, so ratios of u128
+/// values can be compared exactly.
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (middle << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// `sqrt(k) / lp_supply`, the value of one LP token, never falls:
+/// k_after / supply_after² >= k_before / supply_before².
+fn share_value_held(before: (u128, u64), after: (u128, u64)) -> bool {
+    let square = |supply: u64| supply as u128 * supply as u128;
+    mul_wide(after.0, square(before.1)) >= mul_wide(before.0, square(after.1))
+}
+
+/// `D / lp_supply` never falls, allowing for the solver's tolerance of a
+/// unit of `D` on either side.
+fn stable_share_value_held(before: (u128, u64), after: (u128, u64)) -> bool {
+    mul_wide(after.0 + 1, before.1 as u128) >= mul_wide(before.0.saturating_sub(1), after.1 as u128)
+}
+
+fn amount() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        4 => 0u64..1 << 32,
+        1 => any::<u64>(),
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (amount(), amount()).prop_map(|(amount_a, amount_b)| Op::Add { amount_a, amount_b }),
+        (any::<bool>(), amount())
+            .prop_map(|(a_to_b, amount_in)| Op::SwapExactIn { a_to_b, amount_in }),
+        (any::<bool>(), amount())
+            .prop_map(|(a_to_b, amount_out)| Op::SwapExactOut { a_to_b, amount_out }),
+        (0u16..=10_000).prop_map(|share_bps| Op::Remove { share_bps }),
+    ]
+}
+
+fn weighted_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => op(),
+        1 => (any::<bool>(), amount())
+            .prop_map(|(token_a, amount_in)| Op::AddSingle { token_a, amount_in }),
+    ]
+}
+
+fn pool() -> impl Strategy<Value = PoolState> {
+    (
+        prop::sample::select(vec![0u16, 1, 5, 30, 100, 1_000]),
+        0u16..=5_000,
+        0u16..=5_000,
+    )
+        .prop_map(|(fee_bps, protocol_fee_rate, fund_fee_rate)| {
+            PoolState::new(0, 0, fee_bps).with_fee_shares(protocol_fee_rate, fund_fee_rate)
+        })
+}
+
+fn stable_pool() -> impl Strategy<Value = (PoolState, u64)> {
+    (
+        pool(),
+        prop::sample::select(vec![MIN_AMP, 10, 100, 2_000, MAX_AMP]),
+    )
+        .prop_map(|(pool, amp)| (pool.with_curve(Curve::Stable { amp }), amp))
+}
+
+fn weighted_pool() -> impl Strategy<Value = (PoolState, u64, u64)> {
+    (
+        pool(),
+        prop::sample::select(vec![
+            MIN_WEIGHT,
+            WEIGHT_ONE / 5,
+            WEIGHT_ONE / 2,
+            WEIGHT_ONE / 10 * 8,
+        ]),
+    )
+        .prop_map(|(pool, weight_a)| {
+            let weight_b = WEIGHT_ONE - weight_a;
+            (
+                pool.with_curve(Curve::Weighted { weight_a, weight_b }),
+                weight_a,
+                weight_b,
+            )
+        })
+}
+
+proptest! {
+    #[test]
+    fn random_sequences_preserve_invariants(
+        pool in pool(),
+        seed_a in 1_000_000u64..1 << 48,
+        seed_b in 1_000_000u64..1 << 48,
+        ops in prop::collection::vec(op(), 1..32),
+    ) {
+        let mut model = Model::seed(pool, seed_a, seed_b).unwrap();
+        let passive_lp = model.lp_supply;
+
+        for op in &ops {
+            let before = (model.k(), model.lp_supply);
+            model.apply(op)?;
+            let after = (model.k(), model.lp_supply);
+
+            if matches!(op, Op::SwapExactIn { .. } | Op::SwapExactOut { .. }) {
+                prop_assert!(after.0 >= before.0, "k fell on {:?}", op);
+            }
+            prop_assert!(share_value_held(before, after), "LP value fell on {:?}", op);
+        }
+
+        // The trader exits completely; the passive LP is all that is left
+        model.apply(&Op::Remove { share_bps: 10_000 })?;
+        prop_assert_eq!(model.trader_lp, 0);
+        prop_assert_eq!(model.lp_supply, passive_lp);
+
+        // No sequence pays out in one token without costing in the other
+        let gained = model.trader_a.max(0) + model.trader_b.max(0);
+        let paid = model.trader_a.min(0) + model.trader_b.min(0);
+        prop_assert!(gained == 0 || paid < 0, "free tokens: {:?}", model);
+
+        // Every token is accounted for by reserves, kept fees or the trader
+        let vault_a = model.pool.reserve_a as i128 + model.fees_a as i128;
+        let vault_b = model.pool.reserve_b as i128 + model.fees_b as i128;
+        prop_assert_eq!(vault_a + model.trader_a, seed_a as i128);
+        prop_assert_eq!(vault_b + model.trader_b, seed_b as i128);
+    }
+
+    #[test]
+    fn stable_sequences_preserve_invariants(
+        (pool, amp) in stable_pool(),
+        seed_a in 1_000_000u64..1 << 48,
+        seed_b in 1_000_000u64..1 << 48,
+        ops in prop::collection::vec(op(), 1..32),
+    ) {
+        let mut model = Model::seed(pool, seed_a, seed_b).unwrap();
+
+        for op in &ops {
+            let before = (model.d(amp), model.lp_supply);
+            model.apply(op)?;
+            let after = (model.d(amp), model.lp_supply);
+
+            if matches!(op, Op::SwapExactIn { .. } | Op::SwapExactOut { .. }) {
+                prop_assert!(after.0 + 1 >= before.0, "D fell on {:?}", op);
+            }
+            prop_assert!(stable_share_value_held(before, after), "LP value fell on {:?}", op);
+        }
+
+        model.apply(&Op::Remove { share_bps: 10_000 })?;
+
+        let gained = model.trader_a.max(0) + model.trader_b.max(0);
+        let paid = model.trader_a.min(0) + model.trader_b.min(0);
+        prop_assert!(gained == 0 || paid < 0, "free tokens: {:?}", model);
+
+        let vault_a = model.pool.reserve_a as i128 + model.fees_a as i128;
+        let vault_b = model.pool.reserve_b as i128 + model.fees_b as i128;
+        prop_assert_eq!(vault_a + model.trader_a, seed_a as i128);
+        prop_assert_eq!(vault_b + model.trader_b, seed_b as i128);
+    }
+
+    #[test]
+    fn weighted_sequences_preserve_invariants(
+        (pool, weight_a, weight_b) in weighted_pool(),
+        seed_a in 1_000_000u64..1 << 48,
+        seed_b in 1_000_000u64..1 << 48,
+        ops in prop::collection::vec(weighted_op(), 1..32),
+    ) {
+        let mut model = Model::seed(pool, seed_a, seed_b).unwrap();
+
+        for op in &ops {
+            let before = model.ln_weighted_share(weight_a, weight_b);
+            model.apply(op)?;
+            let after = model.ln_weighted_share(weight_a, weight_b);
+
+            // Only f64's own rounding may make the share look cheaper
+            prop_assert!(after >= before - 1e-12, "LP value fell on {:?}", op);
+        }
+
+        model.apply(&Op::Remove { share_bps: 10_000 })?;
+
+        let gained = model.trader_a.max(0) + model.trader_b.max(0);
+        let paid = model.trader_a.min(0) + model.trader_b.min(0);
+        prop_assert!(gained == 0 || paid < 0, "free tokens: {:?}", model);
+
+        let vault_a = model.pool.reserve_a as i128 + model.fees_a as i128;
+        let vault_b = model.pool.reserve_b as i128 + model.fees_b as i128;
+        prop_assert_eq!(vault_a + model.trader_a, seed_a as i128);
+        prop_assert_eq!(vault_b + model.trader_b, seed_b as i128);
+    }
+
+    #[test]
+    fn round_trips_return_no_more_than_paid(
+        pool in pool(),
+        seed_a in 1_000_000u64..1 << 48,
+        seed_b in 1_000_000u64..1 << 48,
+        a_to_b in any::<bool>(),
+        amount in amount(),
+    ) {
+        let model = Model::seed(pool, seed_a, seed_b).unwrap();
+        let state = model.pool;
+
+        // Swap there and back
+        if let Ok(there) = state.quote_exact_in(a_to_b, amount) {
+            let mut moved = state;
+            if a_to_b {
+                (moved.reserve_a, moved.reserve_b) = (there.new_reserve_in, there.new_reserve_out);
+            } else {
+                (moved.reserve_b, moved.reserve_a) = (there.new_reserve_in, there.new_reserve_out);
+            }
+            if let Ok(back) = moved.quote_exact_in(!a_to_b, there.amount_out) {
+                prop_assert!(back.amount_out <= amount);
+            }
+        }
+
+        // Deposit and withdraw
+        if let Ok(deposit) = state.quote_add_liquidity(amount, amount, model.lp_supply) {
+            let grown = PoolState {
+                reserve_a: state.reserve_a + deposit.amount_a,
+                reserve_b: state.reserve_b + deposit.amount_b,
+                ..state
+            };
+            let withdraw = grown
+                .quote_remove_liquidity(deposit.liquidity, model.lp_supply + deposit.liquidity)
+                .unwrap();
+            prop_assert!(withdraw.amount_a <= deposit.amount_a);
+            prop_assert!(withdraw.amount_b <= deposit.amount_b);
+        }
+    }
+
+    // Arguments span all of u64 / u16; in debug builds any unchecked
+    // overflow panics and fails the case.
+    #[test]
+    fn curve_math_never_overflows(
+        amount in any::<u64>(),
+        other in any::<u64>(),
+        reserve_in in any::<u64>(),
+        reserve_out in any::<u64>(),
+        lp_supply in any::<u64>(),
+        fee_bps in any::<u16>(),
+    ) {
+        if let Some(amount_out) = get_amount_out(amount, reserve_in, reserve_out, fee_bps) {
+            prop_assert!(amount_out <= reserve_out);
+        }
+        if let Some(amount_in) = get_amount_in(amount, reserve_in, reserve_out, fee_bps) {
+            prop_assert!(amount < reserve_out);
+            prop_assert!(amount_in > 0 || amount == 0 || reserve_in == 0);
+        }
+        let _ = get_lp_amount(amount, other, reserve_in, reserve_out, lp_supply);
+        let _ = quote(amount, reserve_in, reserve_out);
+        if let Some((amount_a, amount_b)) = get_optimal_deposit(amount, other, reserve_in, reserve_out) {
+            prop_assert!(amount_a <= amount && amount_b <= other);
+        }
+        let _ = price_q64(reserve_in, reserve_out);
+
+        let weight_in = (fee_bps as u64 * (WEIGHT_ONE / 10_000)).clamp(MIN_WEIGHT, WEIGHT_ONE - MIN_WEIGHT);
+        let weight_out = WEIGHT_ONE - weight_in;
+        if let Some(amount_out) = get_weighted_amount_out(amount, reserve_in, weight_in, reserve_out, weight_out) {
+            prop_assert!(amount_out <= reserve_out);
+        }
+        if let Some(amount_in) = get_weighted_amount_in(amount, reserve_in, weight_in, reserve_out, weight_out) {
+            prop_assert!(amount < reserve_out);
+            prop_assert!(amount_in > 0 || amount == 0);
+        }
+        let _ = get_weighted_join_lp(amount, reserve_in, weight_in, lp_supply);
+    }
+
+    #[test]
+    fn pool_quotes_never_overflow(
+        reserve_a in any::<u64>(),
+        reserve_b in any::<u64>(),
+        fee_bps in any::<u16>(),
+        protocol_fee_rate in any::<u16>(),
+        fund_fee_rate in any::<u16>(),
+        a_to_b in any::<bool>(),
+        amount in any::<u64>(),
+        other in any::<u64>(),
+        lp_supply in any::<u64>(),
+    ) {
+        let state = PoolState::new(reserve_a, reserve_b, fee_bps)
+            .with_fee_shares(protocol_fee_rate, fund_fee_rate);
+        let k = reserve_a as u128 * reserve_b as u128;
+
+        for quote in [
+            state.quote_exact_in(a_to_b, amount),
+            state.quote_exact_out(a_to_b, amount),
+            state.settle_swap(a_to_b, amount, other),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            prop_assert!(quote.new_reserve_in as u128 * quote.new_reserve_out as u128 >= k);
+            prop_assert!(quote.protocol_fee + quote.fund_fee + quote.lp_fee == quote.total_fee);
+            prop_assert!(quote.total_fee <= quote.amount_in);
+        }
+
+        let _ = state.quote_add_liquidity(amount, other, lp_supply);
+        if let Ok(deposit) = state.quote_single_sided_deposit(a_to_b, amount, lp_supply) {
+            prop_assert!(deposit.protocol_fee + deposit.fund_fee <= deposit.total_fee);
+        }
+        if let Ok(withdraw) = state.quote_remove_liquidity(amount, lp_supply) {
+            prop_assert!(withdraw.amount_a <= reserve_a && withdraw.amount_b <= reserve_b);
+        }
+    }
+
+    #[test]
+    fn clmm_steps_never_pay_out_more_than_the_range_holds(
+        tick in MIN_TICK + 1..MAX_TICK,
+        target_tick in MIN_TICK..=MAX_TICK,
+        liquidity in 0..=MAX_LIQUIDITY,
+        amount in any::<u64>(),
+        fee_bps in 0u16..10_000,
+        exact_in in any::<bool>(),
+    ) {
+        let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+        let target = sqrt_price_at_tick(target_tick).unwrap();
+        let a_to_b = target <= sqrt_price;
+
+        if let Some(step) = compute_swap_step(sqrt_price, target, liquidity, amount, fee_bps, exact_in) {
+            // The price moves towards the target and never past it
+            if a_to_b {
+                prop_assert!(step.sqrt_price_next >= target && step.sqrt_price_next <= sqrt_price);
+            } else {
+                prop_assert!(step.sqrt_price_next <= target && step.sqrt_price_next >= sqrt_price);
+            }
+            if exact_in {
+                prop_assert!(step.amount_in as u128 + step.fee_amount as u128 <= amount as u128);
+            } else {
+                prop_assert!(step.amount_out <= amount);
+            }
+
+            // What went in covers the move; what came out does not exceed it
+            let (owed_in, held_out) = if a_to_b {
+                (
+                    amount_a_delta(step.sqrt_price_next, sqrt_price, liquidity, true),
+                    amount_b_delta(step.sqrt_price_next, sqrt_price, liquidity, false),
+                )
+            } else {
+                (
+                    amount_b_delta(sqrt_price, step.sqrt_price_next, liquidity, true),
+                    amount_a_delta(sqrt_price, step.sqrt_price_next, liquidity, false),
+                )
+            };
+            prop_assert!(step.amount_in >= owed_in.unwrap());
+            prop_assert!(step.amount_out <= held_out.unwrap());
+        }
+    }
+
+    #[test]
+    fn clmm_positions_return_no_more_than_deposited(
+        tick in -200_000i32..200_000,
+        lower in -200_000i32..200_000,
+        width in 1i32..100_000,
+        amount_a in any::<u64>(),
+        amount_b in any::<u64>(),
+    ) {
+        let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+        let sqrt_price_lower = sqrt_price_at_tick(lower).unwrap();
+        let sqrt_price_upper = sqrt_price_at_tick(lower + width).unwrap();
+
+        if let Some(liquidity) =
+            liquidity_for_amounts(sqrt_price, sqrt_price_lower, sqrt_price_upper, amount_a, amount_b)
+        {
+            let liquidity = liquidity.min(MAX_LIQUIDITY);
+            let (paid_a, paid_b) =
+                position_amounts(sqrt_price, sqrt_price_lower, sqrt_price_upper, liquidity, true).unwrap();
+            let (back_a, back_b) =
+                position_amounts(sqrt_price, sqrt_price_lower, sqrt_price_upper, liquidity, false).unwrap();
+            prop_assert!(paid_a <= amount_a && paid_b <= amount_b);
+            prop_assert!(back_a <= paid_a && back_b <= paid_b);
+        }
+    }
+}
@@ -0,0 +1,521 @@
+#![no_main]
+//! Random sequences of pool instructions, with some accounts in each
+//! swapped for look-alikes, run on the program's native build through
+//! the fixtures of its integration tests.
+//!
+//! Every CPI runs (see `src/svm.rs`), so steps really move tokens.
+//! After every step:
+//! - an instruction that moves tokens must reject a decoy in any
+//!   pool-side slot. The signer's own token accounts are swapped too,
+//!   but where they pay from or into is theirs to choose, and the token
+//!   program enforces authority over it;
+//! - each vault holds exactly its reserve plus accrued protocol and fund
+//!   fees;
+//! - every token of the pool's mints, LP included, is held by a known
+//!   account, and only the LP supply has changed;
+//! - the pool's invariant per LP token has not dropped.
+//!
+//! `create_pool` is sent with the fixture pool's addresses, which exist
+//! already (or do not match the fee tier asked for), so it must fail
+//! without moving anything.
+
+#[path = "../src/svm.rs"]
+mod svm;
+
+#[path = "../../programs/amm-capstone/tests/common/mod.rs"]
+mod common;
+
+use amm_capstone::constants::MIN_RAMP_DURATION;
+use amm_capstone::instructions::{CurveParams, UpdateConfigParams};
+use amm_capstone::state::Pool;
+use amm_capstone_client::math::{compute_d, Curve, DepositQuote, MIN_AMP, MIN_WEIGHT, WEIGHT_ONE};
+use amm_capstone_client::*;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token;
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use common::*;
+
+const MAX_STEPS: usize = 16;
+// Slack for the float logs `lp_value` compares, and for `compute_d`
+// landing one unit either side of the exact invariant
+const LP_VALUE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Arbitrary, Debug)]
+struct Scenario {
+    // Leaves the pool as `create_pool` does instead of seeding it
+    empty: bool,
+    // Prices the pool on StableSwap with this amplification (clamped)
+    stable_amp: Option<u16>,
+    // Otherwise on a weighted curve, with this weight for A in percent
+    // (clamped to 1..=99)
+    weight_a_percent: Option<u8>,
+    // Which then moves to this weight (clamped the same way) over an
+    // hour, as a liquidity bootstrapping pool opening `sale_delay`
+    // seconds in
+    end_weight_a_percent: Option<u8>,
+    sale_delay: u16,
+    // Seeded reserves, in thousands
+    reserve_a: u32,
+    reserve_b: u32,
+    steps: Vec<Step>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Step {
+    action: Action,
+    spoofs: Vec<Spoof>,
+}
+
+/// Replaces the account at `slot` (modulo the account count) with one of
+/// the decoys.
+#[derive(Arbitrary, Debug)]
+struct Spoof {
+    slot: u8,
+    decoy: u8,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Action {
+    CreatePool {
+        fee_tier_index: u8,
+        stable_amp: Option<u64>,
+        // Weight for A, with B taking the rest of `WEIGHT_ONE`
+        weight_a: Option<u64>,
+    },
+    // Slippage bounds are in bps of the harness's own quote, so most
+    // steps pass them and go on to the account checks that matter
+    AddLiquidity {
+        amount_a_desired: u64,
+        amount_b_desired: u64,
+        min_bps: u16,
+    },
+    AddLiquiditySingle {
+        token_a: bool,
+        amount_in: u64,
+        min_bps: u16,
+    },
+    Swap {
+        a_to_b: bool,
+        amount_in: u64,
+        min_out_bps: u16,
+    },
+    SwapBaseOut {
+        a_to_b: bool,
+        amount_out: u64,
+        max_in_bps: u16,
+    },
+    RemoveLiquidity {
+        // Of the user's LP balance
+        share_bps: u16,
+    },
+    CollectFees,
+    CollectFundFees,
+    SetPoolStatus {
+        status: u8,
+    },
+    UpdateFeeShares {
+        protocol_fee_rate: u16,
+        fund_fee_rate: u16,
+    },
+    Observe {
+        seconds_ago: u16,
+    },
+    // Ends `extra_hours` past the shortest ramp allowed
+    RampAmp {
+        target_amp: u64,
+        extra_hours: u8,
+    },
+    StopRampAmp,
+    // Starts `start_in` seconds from now and lasts `duration` seconds
+    UpdateWeightsGradually {
+        start_in: u16,
+        duration: u16,
+        end_weight_a: u64,
+    },
+    Warp {
+        seconds: u16,
+    },
+}
+
+impl Action {
+    /// Whether the instruction can transfer tokens out of (or into) the
+    /// pool, so must never accept a decoy.
+    fn moves_tokens(&self) -> bool {
+        matches!(
+            self,
+            Action::AddLiquidity { .. }
+                | Action::AddLiquiditySingle { .. }
+                | Action::Swap { .. }
+                | Action::SwapBaseOut { .. }
+                | Action::RemoveLiquidity { .. }
+                | Action::CollectFees
+                | Action::CollectFundFees
+        )
+    }
+}
+
+struct World {
+    env: Env,
+    admin_a: Pubkey,
+    admin_b: Pubkey,
+    decoys: Vec<Pubkey>,
+    // Token accounts the signers pass for themselves
+    own_accounts: Vec<Pubkey>,
+    // Every account that can hold each mint
+    holders_a: Vec<Pubkey>,
+    holders_b: Vec<Pubkey>,
+    holders_lp: Vec<Pubkey>,
+}
+
+impl World {
+    fn new(scenario: &Scenario) -> Self {
+        let mut env = Env::new();
+        if let Some(amp) = scenario.stable_amp {
+            env.make_stable((amp as u64).max(MIN_AMP));
+        } else if let Some(percent) = scenario.weight_a_percent {
+            let weight_a = percent.clamp(1, 99) as u64 * MIN_WEIGHT;
+            if let Some(end_percent) = scenario.end_weight_a_percent {
+                let end_weight_a = end_percent.clamp(1, 99) as u64 * MIN_WEIGHT;
+                let start_ts = env.svm.clock().unix_timestamp + scenario.sale_delay as i64;
+                env.make_bootstrapping(weight_a, end_weight_a, start_ts, start_ts + 3_600);
+            } else {
+                env.make_weighted(weight_a, WEIGHT_ONE - weight_a);
+            }
+        }
+        if !scenario.empty {
+            // At least 2_000 each, so the first deposit clears
+            // MINIMUM_LIQUIDITY
+            let reserve = |thousands: u32| (thousands as u64 + 2) * 1_000;
+            env.seed_liquidity(reserve(scenario.reserve_a), reserve(scenario.reserve_b));
+        }
+
+        let keys = env.keys;
+        let admin = env.admin;
+        let stranger = Pubkey::new_unique();
+        let admin_a = env.token_account(&keys.token_a_mint, &admin);
+        let admin_b = env.token_account(&keys.token_b_mint, &admin);
+
+        // Right type, wrong address or contents, as in the account tests
+        let pool_other_config = Pubkey::new_unique();
+        let pool = Pool {
+            amm_config: Pubkey::new_unique(),
+            ..env.pool()
+        };
+        write_account(&mut env.svm, &pool_other_config, 8 + Pool::LEN, &pool);
+
+        let vault_a_copy = env.token_account(&keys.token_a_mint, &keys.vault_authority);
+        let vault_b_copy = env.token_account(&keys.token_b_mint, &keys.vault_authority);
+        let locked_lp_copy = env.token_account(&keys.lp_mint, &keys.locked_lp_authority);
+        let stranger_a = env.token_account(&keys.token_a_mint, &stranger);
+        let stranger_b = env.token_account(&keys.token_b_mint, &stranger);
+        let stranger_lp = env.token_account(&keys.lp_mint, &stranger);
+
+        let decoys = vec![
+            Pubkey::new_unique(),
+            env.clone_with_owner(&keys.pool, &amm_capstone::ID),
+            env.clone_with_owner(&keys.pool, &token::ID),
+            pool_other_config,
+            env.clone_with_owner(&find_amm_config_address().0, &amm_capstone::ID),
+            env.clone_with_owner(&find_treasury_address().0, &amm_capstone::ID),
+            env.clone_with_owner(&keys.observation_state, &amm_capstone::ID),
+            create_mint(&mut env.svm, &admin, 6, MintKind::Classic),
+            vault_a_copy,
+            vault_b_copy,
+            locked_lp_copy,
+            stranger_a,
+            stranger_b,
+            stranger_lp,
+        ];
+
+        let user = env.user_accounts;
+        Self {
+            holders_a: vec![user.token_a, keys.vault_a, admin_a, vault_a_copy, stranger_a],
+            holders_b: vec![user.token_b, keys.vault_b, admin_b, vault_b_copy, stranger_b],
+            holders_lp: vec![user.lp, keys.locked_lp, locked_lp_copy, stranger_lp],
+            own_accounts: vec![user.token_a, user.token_b, user.lp, admin_a, admin_b],
+            env,
+            admin_a,
+            admin_b,
+            decoys,
+        }
+    }
+
+    /// The instruction for `action` with valid accounts, and its signer.
+    fn instruction(&mut self, action: &Action) -> Option<(Instruction, Pubkey)> {
+        let env = &mut self.env;
+        let (admin, user, keys) = (env.admin, env.user, env.keys);
+
+        let ix = match *action {
+            Action::CreatePool {
+                fee_tier_index,
+                stable_amp,
+                weight_a,
+            } => {
+                let curve = match (stable_amp, weight_a) {
+                    (Some(amp), _) => CurveParams::Stable { amp },
+                    (None, Some(weight_a)) => CurveParams::Weighted {
+                        weight_a,
+                        weight_b: WEIGHT_ONE.wrapping_sub(weight_a),
+                    },
+                    (None, None) => CurveParams::ConstantProduct,
+                };
+                // An index past the tiers is left for the program to reject
+                let fee_bps = env
+                    .config()
+                    .fee_tiers
+                    .get(fee_tier_index as usize)
+                    .copied()
+                    .unwrap_or_default();
+                let keys = PoolKeys::new(
+                    keys.token_a_mint,
+                    keys.token_b_mint,
+                    keys.token_a_program,
+                    keys.token_b_program,
+                    fee_bps,
+                    curve.curve_type(),
+                );
+                (create_pool(&admin, &keys, fee_tier_index, curve), admin)
+            }
+            Action::AddLiquidity {
+                amount_a_desired,
+                amount_b_desired,
+                min_bps,
+            } => {
+                let lp_supply = mint_supply(&env.svm, &keys.lp_mint);
+                let quote = env
+                    .quote_state()
+                    .quote_add_liquidity(amount_a_desired, amount_b_desired, lp_supply)
+                    .unwrap_or(DepositQuote {
+                        amount_a: 0,
+                        amount_b: 0,
+                        liquidity: 0,
+                        locked_liquidity: 0,
+                    });
+                let ix = env.add_liquidity_ix(
+                    amount_a_desired,
+                    amount_b_desired,
+                    scale(quote.amount_a, min_bps),
+                    scale(quote.amount_b, min_bps),
+                    scale(quote.liquidity, min_bps),
+                );
+                (ix, user)
+            }
+            Action::AddLiquiditySingle {
+                token_a,
+                amount_in,
+                min_bps,
+            } => {
+                let lp_supply = mint_supply(&env.svm, &keys.lp_mint);
+                let quoted = env
+                    .quote_state()
+                    .quote_single_sided_deposit(token_a, amount_in, lp_supply)
+                    .map_or(0, |quote| quote.liquidity);
+                (env.add_liquidity_single_ix(token_a, amount_in, scale(quoted, min_bps)), user)
+            }
+            Action::Swap {
+                a_to_b,
+                amount_in,
+                min_out_bps,
+            } => {
+                let quoted = env
+                    .quote_state()
+                    .quote_exact_in(a_to_b, amount_in)
+                    .map_or(0, |quote| quote.amount_out);
+                (env.swap_ix(a_to_b, amount_in, scale(quoted, min_out_bps)), user)
+            }
+            Action::SwapBaseOut {
+                a_to_b,
+                amount_out,
+                max_in_bps,
+            } => {
+                let quoted = env
+                    .quote_state()
+                    .quote_exact_out(a_to_b, amount_out)
+                    .map_or(u64::MAX, |quote| quote.amount_in);
+                let ix = env.swap_base_out_ix(a_to_b, scale(quoted, max_in_bps), amount_out);
+                (ix, user)
+            }
+            Action::RemoveLiquidity { share_bps } => {
+                let lp_amount = scale(env.balance(&env.user_accounts.lp), share_bps);
+                (env.remove_liquidity_ix(lp_amount), user)
+            }
+            Action::CollectFees => (
+                collect_fees(&admin, &keys, &self.admin_a, &self.admin_b),
+                admin,
+            ),
+            Action::CollectFundFees => (
+                collect_fund_fees(&admin, &keys, &self.admin_a, &self.admin_b),
+                admin,
+            ),
+            Action::SetPoolStatus { status } => (set_pool_status(&admin, &keys.pool, status), admin),
+            Action::UpdateFeeShares {
+                protocol_fee_rate,
+                fund_fee_rate,
+            } => {
+                let params = UpdateConfigParams {
+                    new_admin: None,
+                    fee_tiers: None,
+                    protocol_fee_rate: Some(protocol_fee_rate),
+                    fund_fee_rate: Some(fund_fee_rate),
+                    create_pool_fee: None,
+                };
+                (update_config(&admin, params), admin)
+            }
+            Action::Observe { seconds_ago } => (observe(&keys, seconds_ago as u32), admin),
+            Action::RampAmp {
+                target_amp,
+                extra_hours,
+            } => {
+                let stop_ts = env.svm.clock().unix_timestamp
+                    + MIN_RAMP_DURATION
+                    + extra_hours as i64 * 3_600;
+                (ramp_amp(&admin, &keys.pool, target_amp, stop_ts), admin)
+            }
+            Action::StopRampAmp => (stop_ramp_amp(&admin, &keys.pool), admin),
+            Action::UpdateWeightsGradually {
+                start_in,
+                duration,
+                end_weight_a,
+            } => {
+                let start_ts = env.svm.clock().unix_timestamp + start_in as i64;
+                let ix = update_weights_gradually(
+                    &admin,
+                    &keys.pool,
+                    start_ts,
+                    start_ts + duration as i64,
+                    end_weight_a,
+                    WEIGHT_ONE.wrapping_sub(end_weight_a),
+                );
+                (ix, admin)
+            }
+            Action::Warp { seconds } => {
+                env.svm.warp(seconds as i64);
+                return None;
+            }
+        };
+
+        Some(ix)
+    }
+
+    /// Applies `spoofs`, returning whether a pool-side account was
+    /// replaced.
+    fn spoof(&self, ix: &mut Instruction, spoofs: &[Spoof]) -> bool {
+        if ix.accounts.is_empty() {
+            return false;
+        }
+
+        let original: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        let mut spoofed = false;
+        for spoof in spoofs {
+            let slot = spoof.slot as usize % ix.accounts.len();
+            spoofed |= !self.own_accounts.contains(&original[slot]);
+            ix.accounts[slot].pubkey = self.decoys[spoof.decoy as usize % self.decoys.len()];
+        }
+
+        spoofed
+    }
+
+    fn supplies(&self) -> [u64; 3] {
+        let keys = self.env.keys;
+        [keys.token_a_mint, keys.token_b_mint, keys.lp_mint]
+            .map(|mint| mint_supply(&self.env.svm, &mint))
+    }
+
+    /// `supplies` holds the pool's token supplies from the start.
+    fn check_balances(&self, supplies: [u64; 2]) {
+        let env = &self.env;
+        let keys = env.keys;
+        let pool = env.pool();
+
+        assert_eq!(
+            env.balance(&keys.vault_a) as u128,
+            pool.reserve_a as u128 + pool.protocol_fees_a as u128 + pool.fund_fees_a as u128,
+            "vault A does not match reserve A plus fees"
+        );
+        assert_eq!(
+            env.balance(&keys.vault_b) as u128,
+            pool.reserve_b as u128 + pool.protocol_fees_b as u128 + pool.fund_fees_b as u128,
+            "vault B does not match reserve B plus fees"
+        );
+
+        let current = self.supplies();
+        assert_eq!(current[..2], supplies, "a token supply changed");
+        for (holders, supply) in [&self.holders_a, &self.holders_b, &self.holders_lp]
+            .into_iter()
+            .zip(current)
+        {
+            let held: u128 = holders.iter().map(|holder| env.balance(holder) as u128).sum();
+            assert_eq!(held, supply as u128, "tokens held outside known accounts");
+        }
+    }
+}
+
+/// Log of the pool's invariant under `curve` per LP token, or `None`
+/// while there is nothing to value. Swap fees stay in the reserves and
+/// deposits and withdrawals round in the pool's favour, so no step may
+/// lower it.
+fn lp_value(curve: Curve, pool: &Pool, lp_supply: u64) -> Option<f64> {
+    if lp_supply == 0 || pool.reserve_a == 0 || pool.reserve_b == 0 {
+        return None;
+    }
+
+    let (a, b) = ((pool.reserve_a as f64).ln(), (pool.reserve_b as f64).ln());
+    let invariant = match curve {
+        Curve::ConstantProduct => (a + b) / 2.0,
+        Curve::Weighted { weight_a, weight_b } => {
+            (weight_a as f64 * a + weight_b as f64 * b) / WEIGHT_ONE as f64
+        }
+        Curve::Stable { amp } => (compute_d(pool.reserve_a, pool.reserve_b, amp)? as f64).ln(),
+    };
+    Some(invariant - (lp_supply as f64).ln())
+}
+
+/// `bps` basis points of `amount`, saturating.
+fn scale(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / 10_000).min(u64::MAX as u128) as u64
+}
+
+fuzz_target!(|scenario: Scenario| {
+    let mut world = World::new(&scenario);
+    let [supply_a, supply_b, _] = world.supplies();
+    let supplies = [supply_a, supply_b];
+    world.check_balances(supplies);
+
+    for step in scenario.steps.iter().take(MAX_STEPS) {
+        let Some((mut ix, signer)) = world.instruction(&step.action) else {
+            continue;
+        };
+        let spoofed = world.spoof(&mut ix, &step.spoofs);
+
+        // Valued on the curve as it stands before the step, which may
+        // change it
+        let env = &world.env;
+        let curve = env.quote_state().curve;
+        let before = lp_value(curve, &env.pool(), mint_supply(&env.svm, &env.keys.lp_mint));
+
+        let result = world.env.send(&[ix], &[signer]);
+
+        if spoofed && step.action.moves_tokens() {
+            assert!(
+                result.is_err(),
+                "{:?} accepted decoys {:?}",
+                step.action,
+                step.spoofs
+            );
+        }
+        world.check_balances(supplies);
+
+        let env = &world.env;
+        let after = lp_value(curve, &env.pool(), mint_supply(&env.svm, &env.keys.lp_mint));
+        if let (Some(before), Some(after)) = (before, after) {
+            assert!(
+                after >= before - LP_VALUE_TOLERANCE,
+                "{:?} lowered the value of an LP token: {before} -> {after}",
+                step.action
+            );
+        }
+    }
+});
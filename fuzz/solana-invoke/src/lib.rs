@@ -0,0 +1,11 @@
+//! Patched in for `solana-invoke` 0.4, which Anchor issues every CPI
+//! through.
+//!
+//! On-chain, `solana_program::program` makes the same
+//! `sol_invoke_signed_rust` syscall after the same borrow checks, so the
+//! program behaves exactly as with the published crate. Off-chain the
+//! published crate panics, while these route to
+//! `program_stubs::sol_invoke_signed`, letting the fuzz target's native
+//! runtime execute CPIs.
+
+pub use solana_program::program::{invoke, invoke_signed, invoke_signed_unchecked, invoke_unchecked};
@@ -0,0 +1,648 @@
+//! A minimal in-process runtime for the fuzz target.
+//!
+//! Runs the program's native build (`amm_capstone::entry`) over accounts
+//! laid out the way the loader passes them, so coverage reaches the
+//! program and a run takes microseconds. It has the same interface as
+//! the LiteSVM runtime the integration tests run the SBF build on
+//! (`programs/amm-capstone/tests/common/svm.rs`), so both share the
+//! fixtures. Sysvars are served through `program_stubs`; SPL Token and
+//! Token-2022 run their real processors, and the system program is
+//! reimplemented for the instructions Anchor issues. Return data never
+//! leaves the program off-chain, so there is no `view`.
+//!
+//! CPIs reach `SyscallStubs::sol_invoke_signed` through the patched
+//! `solana-invoke` (see `fuzz/solana-invoke`). They run in place, on
+//! the caller's own `AccountInfo`s, with signer seeds checked against the
+//! caller's program id. After each program returns, and before it makes
+//! a CPI, its writes are held to the loader's rules: only an account's
+//! owner may change its data or owner or debit it, only writable
+//! accounts change, and lamports are conserved. A program breaking them
+//! panics the run, as it is a bug the real runtime would fail the
+//! transaction on. Signatures are not verified; a signer is just a listed
+//! pubkey.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use anchor_lang::prelude::{AccountInfo, Clock, ProgramError, Pubkey, Rent};
+use anchor_lang::solana_program::entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{bpf_loader_upgradeable, system_program, sysvar};
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use solana_sysvar::program_stubs::{set_syscall_stubs, SyscallStubs};
+
+// Offsets within one account's buffer. Data starts 8 bytes past a
+// 16-byte boundary, like the loader's, so zero-copy accounts (u128
+// fields after the discriminator) are aligned on the host too. It is
+// followed by `MAX_PERMITTED_DATA_INCREASE` bytes to grow into.
+const ORIGINAL_DATA_LEN_OFFSET: usize = 4;
+const KEY_OFFSET: usize = 8;
+const OWNER_OFFSET: usize = 40;
+const LAMPORTS_OFFSET: usize = 72;
+const DATA_LEN_OFFSET: usize = 80;
+const DATA_OFFSET: usize = 88;
+
+static SVM_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountData {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+#[derive(Default)]
+struct RuntimeState {
+    clock: Clock,
+    rent: Rent,
+    // One per program on the call stack, innermost last
+    frames: Vec<Frame>,
+}
+
+struct Frame {
+    program_id: Pubkey,
+    // The accounts as of the program's entry or its last CPI
+    accounts: Vec<Snapshot>,
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    key: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    is_writable: bool,
+}
+
+pub struct Svm {
+    accounts: HashMap<Pubkey, AccountData>,
+    state: Arc<Mutex<RuntimeState>>,
+}
+
+impl Svm {
+    pub fn new() -> Self {
+        let state = Arc::new(Mutex::new(RuntimeState {
+            clock: Clock {
+                slot: 1,
+                unix_timestamp: 1_700_000_000,
+                ..Clock::default()
+            },
+            ..RuntimeState::default()
+        }));
+
+        let mut svm = Self {
+            accounts: HashMap::new(),
+            state,
+        };
+
+        for program in [
+            amm_capstone::ID,
+            spl_token::ID,
+            spl_token_2022::ID,
+            system_program::ID,
+        ] {
+            svm.set_account(
+                program,
+                AccountData {
+                    lamports: 1,
+                    data: vec![],
+                    owner: bpf_loader_upgradeable::ID,
+                    executable: true,
+                },
+            );
+        }
+
+        // bincode `UpgradeableLoaderState::Program { programdata_address }`
+        let mut program_data = 2u32.to_le_bytes().to_vec();
+        program_data.extend_from_slice(program_data_address().as_ref());
+        svm.accounts.get_mut(&amm_capstone::ID).unwrap().data = program_data;
+        svm.set_upgrade_authority(None);
+
+        // bincode `Rent`: lamports_per_byte_year, exemption_threshold,
+        // burn_percent
+        let rent = Rent::default();
+        let mut rent_data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+        rent_data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        rent_data.push(rent.burn_percent);
+        svm.set_account(
+            sysvar::rent::ID,
+            AccountData {
+                lamports: 1,
+                data: rent_data,
+                owner: sysvar::ID,
+                executable: false,
+            },
+        );
+
+        svm
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Option<AccountData> {
+        self.accounts.get(address).cloned()
+    }
+
+    pub fn set_account(&mut self, address: Pubkey, account: AccountData) {
+        self.accounts.insert(address, account);
+    }
+
+    /// Rewrites the program's `ProgramData` with `authority` as its
+    /// upgrade authority, `None` making it immutable.
+    pub fn set_upgrade_authority(&mut self, authority: Option<&Pubkey>) {
+        // bincode `UpgradeableLoaderState::ProgramData { slot,
+        // upgrade_authority_address }`, with no program bytes after it
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        match authority {
+            Some(authority) => {
+                data.push(1);
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.extend_from_slice(&[0; 33]),
+        }
+        self.set_account(
+            program_data_address(),
+            AccountData {
+                lamports: 1,
+                data,
+                owner: bpf_loader_upgradeable::ID,
+                executable: false,
+            },
+        );
+    }
+
+    pub fn airdrop(&mut self, address: &Pubkey, lamports: u64) {
+        self.accounts.entry(*address).or_default().lamports += lamports;
+    }
+
+    /// Decodes an Anchor account, checking its discriminator.
+    pub fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self.account(address).expect("account does not exist");
+        T::try_deserialize(&mut &account.data[..]).expect("account does not decode")
+    }
+
+    pub fn minimum_balance(&self, space: usize) -> u64 {
+        self.state.lock().unwrap().rent.minimum_balance(space)
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.state.lock().unwrap().clock.clone()
+    }
+
+    /// Moves time forward by `seconds`, one slot per second.
+    pub fn warp(&mut self, seconds: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.clock.unix_timestamp += seconds;
+        state.clock.slot += seconds.max(1) as u64;
+    }
+
+    /// Runs the instructions atomically, as one transaction signed by
+    /// `signers`.
+    pub fn send(&mut self, instructions: &[Instruction], signers: &[Pubkey]) -> ProgramResult {
+        // Syscall stubs are process-global, so runtimes take turns
+        let _lock = SVM_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        set_syscall_stubs(Box::new(Stubs {
+            state: self.state.clone(),
+        }));
+
+        let snapshot = self.accounts.clone();
+
+        for instruction in instructions {
+            if let Err(error) = self.process(instruction, signers) {
+                self.accounts = snapshot;
+                return Err(error);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process(&mut self, instruction: &Instruction, signers: &[Pubkey]) -> ProgramResult {
+        // One buffer per distinct account, shared by duplicate metas
+        let mut keys: Vec<Pubkey> = Vec::new();
+        let mut flags: Vec<(bool, bool)> = Vec::new();
+
+        for meta in &instruction.accounts {
+            if meta.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            match keys.iter().position(|key| *key == meta.pubkey) {
+                Some(index) => {
+                    flags[index].0 |= meta.is_signer;
+                    flags[index].1 |= meta.is_writable;
+                }
+                None => {
+                    keys.push(meta.pubkey);
+                    flags.push((meta.is_signer, meta.is_writable));
+                }
+            }
+        }
+
+        if !self
+            .account(&instruction.program_id)
+            .is_some_and(|program| program.executable)
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let executable: Vec<bool> = keys
+            .iter()
+            .map(|key| self.account(key).is_some_and(|account| account.executable))
+            .collect();
+        let mut buffers: Vec<Vec<u128>> = keys
+            .iter()
+            .map(|key| serialize_account(key, &self.accounts.get(key).cloned().unwrap_or_default()))
+            .collect();
+
+        let result = {
+            let unique: Vec<AccountInfo> = buffers
+                .iter_mut()
+                .zip(flags.iter().zip(&executable))
+                .map(|(buffer, ((is_signer, is_writable), executable))| {
+                    // SAFETY: the infos are dropped at the end of this block,
+                    // before the buffers
+                    unsafe { account_info(buffer, *is_signer, *is_writable, *executable) }
+                })
+                .collect();
+
+            let infos: Vec<AccountInfo> = instruction
+                .accounts
+                .iter()
+                .map(|meta| {
+                    let index = keys.iter().position(|key| *key == meta.pubkey).unwrap();
+                    unique[index].clone()
+                })
+                .collect();
+
+            execute(&self.state, &instruction.program_id, &infos, &instruction.data)
+        };
+
+        if result.is_ok() {
+            for ((key, buffer), executable) in keys.iter().zip(&buffers).zip(executable) {
+                let account = AccountData {
+                    executable,
+                    ..deserialize_account(buffer)
+                };
+                self.accounts.insert(*key, account);
+            }
+        }
+
+        result
+    }
+}
+
+fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[amm_capstone::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+fn serialize_account(key: &Pubkey, account: &AccountData) -> Vec<u128> {
+    let len = DATA_OFFSET + account.data.len() + MAX_PERMITTED_DATA_INCREASE;
+    let mut buffer = vec![0u128; len.div_ceil(16)];
+    // SAFETY: u8 has no alignment or validity requirements
+    let bytes = unsafe {
+        std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 16)
+    };
+
+    bytes[ORIGINAL_DATA_LEN_OFFSET..KEY_OFFSET]
+        .copy_from_slice(&(account.data.len() as u32).to_le_bytes());
+    bytes[KEY_OFFSET..OWNER_OFFSET].copy_from_slice(key.as_ref());
+    bytes[OWNER_OFFSET..LAMPORTS_OFFSET].copy_from_slice(account.owner.as_ref());
+    bytes[LAMPORTS_OFFSET..DATA_LEN_OFFSET].copy_from_slice(&account.lamports.to_le_bytes());
+    bytes[DATA_LEN_OFFSET..DATA_OFFSET]
+        .copy_from_slice(&(account.data.len() as u64).to_le_bytes());
+    bytes[DATA_OFFSET..DATA_OFFSET + account.data.len()].copy_from_slice(&account.data);
+
+    buffer
+}
+
+fn deserialize_account(buffer: &[u128]) -> AccountData {
+    // SAFETY: reading plain bytes out of a u128 buffer
+    let bytes =
+        unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * 16) };
+    let read_u64 =
+        |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+    let data_len = read_u64(DATA_LEN_OFFSET) as usize;
+
+    AccountData {
+        lamports: read_u64(LAMPORTS_OFFSET),
+        data: bytes[DATA_OFFSET..DATA_OFFSET + data_len].to_vec(),
+        owner: Pubkey::try_from(&bytes[OWNER_OFFSET..LAMPORTS_OFFSET]).unwrap(),
+        executable: false,
+    }
+}
+
+/// # Safety
+/// The returned info borrows `buffer` with an unbounded lifetime; the
+/// caller must drop it first.
+unsafe fn account_info<'a>(
+    buffer: &mut [u128],
+    is_signer: bool,
+    is_writable: bool,
+    executable: bool,
+) -> AccountInfo<'a> {
+    let base = buffer.as_mut_ptr() as *mut u8;
+    let data_len = (base.add(DATA_LEN_OFFSET) as *const u64).read() as usize;
+
+    AccountInfo::new(
+        &*(base.add(KEY_OFFSET) as *const Pubkey),
+        is_signer,
+        is_writable,
+        &mut *(base.add(LAMPORTS_OFFSET) as *mut u64),
+        std::slice::from_raw_parts_mut(base.add(DATA_OFFSET), data_len),
+        &*(base.add(OWNER_OFFSET) as *const Pubkey),
+        executable,
+        0,
+    )
+}
+
+/// Runs one program, as the top-level instruction or a CPI, and checks
+/// its writes on success.
+fn execute(
+    state: &Mutex<RuntimeState>,
+    program_id: &Pubkey,
+    infos: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let frame = Frame {
+        program_id: *program_id,
+        accounts: Snapshot::take(infos),
+    };
+    state.lock().unwrap().frames.push(frame);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| dispatch(program_id, infos, data)));
+    let frame = state.lock().unwrap().frames.pop().unwrap();
+    let result = result.unwrap_or_else(|payload| panic::resume_unwind(payload));
+
+    if result.is_ok() {
+        verify(program_id, &frame.accounts, &Snapshot::take(infos), true);
+    }
+    result
+}
+
+impl Snapshot {
+    // One per distinct account, writable if any of its infos is
+    fn take(infos: &[AccountInfo]) -> Vec<Snapshot> {
+        let mut snapshots: Vec<Snapshot> = Vec::new();
+        for info in infos {
+            match snapshots.iter_mut().find(|snapshot| snapshot.key == *info.key) {
+                Some(snapshot) => snapshot.is_writable |= info.is_writable,
+                None => snapshots.push(Snapshot {
+                    key: *info.key,
+                    lamports: info.lamports(),
+                    data: info.try_borrow_data().expect("account is borrowed").to_vec(),
+                    owner: *info.owner,
+                    is_writable: info.is_writable,
+                }),
+            }
+        }
+        snapshots
+    }
+}
+
+/// Panics unless every change from `before` to `after` is one
+/// `program_id` may make. Lamports are only balanced over a whole
+/// instruction, since a program may move them between accounts it does
+/// and does not pass to a CPI.
+fn verify(program_id: &Pubkey, before: &[Snapshot], after: &[Snapshot], balanced: bool) {
+    let (mut lamports_before, mut lamports_after) = (0u128, 0u128);
+
+    for post in after {
+        let Some(pre) = before.iter().find(|pre| pre.key == post.key) else {
+            continue;
+        };
+        lamports_before += pre.lamports as u128;
+        lamports_after += post.lamports as u128;
+
+        let data_changed = pre.data != post.data;
+        let owner_changed = pre.owner != post.owner;
+        if !data_changed && !owner_changed && pre.lamports == post.lamports {
+            continue;
+        }
+        let key = post.key;
+        assert!(pre.is_writable, "{program_id} modified read-only account {key}");
+        if data_changed || owner_changed || post.lamports < pre.lamports {
+            assert_eq!(
+                pre.owner, *program_id,
+                "{program_id} modified or debited {key}, owned by {}",
+                pre.owner
+            );
+        }
+        if owner_changed {
+            assert!(
+                post.data.iter().all(|byte| *byte == 0),
+                "{program_id} reassigned {key} with data in it"
+            );
+        }
+    }
+
+    if balanced {
+        assert_eq!(lamports_before, lamports_after, "{program_id} created or destroyed lamports");
+    }
+}
+
+fn dispatch<'a>(program_id: &Pubkey, infos: &[AccountInfo<'a>], data: &[u8]) -> ProgramResult {
+    // SAFETY: Anchor's entry wants the slice to live as long as the infos;
+    // the caller drops both together.
+    let infos: &'a [AccountInfo<'a>] = unsafe { &*(infos as *const [AccountInfo<'a>]) };
+
+    if *program_id == amm_capstone::ID {
+        amm_capstone::entry(program_id, infos, data)
+    } else if *program_id == spl_token::ID {
+        spl_token::processor::Processor::process(program_id, infos, data)
+    } else if *program_id == spl_token_2022::ID {
+        spl_token_2022::processor::Processor::process(program_id, infos, data)
+    } else if *program_id == system_program::ID {
+        system::process(infos, data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+struct Stubs {
+    state: Arc<Mutex<RuntimeState>>,
+}
+
+impl Stubs {
+    /// Checks the running program's writes to `infos` since it started
+    /// or last made a CPI, then takes them as its new starting point.
+    fn sync_caller(&self, infos: &[AccountInfo], check: bool) {
+        let after = Snapshot::take(infos);
+        let (program_id, before) = {
+            let state = self.state.lock().unwrap();
+            let frame = state.frames.last().expect("CPI outside a program");
+            (frame.program_id, frame.accounts.clone())
+        };
+        if check {
+            verify(&program_id, &before, &after, false);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let frame = state.frames.last_mut().unwrap();
+        for post in after {
+            if let Some(pre) = frame.accounts.iter_mut().find(|pre| pre.key == post.key) {
+                *pre = Snapshot {
+                    is_writable: pre.is_writable,
+                    ..post
+                };
+            }
+        }
+    }
+}
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = self.state.lock().unwrap().frames.last().map(|frame| frame.program_id);
+        let caller = caller.expect("CPI outside a program");
+        let pdas = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        // The callee sees each account with the privileges its meta asks
+        // for, which the caller must hold or sign for with seeds
+        let mut infos = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let info = account_infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if meta.is_signer && !info.is_signer && !pdas.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            infos.push(AccountInfo {
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+                ..info.clone()
+            });
+        }
+
+        self.sync_caller(account_infos, true);
+        let result = execute(&self.state, &instruction.program_id, &infos, &instruction.data);
+        self.sync_caller(account_infos, false);
+        result
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: the sysvar getter passes a pointer to a `Clock`
+        unsafe { *(var_addr as *mut Clock) = self.state.lock().unwrap().clock.clone() };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: the sysvar getter passes a pointer to a `Rent`
+        unsafe { *(var_addr as *mut Rent) = self.state.lock().unwrap().rent.clone() };
+        0
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        self.state.lock().unwrap().frames.len() as u64
+    }
+}
+
+/// The system program instructions Anchor's `init` and lamport transfers
+/// use, with the real program's checks and error codes.
+mod system {
+    use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+    use anchor_lang::solana_program::entrypoint::ProgramResult;
+    use anchor_lang::solana_program::system_program;
+
+    // `SystemError` codes
+    const ACCOUNT_ALREADY_IN_USE: u32 = 0;
+    const RESULT_WITH_NEGATIVE_LAMPORTS: u32 = 1;
+    const INVALID_ACCOUNT_DATA_LENGTH: u32 = 3;
+
+    const MAX_PERMITTED_DATA_LENGTH: u64 = 10 * 1024 * 1024;
+
+    pub fn process(infos: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let u64_at = |offset: usize| -> Result<u64, ProgramError> {
+            data.get(offset..offset + 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or(ProgramError::InvalidInstructionData)
+        };
+        let pubkey_at = |offset: usize| -> Result<Pubkey, ProgramError> {
+            data.get(offset..offset + 32)
+                .map(|bytes| Pubkey::try_from(bytes).unwrap())
+                .ok_or(ProgramError::InvalidInstructionData)
+        };
+        let account = |index: usize| infos.get(index).ok_or(ProgramError::NotEnoughAccountKeys);
+
+        // bincode `SystemInstruction`: a u32 variant, then its fields
+        let variant = data
+            .get(..4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        match variant {
+            // CreateAccount { lamports, space, owner }
+            0 => {
+                let (from, to) = (account(0)?, account(1)?);
+                if to.lamports() > 0 {
+                    return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+                }
+                allocate(to, u64_at(12)?)?;
+                assign(to, &pubkey_at(20)?)?;
+                transfer(from, to, u64_at(4)?)
+            }
+            // Assign { owner }
+            1 => assign(account(0)?, &pubkey_at(4)?),
+            // Transfer { lamports }
+            2 => transfer(account(0)?, account(1)?, u64_at(4)?),
+            // Allocate { space }
+            8 => allocate(account(0)?, u64_at(4)?),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+        if !account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if !account.data_is_empty() || *account.owner != system_program::ID {
+            return Err(ProgramError::Custom(ACCOUNT_ALREADY_IN_USE));
+        }
+        if space > MAX_PERMITTED_DATA_LENGTH {
+            return Err(ProgramError::Custom(INVALID_ACCOUNT_DATA_LENGTH));
+        }
+        account.resize(space as usize)
+    }
+
+    fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+        if account.owner == owner {
+            return Ok(());
+        }
+        if !account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        account.assign(owner);
+        Ok(())
+    }
+
+    fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+        if !from.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if !from.data_is_empty() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let remaining = from
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(ProgramError::Custom(RESULT_WITH_NEGATIVE_LAMPORTS))?;
+        **from.try_borrow_mut_lamports()? = remaining;
+        let credited = to.lamports().checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+        **to.try_borrow_mut_lamports()? = credited;
+        Ok(())
+    }
+}
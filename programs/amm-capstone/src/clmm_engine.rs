@@ -0,0 +1,594 @@
+use anchor_lang::prelude::*;
+
+use crate::engine::SwapMode;
+use crate::errors::AmmError;
+use crate::math::{
+    add_liquidity_delta, compute_swap_step, fee_growth_inside, fees_earned, max_liquidity_per_tick,
+    mul_div_floor, mul_div_u128, position_amounts, sqrt_price_at_tick, tick_at_sqrt_price,
+    FEE_RATE_DENOMINATOR, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK, Q64,
+};
+use crate::state::{ClmmPool, Position, TickArrayState, TickState};
+
+/// A fully priced concentrated liquidity swap. Amounts are what reaches
+/// the input vault (the swap fee included) and what leaves the output
+/// vault; Token-2022 transfer fees are the caller's. The pool fields
+/// are as the swap leaves them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClmmSwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    pub fee_growth_global_a_x64: u128,
+    pub fee_growth_global_b_x64: u128,
+}
+
+/// Walks a swap through the pool's ranges, crossing initialized ticks
+/// in `tick_arrays` (in traversal order) and updating them as it goes.
+/// Stops once `amount` is used up or the price reaches
+/// `sqrt_price_limit_x64`; an exact-input swap may fill partially
+/// there, an exact-output swap may not. Pure apart from the tick
+/// arrays, so the instruction and the tests run the same code.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_clmm_swap(
+    pool: &ClmmPool,
+    tick_arrays: &mut [&mut TickArrayState],
+    a_to_b: bool,
+    amount: u64,
+    mode: SwapMode,
+    sqrt_price_limit_x64: u128,
+    protocol_fee_rate: u16,
+    fund_fee_rate: u16,
+) -> Result<ClmmSwapResult> {
+
+    require!(amount > 0, AmmError::InvalidAmount);
+
+    // The limit must lie ahead of the price, inside the usable range
+    let limit_valid = if a_to_b {
+        sqrt_price_limit_x64 < pool.sqrt_price_x64 && sqrt_price_limit_x64 > MIN_SQRT_PRICE_X64
+    } else {
+        sqrt_price_limit_x64 > pool.sqrt_price_x64 && sqrt_price_limit_x64 < MAX_SQRT_PRICE_X64
+    };
+    require!(limit_valid, AmmError::InvalidSqrtPrice);
+
+    let exact_in = mode == SwapMode::ExactIn;
+    let mut remaining = amount;
+    let mut result = ClmmSwapResult {
+        amount_in: 0,
+        amount_out: 0,
+        lp_fee: 0,
+        protocol_fee: 0,
+        fund_fee: 0,
+        sqrt_price_x64: pool.sqrt_price_x64,
+        tick_current: pool.tick_current,
+        liquidity: pool.liquidity,
+        fee_growth_global_a_x64: pool.fee_growth_global_a_x64,
+        fee_growth_global_b_x64: pool.fee_growth_global_b_x64,
+    };
+    let mut array_index = 0;
+
+    while remaining > 0 && result.sqrt_price_x64 != sqrt_price_limit_x64 {
+        // ---------------- Next tick ----------------
+        let (tick_next, initialized) = loop {
+            let array = tick_arrays
+                .get(array_index)
+                .ok_or(AmmError::InvalidTickArray)?;
+            match array.next_initialized_tick(result.tick_current, pool.tick_spacing, a_to_b)? {
+                Some(next) => break next,
+                None => array_index += 1,
+            }
+        };
+        let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next = sqrt_price_at_tick(tick_next).ok_or(AmmError::Overflow)?;
+
+        // ---------------- Step within the range ----------------
+        let target = if a_to_b {
+            sqrt_price_next.max(sqrt_price_limit_x64)
+        } else {
+            sqrt_price_next.min(sqrt_price_limit_x64)
+        };
+        let step = compute_swap_step(
+            result.sqrt_price_x64,
+            target,
+            result.liquidity,
+            remaining,
+            pool.fee_bps,
+            exact_in,
+        )
+        .ok_or(AmmError::Overflow)?;
+
+        let step_in = step
+            .amount_in
+            .checked_add(step.fee_amount)
+            .ok_or(AmmError::Overflow)?;
+        let used = if exact_in { step_in } else { step.amount_out };
+        remaining = remaining.checked_sub(used).ok_or(AmmError::Overflow)?;
+        result.amount_in = result.amount_in.checked_add(step_in).ok_or(AmmError::Overflow)?;
+        result.amount_out = result
+            .amount_out
+            .checked_add(step.amount_out)
+            .ok_or(AmmError::Overflow)?;
+
+        // ---------------- Fees ----------------
+        // Protocol and fund shares round down, so the LPs keep the dust
+        let protocol_fee = mul_div_floor(step.fee_amount, protocol_fee_rate as u64, FEE_RATE_DENOMINATOR)
+            .ok_or(AmmError::Overflow)?;
+        let fund_fee = mul_div_floor(step.fee_amount, fund_fee_rate as u64, FEE_RATE_DENOMINATOR)
+            .ok_or(AmmError::Overflow)?;
+        let lp_fee = step
+            .fee_amount
+            .checked_sub(protocol_fee)
+            .and_then(|fee| fee.checked_sub(fund_fee))
+            .ok_or(AmmError::Overflow)?;
+        // No position is in range to earn the LP share, so the protocol
+        // takes it rather than leaving it unclaimable in the vault
+        let (protocol_fee, lp_fee) = if result.liquidity == 0 {
+            (protocol_fee.checked_add(lp_fee).ok_or(AmmError::Overflow)?, 0)
+        } else {
+            (protocol_fee, lp_fee)
+        };
+
+        result.protocol_fee = result.protocol_fee.checked_add(protocol_fee).ok_or(AmmError::Overflow)?;
+        result.fund_fee = result.fund_fee.checked_add(fund_fee).ok_or(AmmError::Overflow)?;
+        result.lp_fee = result.lp_fee.checked_add(lp_fee).ok_or(AmmError::Overflow)?;
+
+        if result.liquidity > 0 {
+            let growth = mul_div_u128(lp_fee as u128, Q64, result.liquidity).ok_or(AmmError::Overflow)?;
+            if a_to_b {
+                result.fee_growth_global_a_x64 = result.fee_growth_global_a_x64.wrapping_add(growth);
+            } else {
+                result.fee_growth_global_b_x64 = result.fee_growth_global_b_x64.wrapping_add(growth);
+            }
+        }
+
+        // ---------------- Cross or settle ----------------
+        result.sqrt_price_x64 = step.sqrt_price_next;
+        if step.sqrt_price_next == sqrt_price_next {
+            if initialized {
+                let tick = tick_arrays[array_index].tick_mut(tick_next, pool.tick_spacing)?;
+                let liquidity_net = cross_tick(
+                    tick,
+                    result.fee_growth_global_a_x64,
+                    result.fee_growth_global_b_x64,
+                );
+                let delta = if a_to_b {
+                    liquidity_net.checked_neg().ok_or(AmmError::Overflow)?
+                } else {
+                    liquidity_net
+                };
+                result.liquidity =
+                    add_liquidity_delta(result.liquidity, delta).ok_or(AmmError::Overflow)?;
+            }
+            // Below a tick crossed downwards, at one crossed upwards
+            result.tick_current = if a_to_b { tick_next - 1 } else { tick_next };
+        } else {
+            result.tick_current =
+                tick_at_sqrt_price(result.sqrt_price_x64).ok_or(AmmError::InvalidSqrtPrice)?;
+        }
+    }
+
+    require!(
+        exact_in || remaining == 0,
+        AmmError::InsufficientLiquidity
+    );
+
+    Ok(result)
+}
+
+/// Flips `tick`'s outside fee growth as the price crosses it, returning
+/// its `liquidity_net`.
+fn cross_tick(tick: &mut TickState, fee_growth_global_a_x64: u128, fee_growth_global_b_x64: u128) -> i128 {
+    tick.fee_growth_outside_a_x64 = fee_growth_global_a_x64.wrapping_sub(tick.fee_growth_outside_a_x64);
+    tick.fee_growth_outside_b_x64 = fee_growth_global_b_x64.wrapping_sub(tick.fee_growth_outside_b_x64);
+    tick.liquidity_net
+}
+
+/// Changes `position`'s liquidity by `liquidity_delta`, updating its
+/// bounding ticks and the pool's in-range liquidity, and credits the
+/// fees it earned since it was last updated to `tokens_owed`. Returns
+/// the tokens (A, B) the change is worth: owed to the pool when adding,
+/// rounded up, or due to the owner when removing, rounded down. A zero
+/// delta just credits fees.
+pub fn modify_position(
+    pool: &mut ClmmPool,
+    position: &mut Position,
+    tick_lower: &mut TickState,
+    tick_upper: &mut TickState,
+    liquidity_delta: i128,
+) -> Result<(u64, u64)> {
+
+    let new_liquidity = add_liquidity_delta(position.liquidity, liquidity_delta)
+        .ok_or(AmmError::InsufficientLiquidity)?;
+
+    // ---------------- Ticks ----------------
+    if liquidity_delta != 0 {
+        let max_liquidity = max_liquidity_per_tick(pool.tick_spacing);
+        for (tick, index, upper) in [
+            (&mut *tick_lower, position.tick_lower, false),
+            (&mut *tick_upper, position.tick_upper, true),
+        ] {
+            let gross = add_liquidity_delta(tick.liquidity_gross, liquidity_delta)
+                .ok_or(AmmError::InsufficientLiquidity)?;
+            require!(gross <= max_liquidity, AmmError::TickLiquidityExceeded);
+
+            // Growth before a tick is first used counts as below it
+            if !tick.is_initialized() && index <= pool.tick_current {
+                tick.fee_growth_outside_a_x64 = pool.fee_growth_global_a_x64;
+                tick.fee_growth_outside_b_x64 = pool.fee_growth_global_b_x64;
+            }
+            tick.liquidity_gross = gross;
+            tick.liquidity_net = if upper {
+                tick.liquidity_net.checked_sub(liquidity_delta)
+            } else {
+                tick.liquidity_net.checked_add(liquidity_delta)
+            }
+            .ok_or(AmmError::Overflow)?;
+        }
+    }
+
+    // ---------------- Fees ----------------
+    let inside = |global: u128, lower_outside: u128, upper_outside: u128| {
+        fee_growth_inside(
+            pool.tick_current,
+            position.tick_lower,
+            position.tick_upper,
+            global,
+            lower_outside,
+            upper_outside,
+        )
+    };
+    let inside_a = inside(
+        pool.fee_growth_global_a_x64,
+        tick_lower.fee_growth_outside_a_x64,
+        tick_upper.fee_growth_outside_a_x64,
+    );
+    let inside_b = inside(
+        pool.fee_growth_global_b_x64,
+        tick_lower.fee_growth_outside_b_x64,
+        tick_upper.fee_growth_outside_b_x64,
+    );
+
+    let fees_a = fees_earned(position.liquidity, inside_a, position.fee_growth_inside_a_last_x64)
+        .ok_or(AmmError::Overflow)?;
+    let fees_b = fees_earned(position.liquidity, inside_b, position.fee_growth_inside_b_last_x64)
+        .ok_or(AmmError::Overflow)?;
+    position.tokens_owed_a = position.tokens_owed_a.checked_add(fees_a).ok_or(AmmError::Overflow)?;
+    position.tokens_owed_b = position.tokens_owed_b.checked_add(fees_b).ok_or(AmmError::Overflow)?;
+    position.fee_growth_inside_a_last_x64 = inside_a;
+    position.fee_growth_inside_b_last_x64 = inside_b;
+    position.liquidity = new_liquidity;
+
+    // A tick no position uses any more is cleared, fee growth included
+    for tick in [tick_lower, tick_upper] {
+        if !tick.is_initialized() {
+            *tick = TickState::default();
+        }
+    }
+
+    // ---------------- Pool ----------------
+    if (position.tick_lower..position.tick_upper).contains(&pool.tick_current) {
+        pool.liquidity = add_liquidity_delta(pool.liquidity, liquidity_delta).ok_or(AmmError::Overflow)?;
+    }
+
+    let sqrt_price_lower = sqrt_price_at_tick(position.tick_lower).ok_or(AmmError::InvalidTickRange)?;
+    let sqrt_price_upper = sqrt_price_at_tick(position.tick_upper).ok_or(AmmError::InvalidTickRange)?;
+    Ok(position_amounts(
+        pool.sqrt_price_x64,
+        sqrt_price_lower,
+        sqrt_price_upper,
+        liquidity_delta.unsigned_abs(),
+        liquidity_delta > 0,
+    )
+    .ok_or(AmmError::Overflow)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TICK_ARRAY_SIZE;
+    use crate::math::amount_b_delta;
+
+    const SPACING: u16 = 10;
+
+    fn pool_at(tick: i32) -> ClmmPool {
+        ClmmPool {
+            amm_config: Pubkey::default(),
+            token_a_mint: Pubkey::default(),
+            token_b_mint: Pubkey::default(),
+            vault_a: Pubkey::default(),
+            vault_b: Pubkey::default(),
+            vault_authority: Pubkey::default(),
+            vault_authority_bump: 0,
+            tick_spacing: SPACING,
+            fee_bps: 30,
+            status: 0,
+            liquidity: 0,
+            sqrt_price_x64: sqrt_price_at_tick(tick).unwrap(),
+            tick_current: tick,
+            fee_growth_global_a_x64: 0,
+            fee_growth_global_b_x64: 0,
+            protocol_fees_a: 0,
+            protocol_fees_b: 0,
+            fund_fees_a: 0,
+            fund_fees_b: 0,
+        }
+    }
+
+    fn array(start_tick_index: i32) -> TickArrayState {
+        TickArrayState {
+            pool: Pubkey::default(),
+            start_tick_index,
+            padding: [0; 12],
+            ticks: [TickState::default(); TICK_ARRAY_SIZE],
+        }
+    }
+
+    fn position(tick_lower: i32, tick_upper: i32) -> Position {
+        Position {
+            pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_a_last_x64: 0,
+            fee_growth_inside_b_last_x64: 0,
+            tokens_owed_a: 0,
+            tokens_owed_b: 0,
+        }
+    }
+
+    // Adds `liquidity` over `tick_lower..tick_upper`, both in `arrays`
+    fn deposit(
+        pool: &mut ClmmPool,
+        arrays: &mut [TickArrayState],
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: i128,
+    ) -> Position {
+        let mut position = position(tick_lower, tick_upper);
+        let index = |tick: i32| {
+            arrays
+                .iter()
+                .position(|array| array.start_tick_index == TickArrayState::start_index(tick, SPACING))
+                .unwrap()
+        };
+        let (lower, upper) = (index(tick_lower), index(tick_upper));
+
+        if lower == upper {
+            let (lo, hi) = arrays[lower].tick_pair_mut(tick_lower, tick_upper, SPACING).unwrap();
+            modify_position(pool, &mut position, lo, hi, liquidity).unwrap();
+        } else {
+            let (first, second) = arrays.split_at_mut(upper.max(lower));
+            let (lo_array, hi_array) = if lower < upper {
+                (&mut first[lower], &mut second[0])
+            } else {
+                (&mut second[0], &mut first[upper])
+            };
+            let lo = lo_array.tick_mut(tick_lower, SPACING).unwrap();
+            let hi = hi_array.tick_mut(tick_upper, SPACING).unwrap();
+            modify_position(pool, &mut position, lo, hi, liquidity).unwrap();
+        }
+        position
+    }
+
+    fn swap(
+        pool: &ClmmPool,
+        arrays: &mut [TickArrayState],
+        a_to_b: bool,
+        amount: u64,
+        mode: SwapMode,
+    ) -> Result<ClmmSwapResult> {
+        let limit = if a_to_b { MIN_SQRT_PRICE_X64 + 1 } else { MAX_SQRT_PRICE_X64 - 1 };
+        let mut refs: Vec<&mut TickArrayState> = arrays.iter_mut().collect();
+        compute_clmm_swap(pool, &mut refs, a_to_b, amount, mode, limit, 2_000, 1_000)
+    }
+
+    fn apply(pool: &mut ClmmPool, result: &ClmmSwapResult) {
+        pool.sqrt_price_x64 = result.sqrt_price_x64;
+        pool.tick_current = result.tick_current;
+        pool.liquidity = result.liquidity;
+        pool.fee_growth_global_a_x64 = result.fee_growth_global_a_x64;
+        pool.fee_growth_global_b_x64 = result.fee_growth_global_b_x64;
+    }
+
+    #[test]
+    fn in_range_deposit_takes_both_tokens_and_adds_liquidity() {
+        let mut pool = pool_at(5);
+        let mut arrays = [array(-600), array(0)];
+        let position = deposit(&mut pool, &mut arrays, -100, 100, 1_000_000);
+
+        assert_eq!(pool.liquidity, 1_000_000);
+        assert_eq!(position.liquidity, 1_000_000);
+        let lower = arrays[0].tick_mut(-100, SPACING).unwrap();
+        assert_eq!((lower.liquidity_gross, lower.liquidity_net), (1_000_000, 1_000_000));
+        let upper = arrays[1].tick_mut(100, SPACING).unwrap();
+        assert_eq!((upper.liquidity_gross, upper.liquidity_net), (1_000_000, -1_000_000));
+
+        // Out of range, the pool's active liquidity is untouched
+        let mut above = pool_at(500);
+        deposit(&mut above, &mut arrays, -100, 100, 1_000_000);
+        assert_eq!(above.liquidity, 0);
+    }
+
+    #[test]
+    fn withdrawing_more_than_the_position_holds_fails() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0)];
+        let mut position = deposit(&mut pool, &mut arrays, 0, 100, 1_000);
+        let (lo, hi) = arrays[0].tick_pair_mut(0, 100, SPACING).unwrap();
+
+        assert_eq!(
+            modify_position(&mut pool, &mut position, lo, hi, -1_001).unwrap_err(),
+            AmmError::InsufficientLiquidity.into()
+        );
+
+        // Removing all of it clears both ticks
+        let (a, b) = modify_position(&mut pool, &mut position, lo, hi, -1_000).unwrap();
+        assert!(a > 0 && b == 0);
+        assert!(!lo.is_initialized() && !hi.is_initialized());
+        assert_eq!(pool.liquidity, 0);
+    }
+
+    #[test]
+    fn tick_liquidity_is_capped() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0)];
+        let mut position = position(0, 100);
+        let (lo, hi) = arrays[0].tick_pair_mut(0, 100, SPACING).unwrap();
+        let cap = max_liquidity_per_tick(SPACING) as i128;
+
+        assert_eq!(
+            modify_position(&mut pool, &mut position, lo, hi, cap + 1).unwrap_err(),
+            AmmError::TickLiquidityExceeded.into()
+        );
+    }
+
+    #[test]
+    fn swap_within_one_range_matches_the_step_math() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0), array(-600)];
+        deposit(&mut pool, &mut arrays, -600, 590, 1_000_000_000);
+
+        let result = swap(&pool, &mut arrays, true, 10_000, SwapMode::ExactIn).unwrap();
+        assert_eq!(result.amount_in, 10_000);
+        assert_eq!(result.liquidity, pool.liquidity);
+        assert!(result.tick_current < 0 && result.tick_current > -600);
+        assert_eq!(result.protocol_fee, 6);
+        assert_eq!(result.fund_fee, 3);
+        assert_eq!(result.lp_fee, 21);
+        assert!(result.fee_growth_global_a_x64 > 0);
+        assert_eq!(result.fee_growth_global_b_x64, 0);
+
+        // The output is what the price movement releases
+        assert_eq!(
+            result.amount_out,
+            amount_b_delta(result.sqrt_price_x64, pool.sqrt_price_x64, pool.liquidity, false).unwrap()
+        );
+    }
+
+    #[test]
+    fn swap_crosses_ticks_and_updates_liquidity() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0), array(600)];
+        deposit(&mut pool, &mut arrays, 0, 1_100, 1_000_000);
+        deposit(&mut pool, &mut arrays, 100, 700, 3_000_000);
+        assert_eq!(pool.liquidity, 1_000_000);
+
+        // Far enough up to pass 100 and 700, not 1_100
+        let result = swap(&pool, &mut arrays, false, 140_000, SwapMode::ExactIn).unwrap();
+        assert!(result.tick_current > 700 && result.tick_current < 1_100);
+        assert_eq!(result.liquidity, 1_000_000);
+        let crossed = arrays[0].tick_mut(100, SPACING).unwrap();
+        assert!(crossed.fee_growth_outside_b_x64 > 0);
+
+        // Back down into 100..700, where both positions are in range
+        apply(&mut pool, &result);
+        let mut reversed = [arrays[1], arrays[0]];
+        let back = swap(&pool, &mut reversed, true, 20_000, SwapMode::ExactIn).unwrap();
+        assert!(back.tick_current < 700 && back.tick_current >= 100);
+        assert_eq!(back.liquidity, 4_000_000);
+    }
+
+    #[test]
+    fn exact_out_fails_when_liquidity_runs_out() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0), array(600), array(1_200)];
+        deposit(&mut pool, &mut arrays, 0, 600, 1_000_000);
+
+        // Past 600 the pool is empty, then the arrays run out
+        let result = swap(&pool, &mut arrays, false, 1_000_000, SwapMode::ExactOut);
+        assert!(result.is_err());
+
+        // Exact-in stops at the price limit with what it could fill
+        let limit = sqrt_price_at_tick(300).unwrap();
+        let mut refs: Vec<&mut TickArrayState> = arrays.iter_mut().collect();
+        let partial =
+            compute_clmm_swap(&pool, &mut refs, false, 1_000_000, SwapMode::ExactIn, limit, 0, 0).unwrap();
+        assert_eq!(partial.sqrt_price_x64, limit);
+        assert_eq!(partial.tick_current, 300);
+        assert!(partial.amount_in < 1_000_000);
+    }
+
+    #[test]
+    fn arrays_must_cover_the_path() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0), array(1_200)];
+        deposit(&mut pool, &mut arrays, 0, 590, 1_000_000);
+
+        // 600..1_200 is missing
+        assert_eq!(
+            swap(&pool, &mut arrays, false, 1_000_000, SwapMode::ExactIn).unwrap_err(),
+            AmmError::InvalidTickArray.into()
+        );
+        assert_eq!(
+            swap(&pool, &mut [], true, 1, SwapMode::ExactIn).unwrap_err(),
+            AmmError::InvalidTickArray.into()
+        );
+    }
+
+    #[test]
+    fn fees_accrue_to_in_range_positions_only() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0), array(-600)];
+        let mut inside = deposit(&mut pool, &mut arrays, -100, 100, 1_000_000_000);
+        let mut outside = deposit(&mut pool, &mut arrays, -600, -300, 1_000_000_000);
+
+        let result = swap(&pool, &mut arrays, true, 100_000, SwapMode::ExactIn).unwrap();
+        apply(&mut pool, &result);
+        assert!(pool.tick_current > -100);
+
+        let (lo, hi) = {
+            let (first, second) = arrays.split_at_mut(1);
+            (second[0].tick_mut(-100, SPACING).unwrap(), first[0].tick_mut(100, SPACING).unwrap())
+        };
+        modify_position(&mut pool, &mut inside, lo, hi, 0).unwrap();
+        // The LP fee, less rounding
+        assert!(inside.tokens_owed_a <= result.lp_fee && inside.tokens_owed_a + 1 >= result.lp_fee);
+        assert_eq!(inside.tokens_owed_b, 0);
+
+        let (lo, hi) = arrays[1].tick_pair_mut(-600, -300, SPACING).unwrap();
+        modify_position(&mut pool, &mut outside, lo, hi, 0).unwrap();
+        assert_eq!(outside.tokens_owed_a, 0);
+    }
+
+    #[test]
+    fn lp_fees_are_claimable_across_empty_ranges() {
+        let mut pool = pool_at(0);
+        let mut arrays = [array(0), array(-600)];
+        let mut position = deposit(&mut pool, &mut arrays, -600, -300, 1_000_000_000);
+        assert_eq!(pool.liquidity, 0);
+
+        // Crosses the empty range down to -300, then trades into the position
+        let result = swap(&pool, &mut arrays, true, 100_000, SwapMode::ExactIn).unwrap();
+        apply(&mut pool, &result);
+        assert!(pool.tick_current < -300);
+
+        let (lo, hi) = arrays[1].tick_pair_mut(-600, -300, SPACING).unwrap();
+        modify_position(&mut pool, &mut position, lo, hi, 0).unwrap();
+        // Every LP fee charged is owed to the position, less rounding
+        assert!(position.tokens_owed_a <= result.lp_fee && position.tokens_owed_a + 1 >= result.lp_fee);
+    }
+
+    #[test]
+    fn rejects_limits_behind_the_price() {
+        let pool = pool_at(0);
+        let mut arrays = [array(0)];
+        let mut refs: Vec<&mut TickArrayState> = arrays.iter_mut().collect();
+        for (a_to_b, limit) in [
+            (true, pool.sqrt_price_x64),
+            (true, MIN_SQRT_PRICE_X64),
+            (false, pool.sqrt_price_x64 - 1),
+            (false, MAX_SQRT_PRICE_X64),
+        ] {
+            assert_eq!(
+                compute_clmm_swap(&pool, &mut refs, a_to_b, 1, SwapMode::ExactIn, limit, 0, 0)
+                    .unwrap_err(),
+                AmmError::InvalidSqrtPrice.into()
+            );
+        }
+    }
+}
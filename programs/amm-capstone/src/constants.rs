@@ -2,6 +2,37 @@ pub const POOL_SEED: &[u8] = b"pool";
 pub const VAULT_AUTH_SEED: &[u8] = b"vault_authority";
 pub const VAULT_A_SEED: &[u8] = b"vault_a";
 pub const VAULT_B_SEED: &[u8] = b"vault_b";
+pub const LP_MINT_SEED: &[u8] = b"lp_mint";
 
 pub const TREASURY_SEED: &[u8] = b"treasury";
 
+
+pub const LOCKED_LP_SEED: &[u8] = b"locked_lp";
+pub const LOCKED_LP_AUTH_SEED: &[u8] = b"locked_lp_authority";
+
+pub use amm_capstone_math::MINIMUM_LIQUIDITY;
+
+pub const OBSERVATION_SEED: &[u8] = b"observation";
+
+/// Entries in each pool's observation ring.
+pub const OBSERVATION_NUM: usize = 100;
+
+/// Shortest amplification ramp, and the most it may multiply or divide
+/// A by, as in Curve. Together they cap how fast A can move.
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+pub const AMM_CONFIG_SEED: &[u8] = b"amm_config";
+pub const MAX_FEE_TIERS: usize = 8;
+pub use amm_capstone_math::FEE_RATE_DENOMINATOR;
+
+pub const CLMM_POOL_SEED: &[u8] = b"clmm_pool";
+pub const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+pub const POSITION_SEED: &[u8] = b"position";
+
+/// Ticks in each tick array account, `tick_spacing` apart.
+pub const TICK_ARRAY_SIZE: usize = 60;
+
+/// Widest tick spacing a concentrated liquidity pool may use, as in
+/// Uniswap v3.
+pub const MAX_TICK_SPACING: u16 = 16_384;
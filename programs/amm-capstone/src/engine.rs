@@ -0,0 +1,276 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFee;
+
+use crate::errors::AmmError;
+use crate::math::PoolState;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapDirection {
+    AToB,
+    BToA,
+}
+
+/// Which side of the swap `amount` fixes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapMode {
+    // `amount` is what the user sends
+    ExactIn,
+    // `amount` is what the user receives
+    ExactOut,
+}
+
+/// Everything a swap's price depends on: the pool's reserves and fees,
+/// plus each mint's Token-2022 transfer fee for the current epoch
+/// (`None` for mints without one).
+#[derive(Clone, Copy, Debug)]
+pub struct SwapPool {
+    pub state: PoolState,
+    pub transfer_fee_a: Option<TransferFee>,
+    pub transfer_fee_b: Option<TransferFee>,
+}
+
+/// A fully priced swap. `amount_in` / `amount_out` are what leaves the
+/// user and the output vault; the transfer fees are what the token
+/// program withholds from each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapResult {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub transfer_fee_in: u64,
+    pub transfer_fee_out: u64,
+    pub lp_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+    pub new_reserve_a: u64,
+    pub new_reserve_b: u64,
+}
+
+impl SwapResult {
+    /// What actually reaches the user's output account.
+    pub fn received_out(&self) -> Result<u64> {
+        Ok(self
+            .amount_out
+            .checked_sub(self.transfer_fee_out)
+            .ok_or(AmmError::Overflow)?)
+    }
+}
+
+/// The one place a swap is priced. Pure, so the instruction and the
+/// tests run exactly the same code.
+pub struct SwapEngine;
+
+impl SwapEngine {
+    pub fn compute(
+        pool: &SwapPool,
+        direction: SwapDirection,
+        amount: u64,
+        mode: SwapMode,
+    ) -> Result<SwapResult> {
+
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        let a_to_b = direction == SwapDirection::AToB;
+        let (fee_in, fee_out) = if a_to_b {
+            (pool.transfer_fee_a, pool.transfer_fee_b)
+        } else {
+            (pool.transfer_fee_b, pool.transfer_fee_a)
+        };
+
+        let (amount_in, quote) = match mode {
+            SwapMode::ExactIn => {
+                // The curve only prices what actually reaches the vault
+                let received_in = after_transfer_fee(fee_in, amount)?;
+
+                let quote = pool
+                    .state
+                    .quote_exact_in(a_to_b, received_in)
+                    .map_err(AmmError::from)?;
+
+                (amount, quote)
+            }
+            SwapMode::ExactOut => {
+                // The vault sends enough that `amount` survives the
+                // output mint's transfer fee
+                let vault_out = pre_fee_amount(fee_out, amount)?;
+
+                let needed_in = pool
+                    .state
+                    .quote_exact_out(a_to_b, vault_out)
+                    .map_err(AmmError::from)?
+                    .amount_in;
+
+                let amount_in = pre_fee_amount(fee_in, needed_in)?;
+
+                // The input transfer fee can round in the pool's favour,
+                // so settle on what the vault actually receives
+                let received_in = after_transfer_fee(fee_in, amount_in)?;
+
+                let quote = pool
+                    .state
+                    .settle_swap(a_to_b, received_in, vault_out)
+                    .map_err(AmmError::from)?;
+
+                (amount_in, quote)
+            }
+        };
+
+        let (new_reserve_a, new_reserve_b) = if a_to_b {
+            (quote.new_reserve_in, quote.new_reserve_out)
+        } else {
+            (quote.new_reserve_out, quote.new_reserve_in)
+        };
+
+        Ok(SwapResult {
+            amount_in,
+            amount_out: quote.amount_out,
+            transfer_fee_in: amount_in
+                .checked_sub(quote.amount_in)
+                .ok_or(AmmError::Overflow)?,
+            transfer_fee_out: transfer_fee(fee_out, quote.amount_out)?,
+            lp_fee: quote.lp_fee,
+            protocol_fee: quote.protocol_fee,
+            fund_fee: quote.fund_fee,
+            new_reserve_a,
+            new_reserve_b,
+        })
+    }
+}
+
+pub(crate) fn transfer_fee(fee: Option<TransferFee>, amount: u64) -> Result<u64> {
+    match fee {
+        Some(fee) => Ok(fee.calculate_fee(amount).ok_or(AmmError::Overflow)?),
+        None => Ok(0),
+    }
+}
+
+// What reaches the destination when `amount` is sent
+pub(crate) fn after_transfer_fee(fee: Option<TransferFee>, amount: u64) -> Result<u64> {
+    Ok(amount
+        .checked_sub(transfer_fee(fee, amount)?)
+        .ok_or(AmmError::Overflow)?)
+}
+
+pub(crate) fn pre_fee_amount(fee: Option<TransferFee>, post_fee_amount: u64) -> Result<u64> {
+    match fee {
+        Some(fee) => Ok(fee
+            .calculate_pre_fee_amount(post_fee_amount)
+            .ok_or(AmmError::Overflow)?),
+        None => Ok(post_fee_amount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classic_pool() -> SwapPool {
+        SwapPool {
+            state: PoolState::new(1_000_000, 2_000_000, 30)
+                .with_fee_shares(2_000, 1_000),
+            transfer_fee_a: None,
+            transfer_fee_b: None,
+        }
+    }
+
+    // 1% transfer fee, capped at `maximum_fee`
+    fn one_percent(maximum_fee: u64) -> Option<TransferFee> {
+        Some(TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: 100.into(),
+        })
+    }
+
+    #[test]
+    fn exact_in_matches_quote_for_classic_mints() {
+        let pool = classic_pool();
+        let result =
+            SwapEngine::compute(&pool, SwapDirection::AToB, 10_000, SwapMode::ExactIn)
+                .unwrap();
+        let quote = pool.state.quote_exact_in(true, 10_000).unwrap();
+
+        assert_eq!(result.amount_in, 10_000);
+        assert_eq!(result.amount_out, quote.amount_out);
+        assert_eq!(result.received_out().unwrap(), quote.amount_out);
+        assert_eq!(result.protocol_fee, 6);
+        assert_eq!(result.fund_fee, 3);
+        assert_eq!(result.lp_fee, 21);
+        assert_eq!(result.new_reserve_a, quote.new_reserve_in);
+        assert_eq!(result.new_reserve_b, quote.new_reserve_out);
+    }
+
+    #[test]
+    fn direction_maps_reserves_back_to_a_and_b() {
+        let pool = classic_pool();
+        let result =
+            SwapEngine::compute(&pool, SwapDirection::BToA, 10_000, SwapMode::ExactIn)
+                .unwrap();
+
+        assert!(result.new_reserve_b > 2_000_000);
+        assert_eq!(result.new_reserve_a, 1_000_000 - result.amount_out);
+    }
+
+    #[test]
+    fn exact_out_delivers_exact_amount() {
+        let pool = classic_pool();
+        let result =
+            SwapEngine::compute(&pool, SwapDirection::AToB, 5_000, SwapMode::ExactOut)
+                .unwrap();
+
+        assert_eq!(result.amount_out, 5_000);
+        assert_eq!(
+            result.amount_in,
+            pool.state.quote_exact_out(true, 5_000).unwrap().amount_in
+        );
+    }
+
+    #[test]
+    fn input_transfer_fee_is_not_priced() {
+        let pool = SwapPool {
+            transfer_fee_a: one_percent(u64::MAX),
+            ..classic_pool()
+        };
+        let result =
+            SwapEngine::compute(&pool, SwapDirection::AToB, 10_000, SwapMode::ExactIn)
+                .unwrap();
+
+        assert_eq!(result.transfer_fee_in, 100);
+        assert_eq!(
+            result.amount_out,
+            pool.state.quote_exact_in(true, 9_900).unwrap().amount_out
+        );
+    }
+
+    #[test]
+    fn exact_out_covers_both_transfer_fees() {
+        let pool = SwapPool {
+            transfer_fee_a: one_percent(u64::MAX),
+            transfer_fee_b: one_percent(u64::MAX),
+            ..classic_pool()
+        };
+        let result =
+            SwapEngine::compute(&pool, SwapDirection::AToB, 5_000, SwapMode::ExactOut)
+                .unwrap();
+
+        let needed_in = pool
+            .state
+            .quote_exact_out(true, result.amount_out)
+            .unwrap()
+            .amount_in;
+
+        assert!(result.received_out().unwrap() >= 5_000);
+        assert!(result.amount_in - result.transfer_fee_in >= needed_in);
+    }
+
+    #[test]
+    fn rejects_zero_and_draining_swaps() {
+        let pool = classic_pool();
+        assert!(
+            SwapEngine::compute(&pool, SwapDirection::AToB, 0, SwapMode::ExactIn).is_err()
+        );
+        assert!(
+            SwapEngine::compute(&pool, SwapDirection::AToB, 2_000_000, SwapMode::ExactOut)
+                .is_err()
+        );
+    }
+}
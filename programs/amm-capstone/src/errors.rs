@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
+use amm_capstone_math::QuoteError;
 
 #[error_code]
 pub enum AmmError {
-    #[msg("Slippage exceeded")]
+    #[msg("Slippage exceeded; outputs round down and inputs round up, in the pool's favour")]
     SlippageExceeded,
     #[msg("Invariant violation")]
     InvariantViolation,
@@ -12,6 +13,75 @@ pub enum AmmError {
     InvalidAmount,
     #[msg("Pool has no liquidity")]
     PoolEmpty,
-    #[msg("Math overflow")]
+    #[msg("Arithmetic overflow or underflow; no amount is ever truncated or wrapped")]
     Overflow,
-}
\ No newline at end of file
+    #[msg("Token mint does not match the pool")]
+    InvalidMint,
+    #[msg("Optimal token A amount, rounded down, is below amount_a_min")]
+    InsufficientAmountA,
+    #[msg("Optimal token B amount, rounded down, is below amount_b_min")]
+    InsufficientAmountB,
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized,
+    #[msg("Fee tier is not allowed by the config")]
+    InvalidFeeTier,
+    #[msg("Protocol and fund fee shares exceed 100%")]
+    InvalidFeeRate,
+    #[msg("Operation is disabled for this pool")]
+    OperationDisabled,
+    #[msg("Mint has an unsupported Token-2022 extension")]
+    UnsupportedMint,
+    #[msg("Observation window must end after it starts")]
+    InvalidObservationWindow,
+    #[msg("Vault balances do not cover reserves and accrued fees")]
+    ReserveMismatch,
+    #[msg("Amplification coefficient is out of range")]
+    InvalidAmplification,
+    #[msg("Amplification ramp must last at least a day and change A at most tenfold")]
+    InvalidRamp,
+    #[msg("Pool does not use the StableSwap curve")]
+    NotStablePool,
+    #[msg("Weights must each be at least 1% and sum to WEIGHT_ONE")]
+    InvalidWeights,
+    #[msg("Operation is not supported by this pool's curve")]
+    UnsupportedCurve,
+    #[msg("Weight schedule must end after it starts")]
+    InvalidWeightSchedule,
+    #[msg("Pool is not a liquidity bootstrapping pool")]
+    NotBootstrappingPool,
+    #[msg("Swaps have not opened on this pool yet")]
+    SwapNotStarted,
+    #[msg("Tick spacing must be between 1 and MAX_TICK_SPACING")]
+    InvalidTickSpacing,
+    #[msg("Square-root price is outside the usable tick range")]
+    InvalidSqrtPrice,
+    #[msg("Tick range must be non-empty, within bounds and on the pool's tick spacing")]
+    InvalidTickRange,
+    #[msg("Tick array does not belong to the pool or does not cover the ticks needed")]
+    InvalidTickArray,
+    #[msg("Liquidity referencing a tick would exceed its cap")]
+    TickLiquidityExceeded,
+    #[msg("Pool status sets bits outside PoolStatusBit")]
+    InvalidStatus,
+    #[msg("Token A's mint must sort before token B's")]
+    InvalidMintOrder,
+    #[msg("Amplification ramp started less than a day after the last one")]
+    RampTooSoon,
+    #[msg("Withdrawal would take the treasury below its rent-exempt minimum")]
+    InsufficientTreasuryBalance,
+    #[msg("Observation window starts before the oldest stored observation")]
+    ObservationTooOld,
+}
+
+impl From<QuoteError> for AmmError {
+    fn from(error: QuoteError) -> Self {
+        match error {
+            QuoteError::InvalidAmount => AmmError::InvalidAmount,
+            QuoteError::PoolEmpty => AmmError::PoolEmpty,
+            QuoteError::InsufficientLiquidity => AmmError::InsufficientLiquidity,
+            QuoteError::InvariantViolation => AmmError::InvariantViolation,
+            QuoteError::Overflow => AmmError::Overflow,
+            QuoteError::UnsupportedCurve => AmmError::UnsupportedCurve,
+        }
+    }
+}
@@ -3,6 +3,111 @@ use anchor_lang::prelude::*;
 #[event]
 pub struct SwapEvent {
     pub user: Pubkey,
+    pub pool: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
     pub amount_in: u64,
     pub amount_out: u64,
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct FeesCollected {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct FundFeesCollected {
+    pub pool: Pubkey,
+    pub admin: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct PoolStatusUpdated {
+    pub pool: Pubkey,
+    pub status: u8,
+}
+
+#[event]
+pub struct ReservesSynced {
+    pub pool: Pubkey,
+    pub previous_reserve_a: u64,
+    pub previous_reserve_b: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+pub struct ExcessSkimmed {
+    pub pool: Pubkey,
+    pub recipient_token_a: Pubkey,
+    pub recipient_token_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct AmpRampStarted {
+    pub pool: Pubkey,
+    pub initial_amp: u64,
+    pub target_amp: u64,
+    pub start_ts: i64,
+    pub stop_ts: i64,
+}
+
+#[event]
+pub struct AmpRampStopped {
+    pub pool: Pubkey,
+    pub amp: u64,
+    pub stopped_at: i64,
+}
+
+#[event]
+pub struct GradualWeightUpdateScheduled {
+    pub pool: Pubkey,
+    pub start_weight_a: u64,
+    pub start_weight_b: u64,
+    pub end_weight_a: u64,
+    pub end_weight_b: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+#[event]
+pub struct LiquidityIncreased {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub liquidity: u128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct LiquidityDecreased {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub liquidity: u128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct PositionCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub recipient_token_a: Pubkey,
+    pub recipient_token_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
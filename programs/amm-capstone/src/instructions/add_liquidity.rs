@@ -1,12 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{
-    Token,
-    TokenAccount,
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{
     Mint,
-    Transfer,
-    MintTo,
-    transfer,
-    mint_to,
+    TokenAccount,
+    TokenInterface,
 };
 
 use crate::state::*;
@@ -20,47 +17,66 @@ pub struct AddLiquidity<'info> {
     #[account(mut)] 
     pub user: Signer<'info>,
 
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = lp_mint,
+        has_one = token_a_mint,
+        has_one = token_b_mint
+    )]
+    pub pool: Box<Account<'info, Pool>>,
 
     #[account(
         mut,
         constraint = user_token_a.mint == pool.token_a_mint
     )]
-    pub user_token_a: Account<'info, TokenAccount>,
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         constraint = user_token_b.mint == pool.token_b_mint
     )]
-    pub user_token_b: Account<'info, TokenAccount>,
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
-        constraint = vault_a.mint == pool.token_a_mint,
-        constraint = vault_a.owner == vault_authority.key()
+        constraint = user_lp.mint == lp_mint.key()
     )]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        constraint = vault_b.mint == pool.token_b_mint,
-        constraint = vault_b.owner == vault_authority.key()
+        seeds = [LOCKED_LP_SEED, pool.key().as_ref()],
+        bump,
+        constraint = locked_lp.mint == lp_mint.key()
     )]
-    pub vault_b: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub lp_mint: Account<'info, Mint>,
-
-    #[account(mut)]
-    pub user_lp: Account<'info, TokenAccount>,
+    pub locked_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: PDA authority
     #[account(
-    seeds = [VAULT_AUTH_SEED],
-    bump
+    seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+    bump = pool.vault_authority_bump
     )]
     pub vault_authority: UncheckedAccount<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
 }
\ No newline at end of file
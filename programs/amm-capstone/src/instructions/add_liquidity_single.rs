@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{
+    mint_to,
+    Mint,
+    MintTo,
+    TokenAccount,
+    TokenInterface,
+};
+
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::AmmError;
+use crate::utils::*;
+
+#[derive(Accounts)]
+pub struct AddLiquiditySingle<'info> {
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = amm_config,
+        has_one = lp_mint,
+        has_one = token_a_mint,
+        has_one = token_b_mint
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    // Either pool token; its mint picks the side deposited
+    #[account(
+        mut,
+        constraint = user_token_in.mint == pool.token_a_mint
+            || user_token_in.mint == pool.token_b_mint @ AmmError::InvalidMint,
+    )]
+    pub user_token_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_lp.mint == lp_mint.key()
+    )]
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: PDA authority
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+/// Deposits `amount_in` of one pool token alone and mints LP by the
+/// growth of the weighted invariant (see
+/// `PoolState::quote_single_sided_deposit`). The part that stands in for
+/// a swap pays the swap fee, split with the protocol and fund as on a
+/// swap. Needs a funded pool on a weighted or constant-product curve.
+pub fn add_liquidity_single(
+    ctx: Context<AddLiquiditySingle>,
+    amount_in: u64,
+    min_lp_out: u64,
+) -> Result<()> {
+
+    let pool = &ctx.accounts.pool;
+    require!(
+        !pool.is_disabled(PoolStatusBit::Deposit),
+        AmmError::OperationDisabled
+    );
+    require!(amount_in > 0, AmmError::InvalidAmount);
+
+    // Part of the deposit trades against the pool, so it waits for
+    // swaps to open
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= pool.swap_start_ts, AmmError::SwapNotStarted);
+
+    let token_a = ctx.accounts.user_token_in.mint == pool.token_a_mint;
+    let (mint_in, vault_in, program_in) = if token_a {
+        (&ctx.accounts.token_a_mint, &ctx.accounts.vault_a, &ctx.accounts.token_a_program)
+    } else {
+        (&ctx.accounts.token_b_mint, &ctx.accounts.vault_b, &ctx.accounts.token_b_program)
+    };
+
+    // Only what reaches the vault is credited
+    let received = amount_in
+        .checked_sub(get_transfer_fee(mint_in, amount_in)?)
+        .ok_or(AmmError::Overflow)?;
+
+    let config = &ctx.accounts.amm_config;
+    let deposit = pool
+        .quote_state(now)
+        .with_fee_shares(config.protocol_fee_rate, config.fund_fee_rate)
+        .quote_single_sided_deposit(token_a, received, ctx.accounts.lp_mint.supply)
+        .map_err(AmmError::from)?;
+
+    require!(
+        deposit.liquidity >= min_lp_out,
+        AmmError::SlippageExceeded
+    );
+
+    transfer_to_vault(
+        ctx.accounts.user.to_account_info(),
+        ctx.accounts.user_token_in.to_account_info(),
+        vault_in.to_account_info(),
+        mint_in,
+        program_in.to_account_info(),
+        amount_in,
+    )?;
+
+    // PDA signer
+    let pool_key = pool.key();
+    let bump = pool.vault_authority_bump;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        VAULT_AUTH_SEED,
+        pool_key.as_ref(),
+        &[bump],
+    ]];
+
+    mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                to: ctx.accounts.user_lp.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        deposit.liquidity,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.update_price_cumulative(now);
+
+    // Protocol and fund shares of the fee stay in the vault, outside
+    // reserves, as on a swap
+    if token_a {
+        pool.reserve_a = deposit.new_reserve;
+        pool.protocol_fees_a = pool.protocol_fees_a
+            .checked_add(deposit.protocol_fee)
+            .ok_or(AmmError::Overflow)?;
+        pool.fund_fees_a = pool.fund_fees_a
+            .checked_add(deposit.fund_fee)
+            .ok_or(AmmError::Overflow)?;
+    } else {
+        pool.reserve_b = deposit.new_reserve;
+        pool.protocol_fees_b = pool.protocol_fees_b
+            .checked_add(deposit.protocol_fee)
+            .ok_or(AmmError::Overflow)?;
+        pool.fund_fees_b = pool.fund_fees_b
+            .checked_add(deposit.fund_fee)
+            .ok_or(AmmError::Overflow)?;
+    }
+
+    // Vaults must still back reserves plus accrued fees
+    ctx.accounts.vault_a.reload()?;
+    ctx.accounts.vault_b.reload()?;
+    pool.check_reserves(
+        ctx.accounts.vault_a.amount,
+        ctx.accounts.vault_b.amount,
+    )?;
+
+    Ok(())
+}
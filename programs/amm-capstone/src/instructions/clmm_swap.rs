@@ -0,0 +1,255 @@
+use std::cell::RefMut;
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::*;
+use crate::constants::*;
+use crate::events::SwapEvent;
+use crate::clmm_engine::compute_clmm_swap;
+use crate::engine::{after_transfer_fee, pre_fee_amount, transfer_fee, SwapMode};
+use crate::math::{MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64};
+use crate::utils::*;
+use crate::errors::AmmError as ErrorCode;
+
+#[derive(Accounts)]
+pub struct ClmmSwap<'info> {
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = amm_config,
+        has_one = token_a_mint,
+        has_one = token_b_mint
+    )]
+    pub pool: Box<Account<'info, ClmmPool>>,
+
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(
+        mut,
+        constraint = user_input.owner == user.key(),
+        constraint = user_input.mint == pool.token_a_mint
+            || user_input.mint == pool.token_b_mint @ ErrorCode::InvalidMint,
+    )]
+    pub user_input: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_output.owner == user.key(),
+        constraint = user_output.mint == pool.token_a_mint
+            || user_output.mint == pool.token_b_mint @ ErrorCode::InvalidMint,
+        constraint = user_output.mint != user_input.mint @ ErrorCode::InvalidMint,
+    )]
+    pub user_output: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: PDA authority
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+    // remaining_accounts: the pool's tick arrays along the swap's path,
+    // writable, starting with the one holding the current tick
+}
+
+/// Shared handler for both concentrated liquidity swap entrypoints.
+/// `amount` is the side fixed by `mode`; `other_amount_threshold`
+/// bounds the other side (minimum received for exact-in, maximum spent
+/// for exact-out). `sqrt_price_limit_x64` caps how far the price may
+/// move, zero meaning as far as the tick range allows; an exact-in swap
+/// that reaches it fills partially.
+pub fn clmm_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClmmSwap<'info>>,
+    mode: SwapMode,
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit_x64: u128,
+) -> Result<()> {
+
+    let accounts = ctx.accounts;
+    let pool_key = accounts.pool.key();
+
+    // ---------------- Pool Safety ----------------
+    require!(
+        !accounts.pool.is_disabled(PoolStatusBit::Swap),
+        ErrorCode::OperationDisabled
+    );
+
+    // ---------------- Detect Direction ----------------
+    // As for `swap`, the constraints make the input mint decide it
+    let a_to_b = accounts.user_input.mint == accounts.pool.token_a_mint;
+    let sqrt_price_limit_x64 = match sqrt_price_limit_x64 {
+        0 if a_to_b => MIN_SQRT_PRICE_X64 + 1,
+        0 => MAX_SQRT_PRICE_X64 - 1,
+        limit => limit,
+    };
+
+    let (mint_in, mint_out, program_in, program_out, vault_in, vault_out) = if a_to_b {
+        (
+            &accounts.token_a_mint,
+            &accounts.token_b_mint,
+            &accounts.token_a_program,
+            &accounts.token_b_program,
+            &accounts.vault_a,
+            &accounts.vault_b,
+        )
+    } else {
+        (
+            &accounts.token_b_mint,
+            &accounts.token_a_mint,
+            &accounts.token_b_program,
+            &accounts.token_a_program,
+            &accounts.vault_b,
+            &accounts.vault_a,
+        )
+    };
+    let fee_in = get_epoch_transfer_fee(mint_in)?;
+    let fee_out = get_epoch_transfer_fee(mint_out)?;
+
+    // ---------------- Tick Arrays ----------------
+    let loaders = ctx
+        .remaining_accounts
+        .iter()
+        .map(AccountLoader::<TickArrayState>::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    let mut tick_arrays = loaders
+        .iter()
+        .map(|loader| loader.load_mut())
+        .collect::<Result<Vec<RefMut<TickArrayState>>>>()?;
+    require!(
+        tick_arrays.iter().all(|array| array.pool == pool_key),
+        ErrorCode::InvalidTickArray
+    );
+    let mut tick_arrays: Vec<&mut TickArrayState> =
+        tick_arrays.iter_mut().map(|array| &mut **array).collect();
+
+    // ---------------- Price Calculation ----------------
+    // The curve only prices what reaches or leaves the vaults
+    let config = &accounts.amm_config;
+    let vault_amount = match mode {
+        SwapMode::ExactIn => after_transfer_fee(fee_in, amount)?,
+        SwapMode::ExactOut => pre_fee_amount(fee_out, amount)?,
+    };
+    let result = compute_clmm_swap(
+        &accounts.pool,
+        &mut tick_arrays,
+        a_to_b,
+        vault_amount,
+        mode,
+        sqrt_price_limit_x64,
+        config.protocol_fee_rate,
+        config.fund_fee_rate,
+    )?;
+    drop(tick_arrays);
+
+    // A partial fill takes only what it used
+    let amount_in = if mode == SwapMode::ExactIn && result.amount_in == vault_amount {
+        amount
+    } else {
+        pre_fee_amount(fee_in, result.amount_in)?
+    };
+    let received_out = result
+        .amount_out
+        .checked_sub(transfer_fee(fee_out, result.amount_out)?)
+        .ok_or(ErrorCode::Overflow)?;
+
+    // ---------------- Slippage ----------------
+    match mode {
+        SwapMode::ExactIn => require!(
+            received_out >= other_amount_threshold,
+            ErrorCode::SlippageExceeded
+        ),
+        SwapMode::ExactOut => require!(
+            amount_in <= other_amount_threshold,
+            ErrorCode::SlippageExceeded
+        ),
+    }
+
+    // ---------------- Transfer input ----------------
+    transfer_to_vault(
+        accounts.user.to_account_info(),
+        accounts.user_input.to_account_info(),
+        vault_in.to_account_info(),
+        mint_in,
+        program_in.to_account_info(),
+        amount_in,
+    )?;
+
+    // ---------------- PDA signer ----------------
+    let bump = accounts.pool.vault_authority_bump;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        VAULT_AUTH_SEED,
+        pool_key.as_ref(),
+        &[bump],
+    ]];
+
+    // ---------------- Transfer output ----------------
+    transfer_from_vault(
+        accounts.vault_authority.to_account_info(),
+        vault_out.to_account_info(),
+        accounts.user_output.to_account_info(),
+        mint_out,
+        program_out.to_account_info(),
+        result.amount_out,
+        signer_seeds,
+    )?;
+
+    // ---------------- Pool State ----------------
+    let pool = &mut accounts.pool;
+    pool.sqrt_price_x64 = result.sqrt_price_x64;
+    pool.tick_current = result.tick_current;
+    pool.liquidity = result.liquidity;
+    pool.fee_growth_global_a_x64 = result.fee_growth_global_a_x64;
+    pool.fee_growth_global_b_x64 = result.fee_growth_global_b_x64;
+
+    // ---------------- Protocol / Fund Fee Accrual ----------------
+    if a_to_b {
+        pool.protocol_fees_a = pool.protocol_fees_a
+            .checked_add(result.protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        pool.fund_fees_a = pool.fund_fees_a
+            .checked_add(result.fund_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    } else {
+        pool.protocol_fees_b = pool.protocol_fees_b
+            .checked_add(result.protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        pool.fund_fees_b = pool.fund_fees_b
+            .checked_add(result.fund_fee)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    // ---------------- Emit Event ----------------
+    emit!(SwapEvent {
+        user: accounts.user.key(),
+        pool: pool_key,
+        input_mint: accounts.user_input.mint,
+        output_mint: accounts.user_output.mint,
+        amount_in,
+        amount_out: result.amount_out,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::*;
+use crate::constants::*;
+use crate::events::PositionCollected;
+use crate::utils::*;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct Collect<'info> {
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = token_a_mint,
+        has_one = token_b_mint
+    )]
+    pub pool: Box<Account<'info, ClmmPool>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner @ AmmError::Unauthorized
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    // May be the same account when both bounds fall in one array
+    #[account(mut)]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(mut)]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    // Any account of the right mint; the owner chooses where tokens go
+    #[account(
+        mut,
+        constraint = recipient_token_a.mint == pool.token_a_mint @ AmmError::InvalidMint
+    )]
+    pub recipient_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_b.mint == pool.token_b_mint @ AmmError::InvalidMint
+    )]
+    pub recipient_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: PDA authority
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+/// Sends the position's fees and withdrawn liquidity, up to
+/// `amount_a_max` / `amount_b_max`, to the recipient accounts. Fees
+/// earned since the last update are credited first.
+pub fn collect(
+    ctx: Context<Collect>,
+    amount_a_max: u64,
+    amount_b_max: u64,
+) -> Result<()> {
+
+    let accounts = ctx.accounts;
+
+    if accounts.position.liquidity > 0 {
+        update_position(
+            &mut accounts.pool,
+            &mut accounts.position,
+            &accounts.tick_array_lower,
+            &accounts.tick_array_upper,
+            0,
+        )?;
+    }
+
+    let position = &mut accounts.position;
+    let amount_a = position.tokens_owed_a.min(amount_a_max);
+    let amount_b = position.tokens_owed_b.min(amount_b_max);
+    position.tokens_owed_a -= amount_a;
+    position.tokens_owed_b -= amount_b;
+
+    // ---------------- PDA signer ----------------
+    let pool_key = accounts.pool.key();
+    let bump = accounts.pool.vault_authority_bump;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        VAULT_AUTH_SEED,
+        pool_key.as_ref(),
+        &[bump],
+    ]];
+
+    // ---------------- Transfer owed tokens ----------------
+    transfer_from_vault(
+        accounts.vault_authority.to_account_info(),
+        accounts.vault_a.to_account_info(),
+        accounts.recipient_token_a.to_account_info(),
+        &accounts.token_a_mint,
+        accounts.token_a_program.to_account_info(),
+        amount_a,
+        signer_seeds,
+    )?;
+
+    transfer_from_vault(
+        accounts.vault_authority.to_account_info(),
+        accounts.vault_b.to_account_info(),
+        accounts.recipient_token_b.to_account_info(),
+        &accounts.token_b_mint,
+        accounts.token_b_program.to_account_info(),
+        amount_b,
+        signer_seeds,
+    )?;
+
+    emit!(PositionCollected {
+        pool: pool_key,
+        position: accounts.position.key(),
+        recipient_token_a: accounts.recipient_token_a.key(),
+        recipient_token_b: accounts.recipient_token_b.key(),
+        amount_a,
+        amount_b,
+    });
+
+    Ok(())
+}
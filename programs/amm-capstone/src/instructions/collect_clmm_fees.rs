@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    Mint,
+    TokenAccount,
+    TokenInterface,
+};
+
+use crate::state::*;
+use crate::constants::*;
+use crate::events::FeesCollected;
+use crate::utils::transfer_from_vault;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct CollectClmmFees<'info> {
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [TREASURY_SEED],
+        bump,
+        has_one = authority @ AmmError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = token_a_mint,
+        has_one = token_b_mint
+    )]
+    pub pool: Account<'info, ClmmPool>,
+
+    #[account(mut)]
+    pub vault_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub vault_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    // Fee destinations must belong to the treasury authority
+    #[account(
+        mut,
+        constraint = treasury_token_a.mint == pool.token_a_mint @ AmmError::InvalidMint,
+        constraint = treasury_token_a.owner == treasury.authority @ AmmError::Unauthorized
+    )]
+    pub treasury_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_b.mint == pool.token_b_mint @ AmmError::InvalidMint,
+        constraint = treasury_token_b.owner == treasury.authority @ AmmError::Unauthorized
+    )]
+    pub treasury_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA authority
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+/// Sends a concentrated liquidity pool's accrued protocol fees to the
+/// treasury authority, as `collect_fees` does for `Pool`.
+pub fn collect_clmm_fees(ctx: Context<CollectClmmFees>) -> Result<()> {
+
+    let pool = &mut ctx.accounts.pool;
+
+    let amount_a = pool.protocol_fees_a;
+    let amount_b = pool.protocol_fees_b;
+
+    // ---------------- PDA signer ----------------
+    let pool_key = pool.key();
+    let bump = pool.vault_authority_bump;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        VAULT_AUTH_SEED,
+        pool_key.as_ref(),
+        &[bump],
+    ]];
+
+    // ---------------- Transfer accrued fees ----------------
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_a.to_account_info(),
+        ctx.accounts.treasury_token_a.to_account_info(),
+        &ctx.accounts.token_a_mint,
+        ctx.accounts.token_a_program.to_account_info(),
+        amount_a,
+        signer_seeds,
+    )?;
+
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_b.to_account_info(),
+        ctx.accounts.treasury_token_b.to_account_info(),
+        &ctx.accounts.token_b_mint,
+        ctx.accounts.token_b_program.to_account_info(),
+        amount_b,
+        signer_seeds,
+    )?;
+
+    // ---------------- Reset Counters ----------------
+    pool.protocol_fees_a = 0;
+    pool.protocol_fees_b = 0;
+
+    emit!(FeesCollected {
+        pool: pool.key(),
+        authority: ctx.accounts.authority.key(),
+        amount_a,
+        amount_b,
+    });
+
+    Ok(())
+}
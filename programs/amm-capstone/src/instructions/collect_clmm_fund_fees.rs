@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    Mint,
+    TokenAccount,
+    TokenInterface,
+};
+
+use crate::state::*;
+use crate::constants::*;
+use crate::events::FundFeesCollected;
+use crate::utils::transfer_from_vault;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct CollectClmmFundFees<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [AMM_CONFIG_SEED],
+        bump = amm_config.bump,
+        has_one = admin @ AmmError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = token_a_mint,
+        has_one = token_b_mint,
+        has_one = amm_config
+    )]
+    pub pool: Account<'info, ClmmPool>,
+
+    #[account(mut)]
+    pub vault_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub vault_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    // Fund fee destinations must belong to the config admin
+    #[account(
+        mut,
+        constraint = fund_token_a.mint == pool.token_a_mint @ AmmError::InvalidMint,
+        constraint = fund_token_a.owner == admin.key() @ AmmError::Unauthorized
+    )]
+    pub fund_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = fund_token_b.mint == pool.token_b_mint @ AmmError::InvalidMint,
+        constraint = fund_token_b.owner == admin.key() @ AmmError::Unauthorized
+    )]
+    pub fund_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA authority
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+/// Sends a concentrated liquidity pool's accrued fund fees to the
+/// config admin, as `collect_fund_fees` does for `Pool`.
+pub fn collect_clmm_fund_fees(ctx: Context<CollectClmmFundFees>) -> Result<()> {
+
+    let pool = &mut ctx.accounts.pool;
+
+    let amount_a = pool.fund_fees_a;
+    let amount_b = pool.fund_fees_b;
+
+    // ---------------- PDA signer ----------------
+    let pool_key = pool.key();
+    let bump = pool.vault_authority_bump;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        VAULT_AUTH_SEED,
+        pool_key.as_ref(),
+        &[bump],
+    ]];
+
+    // ---------------- Transfer accrued fees ----------------
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_a.to_account_info(),
+        ctx.accounts.fund_token_a.to_account_info(),
+        &ctx.accounts.token_a_mint,
+        ctx.accounts.token_a_program.to_account_info(),
+        amount_a,
+        signer_seeds,
+    )?;
+
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_b.to_account_info(),
+        ctx.accounts.fund_token_b.to_account_info(),
+        &ctx.accounts.token_b_mint,
+        ctx.accounts.token_b_program.to_account_info(),
+        amount_b,
+        signer_seeds,
+    )?;
+
+    // ---------------- Reset Counters ----------------
+    pool.fund_fees_a = 0;
+    pool.fund_fees_b = 0;
+
+    emit!(FundFeesCollected {
+        pool: pool_key,
+        admin: ctx.accounts.admin.key(),
+        amount_a,
+        amount_b,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::AmmError;
+use crate::utils::is_supported_mint;
+
+#[derive(Accounts)]
+#[instruction(fee_tier_index: u8)]
+pub struct CreateClmmPool<'info> {
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [AMM_CONFIG_SEED],
+        bump = amm_config.bump
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    // Receives the pool-creation fee
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // In canonical order, as for `create_pool`
+    #[account(
+        mint::token_program = token_b_program,
+        constraint = token_a_mint.key() < token_b_mint.key() @ AmmError::InvalidMintOrder
+    )]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            CLMM_POOL_SEED,
+            amm_config.key().as_ref(),
+            token_a_mint.key().as_ref(),
+            token_b_mint.key().as_ref(),
+            &amm_config.fee_tier(fee_tier_index)?.to_le_bytes()
+        ],
+        bump,
+        space = 8 + ClmmPool::LEN
+    )]
+    pub pool: Box<Account<'info, ClmmPool>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [VAULT_A_SEED, pool.key().as_ref()],
+        bump,
+        token::mint = token_a_mint,
+        token::authority = vault_authority,
+        token::token_program = token_a_program
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [VAULT_B_SEED, pool.key().as_ref()],
+        bump,
+        token::mint = token_b_mint,
+        token::authority = vault_authority,
+        token::token_program = token_b_program
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: PDA authority, unique to this pool
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Creates a concentrated liquidity pool at `sqrt_price_x64` (Q64.64
+/// square root of the price of A in B). Positions must be opened, and
+/// the tick arrays they and swaps touch initialized, before it trades.
+pub fn create_clmm_pool(
+    ctx: Context<CreateClmmPool>,
+    fee_tier_index: u8,
+    tick_spacing: u16,
+    sqrt_price_x64: u128,
+) -> Result<()> {
+
+    let fee_bps = ctx.accounts.amm_config.fee_tier(fee_tier_index)?;
+    let tick_current = ClmmPool::validate_params(tick_spacing, sqrt_price_x64)?;
+
+    require!(
+        is_supported_mint(&ctx.accounts.token_a_mint)?
+            && is_supported_mint(&ctx.accounts.token_b_mint)?,
+        AmmError::UnsupportedMint
+    );
+
+    // Pool-creation fee goes to the treasury, as for `create_pool`
+    let create_pool_fee = ctx.accounts.amm_config.create_pool_fee;
+    if create_pool_fee > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            create_pool_fee,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+
+    pool.amm_config = ctx.accounts.amm_config.key();
+    pool.token_a_mint = ctx.accounts.token_a_mint.key();
+    pool.token_b_mint = ctx.accounts.token_b_mint.key();
+    pool.vault_a = ctx.accounts.vault_a.key();
+    pool.vault_b = ctx.accounts.vault_b.key();
+    pool.vault_authority = ctx.accounts.vault_authority.key();
+    pool.vault_authority_bump = ctx.bumps.vault_authority;
+    pool.tick_spacing = tick_spacing;
+    pool.fee_bps = fee_bps;
+    pool.liquidity = 0;
+    pool.sqrt_price_x64 = sqrt_price_x64;
+    pool.tick_current = tick_current;
+    pool.fee_growth_global_a_x64 = 0;
+    pool.fee_growth_global_b_x64 = 0;
+    pool.protocol_fees_a = 0;
+    pool.protocol_fees_b = 0;
+    pool.fund_fees_a = 0;
+    pool.fund_fees_b = 0;
+
+    Ok(())
+}
@@ -1,72 +1,257 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount}; // Import TokenAccount
-use crate::state::pool::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::state::*;
 use crate::constants::*;
+use crate::errors::AmmError;
+use crate::math::{valid_weights, MAX_AMP, MIN_AMP};
+
+/// Pricing curve for a new pool; it cannot change afterwards.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveParams {
+    /// `x * y = k`
+    ConstantProduct,
+    /// StableSwap for pegged pairs. Reserves are priced unit for unit,
+    /// so both mints should share their decimals.
+    Stable { amp: u64 },
+    /// Balancer-style weighted product. Weights are fractions of
+    /// `WEIGHT_ONE`, each at least `MIN_WEIGHT`, summing to `WEIGHT_ONE`.
+    Weighted { weight_a: u64, weight_b: u64 },
+    /// Liquidity bootstrapping pool: a weighted pool whose weights move
+    /// linearly from the start pair at `start_ts` to the end pair at
+    /// `end_ts`. Swaps open at `start_ts`; the creator may reschedule
+    /// the weights with `update_weights_gradually`.
+    LiquidityBootstrapping {
+        start_weight_a: u64,
+        start_weight_b: u64,
+        end_weight_a: u64,
+        end_weight_b: u64,
+        start_ts: i64,
+        end_ts: i64,
+    },
+}
+
+/// Curve fields to store on the pool; zero where a curve has no use
+/// for them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CurveConfig {
+    pub curve_type: u8,
+    pub amp: u64,
+    pub weight_a: u64,
+    pub weight_b: u64,
+    pub end_weight_a: u64,
+    pub end_weight_b: u64,
+    pub weights_start_ts: i64,
+    pub weights_end_ts: i64,
+    /// When swaps open.
+    pub swap_start_ts: i64,
+}
+
+impl CurveParams {
+    /// The kind of curve, part of the pool's address.
+    pub fn curve_type(self) -> CurveType {
+        match self {
+            CurveParams::ConstantProduct => CurveType::ConstantProduct,
+            CurveParams::Stable { .. } => CurveType::Stable,
+            CurveParams::Weighted { .. } => CurveType::Weighted,
+            CurveParams::LiquidityBootstrapping { .. } => CurveType::LiquidityBootstrapping,
+        }
+    }
+
+    /// Checks the parameters for a pool created at `now`.
+    pub fn validate(self, now: i64) -> Result<CurveConfig> {
+        let created = CurveConfig {
+            curve_type: self.curve_type() as u8,
+            weights_start_ts: now,
+            weights_end_ts: now,
+            swap_start_ts: now,
+            ..Default::default()
+        };
+
+        match self {
+            CurveParams::ConstantProduct => Ok(created),
+            CurveParams::Stable { amp } => {
+                require!(
+                    (MIN_AMP..=MAX_AMP).contains(&amp),
+                    AmmError::InvalidAmplification
+                );
+                Ok(CurveConfig {
+                    amp,
+                    ..created
+                })
+            }
+            CurveParams::Weighted { weight_a, weight_b } => {
+                require!(
+                    valid_weights(weight_a, weight_b),
+                    AmmError::InvalidWeights
+                );
+                Ok(CurveConfig {
+                    weight_a,
+                    weight_b,
+                    end_weight_a: weight_a,
+                    end_weight_b: weight_b,
+                    ..created
+                })
+            }
+            CurveParams::LiquidityBootstrapping {
+                start_weight_a,
+                start_weight_b,
+                end_weight_a,
+                end_weight_b,
+                start_ts,
+                end_ts,
+            } => {
+                require!(
+                    valid_weights(start_weight_a, start_weight_b)
+                        && valid_weights(end_weight_a, end_weight_b),
+                    AmmError::InvalidWeights
+                );
+                require!(end_ts > start_ts, AmmError::InvalidWeightSchedule);
+                Ok(CurveConfig {
+                    weight_a: start_weight_a,
+                    weight_b: start_weight_b,
+                    end_weight_a,
+                    end_weight_b,
+                    weights_start_ts: start_ts,
+                    weights_end_ts: end_ts,
+                    swap_start_ts: start_ts,
+                    ..created
+                })
+            }
+        }
+    }
+}
 
 #[derive(Accounts)]
+#[instruction(fee_tier_index: u8, curve: CurveParams)]
 pub struct CreatePool<'info> {
 
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    pub token_a_mint: Account<'info, Mint>,
-    pub token_b_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [AMM_CONFIG_SEED],
+        bump = amm_config.bump
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    // Receives the pool-creation fee
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // Mints in canonical order, so a pair has one pool per config, fee
+    // rate and curve however it is passed
+    #[account(
+        mint::token_program = token_b_program,
+        constraint = token_a_mint.key() < token_b_mint.key() @ AmmError::InvalidMintOrder
+    )]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init,
         payer = payer,
         seeds = [
             POOL_SEED,
+            amm_config.key().as_ref(),
             token_a_mint.key().as_ref(),
-            token_b_mint.key().as_ref()
+            token_b_mint.key().as_ref(),
+            // The rate, not its index, so reordering the config's
+            // tiers cannot open a second pool at the same fee
+            &amm_config.fee_tier(fee_tier_index)?.to_le_bytes(),
+            &[curve.curve_type() as u8]
         ],
         bump,
         space = 8 + Pool::LEN
     )]
     pub pool: Account<'info, Pool>,
 
-    // --- ADD THIS: Vault A Initialization ---
     #[account(
         init,
         payer = payer,
-        seeds = [
-            POOL_SEED,
-            token_a_mint.key().as_ref(),
-            token_b_mint.key().as_ref(),
-            b"vault_a" // Must match the seed in your test
-        ],
+        seeds = [VAULT_A_SEED, pool.key().as_ref()],
         bump,
         token::mint = token_a_mint,
-        token::authority = vault_authority
+        token::authority = vault_authority,
+        token::token_program = token_a_program
     )]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    // --- ADD THIS: Vault B Initialization ---
     #[account(
         init,
         payer = payer,
-        seeds = [
-            POOL_SEED,
-            token_a_mint.key().as_ref(),
-            token_b_mint.key().as_ref(),
-            b"vault_b" // Must match the seed in your test
-        ],
+        seeds = [VAULT_B_SEED, pool.key().as_ref()],
         bump,
         token::mint = token_b_mint,
-        token::authority = vault_authority
+        token::authority = vault_authority,
+        token::token_program = token_b_program
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // LP mint is owned by the program: only the pool's vault authority
+    // can mint, and it always starts with zero supply.
+    #[account(
+        init,
+        payer = payer,
+        seeds = [LP_MINT_SEED, pool.key().as_ref()],
+        bump,
+        mint::decimals = Pool::lp_decimals(
+            token_a_mint.decimals,
+            token_b_mint.decimals
+        ),
+        mint::authority = vault_authority,
+        mint::token_program = token_program
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: PDA that owns the locked LP account. The program never
+    /// signs with these seeds, so anything sent there is burned for good.
+    #[account(
+        seeds = [LOCKED_LP_AUTH_SEED, pool.key().as_ref()],
+        bump
     )]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub locked_lp_authority: UncheckedAccount<'info>,
 
-    pub lp_mint: Account<'info, Mint>,
+    // Receives MINIMUM_LIQUIDITY on the first deposit
+    #[account(
+        init,
+        payer = payer,
+        seeds = [LOCKED_LP_SEED, pool.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = locked_lp_authority,
+        token::token_program = token_program
+    )]
+    pub locked_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Price history ring, written from the swap path
+    #[account(
+        init,
+        payer = payer,
+        seeds = [OBSERVATION_SEED, pool.key().as_ref()],
+        bump,
+        space = 8 + ObservationState::LEN
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
 
-    /// CHECK: PDA authority
+    /// CHECK: PDA authority, unique to this pool
     #[account(
-        seeds = [VAULT_AUTH_SEED],
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
         bump
     )]
     pub vault_authority: UncheckedAccount<'info>,
 
+    // LP mint always lives on the classic token program; each pool
+    // token may be on either SPL Token or Token-2022.
     pub token_program: Program<'info, Token>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::events::LiquidityDecreased;
+use crate::utils::update_position;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct DecreaseLiquidity<'info> {
+
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub pool: Box<Account<'info, ClmmPool>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner @ AmmError::Unauthorized
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    // May be the same account when both bounds fall in one array
+    #[account(mut)]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(mut)]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+}
+
+/// Removes `liquidity` from the position. The tokens it was worth,
+/// rounded down, are added to what the position is owed and leave the
+/// vaults on `collect`; `amount_a_min` / `amount_b_min` bound them.
+pub fn decrease_liquidity(
+    ctx: Context<DecreaseLiquidity>,
+    liquidity: u128,
+    amount_a_min: u64,
+    amount_b_min: u64,
+) -> Result<()> {
+
+    require!(
+        !ctx.accounts.pool.is_disabled(PoolStatusBit::Withdraw),
+        AmmError::OperationDisabled
+    );
+    require!(liquidity > 0, AmmError::InvalidAmount);
+    let liquidity_delta = i128::try_from(liquidity)
+        .map_err(|_| AmmError::InsufficientLiquidity)?
+        .checked_neg()
+        .ok_or(AmmError::Overflow)?;
+
+    let accounts = ctx.accounts;
+    let (amount_a, amount_b) = update_position(
+        &mut accounts.pool,
+        &mut accounts.position,
+        &accounts.tick_array_lower,
+        &accounts.tick_array_upper,
+        liquidity_delta,
+    )?;
+
+    require!(
+        amount_a >= amount_a_min && amount_b >= amount_b_min,
+        AmmError::SlippageExceeded
+    );
+
+    let position = &mut accounts.position;
+    position.tokens_owed_a = position
+        .tokens_owed_a
+        .checked_add(amount_a)
+        .ok_or(AmmError::Overflow)?;
+    position.tokens_owed_b = position
+        .tokens_owed_b
+        .checked_add(amount_b)
+        .ok_or(AmmError::Overflow)?;
+
+    emit!(LiquidityDecreased {
+        pool: accounts.pool.key(),
+        position: position.key(),
+        liquidity,
+        amount_a,
+        amount_b,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::*;
+use crate::engine::pre_fee_amount;
+use crate::events::LiquidityIncreased;
+use crate::utils::*;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct IncreaseLiquidity<'info> {
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = token_a_mint,
+        has_one = token_b_mint
+    )]
+    pub pool: Box<Account<'info, ClmmPool>>,
+
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner @ AmmError::Unauthorized
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    // May be the same account when both bounds fall in one array
+    #[account(mut)]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(mut)]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool.token_a_mint @ AmmError::InvalidMint,
+        constraint = user_token_a.owner == owner.key()
+    )]
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool.token_b_mint @ AmmError::InvalidMint,
+        constraint = user_token_b.owner == owner.key()
+    )]
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+/// Adds `liquidity` to the position. The tokens it takes are rounded
+/// up, and `amount_a_max` / `amount_b_max` bound what leaves the owner,
+/// Token-2022 transfer fees included.
+pub fn increase_liquidity(
+    ctx: Context<IncreaseLiquidity>,
+    liquidity: u128,
+    amount_a_max: u64,
+    amount_b_max: u64,
+) -> Result<()> {
+
+    require!(
+        !ctx.accounts.pool.is_disabled(PoolStatusBit::Deposit),
+        AmmError::OperationDisabled
+    );
+    require!(liquidity > 0, AmmError::InvalidAmount);
+    let liquidity_delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+
+    let accounts = ctx.accounts;
+    let (amount_a, amount_b) = update_position(
+        &mut accounts.pool,
+        &mut accounts.position,
+        &accounts.tick_array_lower,
+        &accounts.tick_array_upper,
+        liquidity_delta,
+    )?;
+
+    // ---------------- Slippage ----------------
+    // The vaults must receive the full amounts, so the owner sends them
+    // grossed up by any transfer fee
+    let sent_a = pre_fee_amount(get_epoch_transfer_fee(&accounts.token_a_mint)?, amount_a)?;
+    let sent_b = pre_fee_amount(get_epoch_transfer_fee(&accounts.token_b_mint)?, amount_b)?;
+    require!(
+        sent_a <= amount_a_max && sent_b <= amount_b_max,
+        AmmError::SlippageExceeded
+    );
+
+    // ---------------- Transfer tokens ----------------
+    transfer_to_vault(
+        accounts.owner.to_account_info(),
+        accounts.user_token_a.to_account_info(),
+        accounts.vault_a.to_account_info(),
+        &accounts.token_a_mint,
+        accounts.token_a_program.to_account_info(),
+        sent_a,
+    )?;
+
+    transfer_to_vault(
+        accounts.owner.to_account_info(),
+        accounts.user_token_b.to_account_info(),
+        accounts.vault_b.to_account_info(),
+        &accounts.token_b_mint,
+        accounts.token_b_program.to_account_info(),
+        sent_b,
+    )?;
+
+    emit!(LiquidityIncreased {
+        pool: accounts.pool.key(),
+        position: accounts.position.key(),
+        liquidity,
+        amount_a,
+        amount_b,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::AmmError;
+use crate::program::AmmCapstone;
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+
+    // Becomes the config admin; only the program's upgrade authority
+    // may claim that, so the config cannot be front-run after deploy
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, AmmCapstone>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ AmmError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [AMM_CONFIG_SEED],
+        bump,
+        space = 8 + AmmConfig::LEN
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_config(
+    ctx: Context<InitConfig>,
+    fee_tiers: Vec<u16>,
+    protocol_fee_rate: u16,
+    fund_fee_rate: u16,
+    create_pool_fee: u64,
+) -> Result<()> {
+
+    let config = &mut ctx.accounts.amm_config;
+
+    config.admin = ctx.accounts.payer.key();
+    config.fee_tiers = fee_tiers;
+    config.protocol_fee_rate = protocol_fee_rate;
+    config.fund_fee_rate = fund_fee_rate;
+    config.create_pool_fee = create_pool_fee;
+    config.bump = ctx.bumps.amm_config;
+
+    config.validate()
+}
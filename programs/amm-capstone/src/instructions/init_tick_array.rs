@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitTickArray<'info> {
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub pool: Account<'info, ClmmPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            TICK_ARRAY_SEED,
+            pool.key().as_ref(),
+            &start_tick_index.to_le_bytes()
+        ],
+        bump,
+        space = 8 + TickArrayState::LEN
+    )]
+    pub tick_array: AccountLoader<'info, TickArrayState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the tick array starting at `start_tick_index`, a multiple of
+/// the pool's tick spacing times `TICK_ARRAY_SIZE`. Permissionless: the
+/// payer funds the rent.
+pub fn init_tick_array(ctx: Context<InitTickArray>, start_tick_index: i32) -> Result<()> {
+
+    require!(
+        TickArrayState::is_valid_start(start_tick_index, ctx.accounts.pool.tick_spacing),
+        AmmError::InvalidTickArray
+    );
+
+    let mut tick_array = ctx.accounts.tick_array.load_init()?;
+    tick_array.pool = ctx.accounts.pool.key();
+    tick_array.start_tick_index = start_tick_index;
+
+    Ok(())
+}
@@ -1,13 +1,26 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::constants::*;
+use crate::errors::AmmError;
+use crate::program::AmmCapstone;
 
 #[derive(Accounts)]
 pub struct InitTreasury<'info> {
 
+    // Becomes the treasury authority; gated on the upgrade authority
+    // like `InitConfig`, so no one can claim the fees first
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, AmmCapstone>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(payer.key())
+            @ AmmError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
     #[account(
         init,
         payer = payer,
@@ -18,4 +31,4 @@ pub struct InitTreasury<'info> {
     pub treasury: Account<'info, Treasury>,
 
     pub system_program: Program<'info, System>,
-}
\ No newline at end of file
+}
@@ -1,13 +1,57 @@
 pub mod create_pool;
 pub mod add_liquidity;
+pub mod add_liquidity_single;
 pub mod remove_liquidity;
 pub mod swap;
 pub mod collect_fees;
 pub mod init_treasury;
+pub mod init_config;
+pub mod update_config;
+pub mod collect_fund_fees;
+pub mod set_pool_status;
+pub mod observe;
+pub mod sync;
+pub mod skim;
+pub mod ramp_amp;
+pub mod update_weights_gradually;
+pub mod create_clmm_pool;
+pub mod init_tick_array;
+pub mod open_position;
+pub mod increase_liquidity;
+pub mod decrease_liquidity;
+pub mod collect;
+pub mod clmm_swap;
+pub mod collect_clmm_fees;
+pub mod collect_clmm_fund_fees;
+pub mod set_treasury_authority;
+pub mod withdraw_treasury;
+pub mod set_clmm_pool_status;
 
 pub use create_pool::*;
 pub use add_liquidity::*;
+pub use add_liquidity_single::*;
 pub use remove_liquidity::*;
 pub use swap::*;
 pub use collect_fees::*;
-pub use init_treasury::*;
\ No newline at end of file
+pub use init_treasury::*;
+pub use init_config::*;
+pub use update_config::*;
+pub use collect_fund_fees::*;
+pub use set_pool_status::*;
+pub use observe::*;
+pub use sync::*;
+pub use skim::*;
+pub use ramp_amp::*;
+pub use update_weights_gradually::*;
+pub use create_clmm_pool::*;
+pub use init_tick_array::*;
+pub use open_position::*;
+pub use increase_liquidity::*;
+pub use decrease_liquidity::*;
+pub use collect::*;
+pub use clmm_swap::*;
+pub use collect_clmm_fees::*;
+pub use collect_clmm_fund_fees::*;
+pub use set_treasury_authority::*;
+pub use withdraw_treasury::*;
+pub use set_clmm_pool_status::*;
\ No newline at end of file
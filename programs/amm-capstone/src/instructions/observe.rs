@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::math::twap_between_observations;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(has_one = observation_state)]
+    pub pool: Account<'info, Pool>,
+
+    pub observation_state: AccountLoader<'info, ObservationState>,
+}
+
+/// A reading of the pool's price accumulators, as stored in the
+/// observation ring or extrapolated to the current time.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceSnapshot {
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+    pub timestamp: i64,
+}
+
+/// Time-weighted average prices, in Q64.64, over `start..current`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Twap {
+    // Price of token A in token B
+    pub price_a: u128,
+    // Price of token B in token A
+    pub price_b: u128,
+    pub start: PriceSnapshot,
+    pub current: PriceSnapshot,
+}
+
+/// Read-only: returns the TWAP over at least the last `seconds_ago`
+/// seconds via return data. The window starts at the newest observation
+/// in the ring taken at or before then, and ends now, with the
+/// accumulators extrapolated without being written, so this can be
+/// simulated or called over CPI.
+pub fn observe(ctx: Context<Observe>, seconds_ago: u32) -> Result<Twap> {
+
+    require!(seconds_ago > 0, AmmError::InvalidObservationWindow);
+
+    let now = Clock::get()?.unix_timestamp;
+    let observations = ctx.accounts.observation_state.load()?;
+
+    let start = observations
+        .observation_at_or_before(now - seconds_ago as i64)
+        .ok_or(AmmError::ObservationTooOld)?;
+
+    let (price_a_cumulative, price_b_cumulative) =
+        ctx.accounts.pool.cumulative_prices_at(now);
+
+    Ok(Twap {
+        price_a: twap_between_observations(
+            start.block_timestamp,
+            start.price_a_cumulative,
+            now,
+            price_a_cumulative,
+        )
+        .ok_or(AmmError::InvalidObservationWindow)?,
+        price_b: twap_between_observations(
+            start.block_timestamp,
+            start.price_b_cumulative,
+            now,
+            price_b_cumulative,
+        )
+        .ok_or(AmmError::InvalidObservationWindow)?,
+        start: PriceSnapshot {
+            price_a_cumulative: start.price_a_cumulative,
+            price_b_cumulative: start.price_b_cumulative,
+            timestamp: start.block_timestamp,
+        },
+        current: PriceSnapshot {
+            price_a_cumulative,
+            price_b_cumulative,
+            timestamp: now,
+        },
+    })
+}
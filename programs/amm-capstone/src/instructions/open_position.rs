@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+use crate::constants::*;
+
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenPosition<'info> {
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub pool: Account<'info, ClmmPool>,
+
+    // One position per owner and range
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            POSITION_SEED,
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes()
+        ],
+        bump,
+        space = 8 + Position::LEN
+    )]
+    pub position: Account<'info, Position>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Opens an empty position over `tick_lower..tick_upper`; liquidity
+/// goes in through `increase_liquidity`.
+pub fn open_position(ctx: Context<OpenPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+
+    ctx.accounts.pool.validate_tick_range(tick_lower, tick_upper)?;
+
+    let position = &mut ctx.accounts.position;
+
+    position.pool = ctx.accounts.pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.tick_lower = tick_lower;
+    position.tick_upper = tick_upper;
+    position.liquidity = 0;
+    position.fee_growth_inside_a_last_x64 = 0;
+    position.fee_growth_inside_b_last_x64 = 0;
+    position.tokens_owed_a = 0;
+    position.tokens_owed_b = 0;
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::events::{AmpRampStarted, AmpRampStopped};
+use crate::errors::AmmError;
+use crate::math::{MAX_AMP, MIN_AMP};
+
+#[derive(Accounts)]
+pub struct RampAmp<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [AMM_CONFIG_SEED],
+        bump = amm_config.bump,
+        has_one = admin @ AmmError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        mut,
+        has_one = amm_config
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+/// Moves a stable pool's amplification linearly from its current value
+/// to `target_amp`, reached at `stop_ts`. Replaces any ramp in progress.
+/// The ramp lasts at least `MIN_RAMP_DURATION` and changes A by at most
+/// `MAX_AMP_CHANGE` times, so a sudden jump cannot be arbitraged. As in
+/// Curve, it may only start `MIN_RAMP_DURATION` after the last ramp did,
+/// so back-to-back ramps cannot compound into one.
+pub fn ramp_amp(ctx: Context<RampAmp>, target_amp: u64, stop_ts: i64) -> Result<()> {
+
+    let pool = &mut ctx.accounts.pool;
+    require!(pool.is_stable(), AmmError::NotStablePool);
+    require!(
+        (MIN_AMP..=MAX_AMP).contains(&target_amp),
+        AmmError::InvalidAmplification
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= pool.ramp_start_ts.saturating_add(MIN_RAMP_DURATION),
+        AmmError::RampTooSoon
+    );
+    require!(
+        stop_ts >= now.saturating_add(MIN_RAMP_DURATION),
+        AmmError::InvalidRamp
+    );
+
+    let initial_amp = pool.amp_at(now);
+    require!(
+        target_amp <= initial_amp.saturating_mul(MAX_AMP_CHANGE)
+            && initial_amp <= target_amp.saturating_mul(MAX_AMP_CHANGE),
+        AmmError::InvalidRamp
+    );
+
+    pool.initial_amp = initial_amp;
+    pool.target_amp = target_amp;
+    pool.ramp_start_ts = now;
+    pool.ramp_stop_ts = stop_ts;
+
+    emit!(AmpRampStarted {
+        pool: pool.key(),
+        initial_amp,
+        target_amp,
+        start_ts: now,
+        stop_ts,
+    });
+
+    Ok(())
+}
+
+/// Freezes a stable pool's amplification at its current value.
+pub fn stop_ramp_amp(ctx: Context<RampAmp>) -> Result<()> {
+
+    let pool = &mut ctx.accounts.pool;
+    require!(pool.is_stable(), AmmError::NotStablePool);
+
+    let now = Clock::get()?.unix_timestamp;
+    let amp = pool.amp_at(now);
+
+    pool.initial_amp = amp;
+    pool.target_amp = amp;
+    pool.ramp_start_ts = now;
+    pool.ramp_stop_ts = now;
+
+    emit!(AmpRampStopped {
+        pool: pool.key(),
+        amp,
+        stopped_at: now,
+    });
+
+    Ok(())
+}
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{
-     TokenAccount,
-      Token,
-       Mint, };
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{
+    Mint,
+    TokenAccount,
+    TokenInterface,
+};
 
 use crate::state::*;
 use crate::constants::*;
@@ -11,45 +13,55 @@ use crate::constants::*;
 pub struct RemoveLiquidity<'info> {
     pub user: Signer<'info>,
 
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
-
-    #[account(mut)]
-    pub user_lp: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub lp_mint: Account<'info, Mint>,
     #[account(
         mut,
-        constraint = user_token_a.mint == pool.token_a_mint
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = lp_mint,
+        has_one = token_a_mint,
+        has_one = token_b_mint
     )]
-    pub user_token_a: Account<'info, TokenAccount>,
+    pub pool: Box<Account<'info, Pool>>,
+
     #[account(
         mut,
-        constraint = user_token_b.mint == pool.token_b_mint
+        constraint = user_lp.mint == lp_mint.key()
     )]
-    pub user_token_b: Account<'info, TokenAccount>,
-
+    pub user_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
-        constraint = vault_a.mint == vault_authority.key()
+        constraint = user_token_a.mint == pool.token_a_mint
     )]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
-        constraint = vault_b.mint == vault_authority.key()
+        constraint = user_token_b.mint == pool.token_b_mint
     )]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(
-        seeds = [VAULT_AUTH_SEED],
-        bump
-    )]
+    #[account(mut)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// CHECK: This is a PDA authority derived from VAULT_AUTH_SEED.
-/// It does not store data and is only used as a signing authority
-/// for token vault transfers.
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
 
+    /// CHECK: This is a PDA authority derived from VAULT_AUTH_SEED and
+    /// the pool key. It does not store data and is only used as a
+    /// signing authority for token vault transfers.
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
+    )]
     pub vault_authority: UncheckedAccount<'info>,
 
     pub token_program: Program<'info, Token>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::events::PoolStatusUpdated;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct SetClmmPoolStatus<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [AMM_CONFIG_SEED],
+        bump = amm_config.bump,
+        has_one = admin @ AmmError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        mut,
+        has_one = amm_config
+    )]
+    pub pool: Account<'info, ClmmPool>,
+}
+
+/// `set_pool_status` for a concentrated liquidity pool: `Deposit` stops
+/// `increase_liquidity`, `Withdraw` stops `decrease_liquidity` and `Swap`
+/// stops `clmm_swap`.
+pub fn set_clmm_pool_status(ctx: Context<SetClmmPoolStatus>, status: u8) -> Result<()> {
+
+    require!(status & !PoolStatusBit::ALL == 0, AmmError::InvalidStatus);
+
+    let pool = &mut ctx.accounts.pool;
+    pool.status = status;
+
+    emit!(PoolStatusUpdated {
+        pool: pool.key(),
+        status,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::events::PoolStatusUpdated;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct SetPoolStatus<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [AMM_CONFIG_SEED],
+        bump = amm_config.bump,
+        has_one = admin @ AmmError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        mut,
+        has_one = amm_config
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+/// Overwrites the pool's status bitmask. Each set `PoolStatusBit`
+/// disables the matching operation; `0` re-enables everything.
+pub fn set_pool_status(ctx: Context<SetPoolStatus>, status: u8) -> Result<()> {
+
+    require!(status & !PoolStatusBit::ALL == 0, AmmError::InvalidStatus);
+
+    let pool = &mut ctx.accounts.pool;
+    pool.status = status;
+
+    emit!(PoolStatusUpdated {
+        pool: pool.key(),
+        status,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct SetTreasuryAuthority<'info> {
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump,
+        has_one = authority @ AmmError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+}
+
+/// Hands the treasury, and with it every protocol fee collected from
+/// now on, to `new_authority`.
+pub fn set_treasury_authority(
+    ctx: Context<SetTreasuryAuthority>,
+    new_authority: Pubkey,
+) -> Result<()> {
+
+    ctx.accounts.treasury.authority = new_authority;
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    Mint,
+    TokenAccount,
+    TokenInterface,
+};
+
+use crate::state::*;
+use crate::constants::*;
+use crate::events::ExcessSkimmed;
+use crate::utils::transfer_from_vault;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct Skim<'info> {
+
+    #[account(
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = token_a_mint,
+        has_one = token_b_mint
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut)]
+    pub vault_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub vault_b: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_a.mint == pool.token_a_mint @ AmmError::InvalidMint
+    )]
+    pub recipient_token_a: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_b.mint == pool.token_b_mint @ AmmError::InvalidMint
+    )]
+    pub recipient_token_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA authority
+    #[account(
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
+
+/// Permissionless, as in Uniswap v2: sends whatever the vaults hold
+/// beyond reserves and accrued fees to the recipient accounts, leaving
+/// the pool exactly backed.
+pub fn skim(ctx: Context<Skim>) -> Result<()> {
+
+    let pool = &ctx.accounts.pool;
+
+    let (backed_a, backed_b) = pool.backed_reserves(
+        ctx.accounts.vault_a.amount,
+        ctx.accounts.vault_b.amount,
+    )?;
+
+    let amount_a = backed_a
+        .checked_sub(pool.reserve_a)
+        .ok_or(AmmError::ReserveMismatch)?;
+    let amount_b = backed_b
+        .checked_sub(pool.reserve_b)
+        .ok_or(AmmError::ReserveMismatch)?;
+
+    // ---------------- PDA signer ----------------
+    let pool_key = pool.key();
+    let bump = pool.vault_authority_bump;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        VAULT_AUTH_SEED,
+        pool_key.as_ref(),
+        &[bump],
+    ]];
+
+    // ---------------- Transfer excess ----------------
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_a.to_account_info(),
+        ctx.accounts.recipient_token_a.to_account_info(),
+        &ctx.accounts.token_a_mint,
+        ctx.accounts.token_a_program.to_account_info(),
+        amount_a,
+        signer_seeds,
+    )?;
+
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_b.to_account_info(),
+        ctx.accounts.recipient_token_b.to_account_info(),
+        &ctx.accounts.token_b_mint,
+        ctx.accounts.token_b_program.to_account_info(),
+        amount_b,
+        signer_seeds,
+    )?;
+
+    ctx.accounts.vault_a.reload()?;
+    ctx.accounts.vault_b.reload()?;
+    ctx.accounts.pool.check_reserves(
+        ctx.accounts.vault_a.amount,
+        ctx.accounts.vault_b.amount,
+    )?;
+
+    emit!(ExcessSkimmed {
+        pool: pool_key,
+        recipient_token_a: ctx.accounts.recipient_token_a.key(),
+        recipient_token_b: ctx.accounts.recipient_token_b.key(),
+        amount_a,
+        amount_b,
+    });
+
+    Ok(())
+}
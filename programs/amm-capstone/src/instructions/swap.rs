@@ -1,13 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{
-    self,
-    Token,
+use anchor_spl::token_interface::{
+    Mint,
     TokenAccount,
-    Transfer,
+    TokenInterface,
 };
 
 use crate::state::*;
 use crate::constants::*;
+use crate::events::SwapEvent;
+use crate::engine::{SwapDirection, SwapEngine, SwapMode, SwapPool, SwapResult};
+use crate::math::integer_sqrt;
+use crate::utils::*;
 use crate::errors::AmmError as ErrorCode;
 
 #[derive(Accounts)]
@@ -16,141 +19,261 @@ pub struct Swap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(mut)]
-    pub pool: Account<'info, Pool>,
-
     #[account(
         mut,
-        constraint = user_input.owner == user.key(),
+        has_one = vault_a,
+        has_one = vault_b,
+        has_one = vault_authority,
+        has_one = amm_config,
+        has_one = observation_state,
+        has_one = token_a_mint,
+        has_one = token_b_mint
     )]
-    pub user_input: Account<'info, TokenAccount>,
+    pub pool: Box<Account<'info, Pool>>,
 
-    #[account(
-        mut,
-        constraint = user_output.owner == user.key(),
-    )]
-    pub user_output: Account<'info, TokenAccount>,
+    pub amm_config: Box<Account<'info, AmmConfig>>,
 
     #[account(
         mut,
-        constraint = vault_a.key() == pool.vault_a,
-        constraint = vault_a.owner == vault_authority.key(),
-        constraint = vault_a.mint == pool.token_a_mint
+        constraint = user_input.owner == user.key(),
+        constraint = user_input.mint == pool.token_a_mint
+            || user_input.mint == pool.token_b_mint @ ErrorCode::InvalidMint,
     )]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub user_input: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
-        constraint = vault_b.key() == pool.vault_b,
-        constraint = vault_b.owner == vault_authority.key(),
-        constraint = vault_b.mint == pool.token_b_mint
+        constraint = user_output.owner == user.key(),
+        constraint = user_output.mint == pool.token_a_mint
+            || user_output.mint == pool.token_b_mint @ ErrorCode::InvalidMint,
+        constraint = user_output.mint != user_input.mint @ ErrorCode::InvalidMint,
     )]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub user_output: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    #[account(mut)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mint::token_program = token_a_program)]
+    pub token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_b_program)]
+    pub token_b_mint: Box<InterfaceAccount<'info, Mint>>,
 
     /// CHECK: PDA authority
     #[account(
-        seeds = [VAULT_AUTH_SEED],
-        bump
+        seeds = [VAULT_AUTH_SEED, pool.key().as_ref()],
+        bump = pool.vault_authority_bump
     )]
     pub vault_authority: UncheckedAccount<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_a_program: Interface<'info, TokenInterface>,
+    pub token_b_program: Interface<'info, TokenInterface>,
+}
 
-    #[account(
-        mut,
-        seeds = [TREASURY_SEED],
-        bump
-    )]
-    pub treasury: Account<'info, Treasury>,
+impl<'info> Swap<'info> {
+    /// (input mint, output mint) for the given direction.
+    fn mints(
+        &self,
+        a_to_b: bool,
+    ) -> (&InterfaceAccount<'info, Mint>, &InterfaceAccount<'info, Mint>) {
+        if a_to_b {
+            (&self.token_a_mint, &self.token_b_mint)
+        } else {
+            (&self.token_b_mint, &self.token_a_mint)
+        }
+    }
+
+    /// (input program, output program) for the given direction.
+    fn token_programs(
+        &self,
+        a_to_b: bool,
+    ) -> (&Interface<'info, TokenInterface>, &Interface<'info, TokenInterface>) {
+        if a_to_b {
+            (&self.token_a_program, &self.token_b_program)
+        } else {
+            (&self.token_b_program, &self.token_a_program)
+        }
+    }
 }
 
-pub fn handler(
+/// Shared handler for both swap entrypoints. `amount` is the side fixed
+/// by `mode`; `other_amount_threshold` bounds the other side (minimum
+/// received for exact-in, maximum spent for exact-out).
+pub fn swap(
     ctx: Context<Swap>,
-    amount_in: u64,
-    min_amount_out: u64,
+    mode: SwapMode,
+    amount: u64,
+    other_amount_threshold: u64,
 ) -> Result<()> {
 
-    require!(amount_in > 0, ErrorCode::InvalidAmount);
+    let pool = &ctx.accounts.pool;
+
+    // ---------------- Pool Safety ----------------
+    require!(
+        !pool.is_disabled(PoolStatusBit::Swap),
+        ErrorCode::OperationDisabled
+    );
 
-    let pool = &mut ctx.accounts.pool;
+    require!(
+        pool.reserve_a > 0 && pool.reserve_b > 0,
+        ErrorCode::PoolEmpty
+    );
 
-    // Detect direction
-    let is_a_to_b = ctx.accounts.user_input.mint == pool.token_a_mint;
+    // A liquidity bootstrapping pool's sale has a set opening time
+    let now = Clock::get()?.unix_timestamp;
+    require!(now >= pool.swap_start_ts, ErrorCode::SwapNotStarted);
 
-    let (reserve_in, reserve_out) = if is_a_to_b {
-        (pool.reserve_a, pool.reserve_b)
+    // ---------------- Detect Direction ----------------
+    // The account constraints guarantee input and output are the two
+    // distinct pool mints, so the input mint alone decides the direction.
+    let direction = if ctx.accounts.user_input.mint == pool.token_a_mint {
+        SwapDirection::AToB
     } else {
-        (pool.reserve_b, pool.reserve_a)
+        SwapDirection::BToA
     };
 
-    // ---- Fee Calculation ----
-    let fee = (amount_in as u128)
-        .checked_mul(pool.fee_bps as u128)
-        .unwrap()
-        / 10_000;
+    // ---------------- Price Calculation ----------------
+    // A stable pool's amplification, or a bootstrapping pool's weights,
+    // may be mid-schedule
+    let config = &ctx.accounts.amm_config;
+    let swap_pool = SwapPool {
+        state: pool
+            .quote_state(now)
+            .with_fee_shares(config.protocol_fee_rate, config.fund_fee_rate),
+        transfer_fee_a: get_epoch_transfer_fee(&ctx.accounts.token_a_mint)?,
+        transfer_fee_b: get_epoch_transfer_fee(&ctx.accounts.token_b_mint)?,
+    };
 
-    let amount_in_after_fee = amount_in - fee as u64;
+    let result = SwapEngine::compute(&swap_pool, direction, amount, mode)?;
 
-    // ---- Constant Product ----
-    let k = (reserve_in as u128)
-        .checked_mul(reserve_out as u128)
-        .unwrap();
+    // ---------------- Slippage ----------------
+    // Exact-in is checked against what the user will actually receive
+    match mode {
+        SwapMode::ExactIn => require!(
+            result.received_out()? >= other_amount_threshold,
+            ErrorCode::SlippageExceeded
+        ),
+        SwapMode::ExactOut => require!(
+            result.amount_in <= other_amount_threshold,
+            ErrorCode::SlippageExceeded
+        ),
+    }
 
-    let new_reserve_in = reserve_in + amount_in_after_fee;
+    execute_swap(ctx, direction == SwapDirection::AToB, result)
+}
 
-    let new_reserve_out =
-        k.checked_div(new_reserve_in as u128).unwrap() as u64;
+/// Moves tokens for a priced swap and updates pool accounting.
+fn execute_swap(
+    ctx: Context<Swap>,
+    a_to_b: bool,
+    result: SwapResult,
+) -> Result<()> {
 
-    let amount_out = reserve_out - new_reserve_out;
+    let accounts = ctx.accounts;
 
-    require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+    let (vault_in, vault_out) = if a_to_b {
+        (&accounts.vault_a, &accounts.vault_b)
+    } else {
+        (&accounts.vault_b, &accounts.vault_a)
+    };
+    let (mint_in, mint_out) = accounts.mints(a_to_b);
+    let (program_in, program_out) = accounts.token_programs(a_to_b);
 
-    // ---- Transfer Input (User → Vault) ----
-    let cpi_ctx_in = CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.user_input.to_account_info(),
-            to: if is_a_to_b {
-                ctx.accounts.vault_a.to_account_info()
-            } else {
-                ctx.accounts.vault_b.to_account_info()
-            },
-            authority: ctx.accounts.user.to_account_info(),
-        },
-    );
+    // ---------------- Transfer input ----------------
+    transfer_to_vault(
+        accounts.user.to_account_info(),
+        accounts.user_input.to_account_info(),
+        vault_in.to_account_info(),
+        mint_in,
+        program_in.to_account_info(),
+        result.amount_in,
+    )?;
 
-    token::transfer(cpi_ctx_in, amount_in)?;
-
-    let bump = ctx.bumps.vault_authority;
-    let bump_seed = [bump];
-    // ---- Transfer Output (Vault → User) ----
-    let seeds: &[&[u8]] = &[VAULT_AUTH_SEED, &bump_seed];
-    let signer_seeds = &[seeds];
-
-    let cpi_ctx_out = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: if is_a_to_b {
-                ctx.accounts.vault_b.to_account_info()
-            } else {
-                ctx.accounts.vault_a.to_account_info()
-            },
-            to: ctx.accounts.user_output.to_account_info(),
-            authority: ctx.accounts.vault_authority.to_account_info(),
-        },
+    // ---------------- PDA signer ----------------
+    let pool_key = accounts.pool.key();
+    let bump = accounts.pool.vault_authority_bump;
+
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        VAULT_AUTH_SEED,
+        pool_key.as_ref(),
+        &[bump],
+    ]];
+
+    // ---------------- Transfer output ----------------
+    transfer_from_vault(
+        accounts.vault_authority.to_account_info(),
+        vault_out.to_account_info(),
+        accounts.user_output.to_account_info(),
+        mint_out,
+        program_out.to_account_info(),
+        result.amount_out,
         signer_seeds,
-    );
+    )?;
+
+    let pool = &mut accounts.pool;
+
+    // ---------------- Price Accumulators ----------------
+    // Accrue at the pre-swap reserves, which held since the last update
+    let clock = Clock::get()?;
+    pool.update_price_cumulative(clock.unix_timestamp);
+
+    // ---------------- Update Reserves ----------------
+    // The engine already checked the new reserves against `k`
+    pool.reserve_a = result.new_reserve_a;
+    pool.reserve_b = result.new_reserve_b;
 
-    token::transfer(cpi_ctx_out, amount_out)?;
+    // ---------------- Price History ----------------
+    // The root of a u64 * u64 product always fits back into u64
+    let liquidity = integer_sqrt(
+        pool.reserve_a as u128 * pool.reserve_b as u128
+    ) as u64;
 
-    // ---- Update Reserves ----
-    if is_a_to_b {
-        pool.reserve_a += amount_in_after_fee;
-        pool.reserve_b -= amount_out;
+    accounts.observation_state.load_mut()?.update(
+        clock.slot,
+        clock.unix_timestamp,
+        pool.price_a_cumulative,
+        pool.price_b_cumulative,
+        liquidity,
+    );
+
+    // ---------------- Protocol / Fund Fee Accrual ----------------
+    if a_to_b {
+        pool.protocol_fees_a = pool.protocol_fees_a
+            .checked_add(result.protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        pool.fund_fees_a = pool.fund_fees_a
+            .checked_add(result.fund_fee)
+            .ok_or(ErrorCode::Overflow)?;
     } else {
-        pool.reserve_b += amount_in_after_fee;
-        pool.reserve_a -= amount_out;
+        pool.protocol_fees_b = pool.protocol_fees_b
+            .checked_add(result.protocol_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        pool.fund_fees_b = pool.fund_fees_b
+            .checked_add(result.fund_fee)
+            .ok_or(ErrorCode::Overflow)?;
     }
 
+    // ---------------- Reserve Check ----------------
+    // Vaults must still back reserves plus accrued fees
+    accounts.vault_a.reload()?;
+    accounts.vault_b.reload()?;
+    pool.check_reserves(accounts.vault_a.amount, accounts.vault_b.amount)?;
+
+    // ---------------- Emit Event ----------------
+    emit!(SwapEvent {
+        user: accounts.user.key(),
+        pool: pool_key,
+        input_mint: accounts.user_input.mint,
+        output_mint: accounts.user_output.mint,
+        amount_in: result.amount_in,
+        amount_out: result.amount_out,
+    });
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::state::*;
+use crate::events::ReservesSynced;
+
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+
+    #[account(
+        mut,
+        has_one = vault_a,
+        has_one = vault_b
+    )]
+    pub pool: Account<'info, Pool>,
+
+    pub vault_a: InterfaceAccount<'info, TokenAccount>,
+
+    pub vault_b: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Permissionless, as in Uniswap v2: sets the reserves to what the
+/// vaults back once accrued protocol and fund fees are set aside, so
+/// tokens sent straight to a vault are absorbed into the pool.
+pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+
+    let pool = &mut ctx.accounts.pool;
+
+    let (reserve_a, reserve_b) = pool.backed_reserves(
+        ctx.accounts.vault_a.amount,
+        ctx.accounts.vault_b.amount,
+    )?;
+
+    // Accrue at the old reserves, which held until now
+    pool.update_price_cumulative(Clock::get()?.unix_timestamp);
+
+    emit!(ReservesSynced {
+        pool: pool.key(),
+        previous_reserve_a: pool.reserve_a,
+        previous_reserve_b: pool.reserve_b,
+        reserve_a,
+        reserve_b,
+    });
+
+    pool.reserve_a = reserve_a;
+    pool.reserve_b = reserve_b;
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::errors::AmmError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UpdateConfigParams {
+    pub new_admin: Option<Pubkey>,
+    pub fee_tiers: Option<Vec<u16>>,
+    pub protocol_fee_rate: Option<u16>,
+    pub fund_fee_rate: Option<u16>,
+    pub create_pool_fee: Option<u64>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [AMM_CONFIG_SEED],
+        bump = amm_config.bump,
+        has_one = admin @ AmmError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+}
+
+pub fn update_config(
+    ctx: Context<UpdateConfig>,
+    params: UpdateConfigParams,
+) -> Result<()> {
+
+    let config = &mut ctx.accounts.amm_config;
+
+    // Existing pools keep the fee tier they were created with; the fee
+    // shares apply to every pool from the next swap on.
+    if let Some(new_admin) = params.new_admin {
+        config.admin = new_admin;
+    }
+    if let Some(fee_tiers) = params.fee_tiers {
+        config.fee_tiers = fee_tiers;
+    }
+    if let Some(protocol_fee_rate) = params.protocol_fee_rate {
+        config.protocol_fee_rate = protocol_fee_rate;
+    }
+    if let Some(fund_fee_rate) = params.fund_fee_rate {
+        config.fund_fee_rate = fund_fee_rate;
+    }
+    if let Some(create_pool_fee) = params.create_pool_fee {
+        config.create_pool_fee = create_pool_fee;
+    }
+
+    config.validate()
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::events::GradualWeightUpdateScheduled;
+use crate::errors::AmmError;
+use crate::math::valid_weights;
+
+#[derive(Accounts)]
+pub struct UpdateWeightsGradually<'info> {
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ AmmError::Unauthorized
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+/// Moves a liquidity bootstrapping pool's weights linearly from their
+/// current values to `end_weight_a`/`end_weight_b`, starting at
+/// `start_ts` (or now, if that has passed) and reaching them at `end_ts`.
+/// Replaces any schedule in progress. Only the pool's creator may call
+/// it; it does not move the time swaps open.
+pub fn update_weights_gradually(
+    ctx: Context<UpdateWeightsGradually>,
+    start_ts: i64,
+    end_ts: i64,
+    end_weight_a: u64,
+    end_weight_b: u64,
+) -> Result<()> {
+
+    let pool = &mut ctx.accounts.pool;
+    require!(pool.is_bootstrapping(), AmmError::NotBootstrappingPool);
+    require!(
+        valid_weights(end_weight_a, end_weight_b),
+        AmmError::InvalidWeights
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let start_ts = start_ts.max(now);
+    require!(end_ts > start_ts, AmmError::InvalidWeightSchedule);
+
+    // The new schedule picks up from wherever the old one has reached
+    let (weight_a, weight_b) = pool.weights_at(now);
+
+    pool.weight_a = weight_a;
+    pool.weight_b = weight_b;
+    pool.end_weight_a = end_weight_a;
+    pool.end_weight_b = end_weight_b;
+    pool.weights_start_ts = start_ts;
+    pool.weights_end_ts = end_ts;
+
+    emit!(GradualWeightUpdateScheduled {
+        pool: pool.key(),
+        start_weight_a: weight_a,
+        start_weight_b: weight_b,
+        end_weight_a,
+        end_weight_b,
+        start_ts,
+        end_ts,
+    });
+
+    Ok(())
+}
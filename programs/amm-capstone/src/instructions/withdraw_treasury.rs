@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::constants::*;
+use crate::events::TreasuryWithdrawn;
+use crate::errors::AmmError;
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump,
+        has_one = authority @ AmmError::Unauthorized
+    )]
+    pub treasury: Account<'info, Treasury>,
+
+    #[account(mut)]
+    pub destination: SystemAccount<'info>,
+}
+
+/// Pays `amount` of the pool-creation fees the treasury has collected to
+/// `destination`. The treasury keeps its rent-exempt minimum, so it can
+/// never be closed this way.
+pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+
+    require!(amount > 0, AmmError::InvalidAmount);
+
+    let treasury = ctx.accounts.treasury.to_account_info();
+    let destination = ctx.accounts.destination.to_account_info();
+
+    // The program owns the treasury, so it debits it directly; a system
+    // transfer would need the PDA to be system-owned
+    let withdrawable = treasury
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(treasury.data_len()));
+    require!(amount <= withdrawable, AmmError::InsufficientTreasuryBalance);
+
+    treasury.sub_lamports(amount)?;
+    destination.add_lamports(amount)?;
+
+    emit!(TreasuryWithdrawn {
+        authority: ctx.accounts.authority.key(),
+        destination: destination.key(),
+        amount,
+    });
+
+    Ok(())
+}
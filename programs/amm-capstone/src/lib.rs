@@ -1,8 +1,7 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{
-    transfer,
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{
     mint_to,
-    Transfer,
     MintTo,
 };
 
@@ -13,13 +12,16 @@ pub mod errors;
 pub mod constants;
 pub mod events;
 pub mod utils;
+pub mod engine;
+pub mod clmm_engine;
 
 use instructions::*;
-use anchor_spl::token::{Burn , burn};
+use engine::SwapMode;
+use anchor_spl::token_interface::{Burn , burn};
 use crate::constants::*; 
-use crate::math::*;
-use crate::events::*;
 use errors::AmmError; 
+use state::PoolStatusBit;
+use utils::*;
 
 declare_id!("7kkDWEga2EJyMARYWH7SwjEBqCfpPWbzdQLDZB5psQ4F");
 
@@ -30,22 +32,76 @@ pub mod amm_capstone {
     // ------------------------------------------------
     // CREATE POOL
     // ------------------------------------------------
-    pub fn create_pool(ctx: Context<CreatePool>) -> Result<()> {
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        fee_tier_index: u8,
+        curve: CurveParams,
+    ) -> Result<()> {
+        let fee_bps = ctx.accounts.amm_config.fee_tier(fee_tier_index)?;
+        let now = Clock::get()?.unix_timestamp;
+        let curve = curve.validate(now)?;
+
+        require!(
+            is_supported_mint(&ctx.accounts.token_a_mint)?
+                && is_supported_mint(&ctx.accounts.token_b_mint)?,
+            AmmError::UnsupportedMint
+        );
+
+        // Pool-creation fee goes to the treasury
+        let create_pool_fee = ctx.accounts.amm_config.create_pool_fee;
+        if create_pool_fee > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                create_pool_fee,
+            )?;
+        }
+
         let pool = &mut ctx.accounts.pool;
 
+        pool.authority = ctx.accounts.payer.key();
+        pool.amm_config = ctx.accounts.amm_config.key();
+
         pool.token_a_mint = ctx.accounts.token_a_mint.key();
         pool.token_b_mint = ctx.accounts.token_b_mint.key();
         pool.vault_authority = ctx.accounts.vault_authority.key();
+        pool.vault_authority_bump = ctx.bumps.vault_authority;
         pool.lp_mint = ctx.accounts.lp_mint.key();
-
-        // --- ADD THESE LINES ---
-    // We must save the vault addresses so we can verify them later!
-    pool.vault_a = ctx.accounts.vault_a.key();
-    pool.vault_b = ctx.accounts.vault_b.key();
+        pool.observation_state = ctx.accounts.observation_state.key();
+        pool.vault_a = ctx.accounts.vault_a.key();
+        pool.vault_b = ctx.accounts.vault_b.key();
 
         pool.reserve_a = 0;
         pool.reserve_b = 0;
-        pool.fee_bps = 30; // 0.3%
+        pool.fee_bps = fee_bps;
+        pool.status = 0;
+        pool.protocol_fees_a = 0;
+        pool.protocol_fees_b = 0;
+        pool.fund_fees_a = 0;
+        pool.fund_fees_b = 0;
+        pool.price_a_cumulative = 0;
+        pool.price_b_cumulative = 0;
+        pool.last_update_ts = now;
+        pool.curve_type = curve.curve_type;
+        pool.initial_amp = curve.amp;
+        pool.target_amp = curve.amp;
+        pool.ramp_start_ts = pool.last_update_ts;
+        pool.ramp_stop_ts = pool.last_update_ts;
+        pool.weight_a = curve.weight_a;
+        pool.weight_b = curve.weight_b;
+        pool.end_weight_a = curve.end_weight_a;
+        pool.end_weight_b = curve.end_weight_b;
+        pool.weights_start_ts = curve.weights_start_ts;
+        pool.weights_end_ts = curve.weights_end_ts;
+        pool.swap_start_ts = curve.swap_start_ts;
+
+        let mut observation_state = ctx.accounts.observation_state.load_init()?;
+        observation_state.pool = pool.key();
 
         Ok(())
     }
@@ -55,58 +111,114 @@ pub mod amm_capstone {
     // ------------------------------------------------
     pub fn add_liquidity(
         ctx: Context<AddLiquidity>,
-        amount_a: u64,
-        amount_b: u64,
+        amount_a_desired: u64,
+        amount_b_desired: u64,
+        amount_a_min: u64,
+        amount_b_min: u64,
+        min_lp_out: u64,
     ) -> Result<()> {
 
         let pool = &mut ctx.accounts.pool;
-        require!(amount_a > 0, AmmError::InvalidAmount);
-        require!(amount_b > 0, AmmError::InvalidAmount);
+        require!(
+            !pool.is_disabled(PoolStatusBit::Deposit),
+            AmmError::OperationDisabled
+        );
+        require!(amount_a_desired > 0, AmmError::InvalidAmount);
+        require!(amount_b_desired > 0, AmmError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let state = pool.quote_state(now);
+        let lp_supply = ctx.accounts.lp_mint.supply;
+
+        // Only take what matches the pool ratio; the rest stays with the user
+        let quote = state
+            .quote_add_liquidity(amount_a_desired, amount_b_desired, lp_supply)
+            .map_err(AmmError::from)?;
+        let (amount_a, amount_b) = (quote.amount_a, quote.amount_b);
+
+        require!(amount_a >= amount_a_min, AmmError::InsufficientAmountA);
+        require!(amount_b >= amount_b_min, AmmError::InsufficientAmountB);
+
+        // Transfer-fee mints deliver less than the user sends; only what
+        // actually reaches the vaults is credited.
+        let received_a = amount_a
+            .checked_sub(get_transfer_fee(&ctx.accounts.token_a_mint, amount_a)?)
+            .ok_or(AmmError::Overflow)?;
+        let received_b = amount_b
+            .checked_sub(get_transfer_fee(&ctx.accounts.token_b_mint, amount_b)?)
+            .ok_or(AmmError::Overflow)?;
 
-        if pool.reserve_a > 0 && pool.reserve_b > 0 {
-        let expected_b =
-            (amount_a as u128 * pool.reserve_b as u128)
-            / pool.reserve_a as u128;
+        // LP share: sqrt(a * b) on the first deposit, proportional after,
+        // re-quoted when a transfer fee shrank either side
+        let deposit = if (received_a, received_b) == (amount_a, amount_b) {
+            quote
+        } else {
+            state
+                .quote_deposit(received_a, received_b, lp_supply)
+                .map_err(AmmError::from)?
+        };
 
         require!(
-            amount_b as u128 >= expected_b,
+            deposit.liquidity >= min_lp_out,
             AmmError::SlippageExceeded
-            );
-        }
+        );
+
+        // Checked before any tokens move
+        let new_reserve_a = pool
+            .reserve_a
+            .checked_add(received_a)
+            .ok_or(AmmError::Overflow)?;
+        let new_reserve_b = pool
+            .reserve_b
+            .checked_add(received_b)
+            .ok_or(AmmError::Overflow)?;
+
         // Transfer Token A
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_token_a.to_account_info(),
-                    to: ctx.accounts.vault_a.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        transfer_to_vault(
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_a.to_account_info(),
+            ctx.accounts.vault_a.to_account_info(),
+            &ctx.accounts.token_a_mint,
+            ctx.accounts.token_a_program.to_account_info(),
             amount_a,
         )?;
 
         // Transfer Token B
-        transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_token_b.to_account_info(),
-                    to: ctx.accounts.vault_b.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        transfer_to_vault(
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.user_token_b.to_account_info(),
+            ctx.accounts.vault_b.to_account_info(),
+            &ctx.accounts.token_b_mint,
+            ctx.accounts.token_b_program.to_account_info(),
             amount_b,
         )?;
 
         // PDA signer
-        let bump = ctx.bumps.vault_authority;
+        let pool_key = pool.key();
+        let bump = pool.vault_authority_bump;
 
         let signer_seeds: &[&[&[u8]]] = &[&[
             VAULT_AUTH_SEED,
+            pool_key.as_ref(),
             &[bump],
         ]];
 
+        // Lock MINIMUM_LIQUIDITY forever on the first deposit
+        if deposit.locked_liquidity > 0 {
+            mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        to: ctx.accounts.locked_lp.to_account_info(),
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                deposit.locked_liquidity,
+            )?;
+        }
+
         // Mint LP tokens
         mint_to(
             CpiContext::new_with_signer(
@@ -118,15 +230,36 @@ pub mod amm_capstone {
                 },
                 signer_seeds,
             ),
-            amount_a.min(amount_b),
+            deposit.liquidity,
         )?;
 
-        pool.reserve_a += amount_a;
-        pool.reserve_b += amount_b;
+        pool.update_price_cumulative(now);
+
+        pool.reserve_a = new_reserve_a;
+        pool.reserve_b = new_reserve_b;
+
+        // Vaults must still back reserves plus accrued fees
+        ctx.accounts.vault_a.reload()?;
+        ctx.accounts.vault_b.reload()?;
+        pool.check_reserves(
+            ctx.accounts.vault_a.amount,
+            ctx.accounts.vault_b.amount,
+        )?;
 
         Ok(())
     }
 
+    // ------------------------------------------------
+    // ADD LIQUIDITY (ONE TOKEN)
+    // ------------------------------------------------
+    pub fn add_liquidity_single(
+        ctx: Context<AddLiquiditySingle>,
+        amount_in: u64,
+        min_lp_out: u64,
+    ) -> Result<()> {
+        instructions::add_liquidity_single(ctx, amount_in, min_lp_out)
+    }
+
     // ------------------------------------------------
     // SWAP (CORE AMM ENGINE)
     // ------------------------------------------------
@@ -135,114 +268,18 @@ pub mod amm_capstone {
         amount_in: u64,
         min_out: u64,
     ) -> Result<()> {
+        instructions::swap(ctx, SwapMode::ExactIn, amount_in, min_out)
+    }
 
-    let pool = &mut ctx.accounts.pool;
-
-    // ---------------- Pool Safety ----------------
-    require!(
-        pool.reserve_a > 0 && pool.reserve_b > 0,
-        AmmError::PoolEmpty
-    );
-
-    require!(amount_in > 0, AmmError::InvalidAmount);
-
-    // ---------------- Fee Split ----------------
-    let total_fee =
-        amount_in * pool.fee_bps as u64 / 10_000;
-
-    let protocol_fee = total_fee / 5;
-    let lp_fee = total_fee - protocol_fee;
-
-    let effective_input = amount_in - total_fee;
-
-    // ---------------- Price Calculation ----------------
-    let amount_out = get_amount_out(
-        effective_input,
-        pool.reserve_a,
-        pool.reserve_b,
-        0,
-    );
-
-    require!(
-        amount_out >= min_out,
-        AmmError::SlippageExceeded
-    );
-
-    // ---------------- Transfer input ----------------
-    transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user_input.to_account_info(),
-                to: ctx.accounts.vault_a.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
-        amount_in,
-    )?;
-
-    // ---------------- PDA signer ----------------
-    let bump = ctx.bumps.vault_authority;
-
-    let signer_seeds: &[&[&[u8]]] = &[&[
-        VAULT_AUTH_SEED,
-        &[bump],
-    ]];
-
-    // ---------------- Transfer output ----------------
-    transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.vault_b.to_account_info(),
-                to: ctx.accounts.user_output.to_account_info(),
-                authority: ctx.accounts.vault_authority.to_account_info(),
-            },
-            signer_seeds,
-        ),
-        amount_out,
-    )?;
-
-    // ---------------- Invariant Check ----------------
-    let old_k =
-        (pool.reserve_a as u128) *
-        (pool.reserve_b as u128);
-
-    let new_reserve_a = pool.reserve_a
-        .checked_add(effective_input + lp_fee)
-        .ok_or(AmmError::Overflow)?;
-
-    let new_reserve_b = pool.reserve_b
-        .checked_sub(amount_out)
-        .ok_or(AmmError::Overflow)?;
-
-    let new_k =
-        (new_reserve_a as u128) *
-        (new_reserve_b as u128);
-
-    require!(
-        new_k >= old_k,
-        AmmError::InvariantViolation
-    );
-
-    // ---------------- Update Reserves ----------------
-    pool.reserve_a = new_reserve_a;
-    pool.reserve_b = new_reserve_b;
-
-    // ---------------- Treasury Accounting ----------------
-    ctx.accounts.treasury.total_fees_collected =
-        ctx.accounts.treasury.total_fees_collected
-            .checked_add(protocol_fee)
-            .ok_or(AmmError::Overflow)?;
-
-    // ---------------- Emit Event ----------------
-    emit!(SwapEvent {
-        user: ctx.accounts.user.key(),
-        amount_in,
-        amount_out,
-    });
-
-        Ok(())
+    // ------------------------------------------------
+    // SWAP (EXACT OUTPUT)
+    // ------------------------------------------------
+    pub fn swap_base_out(
+        ctx: Context<Swap>,
+        max_amount_in: u64,
+        amount_out: u64,
+    ) -> Result<()> {
+        instructions::swap(ctx, SwapMode::ExactOut, amount_out, max_amount_in)
     }
 
     // ------------------------------------------------
@@ -253,6 +290,11 @@ pub mod amm_capstone {
 
     let pool = &mut ctx.accounts.pool;
 
+    require!(
+        !pool.is_disabled(PoolStatusBit::Withdraw),
+        AmmError::OperationDisabled
+    );
+
     let total_lp_supply = ctx.accounts.lp_mint.supply;
 
     require!(
@@ -263,13 +305,24 @@ pub mod amm_capstone {
     // -----------------------------
     // Calculate proportional share
     // -----------------------------
-    let amount_a =
-        (pool.reserve_a as u128 * lp_amount as u128
-            / total_lp_supply as u128) as u64;
-
-    let amount_b =
-        (pool.reserve_b as u128 * lp_amount as u128
-            / total_lp_supply as u128) as u64;
+    let now = Clock::get()?.unix_timestamp;
+    let withdrawal = pool
+        .quote_state(now)
+        .quote_remove_liquidity(lp_amount, total_lp_supply)
+        .map_err(AmmError::from)?;
+
+    let amount_a = withdrawal.amount_a;
+    let amount_b = withdrawal.amount_b;
+
+    // Checked before any tokens move
+    let new_reserve_a = pool
+        .reserve_a
+        .checked_sub(amount_a)
+        .ok_or(AmmError::Overflow)?;
+    let new_reserve_b = pool
+        .reserve_b
+        .checked_sub(amount_b)
+        .ok_or(AmmError::Overflow)?;
 
     // -----------------------------
     // Burn LP tokens
@@ -289,66 +342,264 @@ pub mod amm_capstone {
     // -----------------------------
     // PDA signer
     // -----------------------------
-    let bump = ctx.bumps.vault_authority;
+    let pool_key = pool.key();
+    let bump = pool.vault_authority_bump;
 
     let signer_seeds: &[&[&[u8]]] = &[&[
         VAULT_AUTH_SEED,
+        pool_key.as_ref(),
         &[bump],
     ]];
 
     // -----------------------------
     // Transfer Token A back
     // -----------------------------
-    transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.vault_a.to_account_info(),
-                to: ctx.accounts.user_token_a.to_account_info(),
-                authority: ctx.accounts.vault_authority.to_account_info(),
-            },
-            signer_seeds,
-        ),
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_a.to_account_info(),
+        ctx.accounts.user_token_a.to_account_info(),
+        &ctx.accounts.token_a_mint,
+        ctx.accounts.token_a_program.to_account_info(),
         amount_a,
+        signer_seeds,
     )?;
 
     // -----------------------------
     // Transfer Token B back
     // -----------------------------
-    transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.vault_b.to_account_info(),
-                to: ctx.accounts.user_token_b.to_account_info(),
-                authority: ctx.accounts.vault_authority.to_account_info(),
-            },
-            signer_seeds,
-        ),
+    transfer_from_vault(
+        ctx.accounts.vault_authority.to_account_info(),
+        ctx.accounts.vault_b.to_account_info(),
+        ctx.accounts.user_token_b.to_account_info(),
+        &ctx.accounts.token_b_mint,
+        ctx.accounts.token_b_program.to_account_info(),
         amount_b,
+        signer_seeds,
     )?;
 
     // -----------------------------
     // Update reserves
     // -----------------------------
-    pool.reserve_a -= amount_a;
-    pool.reserve_b -= amount_b;
+    pool.update_price_cumulative(now);
+
+    pool.reserve_a = new_reserve_a;
+    pool.reserve_b = new_reserve_b;
+
+    // -----------------------------
+    // Vaults must still back reserves plus accrued fees
+    // -----------------------------
+    ctx.accounts.vault_a.reload()?;
+    ctx.accounts.vault_b.reload()?;
+    pool.check_reserves(
+        ctx.accounts.vault_a.amount,
+        ctx.accounts.vault_b.amount,
+    )?;
 
     Ok(())
 }
 
     // ------------------------------------------------
-    pub fn collect_fees(_ctx: Context<CollectFees>) -> Result<()> {
-        Ok(())
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        instructions::collect_fees(ctx)
+    }
+
+    // ------------------------------------------------
+    pub fn collect_fund_fees(ctx: Context<CollectFundFees>) -> Result<()> {
+        instructions::collect_fund_fees(ctx)
+    }
+
+    // ------------------------------------------------
+    // RESERVE RECONCILIATION
+    // ------------------------------------------------
+    pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+        instructions::sync(ctx)
+    }
+
+    pub fn skim(ctx: Context<Skim>) -> Result<()> {
+        instructions::skim(ctx)
+    }
+
+    // ------------------------------------------------
+    pub fn set_pool_status(
+        ctx: Context<SetPoolStatus>,
+        status: u8,
+    ) -> Result<()> {
+        instructions::set_pool_status(ctx, status)
+    }
+
+    // ------------------------------------------------
+    // AMPLIFICATION RAMPING (STABLE POOLS)
+    // ------------------------------------------------
+    pub fn ramp_amp(
+        ctx: Context<RampAmp>,
+        target_amp: u64,
+        stop_ts: i64,
+    ) -> Result<()> {
+        instructions::ramp_amp(ctx, target_amp, stop_ts)
+    }
+
+    pub fn stop_ramp_amp(ctx: Context<RampAmp>) -> Result<()> {
+        instructions::stop_ramp_amp(ctx)
+    }
+
+    // ------------------------------------------------
+    // WEIGHT SCHEDULE (LIQUIDITY BOOTSTRAPPING POOLS)
+    // ------------------------------------------------
+    pub fn update_weights_gradually(
+        ctx: Context<UpdateWeightsGradually>,
+        start_ts: i64,
+        end_ts: i64,
+        end_weight_a: u64,
+        end_weight_b: u64,
+    ) -> Result<()> {
+        instructions::update_weights_gradually(
+            ctx,
+            start_ts,
+            end_ts,
+            end_weight_a,
+            end_weight_b,
+        )
+    }
+
+    // ------------------------------------------------
+    // CONCENTRATED LIQUIDITY
+    // ------------------------------------------------
+    pub fn create_clmm_pool(
+        ctx: Context<CreateClmmPool>,
+        fee_tier_index: u8,
+        tick_spacing: u16,
+        sqrt_price_x64: u128,
+    ) -> Result<()> {
+        instructions::create_clmm_pool(ctx, fee_tier_index, tick_spacing, sqrt_price_x64)
+    }
+
+    pub fn init_tick_array(
+        ctx: Context<InitTickArray>,
+        start_tick_index: i32,
+    ) -> Result<()> {
+        instructions::init_tick_array(ctx, start_tick_index)
+    }
+
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        instructions::open_position(ctx, tick_lower, tick_upper)
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<IncreaseLiquidity>,
+        liquidity: u128,
+        amount_a_max: u64,
+        amount_b_max: u64,
+    ) -> Result<()> {
+        instructions::increase_liquidity(ctx, liquidity, amount_a_max, amount_b_max)
+    }
+
+    pub fn decrease_liquidity(
+        ctx: Context<DecreaseLiquidity>,
+        liquidity: u128,
+        amount_a_min: u64,
+        amount_b_min: u64,
+    ) -> Result<()> {
+        instructions::decrease_liquidity(ctx, liquidity, amount_a_min, amount_b_min)
+    }
+
+    pub fn collect(
+        ctx: Context<Collect>,
+        amount_a_max: u64,
+        amount_b_max: u64,
+    ) -> Result<()> {
+        instructions::collect(ctx, amount_a_max, amount_b_max)
+    }
+
+    pub fn clmm_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClmmSwap<'info>>,
+        amount_in: u64,
+        min_out: u64,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<()> {
+        instructions::clmm_swap(ctx, SwapMode::ExactIn, amount_in, min_out, sqrt_price_limit_x64)
+    }
+
+    pub fn clmm_swap_base_out<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClmmSwap<'info>>,
+        max_amount_in: u64,
+        amount_out: u64,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<()> {
+        instructions::clmm_swap(ctx, SwapMode::ExactOut, amount_out, max_amount_in, sqrt_price_limit_x64)
+    }
+
+    pub fn collect_clmm_fees(ctx: Context<CollectClmmFees>) -> Result<()> {
+        instructions::collect_clmm_fees(ctx)
+    }
+
+    pub fn collect_clmm_fund_fees(ctx: Context<CollectClmmFundFees>) -> Result<()> {
+        instructions::collect_clmm_fund_fees(ctx)
+    }
+
+    pub fn set_clmm_pool_status(
+        ctx: Context<SetClmmPoolStatus>,
+        status: u8,
+    ) -> Result<()> {
+        instructions::set_clmm_pool_status(ctx, status)
+    }
+
+    // ------------------------------------------------
+    // TWAP (READ-ONLY)
+    // ------------------------------------------------
+    pub fn observe(
+        ctx: Context<Observe>,
+        seconds_ago: u32,
+    ) -> Result<Twap> {
+        instructions::observe(ctx, seconds_ago)
+    }
+
+    // ------------------------------------------------
+    // CONFIG
+    // ------------------------------------------------
+    pub fn init_config(
+        ctx: Context<InitConfig>,
+        fee_tiers: Vec<u16>,
+        protocol_fee_rate: u16,
+        fund_fee_rate: u16,
+        create_pool_fee: u64,
+    ) -> Result<()> {
+        instructions::init_config(
+            ctx,
+            fee_tiers,
+            protocol_fee_rate,
+            fund_fee_rate,
+            create_pool_fee,
+        )
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        params: UpdateConfigParams,
+    ) -> Result<()> {
+        instructions::update_config(ctx, params)
     }
 
     // ------------------------------------------------
     pub fn init_treasury(ctx: Context<InitTreasury>) -> Result<()> {
         let treasury = &mut ctx.accounts.treasury;
         treasury.authority = ctx.accounts.payer.key();
-        treasury.total_fees_collected = 0;
         Ok(())
     }
+
+    pub fn set_treasury_authority(
+        ctx: Context<SetTreasuryAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        instructions::set_treasury_authority(ctx, new_authority)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        instructions::withdraw_treasury(ctx, amount)
+    }
 }
 
 #[derive(Accounts)]
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_TICK_SPACING;
+use crate::errors::AmmError;
+use crate::state::PoolStatusBit;
+use crate::math::{tick_at_sqrt_price, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK};
+
+/// A concentrated liquidity pool. Liquidity sits in positions over tick
+/// ranges rather than in reserves; `liquidity` is the sum of the
+/// positions whose range holds the current price. The vaults, vault
+/// authority and fee shares work as for `Pool`.
+#[account]
+pub struct ClmmPool {
+    pub amm_config: Pubkey,         // 32
+    pub token_a_mint: Pubkey,       // 32
+    pub token_b_mint: Pubkey,       // 32
+    pub vault_a: Pubkey,            // 32
+    pub vault_b: Pubkey,            // 32
+    pub vault_authority: Pubkey,    // 32
+    pub vault_authority_bump: u8,   // 1
+    // Position bounds must be multiples of this
+    pub tick_spacing: u16,          // 2
+    pub fee_bps: u16,               // 2
+    pub status: u8,                 // 1 (see `PoolStatusBit`)
+
+    pub liquidity: u128,            // 16
+    // Square root of the price of A in B, as Q64.64, and the greatest
+    // tick at or below it
+    pub sqrt_price_x64: u128,       // 16
+    pub tick_current: i32,          // 4
+
+    // LP fees earned per unit of liquidity over the pool's life, as
+    // Q64.64. Wrap on overflow; positions take differences.
+    pub fee_growth_global_a_x64: u128, // 16
+    pub fee_growth_global_b_x64: u128, // 16
+
+    // Withdrawable through `collect_clmm_fees` / `collect_clmm_fund_fees`
+    pub protocol_fees_a: u64,       // 8
+    pub protocol_fees_b: u64,       // 8
+    pub fund_fees_a: u64,           // 8
+    pub fund_fees_b: u64,           // 8
+}
+
+impl ClmmPool {
+    pub const LEN: usize = 32 * 6 + 1 + 2 + 2 + 1 + 16 * 2 + 4 + 16 * 2 + 8 * 4;
+
+    /// Checks a new pool's tick spacing and starting price, returning
+    /// the tick the price sits in.
+    pub fn validate_params(tick_spacing: u16, sqrt_price_x64: u128) -> Result<i32> {
+        require!(
+            (1..=MAX_TICK_SPACING).contains(&tick_spacing),
+            AmmError::InvalidTickSpacing
+        );
+        require!(
+            (MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64),
+            AmmError::InvalidSqrtPrice
+        );
+
+        Ok(tick_at_sqrt_price(sqrt_price_x64).ok_or(AmmError::InvalidSqrtPrice)?)
+    }
+
+    pub fn is_disabled(&self, bit: PoolStatusBit) -> bool {
+        self.status & (1 << bit as u8) != 0
+    }
+
+    /// Fails unless `tick_lower..tick_upper` is a non-empty range of
+    /// usable ticks on this pool's spacing.
+    pub fn validate_tick_range(&self, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        let spacing = self.tick_spacing as i32;
+        require!(
+            tick_lower < tick_upper
+                && tick_lower >= MIN_TICK
+                && tick_upper <= MAX_TICK
+                && tick_lower % spacing == 0
+                && tick_upper % spacing == 0,
+            AmmError::InvalidTickRange
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::errors::AmmError;
+
+#[account]
+pub struct AmmConfig {
+    pub admin: Pubkey,
+    // Swap fees (in bps of the input) a pool may be created with
+    pub fee_tiers: Vec<u16>,
+    // Shares of each swap fee (in bps of the fee) kept out of reserves
+    pub protocol_fee_rate: u16,
+    pub fund_fee_rate: u16,
+    // Lamports paid to the treasury for every new pool
+    pub create_pool_fee: u64,
+    pub bump: u8,
+}
+
+impl AmmConfig {
+    pub const LEN: usize = 32 + (4 + 2 * MAX_FEE_TIERS) + 2 + 2 + 8 + 1;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            !self.fee_tiers.is_empty() && self.fee_tiers.len() <= MAX_FEE_TIERS,
+            AmmError::InvalidFeeTier
+        );
+        require!(
+            self.fee_tiers
+                .iter()
+                .all(|fee| (*fee as u64) < FEE_RATE_DENOMINATOR),
+            AmmError::InvalidFeeTier
+        );
+        require!(
+            self.protocol_fee_rate as u64 + self.fund_fee_rate as u64
+                <= FEE_RATE_DENOMINATOR,
+            AmmError::InvalidFeeRate
+        );
+        Ok(())
+    }
+
+    pub fn fee_tier(&self, index: u8) -> Result<u16> {
+        self.fee_tiers
+            .get(index as usize)
+            .copied()
+            .ok_or(AmmError::InvalidFeeTier.into())
+    }
+}
\ No newline at end of file
@@ -1,7 +1,18 @@
+//! Account layouts. Each type's `LEN` excludes the 8-byte discriminator,
+//! which callers add to `space`.
+
+pub mod clmm_pool;
 pub mod config;
+pub mod observation;
 pub mod pool;
+pub mod position;
+pub mod tick_array;
 pub mod treasury;
 
+pub use clmm_pool::*;
 pub use config::*;
+pub use observation::*;
 pub use pool::*;
+pub use position::*;
+pub use tick_array::*;
 pub use treasury::*;
\ No newline at end of file
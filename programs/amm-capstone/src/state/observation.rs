@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::OBSERVATION_NUM;
+
+/// One entry of the price history. Cumulative prices are copies of the
+/// pool's accumulators at `block_timestamp`, so the TWAP between any two
+/// entries is their difference over the elapsed time.
+#[zero_copy]
+#[derive(Default, Debug)]
+pub struct Observation {
+    pub block_timestamp: i64,     // 8
+    pub slot: u64,                // 8
+    pub price_a_cumulative: u128, // 16
+    pub price_b_cumulative: u128, // 16
+    // sqrt(reserve_a * reserve_b) after the write
+    pub liquidity: u64,           // 8
+    pub padding: u64,             // 8
+}
+
+impl Observation {
+    pub const LEN: usize = 8 + 8 + 16 + 16 + 8 + 8;
+}
+
+/// Fixed-size ring of observations for one pool, written from the swap
+/// path at most once per slot. Zero-copy so the history can grow well
+/// past what fits on the stack.
+#[account(zero_copy)]
+pub struct ObservationState {
+    pub pool: Pubkey,               // 32
+    // Index of the most recent write in `observations`
+    pub observation_index: u16,     // 2
+    // Entries written so far, capped at OBSERVATION_NUM
+    pub observation_count: u16,     // 2
+    pub padding: [u8; 12],          // 12
+    pub observations: [Observation; OBSERVATION_NUM],
+}
+
+impl ObservationState {
+    pub const LEN: usize = 32 + 2 + 2 + 12 + Observation::LEN * OBSERVATION_NUM;
+
+    /// The observation `age` writes ago (`0` is the latest), if stored.
+    pub fn observation(&self, age: usize) -> Option<&Observation> {
+        if age >= self.observation_count as usize {
+            return None;
+        }
+
+        let index = (self.observation_index as usize + OBSERVATION_NUM - age)
+            % OBSERVATION_NUM;
+
+        Some(&self.observations[index])
+    }
+
+    /// The newest stored observation taken at or before `timestamp`, or
+    /// `None` if the ring does not reach back that far.
+    pub fn observation_at_or_before(&self, timestamp: i64) -> Option<&Observation> {
+        (0..self.observation_count as usize)
+            .filter_map(|age| self.observation(age))
+            .find(|observation| observation.block_timestamp <= timestamp)
+    }
+
+    /// Records the pool's accumulators, overwriting the oldest entry once
+    /// the ring is full. A second write in the same slot is ignored.
+    pub fn update(
+        &mut self,
+        slot: u64,
+        block_timestamp: i64,
+        price_a_cumulative: u128,
+        price_b_cumulative: u128,
+        liquidity: u64,
+    ) {
+        if let Some(latest) = self.observation(0) {
+            if latest.slot == slot {
+                return;
+            }
+        }
+
+        let index = if self.observation_count == 0 {
+            0
+        } else {
+            (self.observation_index as usize + 1) % OBSERVATION_NUM
+        };
+
+        self.observations[index] = Observation {
+            block_timestamp,
+            slot,
+            price_a_cumulative,
+            price_b_cumulative,
+            liquidity,
+            padding: 0,
+        };
+
+        self.observation_index = index as u16;
+        if (self.observation_count as usize) < OBSERVATION_NUM {
+            self.observation_count += 1;
+        }
+    }
+}
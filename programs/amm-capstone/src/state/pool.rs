@@ -2,9 +2,17 @@
 
 use anchor_lang::prelude::*;
 
+use crate::errors::AmmError;
+use crate::math::{
+    gradual_weights_at, price_q64, ramp_amp_at, stable_price_q64, weighted_price_q64, Curve,
+    PoolState,
+};
+
 #[account]
 pub struct Pool {
+    // Creator; owns a liquidity bootstrapping pool's weight schedule.
     pub authority: Pubkey,      // 32
+    pub amm_config: Pubkey,     // 32
     pub token_a_mint: Pubkey,   // 32
     pub token_b_mint: Pubkey,   // 32
     
@@ -14,14 +22,253 @@ pub struct Pool {
     // --------------------------
 
     pub vault_authority: Pubkey,// 32
+    pub vault_authority_bump: u8, // 1
     pub lp_mint: Pubkey,        // 32
+    pub observation_state: Pubkey, // 32
     pub reserve_a: u64,         // 8
     pub reserve_b: u64,         // 8
     pub fee_bps: u16,           // 2
+    pub status: u8,             // 1 (see `PoolStatusBit`)
+
+    // Protocol fees sitting in the vaults but excluded from reserves,
+    // withdrawable by the treasury authority through `collect_fees`.
+    pub protocol_fees_a: u64,   // 8
+    pub protocol_fees_b: u64,   // 8
+
+    // Fund fees, withdrawable by the config admin through
+    // `collect_fund_fees`.
+    pub fund_fees_a: u64,       // 8
+    pub fund_fees_b: u64,       // 8
+
+    // Uniswap-v2 style price accumulators: the sum of the Q64.64 spot
+    // price (A in B, and B in A) times the seconds it held, up to
+    // `last_update_ts`. Wrap on overflow; consumers take differences.
+    pub price_a_cumulative: u128, // 16
+    pub price_b_cumulative: u128, // 16
+    pub last_update_ts: i64,      // 8
+
+    // Pricing curve (see `CurveType`), fixed at `create_pool`.
+    pub curve_type: u8,           // 1
+
+    // A stable pool's amplification ramps linearly from `initial_amp`
+    // at `ramp_start_ts` to `target_amp` at `ramp_stop_ts` (see
+    // `amp_at`); outside a ramp both are the current value. Zero for
+    // other curves.
+    pub initial_amp: u64,         // 8
+    pub target_amp: u64,          // 8
+    pub ramp_start_ts: i64,       // 8
+    pub ramp_stop_ts: i64,        // 8
+
+    // A weighted pool's normalized weights (summing to `WEIGHT_ONE`).
+    // Zero for other curves. A liquidity bootstrapping pool moves them
+    // linearly to `end_weight_a`/`end_weight_b` between
+    // `weights_start_ts` and `weights_end_ts` (see `weights_at`); a
+    // plain weighted pool keeps the end weights equal to these.
+    pub weight_a: u64,            // 8
+    pub weight_b: u64,            // 8
+
+    pub end_weight_a: u64,        // 8
+    pub end_weight_b: u64,        // 8
+    pub weights_start_ts: i64,    // 8
+    pub weights_end_ts: i64,      // 8
+
+    // Swaps fail before this; a liquidity bootstrapping pool's sale
+    // opens here. Creation time for other pools.
+    pub swap_start_ts: i64,       // 8
 }
 
 impl Pool {
-    // 8 discriminator + 32*6 + 8*2 + 2 = 210 bytes (approx)
-    // Ensure you have enough space. A safe buffer is usually:
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 8 + 8 + 2 + 64; 
+    pub const LEN: usize = 32 * 9 + 1 + 8 * 2 + 2 + 1 + 8 * 4 + 16 * 2 + 8 + 1 + 8 * 4 + 8 * 2 + 8 * 5;
+
+    /// LP decimals for a pair. The first deposit mints `sqrt(a * b)`,
+    /// whose natural precision is the mean of the two mints' decimals.
+    pub fn lp_decimals(decimals_a: u8, decimals_b: u8) -> u8 {
+        ((decimals_a as u16 + decimals_b as u16) / 2) as u8
+    }
+
+    /// Pricing view of this pool at `now`, which sets a stable pool's
+    /// amplification. Fee shares live on `AmmConfig`; swaps add them
+    /// with `PoolState::with_fee_shares`.
+    pub fn quote_state(&self, now: i64) -> PoolState {
+        PoolState::new(self.reserve_a, self.reserve_b, self.fee_bps).with_curve(self.curve(now))
+    }
+
+    pub fn curve(&self, now: i64) -> Curve {
+        if self.is_stable() {
+            Curve::Stable {
+                amp: self.amp_at(now),
+            }
+        } else if self.is_weighted() {
+            let (weight_a, weight_b) = self.weights_at(now);
+            Curve::Weighted { weight_a, weight_b }
+        } else {
+            Curve::ConstantProduct
+        }
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.curve_type == CurveType::Stable as u8
+    }
+
+    /// Whether the pool prices on the weighted curve, with fixed or
+    /// scheduled weights.
+    pub fn is_weighted(&self) -> bool {
+        self.curve_type == CurveType::Weighted as u8 || self.is_bootstrapping()
+    }
+
+    pub fn is_bootstrapping(&self) -> bool {
+        self.curve_type == CurveType::LiquidityBootstrapping as u8
+    }
+
+    /// Weights at `now`, part way along any schedule.
+    pub fn weights_at(&self, now: i64) -> (u64, u64) {
+        if !self.is_bootstrapping() {
+            return (self.weight_a, self.weight_b);
+        }
+
+        gradual_weights_at(
+            self.weight_a,
+            self.end_weight_a,
+            self.weights_start_ts,
+            self.weights_end_ts,
+            now,
+        )
+    }
+
+    /// Spot prices (A in B, and B in A) as Q64.64 at `now`: the reserve
+    /// ratio, scaled by the weights for a weighted pool. A stable pool
+    /// prices at the StableSwap curve's slope under the amplification at
+    /// `now`, which stays near par where the reserve ratio would not.
+    /// `None` when either reserve is empty or a price does not fit.
+    pub fn spot_prices(&self, now: i64) -> Option<(u128, u128)> {
+        if self.is_stable() {
+            let amp = self.amp_at(now);
+            return Some((
+                stable_price_q64(self.reserve_a, self.reserve_b, amp)?,
+                stable_price_q64(self.reserve_b, self.reserve_a, amp)?,
+            ));
+        }
+
+        if self.is_weighted() {
+            let (weight_a, weight_b) = self.weights_at(now);
+            return Some((
+                weighted_price_q64(self.reserve_a, weight_a, self.reserve_b, weight_b)?,
+                weighted_price_q64(self.reserve_b, weight_b, self.reserve_a, weight_a)?,
+            ));
+        }
+
+        Some((
+            price_q64(self.reserve_a, self.reserve_b)?,
+            price_q64(self.reserve_b, self.reserve_a)?,
+        ))
+    }
+
+    /// Amplification at `now`, part way along any ramp.
+    pub fn amp_at(&self, now: i64) -> u64 {
+        ramp_amp_at(
+            self.initial_amp,
+            self.target_amp,
+            self.ramp_start_ts,
+            self.ramp_stop_ts,
+            now,
+        )
+    }
+
+    /// Cumulative prices extrapolated to `now` at the current reserves,
+    /// without writing them back.
+    pub fn cumulative_prices_at(&self, now: i64) -> (u128, u128) {
+        let elapsed = now.saturating_sub(self.last_update_ts);
+        if elapsed <= 0 {
+            return (self.price_a_cumulative, self.price_b_cumulative);
+        }
+
+        // Weights and amplification, like reserves, are taken as they
+        // stood at the last update; a schedule's or ramp's drift since
+        // then is not integrated
+        match self.spot_prices(self.last_update_ts) {
+            Some((price_a, price_b)) => (
+                self.price_a_cumulative
+                    .wrapping_add(price_a.wrapping_mul(elapsed as u128)),
+                self.price_b_cumulative
+                    .wrapping_add(price_b.wrapping_mul(elapsed as u128)),
+            ),
+            // An empty pool has no price; the accumulators just idle
+            None => (self.price_a_cumulative, self.price_b_cumulative),
+        }
+    }
+
+    /// Folds the time since the last update into the accumulators. Must
+    /// run before reserves change, so the elapsed time is priced at the
+    /// reserves that actually held over it.
+    pub fn update_price_cumulative(&mut self, now: i64) {
+        if now <= self.last_update_ts {
+            return;
+        }
+
+        let (price_a_cumulative, price_b_cumulative) =
+            self.cumulative_prices_at(now);
+
+        self.price_a_cumulative = price_a_cumulative;
+        self.price_b_cumulative = price_b_cumulative;
+        self.last_update_ts = now;
+    }
+
+    pub fn is_disabled(&self, bit: PoolStatusBit) -> bool {
+        self.status & (1 << bit as u8) != 0
+    }
+
+    /// Vault balances less the protocol and fund fees owed out of them:
+    /// the most the vaults can back as reserves. Anything above the
+    /// reserves is a donation, left for `sync` or `skim`.
+    pub fn backed_reserves(&self, vault_a: u64, vault_b: u64) -> Result<(u64, u64)> {
+        let backed = |vault: u64, protocol_fees: u64, fund_fees: u64| {
+            vault
+                .checked_sub(protocol_fees)
+                .and_then(|amount| amount.checked_sub(fund_fees))
+                .ok_or(AmmError::ReserveMismatch)
+        };
+
+        Ok((
+            backed(vault_a, self.protocol_fees_a, self.fund_fees_a)?,
+            backed(vault_b, self.protocol_fees_b, self.fund_fees_b)?,
+        ))
+    }
+
+    /// Fails unless the vaults hold at least the reserves plus accrued
+    /// fees. Every instruction that moves vault tokens ends with this.
+    pub fn check_reserves(&self, vault_a: u64, vault_b: u64) -> Result<()> {
+        let (backed_a, backed_b) = self.backed_reserves(vault_a, vault_b)?;
+
+        require!(
+            backed_a >= self.reserve_a && backed_b >= self.reserve_b,
+            AmmError::ReserveMismatch
+        );
+
+        Ok(())
+    }
+}
+
+/// Values of `Pool.curve_type`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveType {
+    ConstantProduct = 0,
+    Stable = 1,
+    Weighted = 2,
+    LiquidityBootstrapping = 3,
+}
+
+/// Bits of `Pool.status` and `ClmmPool.status`; a set bit disables that
+/// operation.
+#[derive(Clone, Copy)]
+pub enum PoolStatusBit {
+    Deposit = 0,
+    Withdraw = 1,
+    Swap = 2,
+}
+
+impl PoolStatusBit {
+    /// Every defined bit; a status may not set any other.
+    pub const ALL: u8 = 1 << PoolStatusBit::Deposit as u8
+        | 1 << PoolStatusBit::Withdraw as u8
+        | 1 << PoolStatusBit::Swap as u8;
 }
\ No newline at end of file
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+/// One owner's liquidity over one tick range of a `ClmmPool`.
+#[account]
+pub struct Position {
+    pub pool: Pubkey,                      // 32
+    pub owner: Pubkey,                     // 32
+    pub tick_lower: i32,                   // 4
+    pub tick_upper: i32,                   // 4
+    pub liquidity: u128,                   // 16
+
+    // Fee growth inside the range when fees were last credited; the
+    // growth since, times `liquidity`, is owed on the next update
+    pub fee_growth_inside_a_last_x64: u128, // 16
+    pub fee_growth_inside_b_last_x64: u128, // 16
+
+    // Fees and withdrawn liquidity not yet sent out by `collect`
+    pub tokens_owed_a: u64,                // 8
+    pub tokens_owed_b: u64,                // 8
+}
+
+impl Position {
+    pub const LEN: usize = 32 * 2 + 4 * 2 + 16 * 3 + 8 * 2;
+}
@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::TICK_ARRAY_SIZE;
+use crate::errors::AmmError;
+use crate::math::{MAX_TICK, MIN_TICK};
+
+/// One usable tick. A tick is initialized while any position uses it as
+/// a bound (`liquidity_gross > 0`).
+#[zero_copy]
+#[derive(Default, Debug)]
+pub struct TickState {
+    // Liquidity added when the price crosses this tick upwards, removed
+    // when it crosses downwards
+    pub liquidity_net: i128,            // 16
+    // Liquidity of every position bounded by this tick
+    pub liquidity_gross: u128,          // 16
+    // Fee growth on the side of this tick away from the current price,
+    // flipped each time the price crosses it
+    pub fee_growth_outside_a_x64: u128, // 16
+    pub fee_growth_outside_b_x64: u128, // 16
+}
+
+impl TickState {
+    pub const LEN: usize = 16 * 4;
+
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross > 0
+    }
+}
+
+/// `TICK_ARRAY_SIZE` consecutive usable ticks of one pool, from
+/// `start_tick_index` in steps of the pool's tick spacing. Separate
+/// accounts, created on demand, so a pool's tick storage grows with the
+/// ranges actually in use. Zero-copy, as the array is too large for the
+/// stack.
+#[account(zero_copy)]
+pub struct TickArrayState {
+    pub pool: Pubkey,            // 32
+    pub start_tick_index: i32,   // 4
+    pub padding: [u8; 12],       // 12
+    pub ticks: [TickState; TICK_ARRAY_SIZE],
+}
+
+impl TickArrayState {
+    pub const LEN: usize = 32 + 4 + 12 + TickState::LEN * TICK_ARRAY_SIZE;
+
+    /// Ticks one array spans at `tick_spacing`.
+    pub fn span(tick_spacing: u16) -> i32 {
+        tick_spacing as i32 * TICK_ARRAY_SIZE as i32
+    }
+
+    /// Start index of the array holding `tick`.
+    pub fn start_index(tick: i32, tick_spacing: u16) -> i32 {
+        let span = Self::span(tick_spacing);
+        tick.div_euclid(span) * span
+    }
+
+    /// Whether an array may start at `start_tick_index`: on an array
+    /// boundary, and covering some usable tick.
+    pub fn is_valid_start(start_tick_index: i32, tick_spacing: u16) -> bool {
+        start_tick_index == Self::start_index(start_tick_index, tick_spacing)
+            && start_tick_index <= MAX_TICK
+            && start_tick_index + Self::span(tick_spacing) > MIN_TICK
+    }
+
+    fn offset(&self, tick: i32, tick_spacing: u16) -> Result<usize> {
+        let spacing = tick_spacing as i32;
+        require!(
+            Self::start_index(tick, tick_spacing) == self.start_tick_index
+                && tick % spacing == 0,
+            AmmError::InvalidTickArray
+        );
+        Ok(((tick - self.start_tick_index) / spacing) as usize)
+    }
+
+    pub fn tick_mut(&mut self, tick: i32, tick_spacing: u16) -> Result<&mut TickState> {
+        let offset = self.offset(tick, tick_spacing)?;
+        Ok(&mut self.ticks[offset])
+    }
+
+    /// Two distinct ticks of this array, for a position whose bounds
+    /// both fall in it.
+    pub fn tick_pair_mut(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        tick_spacing: u16,
+    ) -> Result<(&mut TickState, &mut TickState)> {
+        let lower = self.offset(tick_lower, tick_spacing)?;
+        let upper = self.offset(tick_upper, tick_spacing)?;
+        require!(lower < upper, AmmError::InvalidTickRange);
+
+        let (below, above) = self.ticks.split_at_mut(upper);
+        Ok((&mut below[lower], &mut above[0]))
+    }
+
+    /// The next tick a swap from `tick_current` must stop at within this
+    /// array, and whether it is initialized: the nearest initialized
+    /// tick at or below `tick_current` when the price falls (`a_to_b`),
+    /// above it when it rises, or else the array's far end. `None` when
+    /// the search starts past the array, so the swap moves on to the
+    /// next one; an error when it starts before it, as the array passed
+    /// in would skip ticks.
+    pub fn next_initialized_tick(
+        &self,
+        tick_current: i32,
+        tick_spacing: u16,
+        a_to_b: bool,
+    ) -> Result<Option<(i32, bool)>> {
+        let spacing = tick_spacing as i32;
+        let start = self.start_tick_index;
+        let end = start + Self::span(tick_spacing);
+
+        if a_to_b {
+            let search_from = tick_current.div_euclid(spacing) * spacing;
+            if search_from < start {
+                return Ok(None);
+            }
+            require!(search_from < end, AmmError::InvalidTickArray);
+
+            let from = ((search_from - start) / spacing) as usize;
+            let found = (0..=from).rev().find(|i| self.ticks[*i].is_initialized());
+            Ok(Some(match found {
+                Some(i) => (start + i as i32 * spacing, true),
+                None => (start, false),
+            }))
+        } else {
+            let search_from = (tick_current.div_euclid(spacing) + 1) * spacing;
+            if search_from >= end {
+                return Ok(None);
+            }
+            require!(search_from >= start, AmmError::InvalidTickArray);
+
+            let from = ((search_from - start) / spacing) as usize;
+            let found = (from..TICK_ARRAY_SIZE).find(|i| self.ticks[*i].is_initialized());
+            Ok(Some(match found {
+                Some(i) => (start + i as i32 * spacing, true),
+                None => (end - spacing, false),
+            }))
+        }
+    }
+}
@@ -3,9 +3,8 @@ use anchor_lang::prelude::*;
 #[account]
 pub struct Treasury {
     pub authority: Pubkey,
-    pub total_fees_collected: u64,
 }
 
 impl Treasury {
-    pub const LEN: usize = 32 + 8;
+    pub const LEN: usize = 32;
 }
\ No newline at end of file
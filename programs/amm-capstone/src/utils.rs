@@ -0,0 +1,196 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions,
+        ExtensionType,
+        StateWithExtensions,
+    },
+};
+use anchor_spl::token_interface::{
+    transfer_checked,
+    Mint,
+    TransferChecked,
+};
+
+use crate::clmm_engine::modify_position;
+use crate::errors::AmmError;
+use crate::state::{ClmmPool, Position, TickArrayState};
+
+/// Token-2022 mint extensions a pool can hold. Anything else (e.g.
+/// non-transferable, permanent delegate, transfer hooks, confidential
+/// transfers) could freeze, claw back or hide vault balances.
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 3] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+// ------------------------------------------------
+// TRANSFERS
+// ------------------------------------------------
+
+/// User → vault, signed by the user.
+pub fn transfer_to_vault<'info>(
+    authority: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to_vault: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+
+    if amount == 0 {
+        return Ok(());
+    }
+
+    transfer_checked(
+        CpiContext::new(
+            token_program,
+            TransferChecked {
+                from,
+                mint: mint.to_account_info(),
+                to: to_vault,
+                authority,
+            },
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Vault → user, signed by the pool's vault authority PDA. A zero
+/// `amount` is skipped, so callers pay out accrued balances unchecked.
+pub fn transfer_from_vault<'info>(
+    vault_authority: AccountInfo<'info>,
+    from_vault: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+
+    if amount == 0 {
+        return Ok(());
+    }
+
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program,
+            TransferChecked {
+                from: from_vault,
+                mint: mint.to_account_info(),
+                to,
+                authority: vault_authority,
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+// ------------------------------------------------
+// TOKEN-2022 EXTENSIONS
+// ------------------------------------------------
+
+/// `mint`'s transfer fee for the current epoch, or `None` for classic
+/// mints and Token-2022 mints without the extension.
+pub fn get_epoch_transfer_fee(
+    mint: &InterfaceAccount<Mint>,
+) -> Result<Option<TransferFee>> {
+
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == Token::id() {
+        return Ok(None);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => Some(*config.get_epoch_fee(Clock::get()?.epoch)),
+        Err(_) => None,
+    };
+
+    Ok(fee)
+}
+
+/// Transfer fee withheld when sending `amount` of `mint`.
+pub fn get_transfer_fee(
+    mint: &InterfaceAccount<Mint>,
+    amount: u64,
+) -> Result<u64> {
+
+    let fee = match get_epoch_transfer_fee(mint)? {
+        Some(fee) => fee.calculate_fee(amount).ok_or(AmmError::Overflow)?,
+        None => 0,
+    };
+
+    Ok(fee)
+}
+
+/// Whether every extension on `mint` is in `SUPPORTED_MINT_EXTENSIONS`.
+pub fn is_supported_mint(mint: &InterfaceAccount<Mint>) -> Result<bool> {
+
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == Token::id() {
+        return Ok(true);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+
+    Ok(mint_state
+        .get_extension_types()?
+        .iter()
+        .all(|extension| SUPPORTED_MINT_EXTENSIONS.contains(extension)))
+}
+
+// ------------------------------------------------
+// CONCENTRATED LIQUIDITY
+// ------------------------------------------------
+
+/// `modify_position` on the position's bounding ticks, which sit in one
+/// tick array or two. Each array must belong to the position's pool and
+/// hold the bound it is passed for.
+pub fn update_position(
+    pool: &mut ClmmPool,
+    position: &mut Position,
+    tick_array_lower: &AccountLoader<TickArrayState>,
+    tick_array_upper: &AccountLoader<TickArrayState>,
+    liquidity_delta: i128,
+) -> Result<(u64, u64)> {
+
+    let spacing = pool.tick_spacing;
+    let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+    // One account cannot be borrowed twice
+    if tick_array_lower.key() == tick_array_upper.key() {
+        let mut array = tick_array_lower.load_mut()?;
+        require!(array.pool == position.pool, AmmError::InvalidTickArray);
+
+        let (lower, upper) = array.tick_pair_mut(tick_lower, tick_upper, spacing)?;
+        return modify_position(pool, position, lower, upper, liquidity_delta);
+    }
+
+    let mut lower_array = tick_array_lower.load_mut()?;
+    let mut upper_array = tick_array_upper.load_mut()?;
+    require!(
+        lower_array.pool == position.pool && upper_array.pool == position.pool,
+        AmmError::InvalidTickArray
+    );
+
+    modify_position(
+        pool,
+        position,
+        lower_array.tick_mut(tick_lower, spacing)?,
+        upper_array.tick_mut(tick_upper, spacing)?,
+        liquidity_delta,
+    )
+}
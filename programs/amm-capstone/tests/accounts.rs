@@ -0,0 +1,1144 @@
+//! Account validation for every instruction: each case swaps one account
+//! in an otherwise valid instruction for a decoy and expects Anchor's
+//! error for that constraint. Constraints with a custom `AmmError` are
+//! covered in `errors.rs`.
+//!
+//! Instructions that `init` their accounts are sent for a pool that
+//! does not exist yet, so every case gets as far as its seeds check.
+
+mod common;
+
+use amm_capstone::instructions::{CurveParams, UpdateConfigParams};
+use amm_capstone::math::{sqrt_price_at_tick, WEIGHT_ONE};
+use amm_capstone::state::{CurveType, Pool, Position};
+use amm_capstone::{accounts, instruction};
+use amm_capstone_client::*;
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::{token, token_2022};
+
+use common::*;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: amm_capstone::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn unsigned(mut ix: Instruction, key: &Pubkey) -> Instruction {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = false;
+    }
+    ix
+}
+
+fn readonly(mut ix: Instruction, key: &Pubkey) -> Instruction {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_writable = false;
+    }
+    ix
+}
+
+#[track_caller]
+fn assert_rejected(env: &mut Env, signers: &[Pubkey], cases: Vec<(&str, Instruction, ErrorCode)>) {
+    for (case, ix, error) in cases {
+        assert_eq!(
+            env.send(&[ix], signers),
+            Err(anchor_error(error)),
+            "{case}: expected {error:?}"
+        );
+    }
+}
+
+/// Look-alike accounts: right type, wrong address or contents.
+struct Decoys {
+    // Never created
+    missing: Pubkey,
+    // The pool, copied to a non-PDA address
+    pool_copy: Pubkey,
+    // The pool, copied and owned by the token program
+    pool_foreign: Pubkey,
+    // The pool, copied with another `amm_config`
+    pool_other_config: Pubkey,
+    config_copy: Pubkey,
+    treasury_copy: Pubkey,
+    observation_copy: Pubkey,
+    mint: Pubkey,
+    token_a: Pubkey,
+    token_b: Pubkey,
+    lp: Pubkey,
+    // The user's token A account, owned by someone else
+    stranger_token_a: Pubkey,
+}
+
+fn decoys(env: &mut Env) -> Decoys {
+    let keys = env.keys;
+    let admin = env.admin;
+    let stranger = Pubkey::new_unique();
+
+    let config = find_amm_config_address().0;
+    let treasury = find_treasury_address().0;
+
+    let pool_other_config = Pubkey::new_unique();
+    let pool = Pool {
+        amm_config: Pubkey::new_unique(),
+        ..env.pool()
+    };
+    write_account(&mut env.svm, &pool_other_config, 8 + Pool::LEN, &pool);
+
+    let mint = create_mint(&mut env.svm, &admin, 6, MintKind::Classic);
+
+    Decoys {
+        missing: Pubkey::new_unique(),
+        pool_copy: env.clone_with_owner(&keys.pool, &amm_capstone::ID),
+        pool_foreign: env.clone_with_owner(&keys.pool, &token::ID),
+        pool_other_config,
+        config_copy: env.clone_with_owner(&config, &amm_capstone::ID),
+        treasury_copy: env.clone_with_owner(&treasury, &amm_capstone::ID),
+        observation_copy: env.clone_with_owner(&keys.observation_state, &amm_capstone::ID),
+        mint,
+        token_a: env.token_account(&keys.token_a_mint, &keys.vault_authority),
+        token_b: env.token_account(&keys.token_b_mint, &keys.vault_authority),
+        lp: env.token_account(&keys.lp_mint, &keys.locked_lp_authority),
+        stranger_token_a: env.token_account(&keys.token_a_mint, &stranger),
+    }
+}
+
+fn seeded() -> (Env, Decoys) {
+    let mut env = Env::new();
+    env.seed_liquidity(1_000_000, 2_000_000);
+    let decoys = decoys(&mut env);
+    (env, decoys)
+}
+
+// `seeded`, plus a concentrated liquidity pool on the same mints with
+// the user's position over -600..590
+fn clmm_seeded() -> (Env, Decoys, PositionKeys) {
+    let (mut env, d) = seeded();
+    env.make_clmm(10, sqrt_price_at_tick(0).unwrap());
+    let position = env.deposit_clmm(-600, 590, 1_000_000_000);
+    (env, d, position)
+}
+
+#[test]
+fn swap_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let user = env.user;
+    let user_accounts = env.user_accounts;
+
+    let base = || accounts::Swap {
+        user,
+        pool: keys.pool,
+        amm_config: find_amm_config_address().0,
+        user_input: user_accounts.token_a,
+        user_output: user_accounts.token_b,
+        observation_state: keys.observation_state,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let data = || instruction::Swap {
+        amount_in: 1_000,
+        min_out: u64::MAX,
+    };
+    let ix = |accounts| build(accounts, data());
+
+    let cases = vec![
+        ("user signs", unsigned(ix(base()), &user), ErrorCode::AccountNotSigner),
+        ("pool exists", ix(accounts::Swap { pool: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("pool owner", ix(accounts::Swap { pool: d.pool_foreign, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("pool type", ix(accounts::Swap { pool: d.config_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool address", ix(accounts::Swap { pool: d.pool_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool.amm_config", ix(accounts::Swap { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintHasOne),
+        ("user_input owner", ix(accounts::Swap { user_input: d.stranger_token_a, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_input mut", readonly(ix(base()), &user_accounts.token_a), ErrorCode::ConstraintMut),
+        ("user_output owner", ix(accounts::Swap { user_output: d.token_b, ..base() }), ErrorCode::ConstraintRaw),
+        ("observation type", ix(accounts::Swap { observation_state: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("observation mut", readonly(ix(base()), &keys.observation_state), ErrorCode::ConstraintMut),
+        ("pool.observation_state", ix(accounts::Swap { observation_state: d.observation_copy, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_a", ix(accounts::Swap { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::Swap { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("vault_a mut", readonly(ix(base()), &keys.vault_a), ErrorCode::ConstraintMut),
+        ("vault_b mut", readonly(ix(base()), &keys.vault_b), ErrorCode::ConstraintMut),
+        ("pool.token_a_mint", ix(accounts::Swap { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_b_mint", ix(accounts::Swap { token_b_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::Swap { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintHasOne),
+        ("token_a_program matches mint", ix(accounts::Swap { token_a_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_b_program matches mint", ix(accounts::Swap { token_b_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_a_program id", ix(accounts::Swap { token_a_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[user], cases);
+
+    // A token account where a mint belongs fails to unpack
+    let ix = ix(accounts::Swap { token_a_mint: keys.vault_a, ..base() });
+    assert_eq!(env.send(&[ix], &[user]), Err(ProgramError::InvalidAccountData));
+}
+
+#[test]
+fn add_liquidity_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let user = env.user;
+    let user_accounts = env.user_accounts;
+
+    let base = || accounts::AddLiquidity {
+        user,
+        pool: keys.pool,
+        user_token_a: user_accounts.token_a,
+        user_token_b: user_accounts.token_b,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        lp_mint: keys.lp_mint,
+        user_lp: user_accounts.lp,
+        locked_lp: keys.locked_lp,
+        vault_authority: keys.vault_authority,
+        token_program: token::ID,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let ix = |accounts| {
+        build(
+            accounts,
+            instruction::AddLiquidity {
+                amount_a_desired: 1_000,
+                amount_b_desired: 2_000,
+                amount_a_min: 0,
+                amount_b_min: 0,
+                min_lp_out: u64::MAX,
+            },
+        )
+    };
+
+    let cases = vec![
+        ("user signs", unsigned(ix(base()), &user), ErrorCode::AccountNotSigner),
+        ("pool type", ix(accounts::AddLiquidity { pool: d.config_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool address", ix(accounts::AddLiquidity { pool: d.pool_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool.vault_a", ix(accounts::AddLiquidity { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::AddLiquidity { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::AddLiquidity { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.lp_mint", ix(accounts::AddLiquidity { lp_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_a_mint", ix(accounts::AddLiquidity { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_b_mint", ix(accounts::AddLiquidity { token_b_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("user_token_a mint", ix(accounts::AddLiquidity { user_token_a: user_accounts.token_b, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_token_b mint", ix(accounts::AddLiquidity { user_token_b: user_accounts.token_a, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_token_a mut", readonly(ix(base()), &user_accounts.token_a), ErrorCode::ConstraintMut),
+        ("user_token_b mut", readonly(ix(base()), &user_accounts.token_b), ErrorCode::ConstraintMut),
+        ("vault_a mut", readonly(ix(base()), &keys.vault_a), ErrorCode::ConstraintMut),
+        ("vault_b mut", readonly(ix(base()), &keys.vault_b), ErrorCode::ConstraintMut),
+        ("lp_mint mut", readonly(ix(base()), &keys.lp_mint), ErrorCode::ConstraintMut),
+        ("user_lp mint", ix(accounts::AddLiquidity { user_lp: user_accounts.token_a, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_lp mut", readonly(ix(base()), &user_accounts.lp), ErrorCode::ConstraintMut),
+        ("locked_lp address", ix(accounts::AddLiquidity { locked_lp: d.lp, ..base() }), ErrorCode::ConstraintSeeds),
+        ("locked_lp mut", readonly(ix(base()), &keys.locked_lp), ErrorCode::ConstraintMut),
+        ("token_program id", ix(accounts::AddLiquidity { token_program: token_2022::ID, ..base() }), ErrorCode::InvalidProgramId),
+        ("token_a_program matches mint", ix(accounts::AddLiquidity { token_a_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_b_program id", ix(accounts::AddLiquidity { token_b_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[user], cases);
+}
+
+#[test]
+fn add_liquidity_single_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let user = env.user;
+    let user_accounts = env.user_accounts;
+
+    let base = || accounts::AddLiquiditySingle {
+        user,
+        pool: keys.pool,
+        amm_config: find_amm_config_address().0,
+        user_token_in: user_accounts.token_a,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        lp_mint: keys.lp_mint,
+        user_lp: user_accounts.lp,
+        vault_authority: keys.vault_authority,
+        token_program: token::ID,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let ix = |accounts| {
+        build(
+            accounts,
+            instruction::AddLiquiditySingle {
+                amount_in: 1_000,
+                min_lp_out: u64::MAX,
+            },
+        )
+    };
+
+    let cases = vec![
+        ("user signs", unsigned(ix(base()), &user), ErrorCode::AccountNotSigner),
+        ("pool type", ix(accounts::AddLiquiditySingle { pool: d.config_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool address", ix(accounts::AddLiquiditySingle { pool: d.pool_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool.amm_config", ix(accounts::AddLiquiditySingle { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_a", ix(accounts::AddLiquiditySingle { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::AddLiquiditySingle { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::AddLiquiditySingle { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.lp_mint", ix(accounts::AddLiquiditySingle { lp_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_a_mint", ix(accounts::AddLiquiditySingle { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_b_mint", ix(accounts::AddLiquiditySingle { token_b_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("user_token_in mut", readonly(ix(base()), &user_accounts.token_a), ErrorCode::ConstraintMut),
+        ("vault_a mut", readonly(ix(base()), &keys.vault_a), ErrorCode::ConstraintMut),
+        ("vault_b mut", readonly(ix(base()), &keys.vault_b), ErrorCode::ConstraintMut),
+        ("lp_mint mut", readonly(ix(base()), &keys.lp_mint), ErrorCode::ConstraintMut),
+        ("user_lp mint", ix(accounts::AddLiquiditySingle { user_lp: user_accounts.token_a, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_lp mut", readonly(ix(base()), &user_accounts.lp), ErrorCode::ConstraintMut),
+        ("token_program id", ix(accounts::AddLiquiditySingle { token_program: token_2022::ID, ..base() }), ErrorCode::InvalidProgramId),
+        ("token_a_program matches mint", ix(accounts::AddLiquiditySingle { token_a_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_b_program id", ix(accounts::AddLiquiditySingle { token_b_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[user], cases);
+}
+
+#[test]
+fn remove_liquidity_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let user = env.user;
+    let user_accounts = env.user_accounts;
+
+    let base = || accounts::RemoveLiquidity {
+        user,
+        pool: keys.pool,
+        user_lp: user_accounts.lp,
+        lp_mint: keys.lp_mint,
+        user_token_a: user_accounts.token_a,
+        user_token_b: user_accounts.token_b,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        vault_authority: keys.vault_authority,
+        token_program: token::ID,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let ix = |accounts| build(accounts, instruction::RemoveLiquidity { lp_amount: 1_000 });
+
+    let cases = vec![
+        ("user signs", unsigned(ix(base()), &user), ErrorCode::AccountNotSigner),
+        ("pool owner", ix(accounts::RemoveLiquidity { pool: d.pool_foreign, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool address", ix(accounts::RemoveLiquidity { pool: d.pool_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool.vault_a", ix(accounts::RemoveLiquidity { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::RemoveLiquidity { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::RemoveLiquidity { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.lp_mint", ix(accounts::RemoveLiquidity { lp_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_a_mint", ix(accounts::RemoveLiquidity { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_b_mint", ix(accounts::RemoveLiquidity { token_b_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("user_lp mint", ix(accounts::RemoveLiquidity { user_lp: user_accounts.token_a, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_lp mut", readonly(ix(base()), &user_accounts.lp), ErrorCode::ConstraintMut),
+        ("lp_mint mut", readonly(ix(base()), &keys.lp_mint), ErrorCode::ConstraintMut),
+        ("user_token_a mint", ix(accounts::RemoveLiquidity { user_token_a: user_accounts.token_b, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_token_b mint", ix(accounts::RemoveLiquidity { user_token_b: user_accounts.token_a, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_token_a mut", readonly(ix(base()), &user_accounts.token_a), ErrorCode::ConstraintMut),
+        ("user_token_b mut", readonly(ix(base()), &user_accounts.token_b), ErrorCode::ConstraintMut),
+        ("vault_a mut", readonly(ix(base()), &keys.vault_a), ErrorCode::ConstraintMut),
+        ("vault_b mut", readonly(ix(base()), &keys.vault_b), ErrorCode::ConstraintMut),
+        ("token_program id", ix(accounts::RemoveLiquidity { token_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+        ("token_a_program matches mint", ix(accounts::RemoveLiquidity { token_a_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_b_program matches mint", ix(accounts::RemoveLiquidity { token_b_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+    ];
+    assert_rejected(&mut env, &[user], cases);
+}
+
+#[test]
+fn collect_fees_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let admin = env.admin;
+    let treasury_a = env.token_account(&keys.token_a_mint, &admin);
+    let treasury_b = env.token_account(&keys.token_b_mint, &admin);
+
+    let base = || accounts::CollectFees {
+        authority: admin,
+        treasury: find_treasury_address().0,
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        treasury_token_a: treasury_a,
+        treasury_token_b: treasury_b,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let ix = |accounts| build(accounts, instruction::CollectFees {});
+
+    let cases = vec![
+        ("authority signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("treasury type", ix(accounts::CollectFees { treasury: d.config_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("treasury address", ix(accounts::CollectFees { treasury: d.treasury_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool address", ix(accounts::CollectFees { pool: d.pool_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool.vault_a", ix(accounts::CollectFees { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::CollectFees { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::CollectFees { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_a_mint", ix(accounts::CollectFees { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_b_mint", ix(accounts::CollectFees { token_b_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("vault_a mut", readonly(ix(base()), &keys.vault_a), ErrorCode::ConstraintMut),
+        ("treasury_token_a mut", readonly(ix(base()), &treasury_a), ErrorCode::ConstraintMut),
+        ("treasury_token_b mut", readonly(ix(base()), &treasury_b), ErrorCode::ConstraintMut),
+        ("token_a_program matches mint", ix(accounts::CollectFees { token_a_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_b_program id", ix(accounts::CollectFees { token_b_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn collect_fund_fees_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let admin = env.admin;
+    let fund_a = env.token_account(&keys.token_a_mint, &admin);
+    let fund_b = env.token_account(&keys.token_b_mint, &admin);
+
+    let base = || accounts::CollectFundFees {
+        admin,
+        amm_config: find_amm_config_address().0,
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        fund_token_a: fund_a,
+        fund_token_b: fund_b,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let ix = |accounts| build(accounts, instruction::CollectFundFees {});
+
+    let cases = vec![
+        ("admin signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("amm_config type", ix(accounts::CollectFundFees { amm_config: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("amm_config address", ix(accounts::CollectFundFees { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool address", ix(accounts::CollectFundFees { pool: d.pool_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool.amm_config", ix(accounts::CollectFundFees { pool: d.pool_other_config, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_a", ix(accounts::CollectFundFees { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::CollectFundFees { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::CollectFundFees { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_a_mint", ix(accounts::CollectFundFees { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_b_mint", ix(accounts::CollectFundFees { token_b_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("vault_b mut", readonly(ix(base()), &keys.vault_b), ErrorCode::ConstraintMut),
+        ("fund_token_a mut", readonly(ix(base()), &fund_a), ErrorCode::ConstraintMut),
+        ("fund_token_b mut", readonly(ix(base()), &fund_b), ErrorCode::ConstraintMut),
+        ("token_b_program matches mint", ix(accounts::CollectFundFees { token_b_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_a_program id", ix(accounts::CollectFundFees { token_a_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn sync_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+
+    let base = || accounts::SyncReserves {
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+    };
+    let ix = |accounts| build(accounts, instruction::Sync {});
+
+    let cases = vec![
+        ("pool exists", ix(accounts::SyncReserves { pool: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("pool type", ix(accounts::SyncReserves { pool: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool.vault_a", ix(accounts::SyncReserves { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::SyncReserves { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("vault_a owner", ix(accounts::SyncReserves { vault_a: keys.pool, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+    ];
+    assert_rejected(&mut env, &[], cases);
+}
+
+#[test]
+fn skim_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let recipient = Pubkey::new_unique();
+    let to_a = env.token_account(&keys.token_a_mint, &recipient);
+    let to_b = env.token_account(&keys.token_b_mint, &recipient);
+
+    let base = || accounts::Skim {
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        recipient_token_a: to_a,
+        recipient_token_b: to_b,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let ix = |accounts| build(accounts, instruction::Skim {});
+
+    let cases = vec![
+        ("pool type", ix(accounts::Skim { pool: d.config_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool address", ix(accounts::Skim { pool: d.pool_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool.vault_a", ix(accounts::Skim { vault_a: d.token_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::Skim { vault_b: d.token_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::Skim { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_a_mint", ix(accounts::Skim { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.token_b_mint", ix(accounts::Skim { token_b_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("vault_a mut", readonly(ix(base()), &keys.vault_a), ErrorCode::ConstraintMut),
+        ("vault_b mut", readonly(ix(base()), &keys.vault_b), ErrorCode::ConstraintMut),
+        ("recipient_token_a mut", readonly(ix(base()), &to_a), ErrorCode::ConstraintMut),
+        ("recipient_token_b mut", readonly(ix(base()), &to_b), ErrorCode::ConstraintMut),
+        ("token_a_program matches mint", ix(accounts::Skim { token_a_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_b_program id", ix(accounts::Skim { token_b_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[], cases);
+}
+
+#[test]
+fn set_pool_status_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let admin = env.admin;
+
+    let base = || accounts::SetPoolStatus {
+        admin,
+        amm_config: find_amm_config_address().0,
+        pool: keys.pool,
+    };
+    let ix = |accounts| build(accounts, instruction::SetPoolStatus { status: 0 });
+
+    let cases = vec![
+        ("admin signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("amm_config exists", ix(accounts::SetPoolStatus { amm_config: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("amm_config address", ix(accounts::SetPoolStatus { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool type", ix(accounts::SetPoolStatus { pool: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool.amm_config", ix(accounts::SetPoolStatus { pool: d.pool_other_config, ..base() }), ErrorCode::ConstraintHasOne),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn ramp_amp_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let admin = env.admin;
+
+    let base = || accounts::RampAmp {
+        admin,
+        amm_config: find_amm_config_address().0,
+        pool: keys.pool,
+    };
+    let ix = |accounts| build(accounts, instruction::StopRampAmp {});
+
+    let cases = vec![
+        ("admin signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("amm_config exists", ix(accounts::RampAmp { amm_config: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("amm_config address", ix(accounts::RampAmp { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool type", ix(accounts::RampAmp { pool: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool.amm_config", ix(accounts::RampAmp { pool: d.pool_other_config, ..base() }), ErrorCode::ConstraintHasOne),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn update_weights_gradually_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    let admin = env.admin;
+    let mut pool = env.pool();
+    pool.authority = admin;
+    env.set_pool(&pool);
+
+    let base = || accounts::UpdateWeightsGradually {
+        authority: admin,
+        pool: keys.pool,
+    };
+    let ix = |accounts| {
+        build(
+            accounts,
+            instruction::UpdateWeightsGradually {
+                start_ts: 0,
+                end_ts: i64::MAX,
+                end_weight_a: WEIGHT_ONE / 2,
+                end_weight_b: WEIGHT_ONE / 2,
+            },
+        )
+    };
+
+    let cases = vec![
+        ("authority signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("pool exists", ix(accounts::UpdateWeightsGradually { pool: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("pool owner", ix(accounts::UpdateWeightsGradually { pool: d.pool_foreign, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("pool type", ix(accounts::UpdateWeightsGradually { pool: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn update_config_accounts() {
+    let (mut env, d) = seeded();
+    let admin = env.admin;
+    let config = find_amm_config_address().0;
+
+    let base = || accounts::UpdateConfig {
+        admin,
+        amm_config: config,
+    };
+    let ix = |accounts| {
+        build(
+            accounts,
+            instruction::UpdateConfig {
+                params: UpdateConfigParams {
+                    new_admin: None,
+                    fee_tiers: None,
+                    protocol_fee_rate: None,
+                    fund_fee_rate: None,
+                    create_pool_fee: None,
+                },
+            },
+        )
+    };
+
+    let cases = vec![
+        ("admin signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("amm_config owner", ix(accounts::UpdateConfig { amm_config: d.pool_foreign, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("amm_config mut", readonly(ix(base()), &config), ErrorCode::ConstraintMut),
+        ("amm_config address", ix(accounts::UpdateConfig { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintSeeds),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn observe_accounts() {
+    let (mut env, d) = seeded();
+    let keys = env.keys;
+    env.record_observation();
+    env.svm.warp(1);
+
+    let base = || accounts::Observe {
+        pool: keys.pool,
+        observation_state: keys.observation_state,
+    };
+    let ix = |accounts| build(accounts, instruction::Observe { seconds_ago: 1 });
+
+    let cases = vec![
+        ("pool exists", ix(accounts::Observe { pool: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("pool owner", ix(accounts::Observe { pool: d.pool_foreign, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("pool type", ix(accounts::Observe { pool: d.observation_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("observation type", ix(accounts::Observe { observation_state: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool.observation_state", ix(accounts::Observe { observation_state: d.observation_copy, ..base() }), ErrorCode::ConstraintHasOne),
+    ];
+    assert_rejected(&mut env, &[], cases);
+}
+
+#[test]
+fn create_pool_accounts() {
+    let (mut env, d) = seeded();
+    let admin = env.admin;
+    // A pair with no pool yet
+    let mint_a = create_mint(&mut env.svm, &admin, 6, MintKind::Classic);
+    let mint_b = create_mint(&mut env.svm, &admin, 6, MintKind::Classic);
+    let keys = PoolKeys::new(mint_a, mint_b, token::ID, token::ID, POOL_FEE_BPS, CurveType::ConstantProduct);
+
+    let base = || accounts::CreatePool {
+        payer: admin,
+        amm_config: find_amm_config_address().0,
+        treasury: find_treasury_address().0,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        lp_mint: keys.lp_mint,
+        locked_lp_authority: keys.locked_lp_authority,
+        locked_lp: keys.locked_lp,
+        observation_state: keys.observation_state,
+        vault_authority: keys.vault_authority,
+        token_program: token::ID,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    let ix = |accounts| {
+        let curve = CurveParams::ConstantProduct;
+        build(accounts, instruction::CreatePool { fee_tier_index: POOL_FEE_TIER, curve })
+    };
+
+    let cases = vec![
+        ("payer signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("amm_config type", ix(accounts::CreatePool { amm_config: d.treasury_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("treasury exists", ix(accounts::CreatePool { treasury: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("token_b_mint owner", ix(accounts::CreatePool { token_b_mint: d.pool_copy, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("token_program id", ix(accounts::CreatePool { token_program: token_2022::ID, ..base() }), ErrorCode::InvalidProgramId),
+        ("token_a_program id", ix(accounts::CreatePool { token_a_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+        ("system_program id", ix(accounts::CreatePool { system_program: token::ID, ..base() }), ErrorCode::InvalidProgramId),
+        ("rent sysvar", ix(accounts::CreatePool { rent: sysvar::clock::ID, ..base() }), ErrorCode::AccountSysvarMismatch),
+        ("pool address", ix(accounts::CreatePool { pool: d.missing, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool fee rate", build(base(), instruction::CreatePool { fee_tier_index: POOL_FEE_TIER + 1, curve: CurveParams::ConstantProduct }), ErrorCode::ConstraintSeeds),
+        ("pool curve", build(base(), instruction::CreatePool { fee_tier_index: POOL_FEE_TIER, curve: CurveParams::Stable { amp: 100 } }), ErrorCode::ConstraintSeeds),
+        ("vault_a address", ix(accounts::CreatePool { vault_a: d.missing, ..base() }), ErrorCode::ConstraintSeeds),
+        ("vault_b address", ix(accounts::CreatePool { vault_b: d.missing, ..base() }), ErrorCode::ConstraintSeeds),
+        ("lp_mint address", ix(accounts::CreatePool { lp_mint: d.missing, ..base() }), ErrorCode::ConstraintSeeds),
+        ("locked_lp address", ix(accounts::CreatePool { locked_lp: d.missing, ..base() }), ErrorCode::ConstraintSeeds),
+        ("observation_state address", ix(accounts::CreatePool { observation_state: d.missing, ..base() }), ErrorCode::ConstraintSeeds),
+        ("vault_authority address", ix(accounts::CreatePool { vault_authority: d.missing, ..base() }), ErrorCode::ConstraintSeeds),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+
+    let ix = ix(accounts::CreatePool { token_a_mint: env.keys.vault_a, ..base() });
+    assert_eq!(env.send(&[ix], &[admin]), Err(ProgramError::InvalidAccountData));
+}
+
+#[test]
+fn init_accounts() {
+    let (mut env, d) = seeded();
+    let admin = env.admin;
+
+    let treasury = || accounts::InitTreasury {
+        payer: admin,
+        program: amm_capstone::ID,
+        program_data: find_program_data_address().0,
+        treasury: find_treasury_address().0,
+        system_program: system_program::ID,
+    };
+    let config = || accounts::InitConfig {
+        payer: admin,
+        program: amm_capstone::ID,
+        program_data: find_program_data_address().0,
+        amm_config: find_amm_config_address().0,
+        system_program: system_program::ID,
+    };
+    // The program's `ProgramData`, at an address the program does not name
+    let program_data_copy = Pubkey::new_unique();
+    let program_data = env.svm.account(&find_program_data_address().0).unwrap();
+    env.svm.set_account(program_data_copy, program_data);
+    // `init` runs before the constraints, so the treasury and config
+    // must not exist for the `program_data` checks to be reached
+    env.svm.set_account(find_treasury_address().0, AccountData::default());
+    env.svm.set_account(find_amm_config_address().0, AccountData::default());
+
+    let init_treasury = |accounts| build(accounts, instruction::InitTreasury {});
+    let init_config = |accounts| {
+        build(
+            accounts,
+            instruction::InitConfig {
+                fee_tiers: FEE_TIERS.to_vec(),
+                protocol_fee_rate: PROTOCOL_FEE_RATE,
+                fund_fee_rate: FUND_FEE_RATE,
+                create_pool_fee: 0,
+            },
+        )
+    };
+
+    let cases = vec![
+        ("treasury payer signs", unsigned(init_treasury(treasury()), &admin), ErrorCode::AccountNotSigner),
+        ("treasury program id", init_treasury(accounts::InitTreasury { program: token::ID, ..treasury() }), ErrorCode::InvalidProgramId),
+        ("treasury program_data owner", init_treasury(accounts::InitTreasury { program_data: d.config_copy, ..treasury() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("treasury program_data address", init_treasury(accounts::InitTreasury { program_data: program_data_copy, ..treasury() }), ErrorCode::ConstraintRaw),
+        ("treasury system_program id", init_treasury(accounts::InitTreasury { system_program: token::ID, ..treasury() }), ErrorCode::InvalidProgramId),
+        ("treasury address", init_treasury(accounts::InitTreasury { treasury: d.missing, ..treasury() }), ErrorCode::ConstraintSeeds),
+        ("config payer signs", unsigned(init_config(config()), &admin), ErrorCode::AccountNotSigner),
+        ("config program id", init_config(accounts::InitConfig { program: token::ID, ..config() }), ErrorCode::InvalidProgramId),
+        ("config program_data owner", init_config(accounts::InitConfig { program_data: d.config_copy, ..config() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("config program_data address", init_config(accounts::InitConfig { program_data: program_data_copy, ..config() }), ErrorCode::ConstraintRaw),
+        ("config system_program id", init_config(accounts::InitConfig { system_program: token::ID, ..config() }), ErrorCode::InvalidProgramId),
+        ("config address", init_config(accounts::InitConfig { amm_config: d.missing, ..config() }), ErrorCode::ConstraintSeeds),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn set_clmm_pool_status_accounts() {
+    let (mut env, d, _) = clmm_seeded();
+    let keys = env.clmm_keys;
+    let admin = env.admin;
+
+    let pool_other_config = Pubkey::new_unique();
+    let pool = ClmmPool {
+        amm_config: Pubkey::new_unique(),
+        ..env.clmm_pool()
+    };
+    write_account(&mut env.svm, &pool_other_config, 8 + ClmmPool::LEN, &pool);
+
+    let base = || accounts::SetClmmPoolStatus {
+        admin,
+        amm_config: find_amm_config_address().0,
+        pool: keys.pool,
+    };
+    let ix = |accounts| build(accounts, instruction::SetClmmPoolStatus { status: 0 });
+
+    let cases = vec![
+        ("admin signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("amm_config exists", ix(accounts::SetClmmPoolStatus { amm_config: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("amm_config address", ix(accounts::SetClmmPoolStatus { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("pool type", ix(accounts::SetClmmPoolStatus { pool: env.keys.pool, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool.amm_config", ix(accounts::SetClmmPoolStatus { pool: pool_other_config, ..base() }), ErrorCode::ConstraintHasOne),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn set_treasury_authority_accounts() {
+    let (mut env, d) = seeded();
+    let admin = env.admin;
+
+    let base = || accounts::SetTreasuryAuthority {
+        authority: admin,
+        treasury: find_treasury_address().0,
+    };
+    let ix = |accounts| build(accounts, instruction::SetTreasuryAuthority { new_authority: admin });
+
+    let cases = vec![
+        ("authority signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("treasury type", ix(accounts::SetTreasuryAuthority { treasury: d.config_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("treasury mut", readonly(ix(base()), &find_treasury_address().0), ErrorCode::ConstraintMut),
+        ("treasury address", ix(accounts::SetTreasuryAuthority { treasury: d.treasury_copy, ..base() }), ErrorCode::ConstraintSeeds),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn withdraw_treasury_accounts() {
+    let (mut env, d) = seeded();
+    let admin = env.admin;
+    let destination = Pubkey::new_unique();
+    env.svm.airdrop(&find_treasury_address().0, 1_000_000);
+    env.svm.airdrop(&destination, 1_000_000);
+
+    let base = || accounts::WithdrawTreasury {
+        authority: admin,
+        treasury: find_treasury_address().0,
+        destination,
+    };
+    let ix = |accounts| build(accounts, instruction::WithdrawTreasury { amount: 1 });
+
+    let cases = vec![
+        ("authority signs", unsigned(ix(base()), &admin), ErrorCode::AccountNotSigner),
+        ("treasury type", ix(accounts::WithdrawTreasury { treasury: d.config_copy, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("treasury mut", readonly(ix(base()), &find_treasury_address().0), ErrorCode::ConstraintMut),
+        ("treasury address", ix(accounts::WithdrawTreasury { treasury: d.treasury_copy, ..base() }), ErrorCode::ConstraintSeeds),
+        ("destination owner", ix(accounts::WithdrawTreasury { destination: d.token_a, ..base() }), ErrorCode::AccountNotSystemOwned),
+        ("destination mut", readonly(ix(base()), &destination), ErrorCode::ConstraintMut),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn clmm_swap_accounts() {
+    let (mut env, d, position) = clmm_seeded();
+    let keys = env.clmm_keys;
+    let user = env.user;
+    let user_accounts = env.user_accounts;
+    let pool_foreign = env.clone_with_owner(&keys.pool, &token::ID);
+
+    let base = || accounts::ClmmSwap {
+        user,
+        pool: keys.pool,
+        amm_config: find_amm_config_address().0,
+        user_input: user_accounts.token_a,
+        user_output: user_accounts.token_b,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let data = || instruction::ClmmSwap {
+        amount_in: 1_000,
+        min_out: u64::MAX,
+        sqrt_price_limit_x64: 0,
+    };
+    let ix = |accounts| build(accounts, data());
+    let with_array = |array: Pubkey| {
+        let mut ix = ix(base());
+        ix.accounts.push(AccountMeta::new(array, false));
+        ix
+    };
+    let array_foreign = env.clone_with_owner(&position.tick_array_upper, &token::ID);
+
+    let cases = vec![
+        ("user signs", unsigned(ix(base()), &user), ErrorCode::AccountNotSigner),
+        ("pool exists", ix(accounts::ClmmSwap { pool: d.missing, ..base() }), ErrorCode::AccountNotInitialized),
+        ("pool owner", ix(accounts::ClmmSwap { pool: pool_foreign, ..base() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("pool type", ix(accounts::ClmmSwap { pool: env.keys.pool, ..base() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(ix(base()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool.amm_config", ix(accounts::ClmmSwap { amm_config: d.config_copy, ..base() }), ErrorCode::ConstraintHasOne),
+        ("user_input owner", ix(accounts::ClmmSwap { user_input: d.stranger_token_a, ..base() }), ErrorCode::ConstraintRaw),
+        ("user_output owner", ix(accounts::ClmmSwap { user_output: d.token_b, ..base() }), ErrorCode::ConstraintRaw),
+        ("pool.vault_a", ix(accounts::ClmmSwap { vault_a: env.keys.vault_a, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_b", ix(accounts::ClmmSwap { vault_b: env.keys.vault_b, ..base() }), ErrorCode::ConstraintHasOne),
+        ("vault_a mut", readonly(ix(base()), &keys.vault_a), ErrorCode::ConstraintMut),
+        ("pool.token_a_mint", ix(accounts::ClmmSwap { token_a_mint: d.mint, ..base() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", ix(accounts::ClmmSwap { vault_authority: env.keys.vault_authority, ..base() }), ErrorCode::ConstraintHasOne),
+        ("token_a_program matches mint", ix(accounts::ClmmSwap { token_a_program: token_2022::ID, ..base() }), ErrorCode::ConstraintMintTokenProgram),
+        ("token_b_program id", ix(accounts::ClmmSwap { token_b_program: system_program::ID, ..base() }), ErrorCode::InvalidProgramId),
+        ("tick array owner", with_array(array_foreign), ErrorCode::AccountOwnedByWrongProgram),
+        ("tick array type", with_array(keys.pool), ErrorCode::AccountDiscriminatorMismatch),
+        ("tick array mut", readonly(with_array(position.tick_array_upper), &position.tick_array_upper), ErrorCode::AccountNotMutable),
+    ];
+    assert_rejected(&mut env, &[user], cases);
+}
+
+#[test]
+fn position_accounts() {
+    let (mut env, d, position) = clmm_seeded();
+    let keys = env.clmm_keys;
+    let user = env.user;
+    let user_accounts = env.user_accounts;
+
+    // The user's position, in another pool
+    let other_position = Pubkey::new_unique();
+    let moved = Position {
+        pool: Pubkey::new_unique(),
+        ..env.position(&position.position)
+    };
+    write_account(&mut env.svm, &other_position, 8 + Position::LEN, &moved);
+    let array_foreign = env.clone_with_owner(&position.tick_array_lower, &token::ID);
+
+    let decrease = || accounts::DecreaseLiquidity {
+        owner: user,
+        pool: keys.pool,
+        position: position.position,
+        tick_array_lower: position.tick_array_lower,
+        tick_array_upper: position.tick_array_upper,
+    };
+    let increase = || accounts::IncreaseLiquidity {
+        owner: user,
+        pool: keys.pool,
+        position: position.position,
+        tick_array_lower: position.tick_array_lower,
+        tick_array_upper: position.tick_array_upper,
+        user_token_a: user_accounts.token_a,
+        user_token_b: user_accounts.token_b,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let collect = || accounts::Collect {
+        owner: user,
+        pool: keys.pool,
+        position: position.position,
+        tick_array_lower: position.tick_array_lower,
+        tick_array_upper: position.tick_array_upper,
+        recipient_token_a: user_accounts.token_a,
+        recipient_token_b: user_accounts.token_b,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let decrease_ix = |accounts| {
+        build(
+            accounts,
+            instruction::DecreaseLiquidity {
+                liquidity: 1_000,
+                amount_a_min: u64::MAX,
+                amount_b_min: u64::MAX,
+            },
+        )
+    };
+    let increase_ix = |accounts| {
+        build(
+            accounts,
+            instruction::IncreaseLiquidity {
+                liquidity: 1_000,
+                amount_a_max: 0,
+                amount_b_max: 0,
+            },
+        )
+    };
+    let collect_ix = |accounts| {
+        build(
+            accounts,
+            instruction::Collect {
+                amount_a_max: u64::MAX,
+                amount_b_max: u64::MAX,
+            },
+        )
+    };
+
+    let cases = vec![
+        ("decrease owner signs", unsigned(decrease_ix(decrease()), &user), ErrorCode::AccountNotSigner),
+        ("decrease pool type", decrease_ix(accounts::DecreaseLiquidity { pool: env.keys.pool, ..decrease() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("decrease pool mut", readonly(decrease_ix(decrease()), &keys.pool), ErrorCode::ConstraintMut),
+        ("decrease position type", decrease_ix(accounts::DecreaseLiquidity { position: keys.pool, ..decrease() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("decrease position mut", readonly(decrease_ix(decrease()), &position.position), ErrorCode::ConstraintMut),
+        ("decrease position.pool", decrease_ix(accounts::DecreaseLiquidity { position: other_position, ..decrease() }), ErrorCode::ConstraintHasOne),
+        ("decrease tick array owner", decrease_ix(accounts::DecreaseLiquidity { tick_array_lower: array_foreign, ..decrease() }), ErrorCode::AccountOwnedByWrongProgram),
+        ("decrease tick array type", decrease_ix(accounts::DecreaseLiquidity { tick_array_upper: d.pool_copy, ..decrease() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("decrease tick array mut", readonly(decrease_ix(decrease()), &position.tick_array_upper), ErrorCode::ConstraintMut),
+        ("increase owner signs", unsigned(increase_ix(increase()), &user), ErrorCode::AccountNotSigner),
+        ("increase pool.vault_a", increase_ix(accounts::IncreaseLiquidity { vault_a: env.keys.vault_a, ..increase() }), ErrorCode::ConstraintHasOne),
+        ("increase pool.token_b_mint", increase_ix(accounts::IncreaseLiquidity { token_b_mint: d.mint, ..increase() }), ErrorCode::ConstraintHasOne),
+        ("increase user_token_a owner", increase_ix(accounts::IncreaseLiquidity { user_token_a: d.stranger_token_a, ..increase() }), ErrorCode::ConstraintRaw),
+        ("increase user_token_b mut", readonly(increase_ix(increase()), &user_accounts.token_b), ErrorCode::ConstraintMut),
+        ("increase token_a_program matches mint", increase_ix(accounts::IncreaseLiquidity { token_a_program: token_2022::ID, ..increase() }), ErrorCode::ConstraintMintTokenProgram),
+        ("collect owner signs", unsigned(collect_ix(collect()), &user), ErrorCode::AccountNotSigner),
+        ("collect pool.vault_b", collect_ix(accounts::Collect { vault_b: env.keys.vault_b, ..collect() }), ErrorCode::ConstraintHasOne),
+        ("collect pool.vault_authority", collect_ix(accounts::Collect { vault_authority: env.keys.vault_authority, ..collect() }), ErrorCode::ConstraintHasOne),
+        ("collect recipient mut", readonly(collect_ix(collect()), &user_accounts.token_a), ErrorCode::ConstraintMut),
+        ("collect token_b_program id", collect_ix(accounts::Collect { token_b_program: system_program::ID, ..collect() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[user], cases);
+}
+
+#[test]
+fn collect_clmm_fees_accounts() {
+    let (mut env, d, _) = clmm_seeded();
+    let keys = env.clmm_keys;
+    let admin = env.admin;
+    let to_a = env.token_account(&keys.token_a_mint, &admin);
+    let to_b = env.token_account(&keys.token_b_mint, &admin);
+
+    let fees = || accounts::CollectClmmFees {
+        authority: admin,
+        treasury: find_treasury_address().0,
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        treasury_token_a: to_a,
+        treasury_token_b: to_b,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let fund_fees = || accounts::CollectClmmFundFees {
+        admin,
+        amm_config: find_amm_config_address().0,
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        fund_token_a: to_a,
+        fund_token_b: to_b,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+    };
+    let fees_ix = |accounts| build(accounts, instruction::CollectClmmFees {});
+    let fund_fees_ix = |accounts| build(accounts, instruction::CollectClmmFundFees {});
+
+    let cases = vec![
+        ("authority signs", unsigned(fees_ix(fees()), &admin), ErrorCode::AccountNotSigner),
+        ("treasury address", fees_ix(accounts::CollectClmmFees { treasury: d.treasury_copy, ..fees() }), ErrorCode::ConstraintSeeds),
+        ("pool type", fees_ix(accounts::CollectClmmFees { pool: env.keys.pool, ..fees() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool mut", readonly(fees_ix(fees()), &keys.pool), ErrorCode::ConstraintMut),
+        ("pool.vault_a", fees_ix(accounts::CollectClmmFees { vault_a: env.keys.vault_a, ..fees() }), ErrorCode::ConstraintHasOne),
+        ("pool.vault_authority", fees_ix(accounts::CollectClmmFees { vault_authority: env.keys.vault_authority, ..fees() }), ErrorCode::ConstraintHasOne),
+        ("treasury_token_b mut", readonly(fees_ix(fees()), &to_b), ErrorCode::ConstraintMut),
+        ("admin signs", unsigned(fund_fees_ix(fund_fees()), &admin), ErrorCode::AccountNotSigner),
+        ("amm_config address", fund_fees_ix(accounts::CollectClmmFundFees { amm_config: d.config_copy, ..fund_fees() }), ErrorCode::ConstraintSeeds),
+        ("fund pool.vault_b", fund_fees_ix(accounts::CollectClmmFundFees { vault_b: env.keys.vault_b, ..fund_fees() }), ErrorCode::ConstraintHasOne),
+        ("fund token_a_program id", fund_fees_ix(accounts::CollectClmmFundFees { token_a_program: system_program::ID, ..fund_fees() }), ErrorCode::InvalidProgramId),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
+
+#[test]
+fn clmm_init_accounts() {
+    let (mut env, d, _) = clmm_seeded();
+    let keys = env.clmm_keys;
+    let admin = env.admin;
+
+    let pool = || accounts::CreateClmmPool {
+        payer: admin,
+        amm_config: find_amm_config_address().0,
+        treasury: find_treasury_address().0,
+        token_a_mint: keys.token_a_mint,
+        token_b_mint: keys.token_b_mint,
+        pool: keys.pool,
+        vault_a: keys.vault_a,
+        vault_b: keys.vault_b,
+        vault_authority: keys.vault_authority,
+        token_a_program: keys.token_a_program,
+        token_b_program: keys.token_b_program,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+    };
+    let tick_array = || accounts::InitTickArray {
+        payer: admin,
+        pool: keys.pool,
+        tick_array: find_tick_array_address(&keys.pool, 600).0,
+        system_program: system_program::ID,
+    };
+    let position = || accounts::OpenPosition {
+        owner: admin,
+        pool: keys.pool,
+        position: find_position_address(&keys.pool, &admin, 0, 10).0,
+        system_program: system_program::ID,
+    };
+    let pool_ix = |accounts| {
+        build(
+            accounts,
+            instruction::CreateClmmPool {
+                fee_tier_index: POOL_FEE_TIER,
+                tick_spacing: 10,
+                sqrt_price_x64: sqrt_price_at_tick(0).unwrap(),
+            },
+        )
+    };
+    let tick_array_ix = |accounts| build(accounts, instruction::InitTickArray { start_tick_index: 600 });
+    let position_ix = |accounts| {
+        build(
+            accounts,
+            instruction::OpenPosition {
+                tick_lower: 0,
+                tick_upper: 10,
+            },
+        )
+    };
+
+    let cases = vec![
+        ("pool payer signs", unsigned(pool_ix(pool()), &admin), ErrorCode::AccountNotSigner),
+        ("pool amm_config type", pool_ix(accounts::CreateClmmPool { amm_config: d.treasury_copy, ..pool() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("pool treasury exists", pool_ix(accounts::CreateClmmPool { treasury: d.missing, ..pool() }), ErrorCode::AccountNotInitialized),
+        ("pool token_a_program id", pool_ix(accounts::CreateClmmPool { token_a_program: system_program::ID, ..pool() }), ErrorCode::InvalidProgramId),
+        ("pool system_program id", pool_ix(accounts::CreateClmmPool { system_program: token::ID, ..pool() }), ErrorCode::InvalidProgramId),
+        ("pool rent sysvar", pool_ix(accounts::CreateClmmPool { rent: sysvar::clock::ID, ..pool() }), ErrorCode::AccountSysvarMismatch),
+        ("pool address", pool_ix(accounts::CreateClmmPool { pool: d.missing, ..pool() }), ErrorCode::ConstraintSeeds),
+        ("tick array payer signs", unsigned(tick_array_ix(tick_array()), &admin), ErrorCode::AccountNotSigner),
+        ("tick array pool type", tick_array_ix(accounts::InitTickArray { pool: env.keys.pool, ..tick_array() }), ErrorCode::AccountDiscriminatorMismatch),
+        ("tick array system_program id", tick_array_ix(accounts::InitTickArray { system_program: token::ID, ..tick_array() }), ErrorCode::InvalidProgramId),
+        ("tick array address", tick_array_ix(accounts::InitTickArray { tick_array: d.missing, ..tick_array() }), ErrorCode::ConstraintSeeds),
+        ("position owner signs", unsigned(position_ix(position()), &admin), ErrorCode::AccountNotSigner),
+        ("position pool exists", position_ix(accounts::OpenPosition { pool: d.missing, ..position() }), ErrorCode::AccountNotInitialized),
+        ("position system_program id", position_ix(accounts::OpenPosition { system_program: token::ID, ..position() }), ErrorCode::InvalidProgramId),
+        ("position address", position_ix(accounts::OpenPosition { position: d.missing, ..position() }), ErrorCode::ConstraintSeeds),
+    ];
+    assert_rejected(&mut env, &[admin], cases);
+}
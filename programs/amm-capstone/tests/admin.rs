@@ -0,0 +1,460 @@
+//! Instructions that move no tokens: config and status updates,
+//! amplification ramps, weight schedules, `observe`, `sync`,
+//! `decrease_liquidity`, and collecting or skimming when there is
+//! nothing to move. Flows that do move tokens are in `flows.rs`.
+
+mod common;
+
+use amm_capstone::instructions::{Twap, UpdateConfigParams};
+use amm_capstone::constants::MIN_RAMP_DURATION;
+use amm_capstone::errors::AmmError;
+use amm_capstone::math::{
+    position_amounts, price_q64, sqrt_price_at_tick, stable_price_q64, twap_between_observations,
+    Curve, WEIGHT_ONE,
+};
+use amm_capstone::state::PoolStatusBit;
+use amm_capstone_client::*;
+use anchor_lang::prelude::Pubkey;
+
+use common::*;
+
+#[test]
+fn init_config_is_reserved_for_the_upgrade_authority() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let stranger = Pubkey::new_unique();
+    env.svm.airdrop(&stranger, 1_000_000_000);
+    env.svm.set_account(find_amm_config_address().0, AccountData::default());
+
+    let init = |payer: &Pubkey| init_config(payer, FEE_TIERS.to_vec(), PROTOCOL_FEE_RATE, FUND_FEE_RATE, 0);
+    assert_amm_error(env.send(&[init(&stranger)], &[stranger]), AmmError::Unauthorized);
+
+    // An immutable program has no one to initialize it
+    env.svm.set_upgrade_authority(None);
+    assert_amm_error(env.send(&[init(&admin)], &[admin]), AmmError::Unauthorized);
+
+    env.svm.set_upgrade_authority(Some(&admin));
+    env.send(&[init(&admin)], &[admin]).unwrap();
+    assert_eq!(env.config().admin, admin);
+}
+
+#[test]
+fn init_treasury_is_reserved_for_the_upgrade_authority() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let stranger = Pubkey::new_unique();
+    env.svm.airdrop(&stranger, 1_000_000_000);
+    env.svm.set_account(find_treasury_address().0, AccountData::default());
+
+    assert_amm_error(env.send(&[init_treasury(&stranger)], &[stranger]), AmmError::Unauthorized);
+
+    env.svm.set_upgrade_authority(None);
+    assert_amm_error(env.send(&[init_treasury(&admin)], &[admin]), AmmError::Unauthorized);
+
+    env.svm.set_upgrade_authority(Some(&admin));
+    env.send(&[init_treasury(&admin)], &[admin]).unwrap();
+    assert_eq!(env.treasury().authority, admin);
+}
+
+#[test]
+fn set_treasury_authority_hands_over_fee_collection() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let new_authority = Pubkey::new_unique();
+
+    env.send(&[set_treasury_authority(&admin, &new_authority)], &[admin])
+        .unwrap();
+    assert_eq!(env.treasury().authority, new_authority);
+
+    // The old authority has no say any more; the new one can pass it on
+    let ix = set_treasury_authority(&admin, &admin);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::Unauthorized);
+    env.send(&[set_treasury_authority(&new_authority, &admin)], &[new_authority])
+        .unwrap();
+    assert_eq!(env.treasury().authority, admin);
+}
+
+#[test]
+fn withdraw_treasury_pays_out_creation_fees() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let destination = Pubkey::new_unique();
+    let treasury = find_treasury_address().0;
+    let rent = env.svm.minimum_balance(8 + Treasury::LEN);
+    assert_eq!(env.svm.account(&treasury).unwrap().lamports, rent);
+
+    // Pool-creation fees, as `create_pool` pays them
+    env.svm.airdrop(&treasury, 3_000_000);
+    env.send(&[withdraw_treasury(&admin, &destination, 1_000_000)], &[admin])
+        .unwrap();
+    assert_eq!(env.svm.account(&destination).unwrap().lamports, 1_000_000);
+
+    // Everything above the rent-exempt minimum, but no more
+    env.send(&[withdraw_treasury(&admin, &destination, 2_000_000)], &[admin])
+        .unwrap();
+    assert_eq!(env.svm.account(&destination).unwrap().lamports, 3_000_000);
+    assert_eq!(env.svm.account(&treasury).unwrap().lamports, rent);
+    assert_eq!(env.treasury().authority, admin);
+}
+
+#[test]
+fn update_config_applies_only_given_fields() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let new_admin = Pubkey::new_unique();
+
+    let ix = update_config(
+        &admin,
+        UpdateConfigParams {
+            new_admin: Some(new_admin),
+            fee_tiers: Some(vec![5, 25]),
+            protocol_fee_rate: None,
+            fund_fee_rate: Some(500),
+            create_pool_fee: None,
+        },
+    );
+    env.send(&[ix], &[admin]).unwrap();
+
+    let config = env.config();
+    assert_eq!(config.admin, new_admin);
+    assert_eq!(config.fee_tiers, vec![5, 25]);
+    assert_eq!(config.protocol_fee_rate, PROTOCOL_FEE_RATE);
+    assert_eq!(config.fund_fee_rate, 500);
+
+    // The old admin is locked out from here on
+    let ix = set_pool_status(&admin, &env.keys.pool, 0);
+    assert!(env.send(&[ix], &[admin]).is_err());
+}
+
+#[test]
+fn set_pool_status_toggles_operations() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let pool = env.keys.pool;
+
+    let status = 1 << PoolStatusBit::Swap as u8 | 1 << PoolStatusBit::Withdraw as u8;
+    env.send(&[set_pool_status(&admin, &pool, status)], &[admin])
+        .unwrap();
+
+    let state = env.pool();
+    assert!(state.is_disabled(PoolStatusBit::Swap));
+    assert!(state.is_disabled(PoolStatusBit::Withdraw));
+    assert!(!state.is_disabled(PoolStatusBit::Deposit));
+
+    env.send(&[set_pool_status(&admin, &pool, 0)], &[admin])
+        .unwrap();
+    assert_eq!(env.pool().status, 0);
+}
+
+#[test]
+fn ramp_amp_moves_amplification_linearly() {
+    let mut env = Env::new();
+    env.make_stable(100);
+    env.svm.warp(MIN_RAMP_DURATION);
+    let (admin, pool) = (env.admin, env.keys.pool);
+    let start = env.svm.clock().unix_timestamp;
+    let stop = start + 2 * MIN_RAMP_DURATION;
+
+    env.send(&[ramp_amp(&admin, &pool, 1_000, stop)], &[admin])
+        .unwrap();
+    let state = env.pool();
+    assert_eq!((state.initial_amp, state.target_amp), (100, 1_000));
+    assert_eq!((state.ramp_start_ts, state.ramp_stop_ts), (start, stop));
+
+    // Swaps, deposits and withdrawals all price through `quote_state`
+    env.svm.warp(MIN_RAMP_DURATION);
+    let now = start + MIN_RAMP_DURATION;
+    assert_eq!(env.pool().amp_at(now), 550);
+    assert_eq!(env.pool().quote_state(now).curve, Curve::Stable { amp: 550 });
+
+    // Stopping freezes it where it is
+    env.send(&[stop_ramp_amp(&admin, &pool)], &[admin]).unwrap();
+    env.svm.warp(MIN_RAMP_DURATION);
+    assert_eq!(env.pool().amp_at(stop), 550);
+
+    // A new ramp starts from the current value, not the old target
+    let later = stop + MIN_RAMP_DURATION;
+    env.send(&[ramp_amp(&admin, &pool, 55, later)], &[admin])
+        .unwrap();
+    assert_eq!(env.pool().initial_amp, 550);
+    assert_eq!(env.pool().amp_at(later), 55);
+}
+
+#[test]
+fn update_weights_gradually_moves_weights_linearly() {
+    let eighty = WEIGHT_ONE / 10 * 8;
+    let twenty = WEIGHT_ONE / 10 * 2;
+    let half = WEIGHT_ONE / 2;
+
+    let mut env = Env::new();
+    let start = env.svm.clock().unix_timestamp;
+    let end = start + 1_000;
+    env.make_bootstrapping(eighty, twenty, start, end);
+    let (admin, pool) = (env.admin, env.keys.pool);
+
+    // Swaps and deposits price through `quote_state`
+    env.svm.warp(500);
+    let now = start + 500;
+    assert_eq!(env.pool().weights_at(now), (half, half));
+    assert_eq!(
+        env.pool().quote_state(now).curve,
+        Curve::Weighted { weight_a: half, weight_b: half }
+    );
+    assert_eq!(env.pool().weights_at(end + 1), (twenty, eighty));
+
+    // A new schedule starts from the current weights, not the old end,
+    // and waits for its own start
+    let later = now + 100;
+    env.send(
+        &[update_weights_gradually(&admin, &pool, later, later + 400, eighty, twenty)],
+        &[admin],
+    )
+    .unwrap();
+    let state = env.pool();
+    assert_eq!((state.weight_a, state.weight_b), (half, half));
+    assert_eq!((state.weights_start_ts, state.weights_end_ts), (later, later + 400));
+    assert_eq!(state.weights_at(later), (half, half));
+    assert_eq!(state.weights_at(later + 200).0, (half + eighty) / 2);
+    assert_eq!(state.weights_at(later + 400), (eighty, twenty));
+
+    // The sale's opening time stays put
+    assert_eq!(state.swap_start_ts, start);
+}
+
+#[test]
+fn observe_returns_twap_over_the_window() {
+    let mut env = Env::new();
+    env.seed_liquidity(1_000_000, 4_000_000);
+    env.record_observation();
+    let pool = env.pool();
+    env.svm.warp(600);
+
+    // The instruction succeeds without writing the pool
+    env.send(&[observe(&env.keys, 600)], &[]).unwrap();
+    assert_eq!(env.pool().price_a_cumulative, pool.price_a_cumulative);
+    assert_eq!(env.pool().last_update_ts, pool.last_update_ts);
+
+    let twap = run_observe(&mut env, 600);
+    assert_eq!(twap.price_a, price_q64(1_000_000, 4_000_000).unwrap());
+    assert_eq!(twap.price_b, price_q64(4_000_000, 1_000_000).unwrap());
+    let now = env.svm.clock().unix_timestamp;
+    assert_eq!((twap.start.timestamp, twap.current.timestamp), (now - 600, now));
+}
+
+#[test]
+fn observe_starts_at_the_newest_observation_in_the_window() {
+    let mut env = Env::new();
+    env.seed_liquidity(1_000_000, 1_000_000);
+    env.record_observation();
+    env.svm.warp(600);
+
+    // A second price from here on, recorded in the ring
+    let mut pool = env.pool();
+    let (price_a_cumulative, price_b_cumulative) = pool.cumulative_prices_at(env.svm.clock().unix_timestamp);
+    pool.price_a_cumulative = price_a_cumulative;
+    pool.price_b_cumulative = price_b_cumulative;
+    pool.last_update_ts = env.svm.clock().unix_timestamp;
+    pool.reserve_b = 3_000_000;
+    env.set_pool(&pool);
+    env.record_observation();
+    env.svm.warp(600);
+
+    // Only the second price, then both, each for half the window. A
+    // window between observations reaches back to the earlier one.
+    let one = 1u128 << 64;
+    assert_eq!(run_observe(&mut env, 600).price_a, 3 * one);
+    assert_eq!(run_observe(&mut env, 1_200).price_a, 2 * one);
+    let twap = run_observe(&mut env, 900);
+    assert_eq!(twap.price_a, 2 * one);
+    assert_eq!(twap.start, run_observe(&mut env, 1_200).start);
+}
+
+#[test]
+fn observe_weights_a_weighted_pools_price() {
+    let mut env = Env::new();
+    env.make_weighted(WEIGHT_ONE / 10 * 8, WEIGHT_ONE / 10 * 2);
+    env.seed_liquidity(4_000_000, 1_000_000);
+    env.record_observation();
+    env.svm.warp(600);
+
+    // Four times the A at 80/20 trades at par, not at the reserve ratio
+    let twap = run_observe(&mut env, 600);
+    assert_eq!((twap.price_a, twap.price_b), (1 << 64, 1 << 64));
+}
+
+#[test]
+fn observe_prices_a_stable_pool_on_its_curve() {
+    let mut env = Env::new();
+    env.make_stable(100);
+    env.seed_liquidity(1_900_000_000, 100_000_000);
+    let user = env.user;
+
+    // Each swap folds the time since the last one into the accumulators
+    // and writes them to the ring
+    let ix = env.swap_ix(true, 1_000_000, 0);
+    env.send(&[ix], &[user]).unwrap();
+    let pool = env.pool();
+    env.svm.warp(600);
+
+    // Held at the curve's slope, far from the nineteen-to-one reserves
+    let price_a = stable_price_q64(pool.reserve_a, pool.reserve_b, 100).unwrap();
+    let price_b = stable_price_q64(pool.reserve_b, pool.reserve_a, 100).unwrap();
+    assert!(price_a > 8 * price_q64(pool.reserve_a, pool.reserve_b).unwrap());
+    let twap = run_observe(&mut env, 600);
+    assert_eq!((twap.price_a, twap.price_b), (price_a, price_b));
+
+    // The ring records the same prices
+    let ix = env.swap_ix(true, 1_000, 0);
+    env.send(&[ix], &[user]).unwrap();
+    let ring = env.observations();
+    let (start, end) = (ring.observation(1).unwrap(), ring.observation(0).unwrap());
+    let between = |start_cumulative, end_cumulative| {
+        twap_between_observations(start.block_timestamp, start_cumulative, end.block_timestamp, end_cumulative)
+    };
+    assert_eq!(between(start.price_a_cumulative, end.price_a_cumulative), Some(price_a));
+    assert_eq!(between(start.price_b_cumulative, end.price_b_cumulative), Some(price_b));
+}
+
+fn run_observe(env: &mut Env, seconds_ago: u32) -> Twap {
+    env.svm.view(&observe(&env.keys, seconds_ago)).unwrap()
+}
+
+#[test]
+fn collect_with_nothing_accrued_moves_nothing() {
+    let mut env = Env::new();
+    env.seed_liquidity(1_000_000, 2_000_000);
+    let admin = env.admin;
+    let keys = env.keys;
+    let to_a = env.token_account(&keys.token_a_mint, &admin);
+    let to_b = env.token_account(&keys.token_b_mint, &admin);
+
+    env.send(
+        &[
+            collect_fees(&admin, &keys, &to_a, &to_b),
+            collect_fund_fees(&admin, &keys, &to_a, &to_b),
+        ],
+        &[admin],
+    )
+    .unwrap();
+
+    assert_eq!(env.balance(&to_a), 0);
+    assert_eq!(env.balance(&to_b), 0);
+    assert_eq!(env.balance(&keys.vault_a), 1_000_000);
+    assert_eq!(env.balance(&keys.vault_b), 2_000_000);
+}
+
+#[test]
+fn sync_absorbs_donations_but_not_fees() {
+    let mut env = Env::new();
+    env.seed_liquidity(1_000_000, 2_000_000);
+    let admin = env.admin;
+    let keys = env.keys;
+
+    // Fees accrued by earlier swaps sit in the vaults on top of reserves
+    mint_to(&mut env.svm, &keys.token_a_mint, &keys.vault_a, &admin, 300);
+    let mut pool = env.pool();
+    pool.protocol_fees_a = 200;
+    pool.fund_fees_a = 100;
+    env.set_pool(&pool);
+
+    // A transfer straight into the vaults
+    mint_to(&mut env.svm, &keys.token_a_mint, &keys.vault_a, &admin, 5_000);
+    mint_to(&mut env.svm, &keys.token_b_mint, &keys.vault_b, &admin, 7);
+
+    env.svm.warp(60);
+    env.send(&[sync(&keys)], &[]).unwrap();
+
+    let synced = env.pool();
+    assert_eq!(synced.reserve_a, 1_005_000);
+    assert_eq!(synced.reserve_b, 2_000_007);
+    assert_eq!(synced.protocol_fees_a, 200);
+    assert_eq!(synced.fund_fees_a, 100);
+
+    // The price accumulators ran at the old reserves up to the sync
+    let elapsed = (synced.last_update_ts - pool.last_update_ts) as u128;
+    assert_eq!(synced.last_update_ts, env.svm.clock().unix_timestamp);
+    assert_eq!(
+        synced.price_a_cumulative - pool.price_a_cumulative,
+        price_q64(1_000_000, 2_000_000).unwrap() * elapsed
+    );
+
+    // Nothing left over
+    let before = env.pool();
+    env.send(&[sync(&keys)], &[]).unwrap();
+    assert_eq!(env.pool().reserve_a, before.reserve_a);
+    assert_eq!(env.pool().reserve_b, before.reserve_b);
+}
+
+#[test]
+fn skim_with_no_excess_moves_nothing() {
+    let mut env = Env::new();
+    env.seed_liquidity(1_000_000, 2_000_000);
+    let keys = env.keys;
+    let recipient = Pubkey::new_unique();
+    let to_a = env.token_account(&keys.token_a_mint, &recipient);
+    let to_b = env.token_account(&keys.token_b_mint, &recipient);
+
+    env.send(&[skim(&keys, &to_a, &to_b)], &[]).unwrap();
+
+    assert_eq!(env.balance(&to_a), 0);
+    assert_eq!(env.balance(&to_b), 0);
+    assert_eq!(env.balance(&keys.vault_a), 1_000_000);
+    assert_eq!(env.balance(&keys.vault_b), 2_000_000);
+}
+
+#[test]
+fn decrease_liquidity_credits_the_position_and_clears_its_ticks() {
+    let mut env = Env::new();
+    env.make_clmm(10, sqrt_price_at_tick(0).unwrap());
+    let position = env.deposit_clmm(-600, 590, 1_000_000_000);
+    let (user, pool) = (env.user, env.clmm_keys.pool);
+
+    let ix = decrease_liquidity(&user, &pool, &position, 400_000_000, 0, 0);
+    env.send(&[ix], &[user]).unwrap();
+
+    // Owed what the liquidity was worth, rounded down; nothing leaves
+    // the vaults until `collect`
+    let (owed_a, owed_b) = position_amounts(
+        sqrt_price_at_tick(0).unwrap(),
+        sqrt_price_at_tick(-600).unwrap(),
+        sqrt_price_at_tick(590).unwrap(),
+        400_000_000,
+        false,
+    )
+    .unwrap();
+    let state = env.position(&position.position);
+    assert_eq!(state.liquidity, 600_000_000);
+    assert_eq!((state.tokens_owed_a, state.tokens_owed_b), (owed_a, owed_b));
+    assert_eq!(env.clmm_pool().liquidity, 600_000_000);
+    assert!(env.balance(&env.clmm_keys.vault_a) >= owed_a);
+
+    // Withdrawing the rest leaves both bounds unused
+    let ix = decrease_liquidity(&user, &pool, &position, 600_000_000, 0, 0);
+    env.send(&[ix], &[user]).unwrap();
+
+    assert_eq!(env.clmm_pool().liquidity, 0);
+    let lower = env.tick_array(&position.tick_array_lower);
+    let upper = env.tick_array(&position.tick_array_upper);
+    assert!(!lower.ticks[0].is_initialized());
+    assert!(!upper.ticks[59].is_initialized());
+    assert_eq!(upper.ticks[59].liquidity_net, 0);
+}
+
+#[test]
+fn collect_with_nothing_owed_moves_nothing() {
+    let mut env = Env::new();
+    env.make_clmm(10, sqrt_price_at_tick(0).unwrap());
+    let position = env.deposit_clmm(-600, 590, 1_000_000_000);
+    let (user, keys) = (env.user, env.clmm_keys);
+    let (token_a, token_b) = (env.user_accounts.token_a, env.user_accounts.token_b);
+    let vault_a = env.balance(&keys.vault_a);
+
+    // No swaps yet, so the poke credits no fees
+    let ix = collect(&user, &keys, &position, &token_a, &token_b, u64::MAX, u64::MAX);
+    env.send(&[ix], &[user]).unwrap();
+
+    let state = env.position(&position.position);
+    assert_eq!((state.tokens_owed_a, state.tokens_owed_b), (0, 0));
+    assert_eq!(env.balance(&token_a), USER_BALANCE);
+    assert_eq!(env.balance(&keys.vault_a), vault_a);
+}
@@ -0,0 +1,681 @@
+//! Shared fixtures: a config, treasury and pool written straight into
+//! the account store, so tests can start from states the instructions
+//! would take many steps to reach, with real mints and token accounts
+//! and a funded user. A concentrated liquidity pool on the same mints is
+//! written on demand. `flows.rs` builds its pools with the instructions
+//! instead.
+#![allow(dead_code)]
+
+// The fuzz target runs the native build instead, on a runtime of its own
+// with the same interface (`fuzz/src/svm.rs`)
+#[cfg(not(fuzzing))]
+pub mod svm;
+#[cfg(fuzzing)]
+use crate::svm;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountSerialize, Discriminator};
+use bytemuck::Zeroable;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::{Account, Mint};
+
+use amm_capstone::errors::AmmError;
+use amm_capstone::math::{PoolState, WEIGHT_ONE};
+use amm_capstone::clmm_engine::modify_position;
+use amm_capstone::math::tick_at_sqrt_price;
+use amm_capstone::state::{
+    AmmConfig, ClmmPool, CurveType, ObservationState, Pool, Position, TickArrayState, Treasury,
+};
+use amm_capstone_client::*;
+
+pub use svm::{AccountData, Svm};
+
+pub const FEE_TIERS: [u16; 4] = [1, 5, 30, 100];
+pub const POOL_FEE_BPS: u16 = 30;
+// Where `POOL_FEE_BPS` sits in `FEE_TIERS`, which the fixture pools use
+pub const POOL_FEE_TIER: u8 = 2;
+pub const PROTOCOL_FEE_RATE: u16 = 2_000;
+pub const FUND_FEE_RATE: u16 = 1_000;
+pub const USER_BALANCE: u64 = 1_000_000_000_000;
+
+/// Token program and extensions a test mint is created with.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum MintKind {
+    #[default]
+    Classic,
+    // Token-2022 with a `bps` transfer fee capped at `maximum_fee`
+    TransferFee { bps: u16, maximum_fee: u64 },
+    NonTransferable,
+}
+
+impl MintKind {
+    pub fn program(self) -> Pubkey {
+        match self {
+            MintKind::Classic => spl_token::ID,
+            _ => spl_token_2022::ID,
+        }
+    }
+
+    fn extensions(self) -> Vec<ExtensionType> {
+        match self {
+            MintKind::Classic => vec![],
+            MintKind::TransferFee { .. } => vec![ExtensionType::TransferFeeConfig],
+            MintKind::NonTransferable => vec![ExtensionType::NonTransferable],
+        }
+    }
+}
+
+pub struct Env {
+    pub svm: Svm,
+    pub admin: Pubkey,
+    pub user: Pubkey,
+    pub keys: PoolKeys,
+    pub user_accounts: UserTokenAccounts,
+    // Not written until `make_clmm`
+    pub clmm_keys: ClmmPoolKeys,
+}
+
+impl Env {
+    /// A 30 bps pool of two classic mints, as `create_pool` leaves it.
+    pub fn new() -> Self {
+        Self::with_mints(MintKind::Classic, MintKind::Classic)
+    }
+
+    pub fn with_mints(kind_a: MintKind, kind_b: MintKind) -> Self {
+        let mut svm = Svm::new();
+        let admin = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        svm.airdrop(&admin, 100_000_000_000);
+        svm.airdrop(&user, 100_000_000_000);
+        svm.set_upgrade_authority(Some(&admin));
+
+        let (treasury, _) = find_treasury_address();
+        write_account(
+            &mut svm,
+            &treasury,
+            8 + Treasury::LEN,
+            &Treasury { authority: admin },
+        );
+
+        let (amm_config, bump) = find_amm_config_address();
+        write_account(
+            &mut svm,
+            &amm_config,
+            8 + AmmConfig::LEN,
+            &AmmConfig {
+                admin,
+                fee_tiers: FEE_TIERS.to_vec(),
+                protocol_fee_rate: PROTOCOL_FEE_RATE,
+                fund_fee_rate: FUND_FEE_RATE,
+                create_pool_fee: 0,
+                bump,
+            },
+        );
+
+        let mint_a = create_mint(&mut svm, &admin, 6, kind_a);
+        let mint_b = create_mint(&mut svm, &admin, 9, kind_b);
+        let keys = PoolKeys::new(
+            mint_a,
+            mint_b,
+            kind_a.program(),
+            kind_b.program(),
+            POOL_FEE_BPS,
+            CurveType::ConstantProduct,
+        );
+
+        create_token_account_at(&mut svm, &keys.vault_a, &mint_a, &keys.vault_authority);
+        create_token_account_at(&mut svm, &keys.vault_b, &mint_b, &keys.vault_authority);
+        create_mint_at(
+            &mut svm,
+            &keys.lp_mint,
+            &keys.vault_authority,
+            Pool::lp_decimals(6, 9),
+            MintKind::Classic,
+        );
+        create_token_account_at(
+            &mut svm,
+            &keys.locked_lp,
+            &keys.lp_mint,
+            &keys.locked_lp_authority,
+        );
+
+        let mut observation = vec![0; 8 + ObservationState::LEN];
+        observation[..8].copy_from_slice(ObservationState::DISCRIMINATOR);
+        observation[8..40].copy_from_slice(keys.pool.as_ref());
+        set_program_account(&mut svm, &keys.observation_state, observation);
+
+        let created_at = svm.clock().unix_timestamp;
+        write_account(
+            &mut svm,
+            &keys.pool,
+            8 + Pool::LEN,
+            &Pool {
+                authority: Pubkey::default(),
+                amm_config,
+                token_a_mint: mint_a,
+                token_b_mint: mint_b,
+                vault_a: keys.vault_a,
+                vault_b: keys.vault_b,
+                vault_authority: keys.vault_authority,
+                vault_authority_bump: find_vault_authority_address(&keys.pool).1,
+                lp_mint: keys.lp_mint,
+                observation_state: keys.observation_state,
+                reserve_a: 0,
+                reserve_b: 0,
+                fee_bps: POOL_FEE_BPS,
+                status: 0,
+                protocol_fees_a: 0,
+                protocol_fees_b: 0,
+                fund_fees_a: 0,
+                fund_fees_b: 0,
+                price_a_cumulative: 0,
+                price_b_cumulative: 0,
+                last_update_ts: created_at,
+                curve_type: CurveType::ConstantProduct as u8,
+                initial_amp: 0,
+                target_amp: 0,
+                ramp_start_ts: created_at,
+                ramp_stop_ts: created_at,
+                weight_a: 0,
+                weight_b: 0,
+                end_weight_a: 0,
+                end_weight_b: 0,
+                weights_start_ts: created_at,
+                weights_end_ts: created_at,
+                swap_start_ts: created_at,
+            },
+        );
+
+        let token_a = create_token_account(&mut svm, &mint_a, &user);
+        let token_b = create_token_account(&mut svm, &mint_b, &user);
+        let lp = create_token_account(&mut svm, &keys.lp_mint, &user);
+        mint_to(&mut svm, &mint_a, &token_a, &admin, USER_BALANCE);
+        mint_to(&mut svm, &mint_b, &token_b, &admin, USER_BALANCE);
+
+        Self {
+            svm,
+            admin,
+            user,
+            keys,
+            user_accounts: UserTokenAccounts { token_a, token_b, lp },
+            clmm_keys: ClmmPoolKeys::new(
+                mint_a,
+                mint_b,
+                kind_a.program(),
+                kind_b.program(),
+                POOL_FEE_BPS,
+            ),
+        }
+    }
+
+    /// Funds the vaults and mints LP as a first `add_liquidity` of
+    /// exactly `reserve_a` / `reserve_b` would, with `MINIMUM_LIQUIDITY`
+    /// locked and the rest held by the user.
+    pub fn seed_liquidity(&mut self, reserve_a: u64, reserve_b: u64) {
+        let keys = self.keys;
+        mint_to(&mut self.svm, &keys.token_a_mint, &keys.vault_a, &self.admin, reserve_a);
+        mint_to(&mut self.svm, &keys.token_b_mint, &keys.vault_b, &self.admin, reserve_b);
+
+        let now = self.svm.clock().unix_timestamp;
+        let quote = self
+            .pool()
+            .quote_state(now)
+            .quote_add_liquidity(reserve_a, reserve_b, 0)
+            .unwrap();
+        let locked = quote.locked_liquidity;
+        mint_to(&mut self.svm, &keys.lp_mint, &keys.locked_lp, &keys.vault_authority, locked);
+        mint_to(
+            &mut self.svm,
+            &keys.lp_mint,
+            &self.user_accounts.lp,
+            &keys.vault_authority,
+            quote.liquidity,
+        );
+
+        let mut pool = self.pool();
+        pool.reserve_a = reserve_a;
+        pool.reserve_b = reserve_b;
+        self.set_pool(&pool);
+    }
+
+    /// Makes the pool a stable pool, as `create_pool` with
+    /// `CurveParams::Stable { amp }` would. Seed liquidity after this.
+    /// Like the other `make_*` helpers it keeps the constant-product
+    /// pool's address, which only `create_pool` derives.
+    pub fn make_stable(&mut self, amp: u64) {
+        let mut pool = self.pool();
+        pool.curve_type = CurveType::Stable as u8;
+        pool.initial_amp = amp;
+        pool.target_amp = amp;
+        self.set_pool(&pool);
+    }
+
+    /// Makes the pool a weighted pool, as `create_pool` with
+    /// `CurveParams::Weighted` would. Seed liquidity after this.
+    pub fn make_weighted(&mut self, weight_a: u64, weight_b: u64) {
+        let mut pool = self.pool();
+        pool.curve_type = CurveType::Weighted as u8;
+        pool.weight_a = weight_a;
+        pool.weight_b = weight_b;
+        pool.end_weight_a = weight_a;
+        pool.end_weight_b = weight_b;
+        self.set_pool(&pool);
+    }
+
+    /// Makes the pool a liquidity bootstrapping pool owned by the admin,
+    /// as `create_pool` with `CurveParams::LiquidityBootstrapping` would:
+    /// weight A moves from `start_weight_a` to `end_weight_a` over
+    /// `start_ts..end_ts`, and swaps open at `start_ts`.
+    pub fn make_bootstrapping(
+        &mut self,
+        start_weight_a: u64,
+        end_weight_a: u64,
+        start_ts: i64,
+        end_ts: i64,
+    ) {
+        let mut pool = self.pool();
+        pool.authority = self.admin;
+        pool.curve_type = CurveType::LiquidityBootstrapping as u8;
+        pool.weight_a = start_weight_a;
+        pool.weight_b = WEIGHT_ONE - start_weight_a;
+        pool.end_weight_a = end_weight_a;
+        pool.end_weight_b = WEIGHT_ONE - end_weight_a;
+        pool.weights_start_ts = start_ts;
+        pool.weights_end_ts = end_ts;
+        pool.swap_start_ts = start_ts;
+        self.set_pool(&pool);
+    }
+
+    /// Writes a 30 bps concentrated liquidity pool on the same mints at
+    /// `sqrt_price_x64`, with empty vaults, as `create_clmm_pool` leaves
+    /// it.
+    pub fn make_clmm(&mut self, tick_spacing: u16, sqrt_price_x64: u128) {
+        let keys = self.clmm_keys;
+        create_token_account_at(&mut self.svm, &keys.vault_a, &keys.token_a_mint, &keys.vault_authority);
+        create_token_account_at(&mut self.svm, &keys.vault_b, &keys.token_b_mint, &keys.vault_authority);
+
+        self.set_clmm_pool(&ClmmPool {
+            amm_config: find_amm_config_address().0,
+            token_a_mint: keys.token_a_mint,
+            token_b_mint: keys.token_b_mint,
+            vault_a: keys.vault_a,
+            vault_b: keys.vault_b,
+            vault_authority: keys.vault_authority,
+            vault_authority_bump: find_vault_authority_address(&keys.pool).1,
+            tick_spacing,
+            fee_bps: POOL_FEE_BPS,
+            status: 0,
+            liquidity: 0,
+            sqrt_price_x64,
+            tick_current: tick_at_sqrt_price(sqrt_price_x64).unwrap(),
+            fee_growth_global_a_x64: 0,
+            fee_growth_global_b_x64: 0,
+            protocol_fees_a: 0,
+            protocol_fees_b: 0,
+            fund_fees_a: 0,
+            fund_fees_b: 0,
+        });
+    }
+
+    /// Writes the empty tick array holding `tick`, as `init_tick_array`
+    /// would, unless it exists. Returns its address.
+    pub fn init_tick_array_at(&mut self, tick: i32) -> Pubkey {
+        let tick_spacing = self.clmm_pool().tick_spacing;
+        let address = self.clmm_keys.tick_array(tick, tick_spacing);
+        if self.svm.account(&address).is_none() {
+            let mut array = TickArrayState::zeroed();
+            array.pool = self.clmm_keys.pool;
+            array.start_tick_index = TickArrayState::start_index(tick, tick_spacing);
+            self.set_tick_array(&address, &array);
+        }
+        address
+    }
+
+    /// Opens the user's position over `tick_lower..tick_upper` with
+    /// `liquidity`, funding the vaults with what `increase_liquidity`
+    /// would take.
+    pub fn deposit_clmm(&mut self, tick_lower: i32, tick_upper: i32, liquidity: u128) -> PositionKeys {
+        let mut pool = self.clmm_pool();
+        let keys = self
+            .clmm_keys
+            .position(&self.user, tick_lower, tick_upper, pool.tick_spacing);
+        self.init_tick_array_at(tick_lower);
+        self.init_tick_array_at(tick_upper);
+
+        let mut position = Position {
+            pool: self.clmm_keys.pool,
+            owner: self.user,
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_a_last_x64: 0,
+            fee_growth_inside_b_last_x64: 0,
+            tokens_owed_a: 0,
+            tokens_owed_b: 0,
+        };
+        let mut lower = self.tick_array(&keys.tick_array_lower);
+        let mut upper = self.tick_array(&keys.tick_array_upper);
+        let delta = liquidity as i128;
+        let (amount_a, amount_b) = if keys.tick_array_lower == keys.tick_array_upper {
+            let (tick_l, tick_u) = lower
+                .tick_pair_mut(tick_lower, tick_upper, pool.tick_spacing)
+                .unwrap();
+            let amounts = modify_position(&mut pool, &mut position, tick_l, tick_u, delta).unwrap();
+            upper = lower;
+            amounts
+        } else {
+            let tick_l = lower.tick_mut(tick_lower, pool.tick_spacing).unwrap();
+            let tick_u = upper.tick_mut(tick_upper, pool.tick_spacing).unwrap();
+            modify_position(&mut pool, &mut position, tick_l, tick_u, delta).unwrap()
+        };
+
+        self.set_clmm_pool(&pool);
+        self.set_tick_array(&keys.tick_array_lower, &lower);
+        self.set_tick_array(&keys.tick_array_upper, &upper);
+        write_account(&mut self.svm, &keys.position, 8 + Position::LEN, &position);
+
+        let clmm_keys = self.clmm_keys;
+        mint_to(&mut self.svm, &clmm_keys.token_a_mint, &clmm_keys.vault_a, &self.admin, amount_a);
+        mint_to(&mut self.svm, &clmm_keys.token_b_mint, &clmm_keys.vault_b, &self.admin, amount_b);
+        keys
+    }
+
+    pub fn clmm_pool(&self) -> ClmmPool {
+        self.svm.fetch(&self.clmm_keys.pool)
+    }
+
+    pub fn set_clmm_pool(&mut self, pool: &ClmmPool) {
+        let address = self.clmm_keys.pool;
+        write_account(&mut self.svm, &address, 8 + ClmmPool::LEN, pool);
+    }
+
+    pub fn position(&self, address: &Pubkey) -> Position {
+        self.svm.fetch(address)
+    }
+
+    pub fn tick_array(&self, address: &Pubkey) -> TickArrayState {
+        let data = &self.svm.account(address).unwrap().data;
+        assert_eq!(&data[..8], TickArrayState::DISCRIMINATOR);
+        bytemuck::pod_read_unaligned(&data[8..])
+    }
+
+    pub fn set_tick_array(&mut self, address: &Pubkey, array: &TickArrayState) {
+        let mut data = TickArrayState::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(array));
+        set_program_account(&mut self.svm, address, data);
+    }
+
+    pub fn send(&mut self, instructions: &[Instruction], signers: &[Pubkey]) -> ProgramResult {
+        self.svm.send(instructions, signers)
+    }
+
+    pub fn add_liquidity_ix(
+        &self,
+        amount_a_desired: u64,
+        amount_b_desired: u64,
+        amount_a_min: u64,
+        amount_b_min: u64,
+        min_lp_out: u64,
+    ) -> Instruction {
+        add_liquidity(
+            &self.user,
+            &self.keys,
+            &self.user_accounts,
+            amount_a_desired,
+            amount_b_desired,
+            amount_a_min,
+            amount_b_min,
+            min_lp_out,
+        )
+    }
+
+    pub fn add_liquidity_single_ix(&self, token_a: bool, amount_in: u64, min_lp_out: u64) -> Instruction {
+        let (token_in, _) = self.user_pair(token_a);
+        add_liquidity_single(&self.user, &self.keys, &token_in, &self.user_accounts.lp, amount_in, min_lp_out)
+    }
+
+    pub fn remove_liquidity_ix(&self, lp_amount: u64) -> Instruction {
+        remove_liquidity(&self.user, &self.keys, &self.user_accounts, lp_amount)
+    }
+
+    pub fn swap_ix(&self, a_to_b: bool, amount_in: u64, min_out: u64) -> Instruction {
+        let (input, output) = self.user_pair(a_to_b);
+        swap(&self.user, &self.keys, &input, &output, amount_in, min_out)
+    }
+
+    pub fn swap_base_out_ix(&self, a_to_b: bool, max_amount_in: u64, amount_out: u64) -> Instruction {
+        let (input, output) = self.user_pair(a_to_b);
+        swap_base_out(&self.user, &self.keys, &input, &output, max_amount_in, amount_out)
+    }
+
+    /// The user's (input, output) token accounts for a direction.
+    pub fn user_pair(&self, a_to_b: bool) -> (Pubkey, Pubkey) {
+        if a_to_b {
+            (self.user_accounts.token_a, self.user_accounts.token_b)
+        } else {
+            (self.user_accounts.token_b, self.user_accounts.token_a)
+        }
+    }
+
+    /// A token account of `mint` for `owner`.
+    pub fn token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        create_token_account(&mut self.svm, mint, owner)
+    }
+
+    pub fn pool(&self) -> Pool {
+        self.svm.fetch(&self.keys.pool)
+    }
+
+    /// Overwrites the pool, for states the instructions under test
+    /// cannot set up themselves.
+    pub fn set_pool(&mut self, pool: &Pool) {
+        let address = self.keys.pool;
+        write_account(&mut self.svm, &address, 8 + Pool::LEN, pool);
+    }
+
+    pub fn observations(&self) -> ObservationState {
+        let data = &self.svm.account(&self.keys.observation_state).unwrap().data;
+        assert_eq!(&data[..8], ObservationState::DISCRIMINATOR);
+        bytemuck::pod_read_unaligned(&data[8..])
+    }
+
+    /// Records the pool's accumulators in the ring now, as a swap does.
+    pub fn record_observation(&mut self) {
+        let clock = self.svm.clock();
+        let (price_a_cumulative, price_b_cumulative) =
+            self.pool().cumulative_prices_at(clock.unix_timestamp);
+        let mut observations = self.observations();
+        observations.update(clock.slot, clock.unix_timestamp, price_a_cumulative, price_b_cumulative, 0);
+
+        let mut data = ObservationState::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&observations));
+        set_program_account(&mut self.svm, &self.keys.observation_state, data);
+    }
+
+    /// Pricing view of the pool now, with the config's fee shares, as
+    /// the swap path builds it.
+    pub fn quote_state(&self) -> PoolState {
+        let config = self.config();
+        self.pool()
+            .quote_state(self.svm.clock().unix_timestamp)
+            .with_fee_shares(config.protocol_fee_rate, config.fund_fee_rate)
+    }
+
+    pub fn config(&self) -> AmmConfig {
+        self.svm.fetch(&find_amm_config_address().0)
+    }
+
+    pub fn set_config(&mut self, config: &AmmConfig) {
+        let address = find_amm_config_address().0;
+        write_account(&mut self.svm, &address, 8 + AmmConfig::LEN, config);
+    }
+
+    pub fn treasury(&self) -> Treasury {
+        self.svm.fetch(&find_treasury_address().0)
+    }
+
+    pub fn balance(&self, token_account: &Pubkey) -> u64 {
+        token_balance(&self.svm, token_account)
+    }
+
+    /// Copies `address` to a fresh key, owned by `owner` instead.
+    pub fn clone_with_owner(&mut self, address: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let clone = Pubkey::new_unique();
+        let account = AccountData {
+            owner: *owner,
+            ..self.svm.account(address).unwrap()
+        };
+        self.svm.set_account(clone, account);
+        clone
+    }
+}
+
+/// Serializes `account` (discriminator included) into a zero-padded,
+/// program-owned account of `space` bytes.
+pub fn write_account<T: AccountSerialize>(svm: &mut Svm, address: &Pubkey, space: usize, account: &T) {
+    let mut data = Vec::with_capacity(space);
+    account.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    set_program_account(svm, address, data);
+}
+
+fn set_program_account(svm: &mut Svm, address: &Pubkey, data: Vec<u8>) {
+    let lamports = svm.minimum_balance(data.len());
+    svm.set_account(
+        *address,
+        AccountData {
+            lamports,
+            data,
+            owner: amm_capstone::ID,
+            executable: false,
+        },
+    );
+}
+
+pub fn create_mint(svm: &mut Svm, authority: &Pubkey, decimals: u8, kind: MintKind) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    create_mint_at(svm, &mint, authority, decimals, kind);
+    mint
+}
+
+pub fn create_mint_at(svm: &mut Svm, mint: &Pubkey, authority: &Pubkey, decimals: u8, kind: MintKind) {
+    let program = kind.program();
+    let len = ExtensionType::try_calculate_account_len::<Mint>(&kind.extensions()).unwrap();
+    allocate(svm, mint, len, &program);
+
+    let mut instructions = match kind {
+        MintKind::Classic => vec![],
+        MintKind::TransferFee { bps, maximum_fee } => vec![
+            spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                &program,
+                mint,
+                Some(authority),
+                Some(authority),
+                bps,
+                maximum_fee,
+            )
+            .unwrap(),
+        ],
+        MintKind::NonTransferable => vec![
+            spl_token_2022::instruction::initialize_non_transferable_mint(&program, mint).unwrap(),
+        ],
+    };
+    instructions.push(
+        spl_token_2022::instruction::initialize_mint2(&program, mint, authority, None, decimals)
+            .unwrap(),
+    );
+
+    svm.send(&instructions, &[]).expect("mint setup failed");
+}
+
+pub fn create_token_account(svm: &mut Svm, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+    let account = Pubkey::new_unique();
+    create_token_account_at(svm, &account, mint, owner);
+    account
+}
+
+pub fn create_token_account_at(svm: &mut Svm, account: &Pubkey, mint: &Pubkey, owner: &Pubkey) {
+    let mint_account = svm.account(mint).expect("mint does not exist");
+    let program = mint_account.owner;
+
+    let mint_extensions = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .unwrap()
+        .get_extension_types()
+        .unwrap();
+    let len = ExtensionType::try_calculate_account_len::<Account>(
+        &ExtensionType::get_required_init_account_extensions(&mint_extensions),
+    )
+    .unwrap();
+    allocate(svm, account, len, &program);
+
+    svm.send(
+        &[spl_token_2022::instruction::initialize_account3(&program, account, mint, owner).unwrap()],
+        &[],
+    )
+    .expect("token account setup failed");
+}
+
+pub fn mint_to(svm: &mut Svm, mint: &Pubkey, account: &Pubkey, authority: &Pubkey, amount: u64) {
+    let program = svm.account(mint).unwrap().owner;
+    svm.send(
+        &[spl_token_2022::instruction::mint_to(&program, mint, account, authority, &[], amount)
+            .unwrap()],
+        &[*authority],
+    )
+    .expect("mint_to failed");
+}
+
+pub fn mint_supply(svm: &Svm, mint: &Pubkey) -> u64 {
+    let account = svm.account(mint).expect("mint does not exist");
+    StateWithExtensions::<Mint>::unpack(&account.data)
+        .unwrap()
+        .base
+        .supply
+}
+
+pub fn token_balance(svm: &Svm, token_account: &Pubkey) -> u64 {
+    let account = svm.account(token_account).expect("token account does not exist");
+    StateWithExtensions::<Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+// A rent-exempt, zeroed account owned by `owner`, as a system
+// `create_account` would leave it
+fn allocate(svm: &mut Svm, address: &Pubkey, len: usize, owner: &Pubkey) {
+    let lamports = svm.minimum_balance(len);
+    svm.set_account(
+        *address,
+        AccountData {
+            lamports,
+            data: vec![0; len],
+            owner: *owner,
+            executable: false,
+        },
+    );
+}
+
+pub fn amm_error(error: AmmError) -> ProgramError {
+    anchor_lang::error::Error::from(error).into()
+}
+
+pub fn anchor_error(error: anchor_lang::error::ErrorCode) -> ProgramError {
+    anchor_lang::error::Error::from(error).into()
+}
+
+#[track_caller]
+pub fn assert_amm_error(result: ProgramResult, error: AmmError) {
+    assert_eq!(result, Err(amm_error(error)), "expected {error:?}");
+}
+
+#[track_caller]
+pub fn assert_anchor_error(result: ProgramResult, error: anchor_lang::error::ErrorCode) {
+    assert_eq!(result, Err(anchor_error(error)), "expected {error:?}");
+}
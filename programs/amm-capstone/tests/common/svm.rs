@@ -0,0 +1,225 @@
+//! The runtime integration tests run on: LiteSVM executing the program's
+//! SBF build, `target/deploy/amm_capstone.so` (`anchor build` writes it),
+//! beside the SPL Token and Token-2022 programs LiteSVM ships. The
+//! program is deployed under the upgradeable loader, so it has a
+//! `ProgramData` account and an upgrade authority like on a cluster.
+//!
+//! Each `send` is one transaction, paid for by a key of the runtime's own
+//! so the accounts under test only move by what the instructions do.
+//! Signatures are not verified; a signer is just a listed pubkey, and an
+//! instruction wanting one that is not listed fails with
+//! `MissingRequiredSignature`.
+
+use anchor_lang::prelude::{Clock, ProgramError, Pubkey};
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize};
+use litesvm::types::TransactionMetadata;
+use litesvm::LiteSVM;
+use solana_account::Account;
+use solana_message::Message;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+const PROGRAM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../target/deploy/amm_capstone.so"
+);
+
+// bincode `UpgradeableLoaderState::ProgramData`, which the ELF follows
+const PROGRAM_DATA_METADATA_LEN: usize = 45;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccountData {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+pub struct Svm {
+    svm: LiteSVM,
+    // Pays every transaction's fee
+    payer: Pubkey,
+}
+
+impl Svm {
+    pub fn new() -> Self {
+        let elf = std::fs::read(PROGRAM_PATH).unwrap_or_else(|error| {
+            panic!("cannot read {PROGRAM_PATH} ({error}); build the program with `anchor build`")
+        });
+
+        let mut svm = Self {
+            svm: LiteSVM::new()
+                .with_sigverify(false)
+                .with_blockhash_check(false)
+                // Tests resend identical transactions
+                .with_transaction_history(0),
+            payer: Pubkey::new_unique(),
+        };
+        svm.svm.set_sysvar(&Clock {
+            slot: 1,
+            unix_timestamp: 1_700_000_000,
+            ..Clock::default()
+        });
+        let payer = svm.payer;
+        svm.airdrop(&payer, 1_000_000_000_000);
+
+        // `ProgramData` first, as loading the program reads it
+        let mut program_data = program_data_metadata(None);
+        program_data.extend_from_slice(&elf);
+        svm.set_account(
+            program_data_address(),
+            AccountData {
+                lamports: svm.minimum_balance(program_data.len()),
+                data: program_data,
+                owner: bpf_loader_upgradeable::ID,
+                executable: false,
+            },
+        );
+
+        // bincode `UpgradeableLoaderState::Program { programdata_address }`
+        let mut program = 2u32.to_le_bytes().to_vec();
+        program.extend_from_slice(program_data_address().as_ref());
+        svm.set_account(
+            amm_capstone::ID,
+            AccountData {
+                lamports: svm.minimum_balance(program.len()),
+                data: program,
+                owner: bpf_loader_upgradeable::ID,
+                executable: true,
+            },
+        );
+
+        svm
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Option<AccountData> {
+        self.svm.get_account(address).map(|account| AccountData {
+            lamports: account.lamports,
+            data: account.data,
+            owner: account.owner,
+            executable: account.executable,
+        })
+    }
+
+    /// Writes `account` at `address`; an account with no lamports is
+    /// removed.
+    pub fn set_account(&mut self, address: Pubkey, account: AccountData) {
+        let account = Account {
+            lamports: account.lamports,
+            data: account.data,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: 0,
+        };
+        self.svm
+            .set_account(address, account)
+            .expect("account rejected");
+    }
+
+    /// Rewrites the program's `ProgramData` with `authority` as its
+    /// upgrade authority, `None` making it immutable.
+    pub fn set_upgrade_authority(&mut self, authority: Option<&Pubkey>) {
+        let address = program_data_address();
+        let mut account = self.account(&address).expect("program is not deployed");
+        account
+            .data
+            .splice(..PROGRAM_DATA_METADATA_LEN, program_data_metadata(authority));
+        self.set_account(address, account);
+    }
+
+    pub fn airdrop(&mut self, address: &Pubkey, lamports: u64) {
+        let mut account = self.account(address).unwrap_or_default();
+        account.lamports += lamports;
+        self.set_account(*address, account);
+    }
+
+    /// Decodes an Anchor account, checking its discriminator.
+    pub fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self.account(address).expect("account does not exist");
+        T::try_deserialize(&mut &account.data[..]).expect("account does not decode")
+    }
+
+    pub fn minimum_balance(&self, space: usize) -> u64 {
+        self.svm.minimum_balance_for_rent_exemption(space)
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.svm.get_sysvar()
+    }
+
+    /// Moves time forward by `seconds`, one slot per second.
+    pub fn warp(&mut self, seconds: i64) {
+        let mut clock = self.clock();
+        clock.unix_timestamp += seconds;
+        clock.slot += seconds.max(1) as u64;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Runs the instructions atomically, as one transaction signed by
+    /// `signers`.
+    pub fn send(&mut self, instructions: &[Instruction], signers: &[Pubkey]) -> ProgramResult {
+        let transaction = self.transaction(instructions, signers)?;
+        self.svm
+            .send_transaction(transaction)
+            .map(|_| ())
+            .map_err(|failed| program_error(failed.err))
+    }
+
+    /// Simulates `instruction`, which needs no signer, and decodes what
+    /// it returns.
+    pub fn view<T: AnchorDeserialize>(&mut self, instruction: &Instruction) -> Result<T, ProgramError> {
+        let transaction = self.transaction(std::slice::from_ref(instruction), &[])?;
+        let TransactionMetadata { return_data, .. } = self
+            .svm
+            .simulate_transaction(transaction)
+            .map_err(|failed| program_error(failed.err))?
+            .meta;
+        Ok(T::try_from_slice(&return_data.data).expect("return data does not decode"))
+    }
+
+    fn transaction(&self, instructions: &[Instruction], signers: &[Pubkey]) -> Result<Transaction, ProgramError> {
+        let unsigned = instructions
+            .iter()
+            .flat_map(|instruction| &instruction.accounts)
+            .any(|meta| meta.is_signer && !signers.contains(&meta.pubkey));
+        if unsigned {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let message =
+            Message::new_with_blockhash(instructions, Some(&self.payer), &self.svm.latest_blockhash());
+        Ok(Transaction::new_unsigned(message))
+    }
+}
+
+fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[amm_capstone::ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+// bincode `UpgradeableLoaderState::ProgramData { slot,
+// upgrade_authority_address }`
+fn program_data_metadata(authority: Option<&Pubkey>) -> Vec<u8> {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&0u64.to_le_bytes());
+    match authority {
+        Some(authority) => {
+            data.push(1);
+            data.extend_from_slice(authority.as_ref());
+        }
+        None => data.extend_from_slice(&[0; 33]),
+    }
+    data
+}
+
+/// The error the failing instruction returned. A transaction the runtime
+/// rejects outside any instruction is a broken test, not an outcome.
+fn program_error(error: TransactionError) -> ProgramError {
+    match error {
+        TransactionError::InstructionError(_, error) => ProgramError::try_from(error.clone())
+            .unwrap_or_else(|_| panic!("instruction failed in the runtime: {error:?}")),
+        error => panic!("transaction rejected: {error:?}"),
+    }
+}
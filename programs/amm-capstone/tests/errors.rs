@@ -0,0 +1,990 @@
+//! Every `AmmError` variant, raised through the instruction that
+//! returns it.
+
+mod common;
+
+use amm_capstone::constants::{MAX_TICK_SPACING, MIN_RAMP_DURATION};
+use amm_capstone::errors::AmmError;
+use amm_capstone::instructions::{CurveConfig, CurveParams, UpdateConfigParams};
+use amm_capstone::math::{
+    max_liquidity_per_tick, sqrt_price_at_tick, Curve, PoolState, MAX_AMP, MAX_SQRT_PRICE_X64,
+    MAX_TICK, MIN_AMP, MIN_SQRT_PRICE_X64, MIN_TICK, MIN_WEIGHT, WEIGHT_ONE,
+};
+use amm_capstone::state::{CurveType, PoolStatusBit, TickArrayState};
+use amm_capstone_client::*;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token_interface::Mint;
+
+use common::*;
+
+fn seeded() -> Env {
+    let mut env = Env::new();
+    env.seed_liquidity(1_000_000, 2_000_000);
+    env
+}
+
+// A concentrated liquidity pool at tick 0, spacing 10, with the user's
+// position over -600..590. Its upper array holds the current tick, so
+// swaps down pass [upper, lower] and swaps up just [upper].
+fn clmm() -> (Env, PositionKeys) {
+    let mut env = Env::new();
+    env.make_clmm(10, sqrt_price_at_tick(0).unwrap());
+    let position = env.deposit_clmm(-600, 590, 1_000_000_000);
+    (env, position)
+}
+
+fn clmm_swap_ix(
+    env: &Env,
+    a_to_b: bool,
+    tick_arrays: &[Pubkey],
+    amount_in: u64,
+    min_out: u64,
+    sqrt_price_limit_x64: u128,
+) -> Instruction {
+    let (input, output) = env.user_pair(a_to_b);
+    clmm_swap(
+        &env.user,
+        &env.clmm_keys,
+        &input,
+        &output,
+        tick_arrays,
+        amount_in,
+        min_out,
+        sqrt_price_limit_x64,
+    )
+}
+
+fn no_change() -> UpdateConfigParams {
+    UpdateConfigParams {
+        new_admin: None,
+        fee_tiers: None,
+        protocol_fee_rate: None,
+        fund_fee_rate: None,
+        create_pool_fee: None,
+    }
+}
+
+#[test]
+fn slippage_exceeded() {
+    let mut env = seeded();
+    let user = env.user;
+    let state = PoolState::new(1_000_000, 2_000_000, POOL_FEE_BPS)
+        .with_fee_shares(PROTOCOL_FEE_RATE, FUND_FEE_RATE);
+    let amount_out = state.quote_exact_in(true, 10_000).unwrap().amount_out;
+    let amount_in = state.quote_exact_out(false, 500).unwrap().amount_in;
+
+    // One unit past each quote, and a deposit minting less LP than
+    // asked for
+    for ix in [
+        env.swap_ix(true, 10_000, amount_out + 1),
+        env.swap_base_out_ix(false, amount_in - 1, 500),
+        env.add_liquidity_ix(1_000, 2_000, 0, 0, 1_415),
+        env.add_liquidity_single_ix(true, 1_000, u64::MAX),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::SlippageExceeded);
+    }
+}
+
+#[test]
+fn slippage_exceeded_on_stable_pools() {
+    let mut env = Env::new();
+    env.make_stable(100);
+    env.seed_liquidity(1_000_000, 1_000_000);
+    let user = env.user;
+
+    // The stable quote beats constant product, so a bound only the
+    // StableSwap curve meets shows the swap priced on it
+    let stable = PoolState::new(1_000_000, 1_000_000, POOL_FEE_BPS)
+        .with_fee_shares(PROTOCOL_FEE_RATE, FUND_FEE_RATE)
+        .with_curve(Curve::Stable { amp: 100 });
+    let amount_out = stable.quote_exact_in(true, 10_000).unwrap().amount_out;
+    let product = PoolState::new(1_000_000, 1_000_000, POOL_FEE_BPS);
+    assert!(product.quote_exact_in(true, 10_000).unwrap().amount_out < amount_out);
+
+    let ix = env.swap_ix(true, 10_000, amount_out + 1);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::SlippageExceeded);
+
+    let ix = env.swap_ix(true, 10_000, amount_out);
+    env.send(&[ix], &[user]).unwrap();
+}
+
+#[test]
+fn slippage_exceeded_on_weighted_pools() {
+    let (weight_a, weight_b) = (WEIGHT_ONE / 10 * 8, WEIGHT_ONE / 10 * 2);
+    let mut env = Env::new();
+    env.make_weighted(weight_a, weight_b);
+    env.seed_liquidity(1_000_000, 1_000_000);
+    let user = env.user;
+
+    // Selling the heavy token buys more than at 50/50, so a bound only
+    // the weighted curve meets shows the swap priced on it
+    let weighted = PoolState::new(1_000_000, 1_000_000, POOL_FEE_BPS)
+        .with_fee_shares(PROTOCOL_FEE_RATE, FUND_FEE_RATE)
+        .with_curve(Curve::Weighted { weight_a, weight_b });
+    let amount_out = weighted.quote_exact_in(true, 10_000).unwrap().amount_out;
+    let product = PoolState::new(1_000_000, 1_000_000, POOL_FEE_BPS);
+    assert!(product.quote_exact_in(true, 10_000).unwrap().amount_out < amount_out);
+
+    let ix = env.swap_ix(true, 10_000, amount_out + 1);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::SlippageExceeded);
+
+    let ix = env.swap_ix(true, 10_000, amount_out);
+    env.send(&[ix], &[user]).unwrap();
+
+    // Likewise a single-token deposit, minting by the heavy side's weight
+    // from the reserves the swap left
+    let now = env.svm.clock().unix_timestamp;
+    let lp_supply = env.svm.fetch::<Mint>(&env.keys.lp_mint).supply;
+    let liquidity = env
+        .pool()
+        .quote_state(now)
+        .quote_single_sided_deposit(true, 10_000, lp_supply)
+        .unwrap()
+        .liquidity;
+
+    let ix = env.add_liquidity_single_ix(true, 10_000, liquidity + 1);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::SlippageExceeded);
+
+    let ix = env.add_liquidity_single_ix(true, 10_000, liquidity);
+    env.send(&[ix], &[user]).unwrap();
+}
+
+#[test]
+fn invariant_violation() {
+    // Every instruction prices through `settle_swap`, and no quote it
+    // produces can shrink `k`; the check is a backstop, so exercise it
+    // directly with an output the input does not pay for.
+    let state = PoolState::new(1_000_000, 2_000_000, 30);
+    let error = state.settle_swap(true, 1, 1_000).map_err(AmmError::from);
+
+    assert!(matches!(error, Err(AmmError::InvariantViolation)));
+}
+
+#[test]
+fn insufficient_liquidity() {
+    let mut env = Env::new();
+    let user = env.user;
+
+    // First deposit worth no more than the locked minimum
+    let ix = env.add_liquidity_ix(1_000, 1_000, 0, 0, 0);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::InsufficientLiquidity);
+
+    // Buying the whole output reserve
+    env.seed_liquidity(1_000_000, 2_000_000);
+    let ix = env.swap_base_out_ix(true, u64::MAX, 2_000_000);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::InsufficientLiquidity);
+}
+
+#[test]
+fn invalid_amount() {
+    let mut env = seeded();
+    let user = env.user;
+    let lp_balance = env.balance(&env.user_accounts.lp);
+
+    for ix in [
+        env.swap_ix(true, 0, 0),
+        env.swap_base_out_ix(true, u64::MAX, 0),
+        env.add_liquidity_ix(0, 1_000, 0, 0, 0),
+        env.add_liquidity_ix(1_000, 0, 0, 0, 0),
+        env.add_liquidity_single_ix(true, 0, 0),
+        env.remove_liquidity_ix(lp_balance + 1),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidAmount);
+    }
+}
+
+#[test]
+fn pool_empty() {
+    let mut env = Env::new();
+    let user = env.user;
+
+    for ix in [
+        env.swap_ix(true, 1_000, 0),
+        env.swap_base_out_ix(false, u64::MAX, 1_000),
+        env.add_liquidity_single_ix(false, 1_000, 0),
+        env.remove_liquidity_ix(0),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::PoolEmpty);
+    }
+}
+
+#[test]
+fn overflow() {
+    // `validate` keeps fee shares within 100%; a config that somehow
+    // exceeded it must fail the fee split rather than wrap
+    let mut env = seeded();
+    let mut config = env.config();
+    config.protocol_fee_rate = 8_000;
+    config.fund_fee_rate = 8_000;
+    env.set_config(&config);
+
+    let ix = env.swap_ix(true, 10_000, 0);
+    assert_amm_error(env.send(&[ix], &[env.user]), AmmError::Overflow);
+}
+
+#[test]
+fn overflow_near_u64_max() {
+    // Reserves one deposit or swap away from u64::MAX fail before any
+    // tokens move instead of wrapping or truncating
+    let mut env = Env::new();
+    let user = env.user;
+    let mut pool = env.pool();
+    pool.reserve_a = u64::MAX - 1_000;
+    pool.reserve_b = u64::MAX - 1_000;
+    env.set_pool(&pool);
+
+    for ix in [
+        env.add_liquidity_ix(2_000, 2_000, 0, 0, 0),
+        env.swap_ix(true, 2_000, 0),
+        env.swap_ix(false, 2_000, 0),
+        // The input for nearly the whole reserve does not fit in u64
+        env.swap_base_out_ix(true, u64::MAX, u64::MAX - 1_001),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::Overflow);
+    }
+}
+
+#[test]
+fn invalid_mint() {
+    let mut env = seeded();
+    let user = env.user;
+    let admin = env.admin;
+    let keys = env.keys;
+
+    // Output on the input's mint
+    let second_a = env.token_account(&keys.token_a_mint, &user);
+    let ix = swap(&user, &keys, &env.user_accounts.token_a, &second_a, 1_000, 0);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidMint);
+
+    // Input on a mint outside the pool
+    let other_mint = create_mint(&mut env.svm, &admin, 6, MintKind::Classic);
+    let foreign = env.token_account(&other_mint, &user);
+    let ix = swap(&user, &keys, &foreign, &env.user_accounts.token_b, 1_000, 0);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidMint);
+
+    let lp = env.user_accounts.lp;
+    let ix = add_liquidity_single(&user, &keys, &foreign, &lp, 1_000, 0);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidMint);
+
+    // Fee destinations on the wrong mints
+    let treasury_b = env.token_account(&keys.token_b_mint, &admin);
+    let treasury_a = env.token_account(&keys.token_a_mint, &admin);
+    let ix = collect_fees(&admin, &keys, &treasury_b, &treasury_a);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidMint);
+
+    let ix = collect_fund_fees(&admin, &keys, &treasury_b, &treasury_a);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidMint);
+
+    let ix = skim(&keys, &treasury_b, &treasury_a);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidMint);
+}
+
+#[test]
+fn insufficient_amount_a() {
+    // The pool is 1:2, so (100, 900) deposits (100, 200)
+    let mut env = seeded();
+    let ix = env.add_liquidity_ix(100, 900, 101, 0, 0);
+    assert_amm_error(env.send(&[ix], &[env.user]), AmmError::InsufficientAmountA);
+}
+
+#[test]
+fn insufficient_amount_b() {
+    // (900, 100) deposits (50, 100)
+    let mut env = seeded();
+    let ix = env.add_liquidity_ix(900, 100, 0, 101, 0);
+    assert_amm_error(env.send(&[ix], &[env.user]), AmmError::InsufficientAmountB);
+}
+
+#[test]
+fn unauthorized() {
+    let mut env = seeded();
+    let admin = env.admin;
+    let keys = env.keys;
+    let stranger = Pubkey::new_unique();
+
+    let stranger_a = env.token_account(&keys.token_a_mint, &stranger);
+    let stranger_b = env.token_account(&keys.token_b_mint, &stranger);
+    let admin_a = env.token_account(&keys.token_a_mint, &admin);
+    let admin_b = env.token_account(&keys.token_b_mint, &admin);
+
+    // Signed by someone other than the admin / treasury authority
+    for ix in [
+        update_config(&stranger, no_change()),
+        set_treasury_authority(&stranger, &stranger),
+        withdraw_treasury(&stranger, &stranger, 1),
+        set_pool_status(&stranger, &keys.pool, 0),
+        ramp_amp(&stranger, &keys.pool, 10, i64::MAX),
+        stop_ramp_amp(&stranger, &keys.pool),
+        update_weights_gradually(&stranger, &keys.pool, 0, i64::MAX, WEIGHT_ONE / 2, WEIGHT_ONE / 2),
+        collect_fees(&stranger, &keys, &stranger_a, &stranger_b),
+        collect_fund_fees(&stranger, &keys, &stranger_a, &stranger_b),
+    ] {
+        assert_amm_error(env.send(&[ix], &[stranger]), AmmError::Unauthorized);
+    }
+
+    // Signed by the right key, paying out to someone else's accounts
+    for ix in [
+        collect_fees(&admin, &keys, &stranger_a, &admin_b),
+        collect_fees(&admin, &keys, &admin_a, &stranger_b),
+        collect_fund_fees(&admin, &keys, &stranger_a, &admin_b),
+        collect_fund_fees(&admin, &keys, &admin_a, &stranger_b),
+    ] {
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::Unauthorized);
+    }
+
+    // Someone else's position
+    let (mut env, position) = clmm();
+    let pool = env.clmm_keys.pool;
+    let ix = decrease_liquidity(&stranger, &pool, &position, 1, 0, 0);
+    assert_amm_error(env.send(&[ix], &[stranger]), AmmError::Unauthorized);
+    let ix = set_clmm_pool_status(&stranger, &pool, 0);
+    assert_amm_error(env.send(&[ix], &[stranger]), AmmError::Unauthorized);
+}
+
+#[test]
+fn invalid_fee_tier() {
+    let mut env = Env::new();
+    let admin = env.admin;
+
+    for fee_tiers in [vec![], vec![30; 9], vec![30, 10_000]] {
+        let ix = update_config(
+            &admin,
+            UpdateConfigParams {
+                fee_tiers: Some(fee_tiers),
+                ..no_change()
+            },
+        );
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidFeeTier);
+    }
+
+    // Pools are created at an index into the tiers, which must exist
+    let past = FEE_TIERS.len() as u8;
+    let ix = create_pool(&admin, &env.keys, past, CurveParams::ConstantProduct);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidFeeTier);
+    let ix = create_clmm_pool(&admin, &env.clmm_keys, past, 10, sqrt_price_at_tick(0).unwrap());
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidFeeTier);
+}
+
+#[test]
+fn invalid_fee_rate() {
+    let mut env = Env::new();
+    let ix = update_config(
+        &env.admin,
+        UpdateConfigParams {
+            protocol_fee_rate: Some(6_000),
+            fund_fee_rate: Some(4_001),
+            ..no_change()
+        },
+    );
+    assert_amm_error(env.send(&[ix], &[env.admin]), AmmError::InvalidFeeRate);
+}
+
+#[test]
+fn operation_disabled() {
+    let mut env = seeded();
+    let user = env.user;
+    let admin = env.admin;
+    let pool = env.keys.pool;
+
+    let cases = [
+        (PoolStatusBit::Deposit, env.add_liquidity_ix(1_000, 2_000, 0, 0, 0)),
+        (PoolStatusBit::Deposit, env.add_liquidity_single_ix(true, 1_000, 0)),
+        (PoolStatusBit::Withdraw, env.remove_liquidity_ix(1_000)),
+        (PoolStatusBit::Swap, env.swap_ix(true, 1_000, 0)),
+        (PoolStatusBit::Swap, env.swap_base_out_ix(true, u64::MAX, 1_000)),
+    ];
+
+    for (bit, ix) in cases {
+        let status = set_pool_status(&admin, &pool, 1 << bit as u8);
+        env.send(&[status], &[admin]).unwrap();
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::OperationDisabled);
+    }
+}
+
+#[test]
+fn unsupported_mint() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let token_a_mint = env.keys.token_a_mint;
+    let non_transferable = create_mint(&mut env.svm, &admin, 6, MintKind::NonTransferable);
+    let transfer_fee = create_mint(
+        &mut env.svm,
+        &admin,
+        6,
+        MintKind::TransferFee {
+            bps: 100,
+            maximum_fee: u64::MAX,
+        },
+    );
+
+    let keys = PoolKeys::new(token_a_mint, non_transferable, MintKind::Classic.program(), MintKind::NonTransferable.program(), FEE_TIERS[0], CurveType::ConstantProduct);
+    let ix = create_pool(&admin, &keys, 0, CurveParams::ConstantProduct);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::UnsupportedMint);
+
+    // Transfer fees are accounted for, so those mints are fine
+    let keys = PoolKeys::new(token_a_mint, transfer_fee, MintKind::Classic.program(), MintKind::NonTransferable.program(), FEE_TIERS[0], CurveType::ConstantProduct);
+    let ix = create_pool(&admin, &keys, 0, CurveParams::ConstantProduct);
+    env.send(&[ix], &[admin]).unwrap();
+}
+
+#[test]
+fn invalid_observation_window() {
+    let mut env = seeded();
+    env.record_observation();
+
+    let ix = observe(&env.keys, 0);
+    assert_amm_error(env.send(&[ix], &[]), AmmError::InvalidObservationWindow);
+}
+
+#[test]
+fn reserve_mismatch() {
+    // Accrued fees beyond the whole vault, which no instruction can
+    // leave behind
+    let mut env = seeded();
+    let keys = env.keys;
+    let user = env.user_accounts;
+    let mut pool = env.pool();
+    pool.protocol_fees_a = 1_000_001;
+    env.set_pool(&pool);
+
+    for ix in [sync(&keys), skim(&keys, &user.token_a, &user.token_b)] {
+        assert_amm_error(env.send(&[ix], &[]), AmmError::ReserveMismatch);
+    }
+}
+
+/// `create_pool` on two new mints, which validates `curve` once its
+/// accounts are initialized.
+fn create_pool_with(env: &mut Env, curve: CurveParams) -> ProgramResult {
+    let admin = env.admin;
+    let mint_a = create_mint(&mut env.svm, &admin, 6, MintKind::Classic);
+    let mint_b = create_mint(&mut env.svm, &admin, 6, MintKind::Classic);
+    let keys = PoolKeys::new(mint_a, mint_b, MintKind::Classic.program(), MintKind::Classic.program(), FEE_TIERS[0], curve.curve_type());
+    env.send(&[create_pool(&admin, &keys, 0, curve)], &[admin])
+}
+
+#[test]
+fn invalid_amplification() {
+    let mut env = Env::new();
+    for amp in [0, MAX_AMP + 1, u64::MAX] {
+        let curve = CurveParams::Stable { amp };
+        assert_amm_error(create_pool_with(&mut env, curve), AmmError::InvalidAmplification);
+    }
+
+    for amp in [MIN_AMP, MAX_AMP] {
+        let curve = CurveParams::Stable { amp }.validate(0).unwrap();
+        assert_eq!((curve.curve_type, curve.amp), (CurveType::Stable as u8, amp));
+    }
+    assert_eq!(
+        CurveParams::ConstantProduct.validate(0).unwrap(),
+        CurveConfig::default()
+    );
+
+    // Ramps are held to the same range
+    env.make_stable(MIN_AMP);
+    let (admin, pool) = (env.admin, env.keys.pool);
+    let ix = ramp_amp(&admin, &pool, MIN_AMP - 1, i64::MAX);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidAmplification);
+}
+
+#[test]
+fn invalid_ramp() {
+    let mut env = Env::new();
+    env.make_stable(100);
+    env.svm.warp(MIN_RAMP_DURATION);
+    let (admin, pool) = (env.admin, env.keys.pool);
+    let now = env.svm.clock().unix_timestamp;
+
+    for ix in [
+        // Too soon
+        ramp_amp(&admin, &pool, 200, now + MIN_RAMP_DURATION - 1),
+        ramp_amp(&admin, &pool, 200, now),
+        // Too far, either way
+        ramp_amp(&admin, &pool, 1_001, now + MIN_RAMP_DURATION),
+        ramp_amp(&admin, &pool, 9, now + MIN_RAMP_DURATION),
+    ] {
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidRamp);
+    }
+}
+
+#[test]
+fn not_stable_pool() {
+    let mut env = Env::new();
+    let (admin, pool) = (env.admin, env.keys.pool);
+
+    for ix in [ramp_amp(&admin, &pool, 10, i64::MAX), stop_ramp_amp(&admin, &pool)] {
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::NotStablePool);
+    }
+}
+
+#[test]
+fn invalid_weights() {
+    let mut env = Env::new();
+    for (weight_a, weight_b) in [
+        (0, WEIGHT_ONE),
+        (MIN_WEIGHT - 1, WEIGHT_ONE - MIN_WEIGHT + 1),
+        (WEIGHT_ONE / 2, WEIGHT_ONE / 2 + 1),
+        (u64::MAX, 1),
+    ] {
+        let curve = CurveParams::Weighted { weight_a, weight_b };
+        assert_amm_error(create_pool_with(&mut env, curve), AmmError::InvalidWeights);
+    }
+
+    let curve = CurveParams::Weighted {
+        weight_a: MIN_WEIGHT,
+        weight_b: WEIGHT_ONE - MIN_WEIGHT,
+    }
+    .validate(0)
+    .unwrap();
+    assert_eq!(curve.curve_type, CurveType::Weighted as u8);
+    assert_eq!((curve.weight_a, curve.weight_b), (MIN_WEIGHT, WEIGHT_ONE - MIN_WEIGHT));
+
+    // A bootstrapping pool's start and end pairs both
+    let half = WEIGHT_ONE / 2;
+    let bootstrapping = |start_weight_a, end_weight_a| CurveParams::LiquidityBootstrapping {
+        start_weight_a,
+        start_weight_b: half,
+        end_weight_a,
+        end_weight_b: half,
+        start_ts: 0,
+        end_ts: 1,
+    };
+    for curve in [bootstrapping(half + 1, half), bootstrapping(half, 0)] {
+        assert_amm_error(create_pool_with(&mut env, curve), AmmError::InvalidWeights);
+    }
+
+    // And a new schedule's end pair
+    env.make_bootstrapping(half, half, 0, 1);
+    let (admin, pool) = (env.admin, env.keys.pool);
+    let ix = update_weights_gradually(&admin, &pool, 0, i64::MAX, WEIGHT_ONE, 0);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidWeights);
+}
+
+#[test]
+fn invalid_weight_schedule() {
+    let mut env = Env::new();
+    let (heavy, light) = (WEIGHT_ONE - MIN_WEIGHT, MIN_WEIGHT);
+    for (start_ts, end_ts) in [(100, 100), (100, 99), (i64::MAX, i64::MIN)] {
+        let curve = CurveParams::LiquidityBootstrapping {
+            start_weight_a: heavy,
+            start_weight_b: light,
+            end_weight_a: light,
+            end_weight_b: heavy,
+            start_ts,
+            end_ts,
+        };
+        assert_amm_error(create_pool_with(&mut env, curve), AmmError::InvalidWeightSchedule);
+    }
+
+    // The schedule may start well after creation, so a sale can be set
+    // up ahead of time
+    let curve = CurveParams::LiquidityBootstrapping {
+        start_weight_a: heavy,
+        start_weight_b: light,
+        end_weight_a: light,
+        end_weight_b: heavy,
+        start_ts: 1_000,
+        end_ts: 2_000,
+    }
+    .validate(0)
+    .unwrap();
+    assert_eq!(
+        curve,
+        CurveConfig {
+            curve_type: CurveType::LiquidityBootstrapping as u8,
+            amp: 0,
+            weight_a: heavy,
+            weight_b: light,
+            end_weight_a: light,
+            end_weight_b: heavy,
+            weights_start_ts: 1_000,
+            weights_end_ts: 2_000,
+            swap_start_ts: 1_000,
+        }
+    );
+
+    // A reschedule must end after both its start and now
+    env.make_bootstrapping(heavy, light, 0, 1);
+    let (admin, pool) = (env.admin, env.keys.pool);
+    let now = env.svm.clock().unix_timestamp;
+    for ix in [
+        update_weights_gradually(&admin, &pool, now + 10, now + 10, light, heavy),
+        update_weights_gradually(&admin, &pool, 0, now, light, heavy),
+    ] {
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidWeightSchedule);
+    }
+}
+
+#[test]
+fn not_bootstrapping_pool() {
+    // Fixed weights stay fixed, even for the pool's creator
+    let half = WEIGHT_ONE / 2;
+    let mut env = Env::new();
+    env.make_weighted(half, half);
+    let (admin, pool) = (env.admin, env.keys.pool);
+    let mut state = env.pool();
+    state.authority = admin;
+    env.set_pool(&state);
+
+    let ix = update_weights_gradually(&admin, &pool, 0, i64::MAX, half, half);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::NotBootstrappingPool);
+}
+
+#[test]
+fn swap_not_started() {
+    let mut env = Env::new();
+    let now = env.svm.clock().unix_timestamp;
+    env.make_bootstrapping(WEIGHT_ONE - MIN_WEIGHT, WEIGHT_ONE / 2, now + 60, now + 3_600);
+    env.seed_liquidity(1_000_000, 1_000_000);
+    let user = env.user;
+
+    // Anything that trades against the pool waits for the start
+    for ix in [
+        env.swap_ix(true, 1_000, 0),
+        env.swap_base_out_ix(false, u64::MAX, 1_000),
+        env.add_liquidity_single_ix(true, 1_000, 0),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::SwapNotStarted);
+    }
+
+    // Balanced deposits do not trade, so the owner can seed the sale
+    let ix = env.add_liquidity_ix(1_000, 1_000, 0, 0, 0);
+    env.send(&[ix], &[user]).unwrap();
+
+    env.svm.warp(60);
+    let ix = env.swap_ix(true, 1_000, 0);
+    env.send(&[ix], &[user]).unwrap();
+}
+
+#[test]
+fn unsupported_curve() {
+    // StableSwap has no weights to mint a one-sided deposit by
+    let mut env = Env::new();
+    env.make_stable(100);
+    env.seed_liquidity(1_000_000, 1_000_000);
+    let user = env.user;
+
+    let ix = env.add_liquidity_single_ix(true, 1_000, 0);
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::UnsupportedCurve);
+}
+
+#[test]
+fn slippage_exceeded_on_clmm_pools() {
+    let (mut env, position) = clmm();
+    let user = env.user;
+    let keys = env.clmm_keys;
+    let (token_a, token_b) = (env.user_accounts.token_a, env.user_accounts.token_b);
+    let down = [position.tick_array_upper, position.tick_array_lower];
+
+    for ix in [
+        increase_liquidity(&user, &keys, &position, &token_a, &token_b, 1_000_000, 0, u64::MAX),
+        increase_liquidity(&user, &keys, &position, &token_a, &token_b, 1_000_000, u64::MAX, 0),
+        decrease_liquidity(&user, &keys.pool, &position, 1_000_000, u64::MAX, 0),
+        clmm_swap_ix(&env, true, &down, 1_000, u64::MAX, 0),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::SlippageExceeded);
+    }
+
+    let ix = clmm_swap_ix(&env, true, &down, 1_000, 0, 0);
+    env.send(&[ix], &[user]).unwrap();
+}
+
+#[test]
+fn insufficient_liquidity_on_clmm_pools() {
+    let (mut env, position) = clmm();
+    let user = env.user;
+    let keys = env.clmm_keys;
+    let (input, output) = env.user_pair(false);
+
+    // More than the position holds, or than its range pays out before
+    // the price reaches its upper bound
+    for ix in [
+        decrease_liquidity(&user, &keys.pool, &position, 1_000_000_001, 0, 0),
+        clmm_swap_base_out(
+            &user,
+            &keys,
+            &input,
+            &output,
+            &[position.tick_array_upper],
+            u64::MAX,
+            1_000_000_000,
+            sqrt_price_at_tick(590).unwrap(),
+        ),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::InsufficientLiquidity);
+    }
+}
+
+#[test]
+fn operation_disabled_on_clmm_pools() {
+    let (mut env, position) = clmm();
+    let (admin, user) = (env.admin, env.user);
+    let keys = env.clmm_keys;
+    let (token_a, token_b) = (env.user_accounts.token_a, env.user_accounts.token_b);
+    let down = [position.tick_array_upper, position.tick_array_lower];
+
+    let cases = [
+        (PoolStatusBit::Deposit, increase_liquidity(&user, &keys, &position, &token_a, &token_b, 1_000, u64::MAX, u64::MAX)),
+        (PoolStatusBit::Withdraw, decrease_liquidity(&user, &keys.pool, &position, 1_000, 0, 0)),
+        (PoolStatusBit::Swap, clmm_swap_ix(&env, true, &down, 1_000, 0, 0)),
+    ];
+
+    for (bit, ix) in cases {
+        let status = set_clmm_pool_status(&admin, &keys.pool, 1 << bit as u8);
+        env.send(&[status], &[admin]).unwrap();
+        assert_amm_error(env.send(std::slice::from_ref(&ix), &[user]), AmmError::OperationDisabled);
+
+        // Only that operation
+        env.send(&[set_clmm_pool_status(&admin, &keys.pool, 0)], &[admin])
+            .unwrap();
+        env.send(&[ix], &[user]).unwrap();
+    }
+}
+
+#[test]
+fn invalid_tick_spacing() {
+    let mut env = Env::new();
+    let (admin, keys) = (env.admin, env.clmm_keys);
+    let price = sqrt_price_at_tick(0).unwrap();
+
+    for tick_spacing in [0, MAX_TICK_SPACING + 1] {
+        let ix = create_clmm_pool(&admin, &keys, POOL_FEE_TIER, tick_spacing, price);
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidTickSpacing);
+    }
+    let ix = create_clmm_pool(&admin, &keys, POOL_FEE_TIER, MAX_TICK_SPACING, price);
+    env.send(&[ix], &[admin]).unwrap();
+    assert_eq!(env.clmm_pool().tick_spacing, MAX_TICK_SPACING);
+}
+
+#[test]
+fn invalid_sqrt_price() {
+    let mut env = Env::new();
+    let (admin, keys) = (env.admin, env.clmm_keys);
+    for price in [0, MIN_SQRT_PRICE_X64 - 1, MAX_SQRT_PRICE_X64] {
+        let ix = create_clmm_pool(&admin, &keys, POOL_FEE_TIER, 10, price);
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidSqrtPrice);
+    }
+    let ix = create_clmm_pool(&admin, &keys, POOL_FEE_TIER, 10, MIN_SQRT_PRICE_X64);
+    env.send(&[ix], &[admin]).unwrap();
+    assert_eq!(env.clmm_pool().tick_current, MIN_TICK);
+
+    // Swap limits behind the current price, or at the curve's ends
+    let (mut env, position) = clmm();
+    let user = env.user;
+    let price = env.clmm_pool().sqrt_price_x64;
+    let down = [position.tick_array_upper, position.tick_array_lower];
+    for ix in [
+        clmm_swap_ix(&env, true, &down, 1_000, 0, price + 1),
+        clmm_swap_ix(&env, false, &[position.tick_array_upper], 1_000, 0, price - 1),
+        clmm_swap_ix(&env, true, &down, 1_000, 0, MIN_SQRT_PRICE_X64),
+        clmm_swap_ix(&env, false, &[position.tick_array_upper], 1_000, 0, MAX_SQRT_PRICE_X64),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidSqrtPrice);
+    }
+}
+
+#[test]
+fn invalid_tick_range() {
+    let (mut env, _) = clmm();
+    let (user, pool) = (env.user, env.clmm_keys.pool);
+    let far = MAX_TICK / 10 * 10;
+
+    for (tick_lower, tick_upper) in [
+        (10, 10),
+        (20, 10),
+        (5, 20),
+        (0, 15),
+        (-far - 10, 0),
+        (0, far + 10),
+    ] {
+        let ix = open_position(&user, &pool, tick_lower, tick_upper);
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidTickRange);
+    }
+    env.send(&[open_position(&user, &pool, -far, far)], &[user])
+        .unwrap();
+}
+
+#[test]
+fn invalid_tick_array() {
+    let (mut env, position) = clmm();
+    let user = env.user;
+    let pool = env.clmm_keys.pool;
+
+    // Another pool's array at the right start
+    let mut foreign = env.tick_array(&position.tick_array_lower);
+    foreign.pool = Pubkey::new_unique();
+    let foreign_address = Pubkey::new_unique();
+    env.set_tick_array(&foreign_address, &foreign);
+
+    let swapped = PositionKeys {
+        tick_array_lower: position.tick_array_upper,
+        tick_array_upper: position.tick_array_lower,
+        ..position
+    };
+    let foreign_lower = PositionKeys {
+        tick_array_lower: foreign_address,
+        ..position
+    };
+    for ix in [
+        decrease_liquidity(&user, &pool, &swapped, 1, 0, 0),
+        decrease_liquidity(&user, &pool, &foreign_lower, 1, 0, 0),
+        // Swaps that run out of arrays, or are handed one off their path
+        clmm_swap_ix(&env, true, &[], 1_000, 0, 0),
+        clmm_swap_ix(&env, false, &[position.tick_array_lower], 1_000, 0, 0),
+        clmm_swap_ix(&env, true, &[foreign_address], 1_000, 0, 0),
+    ] {
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidTickArray);
+    }
+
+    // Or asked to create one at a tick that does not start an array
+    for start in [10, -599, TickArrayState::start_index(MAX_TICK, 10) + 600] {
+        let ix = init_tick_array(&user, &pool, start);
+        assert_amm_error(env.send(&[ix], &[user]), AmmError::InvalidTickArray);
+    }
+}
+
+#[test]
+fn tick_liquidity_exceeded() {
+    let (mut env, position) = clmm();
+    let user = env.user;
+    let (token_a, token_b) = (env.user_accounts.token_a, env.user_accounts.token_b);
+
+    // The position's bounds already hold 1e9
+    let ix = increase_liquidity(
+        &user,
+        &env.clmm_keys,
+        &position,
+        &token_a,
+        &token_b,
+        max_liquidity_per_tick(10),
+        u64::MAX,
+        u64::MAX,
+    );
+    assert_amm_error(env.send(&[ix], &[user]), AmmError::TickLiquidityExceeded);
+}
+
+#[test]
+fn invalid_status() {
+    let mut env = seeded();
+    let admin = env.admin;
+    let pool = env.keys.pool;
+
+    for status in [1 << 3, 1 << 7, u8::MAX] {
+        let ix = set_pool_status(&admin, &pool, status);
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidStatus);
+    }
+
+    let ix = set_pool_status(&admin, &pool, PoolStatusBit::ALL);
+    env.send(&[ix], &[admin]).unwrap();
+
+    env.make_clmm(10, sqrt_price_at_tick(0).unwrap());
+    let pool = env.clmm_keys.pool;
+    for status in [1 << 3, u8::MAX] {
+        let ix = set_clmm_pool_status(&admin, &pool, status);
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidStatus);
+    }
+    let ix = set_clmm_pool_status(&admin, &pool, PoolStatusBit::ALL);
+    env.send(&[ix], &[admin]).unwrap();
+    assert_eq!(env.clmm_pool().status, PoolStatusBit::ALL);
+}
+
+#[test]
+fn invalid_mint_order() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let (mint_a, mint_b) = (env.keys.token_a_mint, env.keys.token_b_mint);
+    let program = MintKind::Classic.program();
+
+    // Reversed, or the same mint twice, for either kind of pool
+    for (token_a_mint, token_b_mint) in [(mint_b, mint_a), (mint_a, mint_a)] {
+        let keys = PoolKeys::new(token_a_mint, token_b_mint, program, program, FEE_TIERS[0], CurveType::ConstantProduct);
+        let ix = create_pool(&admin, &keys, 0, CurveParams::ConstantProduct);
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidMintOrder);
+
+        let keys = ClmmPoolKeys::new(token_a_mint, token_b_mint, program, program, FEE_TIERS[0]);
+        let ix = create_clmm_pool(&admin, &keys, 0, 10, sqrt_price_at_tick(0).unwrap());
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::InvalidMintOrder);
+    }
+
+    // In order, a pair gets a pool per fee tier and curve
+    let keys = PoolKeys::new(mint_a, mint_b, program, program, FEE_TIERS[0], CurveType::ConstantProduct);
+    env.send(&[create_pool(&admin, &keys, 0, CurveParams::ConstantProduct)], &[admin])
+        .unwrap();
+    assert_ne!(keys.pool, env.keys.pool);
+
+    let curve = CurveParams::Stable { amp: 100 };
+    let keys = PoolKeys::new(mint_a, mint_b, program, program, POOL_FEE_BPS, curve.curve_type());
+    env.send(&[create_pool(&admin, &keys, POOL_FEE_TIER, curve)], &[admin]).unwrap();
+    assert_ne!(keys.pool, env.keys.pool);
+}
+
+#[test]
+fn ramp_too_soon() {
+    let mut env = Env::new();
+    env.make_stable(100);
+    let (admin, pool) = (env.admin, env.keys.pool);
+
+    // A is as new as the pool
+    let stop = env.svm.clock().unix_timestamp + 2 * MIN_RAMP_DURATION;
+    let ix = ramp_amp(&admin, &pool, 200, stop);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::RampTooSoon);
+
+    env.svm.warp(MIN_RAMP_DURATION);
+    let stop = env.svm.clock().unix_timestamp + MIN_RAMP_DURATION;
+    env.send(&[ramp_amp(&admin, &pool, 200, stop)], &[admin])
+        .unwrap();
+
+    // A second ramp inside the window, whatever its target
+    env.svm.warp(MIN_RAMP_DURATION - 1);
+    for ix in [
+        ramp_amp(&admin, &pool, 200, stop + MIN_RAMP_DURATION),
+        ramp_amp(&admin, &pool, 1_000, stop + MIN_RAMP_DURATION),
+    ] {
+        assert_amm_error(env.send(&[ix], &[admin]), AmmError::RampTooSoon);
+    }
+
+    // Stopping sets A too, so the window restarts from there
+    env.send(&[stop_ramp_amp(&admin, &pool)], &[admin]).unwrap();
+    env.svm.warp(1);
+    let ix = ramp_amp(&admin, &pool, 1_000, stop + MIN_RAMP_DURATION);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::RampTooSoon);
+
+    env.svm.warp(MIN_RAMP_DURATION - 1);
+    let now = env.svm.clock().unix_timestamp;
+    env.send(&[ramp_amp(&admin, &pool, 1_000, now + MIN_RAMP_DURATION)], &[admin])
+        .unwrap();
+}
+
+#[test]
+fn insufficient_treasury_balance() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let destination = Pubkey::new_unique();
+    let treasury = find_treasury_address().0;
+
+    // Only its rent-exempt minimum
+    let ix = withdraw_treasury(&admin, &destination, 1);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InsufficientTreasuryBalance);
+
+    env.svm.airdrop(&treasury, 1_000_000);
+    let ix = withdraw_treasury(&admin, &destination, 1_000_001);
+    assert_amm_error(env.send(&[ix], &[admin]), AmmError::InsufficientTreasuryBalance);
+    env.send(&[withdraw_treasury(&admin, &destination, 1_000_000)], &[admin])
+        .unwrap();
+}
+
+#[test]
+fn observation_too_old() {
+    let mut env = seeded();
+
+    // Nothing recorded yet
+    let ix = observe(&env.keys, 1);
+    assert_amm_error(env.send(&[ix], &[]), AmmError::ObservationTooOld);
+
+    env.record_observation();
+    env.svm.warp(600);
+    let ix = observe(&env.keys, 601);
+    assert_amm_error(env.send(&[ix], &[]), AmmError::ObservationTooOld);
+    env.send(&[observe(&env.keys, 600)], &[]).unwrap();
+}
@@ -0,0 +1,524 @@
+//! End-to-end flows on pools made by `create_pool` and
+//! `create_clmm_pool`, with every CPI run: each step checks what left
+//! and reached the user's accounts against the math crate's quote, the
+//! pool's reserves, fee counters and LP supply, and that the vaults hold
+//! exactly the reserves plus accrued fees.
+
+mod common;
+
+use amm_capstone::constants::TICK_ARRAY_SIZE;
+use amm_capstone::instructions::CurveParams;
+use amm_capstone::math::{position_amounts, sqrt_price_at_tick, MINIMUM_LIQUIDITY, WEIGHT_ONE};
+use amm_capstone::state::{CurveType, ObservationState, Pool, TickArrayState};
+use amm_capstone_client::*;
+use anchor_spl::token;
+
+use common::*;
+
+const CREATE_POOL_FEE: u64 = 1_000_000;
+
+/// A fresh `Env` whose pool is the one `create_pool` makes with `curve`
+/// on two new mints, with the user funded in both and holding an empty
+/// LP account.
+fn created(kind_a: MintKind, kind_b: MintKind, curve: CurveParams) -> Env {
+    let mut env = Env::new();
+    let (admin, user) = (env.admin, env.user);
+    let mint_a = create_mint(&mut env.svm, &admin, 6, kind_a);
+    let mint_b = create_mint(&mut env.svm, &admin, 6, kind_b);
+    let keys = PoolKeys::new(mint_a, mint_b, kind_a.program(), kind_b.program(), POOL_FEE_BPS, curve.curve_type());
+
+    env.send(&[create_pool(&admin, &keys, POOL_FEE_TIER, curve)], &[admin])
+        .unwrap();
+
+    let token_a = env.token_account(&mint_a, &user);
+    let token_b = env.token_account(&mint_b, &user);
+    let lp = env.token_account(&keys.lp_mint, &user);
+    mint_to(&mut env.svm, &mint_a, &token_a, &admin, USER_BALANCE);
+    mint_to(&mut env.svm, &mint_b, &token_b, &admin, USER_BALANCE);
+
+    env.keys = keys;
+    env.user_accounts = UserTokenAccounts { token_a, token_b, lp };
+    env
+}
+
+fn classic(curve: CurveParams) -> Env {
+    created(MintKind::Classic, MintKind::Classic, curve)
+}
+
+/// Each vault holds its reserve plus the protocol and fund fees owed out
+/// of it, which `check_reserves` accepts.
+fn assert_backed(env: &Env) {
+    let pool = env.pool();
+    let vault_a = env.balance(&env.keys.vault_a);
+    let vault_b = env.balance(&env.keys.vault_b);
+
+    assert_eq!(vault_a, pool.reserve_a + pool.protocol_fees_a + pool.fund_fees_a);
+    assert_eq!(vault_b, pool.reserve_b + pool.protocol_fees_b + pool.fund_fees_b);
+    pool.check_reserves(vault_a, vault_b).unwrap();
+}
+
+/// The user's (A, B, LP) balances.
+fn user_balances(env: &Env) -> (u64, u64, u64) {
+    let accounts = env.user_accounts;
+    (
+        env.balance(&accounts.token_a),
+        env.balance(&accounts.token_b),
+        env.balance(&accounts.lp),
+    )
+}
+
+fn lp_supply(env: &Env) -> u64 {
+    mint_supply(&env.svm, &env.keys.lp_mint)
+}
+
+/// Deposits `amount_a` and `amount_b`, checking the LP minted and the
+/// tokens taken against the quote.
+fn deposit(env: &mut Env, amount_a: u64, amount_b: u64) {
+    let user = env.user;
+    let supply = lp_supply(env);
+    let quote = env
+        .quote_state()
+        .quote_add_liquidity(amount_a, amount_b, supply)
+        .unwrap();
+    let before = user_balances(env);
+    let pool_before = env.pool();
+
+    let ix = env.add_liquidity_ix(amount_a, amount_b, 0, 0, quote.liquidity);
+    env.send(&[ix], &[user]).unwrap();
+
+    let after = user_balances(env);
+    assert_eq!(before.0 - after.0, quote.amount_a);
+    assert_eq!(before.1 - after.1, quote.amount_b);
+    assert_eq!(after.2 - before.2, quote.liquidity);
+    assert_eq!(lp_supply(env), supply + quote.liquidity + quote.locked_liquidity);
+
+    let pool = env.pool();
+    assert_eq!(pool.reserve_a, pool_before.reserve_a + quote.amount_a);
+    assert_eq!(pool.reserve_b, pool_before.reserve_b + quote.amount_b);
+    assert_backed(env);
+}
+
+/// Swaps `amount_in` exactly, checking both sides and the fees accrued
+/// against the quote.
+fn swap_exact_in(env: &mut Env, a_to_b: bool, amount_in: u64) {
+    let user = env.user;
+    let quote = env.quote_state().quote_exact_in(a_to_b, amount_in).unwrap();
+    let before = user_balances(env);
+    let pool_before = env.pool();
+
+    let ix = env.swap_ix(a_to_b, amount_in, quote.amount_out);
+    env.send(&[ix], &[user]).unwrap();
+
+    assert_swapped(env, a_to_b, before, &pool_before, quote);
+}
+
+/// Swaps for `amount_out` exactly, as `swap_exact_in` checks.
+fn swap_exact_out(env: &mut Env, a_to_b: bool, amount_out: u64) {
+    let user = env.user;
+    let quote = env.quote_state().quote_exact_out(a_to_b, amount_out).unwrap();
+    let before = user_balances(env);
+    let pool_before = env.pool();
+
+    let ix = env.swap_base_out_ix(a_to_b, quote.amount_in, amount_out);
+    env.send(&[ix], &[user]).unwrap();
+
+    assert_swapped(env, a_to_b, before, &pool_before, quote);
+}
+
+fn assert_swapped(
+    env: &Env,
+    a_to_b: bool,
+    before: (u64, u64, u64),
+    pool_before: &Pool,
+    quote: amm_capstone::math::SwapQuote,
+) {
+    let after = user_balances(env);
+    let pool = env.pool();
+    let (paid, received) = if a_to_b {
+        (before.0 - after.0, after.1 - before.1)
+    } else {
+        (before.1 - after.1, after.0 - before.0)
+    };
+    assert_eq!((paid, received), (quote.amount_in, quote.amount_out));
+
+    let (reserve_in, reserve_out, protocol_fees, fund_fees) = if a_to_b {
+        (
+            pool.reserve_a,
+            pool.reserve_b,
+            pool.protocol_fees_a - pool_before.protocol_fees_a,
+            pool.fund_fees_a - pool_before.fund_fees_a,
+        )
+    } else {
+        (
+            pool.reserve_b,
+            pool.reserve_a,
+            pool.protocol_fees_b - pool_before.protocol_fees_b,
+            pool.fund_fees_b - pool_before.fund_fees_b,
+        )
+    };
+    assert_eq!((reserve_in, reserve_out), (quote.new_reserve_in, quote.new_reserve_out));
+    assert_eq!((protocol_fees, fund_fees), (quote.protocol_fee, quote.fund_fee));
+    assert_backed(env);
+}
+
+#[test]
+fn create_pool_initializes_every_account() {
+    let mut env = Env::new();
+    let admin = env.admin;
+    let mut config = env.config();
+    config.create_pool_fee = CREATE_POOL_FEE;
+    env.set_config(&config);
+
+    let mint_a = create_mint(&mut env.svm, &admin, 6, MintKind::Classic);
+    let mint_b = create_mint(&mut env.svm, &admin, 9, MintKind::Classic);
+    let curve = CurveParams::Stable { amp: 100 };
+    let keys = PoolKeys::new(mint_a, mint_b, token::ID, token::ID, POOL_FEE_BPS, curve.curve_type());
+    let treasury = find_treasury_address().0;
+    let treasury_lamports = env.svm.account(&treasury).unwrap().lamports;
+
+    let ix = create_pool(&admin, &keys, POOL_FEE_TIER, curve);
+    env.send(&[ix], &[admin]).unwrap();
+
+    let pool: Pool = env.svm.fetch(&keys.pool);
+    assert_eq!((pool.token_a_mint, pool.token_b_mint), (mint_a, mint_b));
+    assert_eq!((pool.vault_a, pool.vault_b), (keys.vault_a, keys.vault_b));
+    assert_eq!(pool.vault_authority, keys.vault_authority);
+    assert_eq!(pool.vault_authority_bump, find_vault_authority_address(&keys.pool).1);
+    assert_eq!(pool.lp_mint, keys.lp_mint);
+    assert_eq!(pool.observation_state, keys.observation_state);
+    assert_eq!(pool.authority, admin);
+    assert_eq!(pool.fee_bps, POOL_FEE_BPS);
+    assert_eq!(pool.curve_type, CurveType::Stable as u8);
+    assert_eq!((pool.initial_amp, pool.target_amp), (100, 100));
+    assert_eq!((pool.reserve_a, pool.reserve_b, pool.status), (0, 0, 0));
+
+    // Empty vaults and LP, all under the pool's authority
+    for vault in [keys.vault_a, keys.vault_b] {
+        assert_eq!(env.svm.account(&vault).unwrap().owner, token::ID);
+        assert_eq!(env.balance(&vault), 0);
+    }
+    assert_eq!(mint_supply(&env.svm, &keys.lp_mint), 0);
+    assert_eq!(env.balance(&keys.locked_lp), 0);
+
+    let observation = env.svm.account(&keys.observation_state).unwrap();
+    assert_eq!(observation.owner, amm_capstone::ID);
+    assert_eq!(observation.data.len(), 8 + ObservationState::LEN);
+    assert_eq!(&observation.data[8..40], keys.pool.as_ref());
+
+    // The creation fee went to the treasury
+    let treasury_lamports_after = env.svm.account(&treasury).unwrap().lamports;
+    assert_eq!(treasury_lamports_after - treasury_lamports, CREATE_POOL_FEE);
+
+    // And the same mints cannot be pooled again on that curve, even once
+    // the config lists the same rate at another index
+    let ix = create_pool(&admin, &keys, POOL_FEE_TIER, curve);
+    assert!(env.send(&[ix], &[admin]).is_err());
+    let mut config = env.config();
+    config.fee_tiers.reverse();
+    env.set_config(&config);
+    let index = config.fee_tiers.iter().position(|fee| *fee == POOL_FEE_BPS).unwrap();
+    assert_ne!(index, POOL_FEE_TIER as usize);
+    let ix = create_pool(&admin, &keys, index as u8, curve);
+    assert!(env.send(&[ix], &[admin]).is_err());
+}
+
+#[test]
+fn first_deposit_locks_minimum_liquidity() {
+    let mut env = classic(CurveParams::ConstantProduct);
+
+    deposit(&mut env, 1_000_000, 4_000_000);
+
+    // sqrt(1e6 · 4e6), less what stays locked for good
+    assert_eq!(env.balance(&env.keys.locked_lp), MINIMUM_LIQUIDITY);
+    assert_eq!(env.balance(&env.user_accounts.lp), 2_000_000 - MINIMUM_LIQUIDITY);
+    assert_eq!(lp_supply(&env), 2_000_000);
+
+    // A later deposit takes only what matches the ratio
+    deposit(&mut env, 500_000, 5_000_000);
+    let pool = env.pool();
+    assert_eq!((pool.reserve_a, pool.reserve_b), (1_500_000, 6_000_000));
+    assert_eq!(lp_supply(&env), 3_000_000);
+}
+
+#[test]
+fn swaps_both_ways_move_tokens_and_accrue_fees() {
+    let mut env = classic(CurveParams::ConstantProduct);
+    deposit(&mut env, 10_000_000, 20_000_000);
+
+    swap_exact_in(&mut env, true, 100_000);
+    swap_exact_in(&mut env, false, 300_000);
+    swap_exact_out(&mut env, true, 50_000);
+    swap_exact_out(&mut env, false, 70_000);
+
+    // Fees in both tokens, held aside from the reserves
+    let pool = env.pool();
+    assert!(pool.protocol_fees_a > 0 && pool.protocol_fees_b > 0);
+    assert!(pool.fund_fees_a > 0 && pool.fund_fees_b > 0);
+}
+
+#[test]
+fn remove_liquidity_returns_a_proportional_share() {
+    let mut env = classic(CurveParams::ConstantProduct);
+    let user = env.user;
+    deposit(&mut env, 10_000_000, 20_000_000);
+    swap_exact_in(&mut env, true, 1_000_000);
+
+    let lp = env.balance(&env.user_accounts.lp) / 2;
+    let supply = lp_supply(&env);
+    let quote = env.quote_state().quote_remove_liquidity(lp, supply).unwrap();
+    let before = user_balances(&env);
+    let pool_before = env.pool();
+
+    env.send(&[env.remove_liquidity_ix(lp)], &[user]).unwrap();
+
+    let after = user_balances(&env);
+    assert_eq!((after.0 - before.0, after.1 - before.1), (quote.amount_a, quote.amount_b));
+    assert_eq!(before.2 - after.2, lp);
+    assert_eq!(lp_supply(&env), supply - lp);
+
+    let pool = env.pool();
+    assert_eq!(pool.reserve_a, pool_before.reserve_a - quote.amount_a);
+    assert_eq!(pool.reserve_b, pool_before.reserve_b - quote.amount_b);
+    // Fees owed out of the vaults are not the LPs' to withdraw
+    assert_eq!(pool.protocol_fees_a, pool_before.protocol_fees_a);
+    assert_backed(&env);
+}
+
+#[test]
+fn collect_fees_pays_out_and_resets_the_counters() {
+    let mut env = classic(CurveParams::ConstantProduct);
+    let admin = env.admin;
+    let keys = env.keys;
+    deposit(&mut env, 10_000_000, 20_000_000);
+    swap_exact_in(&mut env, true, 1_000_000);
+    swap_exact_in(&mut env, false, 2_000_000);
+
+    // The treasury authority and the config admin are both `admin` here
+    let treasury_a = env.token_account(&keys.token_a_mint, &admin);
+    let treasury_b = env.token_account(&keys.token_b_mint, &admin);
+    let fund_a = env.token_account(&keys.token_a_mint, &admin);
+    let fund_b = env.token_account(&keys.token_b_mint, &admin);
+    let owed = env.pool();
+    assert!(owed.protocol_fees_a > 0 && owed.fund_fees_b > 0);
+
+    let ix = collect_fees(&admin, &keys, &treasury_a, &treasury_b);
+    env.send(&[ix], &[admin]).unwrap();
+    assert_eq!(env.balance(&treasury_a), owed.protocol_fees_a);
+    assert_eq!(env.balance(&treasury_b), owed.protocol_fees_b);
+
+    let ix = collect_fund_fees(&admin, &keys, &fund_a, &fund_b);
+    env.send(&[ix], &[admin]).unwrap();
+    assert_eq!(env.balance(&fund_a), owed.fund_fees_a);
+    assert_eq!(env.balance(&fund_b), owed.fund_fees_b);
+
+    let pool = env.pool();
+    assert_eq!((pool.protocol_fees_a, pool.protocol_fees_b), (0, 0));
+    assert_eq!((pool.fund_fees_a, pool.fund_fees_b), (0, 0));
+    assert_eq!((pool.reserve_a, pool.reserve_b), (owed.reserve_a, owed.reserve_b));
+    assert_backed(&env);
+}
+
+#[test]
+fn every_curve_trades_through_its_vaults() {
+    let now = Env::new().svm.clock().unix_timestamp;
+    let (heavy, light) = (WEIGHT_ONE / 10 * 8, WEIGHT_ONE / 10 * 2);
+
+    for curve in [
+        CurveParams::Stable { amp: 100 },
+        CurveParams::Weighted {
+            weight_a: heavy,
+            weight_b: light,
+        },
+        CurveParams::LiquidityBootstrapping {
+            start_weight_a: heavy,
+            start_weight_b: light,
+            end_weight_a: light,
+            end_weight_b: heavy,
+            start_ts: now,
+            end_ts: now + 3_600,
+        },
+    ] {
+        let mut env = classic(curve);
+        deposit(&mut env, 10_000_000, 10_000_000);
+        swap_exact_in(&mut env, true, 100_000);
+        env.svm.warp(600);
+        swap_exact_in(&mut env, false, 100_000);
+        swap_exact_out(&mut env, true, 10_000);
+
+        if env.pool().is_weighted() {
+            add_single_sided(&mut env, true, 200_000);
+            add_single_sided(&mut env, false, 200_000);
+        }
+    }
+}
+
+/// Deposits `amount_in` of one token, checking the LP minted and the
+/// fee set aside against the quote.
+fn add_single_sided(env: &mut Env, token_a: bool, amount_in: u64) {
+    let user = env.user;
+    let supply = lp_supply(env);
+    let quote = env
+        .quote_state()
+        .quote_single_sided_deposit(token_a, amount_in, supply)
+        .unwrap();
+    let before = user_balances(env);
+    let pool_before = env.pool();
+
+    let ix = env.add_liquidity_single_ix(token_a, amount_in, quote.liquidity);
+    env.send(&[ix], &[user]).unwrap();
+
+    let after = user_balances(env);
+    let paid = if token_a { before.0 - after.0 } else { before.1 - after.1 };
+    assert_eq!(paid, quote.amount_in);
+    assert_eq!(after.2 - before.2, quote.liquidity);
+    assert_eq!(lp_supply(env), supply + quote.liquidity);
+
+    let pool = env.pool();
+    let (reserve, protocol_fees) = if token_a {
+        (pool.reserve_a, pool.protocol_fees_a - pool_before.protocol_fees_a)
+    } else {
+        (pool.reserve_b, pool.protocol_fees_b - pool_before.protocol_fees_b)
+    };
+    assert_eq!((reserve, protocol_fees), (quote.new_reserve, quote.protocol_fee));
+    assert_backed(env);
+}
+
+#[test]
+fn transfer_fee_mints_credit_what_the_vault_receives() {
+    let mint_fee = MintKind::TransferFee {
+        bps: 100,
+        maximum_fee: u64::MAX,
+    };
+    let mut env = created(mint_fee, MintKind::Classic, CurveParams::ConstantProduct);
+    let user = env.user;
+
+    let ix = env.add_liquidity_ix(10_000_000, 10_000_000, 0, 0, 0);
+    env.send(&[ix], &[user]).unwrap();
+    assert_backed(&env);
+    // The 1% withheld on the way in never counts as reserves
+    assert_eq!(env.pool().reserve_a, 9_900_000);
+
+    for a_to_b in [true, false] {
+        let ix = env.swap_ix(a_to_b, 100_000, 1);
+        env.send(&[ix], &[user]).unwrap();
+        assert_backed(&env);
+    }
+
+    let lp = env.balance(&env.user_accounts.lp);
+    env.send(&[env.remove_liquidity_ix(lp)], &[user]).unwrap();
+    assert_backed(&env);
+}
+
+#[test]
+fn clmm_position_lifecycle_moves_tokens() {
+    let mut env = Env::new();
+    let (admin, user) = (env.admin, env.user);
+    let keys = env.clmm_keys;
+    let (token_a, token_b) = (env.user_accounts.token_a, env.user_accounts.token_b);
+    let price = sqrt_price_at_tick(0).unwrap();
+
+    env.send(&[create_clmm_pool(&admin, &keys, POOL_FEE_TIER, 10, price)], &[admin])
+        .unwrap();
+    let span = 10 * TICK_ARRAY_SIZE as i32;
+    for start in [-span, 0] {
+        env.send(&[init_tick_array(&user, &keys.pool, start)], &[user])
+            .unwrap();
+    }
+    let array: TickArrayState = {
+        let data = &env.svm.account(&keys.tick_array(0, 10)).unwrap().data;
+        bytemuck::pod_read_unaligned(&data[8..])
+    };
+    assert_eq!((array.pool, array.start_tick_index), (keys.pool, 0));
+
+    let (tick_lower, tick_upper) = (-600, 590);
+    let position = keys.position(&user, tick_lower, tick_upper, 10);
+    env.send(&[open_position(&user, &keys.pool, tick_lower, tick_upper)], &[user])
+        .unwrap();
+
+    // In range, so it takes both tokens, rounded up
+    let liquidity = 1_000_000_000;
+    let (owed_a, owed_b) = position_amounts(
+        price,
+        sqrt_price_at_tick(tick_lower).unwrap(),
+        sqrt_price_at_tick(tick_upper).unwrap(),
+        liquidity,
+        true,
+    )
+    .unwrap();
+    let ix = increase_liquidity(&user, &keys, &position, &token_a, &token_b, liquidity, u64::MAX, u64::MAX);
+    env.send(&[ix], &[user]).unwrap();
+    assert_eq!(env.balance(&keys.vault_a), owed_a);
+    assert_eq!(env.balance(&keys.vault_b), owed_b);
+    assert_eq!(env.clmm_pool().liquidity, liquidity);
+
+    // Swaps down through [upper, lower] and back up
+    let down = [position.tick_array_upper, position.tick_array_lower];
+    for (a_to_b, arrays) in [(true, &down[..]), (false, &down[..1])] {
+        let (input, output) = env.user_pair(a_to_b);
+        let (paid_before, got_before) = (env.balance(&input), env.balance(&output));
+        let ix = clmm_swap(&user, &keys, &input, &output, arrays, 100_000, 1, 0);
+        env.send(&[ix], &[user]).unwrap();
+        assert_eq!(paid_before - env.balance(&input), 100_000);
+        assert!(env.balance(&output) > got_before);
+        assert_clmm_backed(&env);
+    }
+    let pool = env.clmm_pool();
+    assert!(pool.fee_growth_global_a_x64 > 0 && pool.fee_growth_global_b_x64 > 0);
+
+    // Withdraw everything; the position is then owed its tokens plus fees
+    let ix = decrease_liquidity(&user, &keys.pool, &position, liquidity, 0, 0);
+    env.send(&[ix], &[user]).unwrap();
+    let owed = env.position(&position.position);
+    assert_eq!(owed.liquidity, 0);
+
+    let (a_before, b_before) = (env.balance(&token_a), env.balance(&token_b));
+    let ix = collect(&user, &keys, &position, &token_a, &token_b, u64::MAX, u64::MAX);
+    env.send(&[ix], &[user]).unwrap();
+    assert_eq!(env.balance(&token_a) - a_before, owed.tokens_owed_a);
+    assert_eq!(env.balance(&token_b) - b_before, owed.tokens_owed_b);
+    assert_clmm_backed(&env);
+
+    // Leaving the protocol and fund fees, which their collectors take
+    let (treasury_a, treasury_b) = (
+        env.token_account(&keys.token_a_mint, &admin),
+        env.token_account(&keys.token_b_mint, &admin),
+    );
+    env.send(&[collect_clmm_fees(&admin, &keys, &treasury_a, &treasury_b)], &[admin])
+        .unwrap();
+    let (fund_a, fund_b) = (
+        env.token_account(&keys.token_a_mint, &admin),
+        env.token_account(&keys.token_b_mint, &admin),
+    );
+    env.send(&[collect_clmm_fund_fees(&admin, &keys, &fund_a, &fund_b)], &[admin])
+        .unwrap();
+
+    let pool = env.clmm_pool();
+    assert_eq!((pool.protocol_fees_a, pool.fund_fees_a), (0, 0));
+    assert!(env.balance(&treasury_a) > 0 && env.balance(&fund_b) > 0);
+    // Only the rounding in the pool's favour is left behind
+    assert!(env.balance(&keys.vault_a) <= 4 && env.balance(&keys.vault_b) <= 4);
+}
+
+/// A concentrated liquidity pool's vaults cover its protocol and fund
+/// fees; positions' shares are not tracked on the pool.
+fn assert_clmm_backed(env: &Env) {
+    let pool = env.clmm_pool();
+    assert!(env.balance(&pool.vault_a) >= pool.protocol_fees_a + pool.fund_fees_a);
+    assert!(env.balance(&pool.vault_b) >= pool.protocol_fees_b + pool.fund_fees_b);
+}
+
+#[test]
+fn runtime_rejects_an_unfunded_transfer() {
+    // A swap the user cannot pay for fails in the token program, and the
+    // whole transaction rolls back
+    let mut env = classic(CurveParams::ConstantProduct);
+    let user = env.user;
+    deposit(&mut env, 10_000_000, 10_000_000);
+    let reserves = |env: &Env| (env.pool().reserve_a, env.pool().reserve_b);
+    let before = (reserves(&env), user_balances(&env));
+
+    let ix = env.swap_ix(true, USER_BALANCE + 1, 0);
+    assert!(env.send(&[ix], &[user]).is_err());
+    assert_eq!((reserves(&env), user_balances(&env)), before);
+    assert_backed(&env);
+}
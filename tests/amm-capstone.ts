@@ -1,13 +1,21 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import {
+  Keypair,
   PublicKey,
   SystemProgram,
   SYSVAR_RENT_PUBKEY,
+  Transaction,
+  sendAndConfirmTransaction,
 } from "@solana/web3.js";
 
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializeNonTransferableMintInstruction,
   createMint,
   mintTo,
   getOrCreateAssociatedTokenAccount,
@@ -29,13 +37,35 @@ describe("amm-capstone", () => {
   const VAULT_A_SEED = Buffer.from("vault_a");
   const VAULT_B_SEED = Buffer.from("vault_b");
   const TREASURY_SEED = Buffer.from("treasury");
+  const AMM_CONFIG_SEED = Buffer.from("amm_config");
   const POOL_SEED = Buffer.from("pool");
   const VAULT_AUTH_SEED = Buffer.from("vault_authority");
+  const LP_MINT_SEED = Buffer.from("lp_mint");
+  const LOCKED_LP_SEED = Buffer.from("locked_lp");
+  const LOCKED_LP_AUTH_SEED = Buffer.from("locked_lp_authority");
+  const OBSERVATION_SEED = Buffer.from("observation");
+
+  // Tier index 2 is 30 bps
+  const FEE_TIER_INDEX = 2;
+  const FEE_BPS = 30;
+
+  // `CurveType::ConstantProduct`, the curve every pool here uses
+  const CONSTANT_PRODUCT = 0;
+
+  const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+    "BPFLoaderUpgradeab1e11111111111111111111111"
+  );
+
+  const sortMints = (x: PublicKey, y: PublicKey): [PublicKey, PublicKey] =>
+    Buffer.compare(x.toBuffer(), y.toBuffer()) < 0 ? [x, y] : [y, x];
 
   // Accounts
   let treasuryPda: PublicKey;
+  let ammConfigPda: PublicKey;
+  let programDataPda: PublicKey;
   let poolPda: PublicKey;
   let vaultAuthorityPda: PublicKey;
+  let observationPda: PublicKey;
 
   let tokenAMint: PublicKey;
   let tokenBMint: PublicKey;
@@ -44,6 +74,8 @@ describe("amm-capstone", () => {
   let userTokenA: PublicKey;
   let userTokenB: PublicKey;
   let userLp: PublicKey;
+  let lockedLp: PublicKey;
+  let lockedLpAuthority: PublicKey;
 
   // These are now PDAs, not Keypairs
   let vaultA: PublicKey;
@@ -60,13 +92,20 @@ describe("amm-capstone", () => {
       program.programId
     );
 
-    [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
-      [VAULT_AUTH_SEED],
+    [ammConfigPda] = PublicKey.findProgramAddressSync(
+      [AMM_CONFIG_SEED],
       program.programId
     );
 
+    // Holds the upgrade authority, the only signer `initTreasury` and
+    // `initConfig` accept
+    [programDataPda] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_ID
+    );
+
     // ---------- Token Mints ----------
-    tokenAMint = await createMint(
+    const mintX = await createMint(
       provider.connection,
       payer,
       wallet.publicKey,
@@ -74,7 +113,7 @@ describe("amm-capstone", () => {
       6
     );
 
-    tokenBMint = await createMint(
+    const mintY = await createMint(
       provider.connection,
       payer,
       wallet.publicKey,
@@ -82,18 +121,49 @@ describe("amm-capstone", () => {
       6
     );
 
-    // Derive Pool PDA
+    // Pools take their mints in canonical order
+    [tokenAMint, tokenBMint] = sortMints(mintX, mintY);
+
+    // Derive Pool PDA: one per config, pair, fee rate and curve
     [poolPda] = PublicKey.findProgramAddressSync(
-      [POOL_SEED, tokenAMint.toBuffer(), tokenBMint.toBuffer()],
+      [
+        POOL_SEED,
+        ammConfigPda.toBuffer(),
+        tokenAMint.toBuffer(),
+        tokenBMint.toBuffer(),
+        new anchor.BN(FEE_BPS).toArrayLike(Buffer, "le", 2),
+        Buffer.from([CONSTANT_PRODUCT]),
+      ],
       program.programId
     );
 
-    lpMint = await createMint(
-      provider.connection,
-      payer,
-      vaultAuthorityPda,
-      null,
-      6
+    // Vault authority is unique per pool
+    [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
+      [VAULT_AUTH_SEED, poolPda.toBuffer()],
+      program.programId
+    );
+
+    // LP mint is created by the program inside createPool
+    [lpMint] = PublicKey.findProgramAddressSync(
+      [LP_MINT_SEED, poolPda.toBuffer()],
+      program.programId
+    );
+
+    // Price history ring, created inside createPool
+    [observationPda] = PublicKey.findProgramAddressSync(
+      [OBSERVATION_SEED, poolPda.toBuffer()],
+      program.programId
+    );
+
+    // ---------- LOCKED LP PDAs ----------
+    [lockedLpAuthority] = PublicKey.findProgramAddressSync(
+      [LOCKED_LP_AUTH_SEED, poolPda.toBuffer()],
+      program.programId
+    );
+
+    [lockedLp] = PublicKey.findProgramAddressSync(
+      [LOCKED_LP_SEED, poolPda.toBuffer()],
+      program.programId
     );
 
     // ---------- DERIVE VAULT PDAs (FIXED) ----------
@@ -101,22 +171,12 @@ describe("amm-capstone", () => {
     // that the program expects to initialize.
     
     [vaultA] = PublicKey.findProgramAddressSync(
-      [
-        POOL_SEED, 
-        tokenAMint.toBuffer(), 
-        tokenBMint.toBuffer(), 
-        VAULT_A_SEED
-      ],
+      [VAULT_A_SEED, poolPda.toBuffer()],
       program.programId
     );
 
     [vaultB] = PublicKey.findProgramAddressSync(
-      [
-        POOL_SEED, 
-        tokenAMint.toBuffer(), 
-        tokenBMint.toBuffer(), 
-        VAULT_B_SEED
-      ],
+      [VAULT_B_SEED, poolPda.toBuffer()],
       program.programId
     );
 
@@ -139,15 +199,6 @@ describe("amm-capstone", () => {
       )
     ).address;
 
-    userLp = (
-      await getOrCreateAssociatedTokenAccount(
-        provider.connection,
-        payer,
-        lpMint,
-        wallet.publicKey
-      )
-    ).address;
-
     // ---------- FUND USER ----------
     await mintTo(
       provider.connection,
@@ -179,6 +230,8 @@ describe("amm-capstone", () => {
       .initTreasury()
       .accountsStrict({
         payer: wallet.publicKey,
+        program: program.programId,
+        programData: programDataPda,
         treasury: treasuryPda,
         systemProgram: SystemProgram.programId,
       })
@@ -189,32 +242,164 @@ describe("amm-capstone", () => {
   });
 
   /* -------------------------------------------------- */
-  /* TEST 2: CREATE POOL                                */
+  /* TEST 2: INIT CONFIG                                */
+  /* -------------------------------------------------- */
+
+  it("Initializes config", async () => {
+
+    try {
+    // Fee tiers: 1, 5, 30 and 100 bps; 20% of each fee goes to protocol
+    await program.methods
+      .initConfig([1, 5, 30, 100], 2_000, 0, new anchor.BN(0))
+      .accountsStrict({
+        payer: wallet.publicKey,
+        program: program.programId,
+        programData: programDataPda,
+        ammConfig: ammConfigPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+      console.log("Config initialized"); }
+      catch (err) { console.log("Config already initialized, skipping..."); }
+
+    const config = await program.account.ammConfig.fetch(ammConfigPda);
+    expect(config.feeTiers).to.include(30);
+  });
+
+  /* -------------------------------------------------- */
+  /* TEST 3: CREATE POOL                                */
   /* -------------------------------------------------- */
 
   it("Creates liquidity pool", async () => {
     // We pass vaultA/vaultB here so the program can initialize them via CPI/Context
     await program.methods
-      .createPool()
+      .createPool(FEE_TIER_INDEX, { constantProduct: {} })
       .accountsStrict({
         payer: wallet.publicKey,
+        ammConfig: ammConfigPda,
+        treasury: treasuryPda,
         tokenAMint,
         tokenBMint,
         pool: poolPda,
         vaultA: vaultA, // Derived PDA
         vaultB: vaultB, // Derived PDA
         lpMint,
+        lockedLpAuthority,
+        lockedLp,
+        observationState: observationPda,
         vaultAuthority: vaultAuthorityPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: SYSVAR_RENT_PUBKEY,
       })
       .signers([payer])
       .rpc();
+
+    const poolAccount = await program.account.pool.fetch(poolPda);
+    expect(poolAccount.feeBps).to.equal(FEE_BPS);
+
+    // LP mint only exists once the pool is created
+    userLp = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        lpMint,
+        wallet.publicKey
+      )
+    ).address;
+  });
+
+  /* -------------------------------------------------- */
+  /* TEST 4: UNSUPPORTED TOKEN-2022 EXTENSION           */
+  /* -------------------------------------------------- */
+
+  it("Rejects Token-2022 mints with unsupported extensions", async () => {
+    // Non-transferable mint: vault balances could never leave the pool
+    const mintKeypair = Keypair.generate();
+    const mintLen = getMintLen([ExtensionType.NonTransferable]);
+    const lamports =
+      await provider.connection.getMinimumBalanceForRentExemption(mintLen);
+
+    await sendAndConfirmTransaction(
+      provider.connection,
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: wallet.publicKey,
+          newAccountPubkey: mintKeypair.publicKey,
+          space: mintLen,
+          lamports,
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeNonTransferableMintInstruction(
+          mintKeypair.publicKey,
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(
+          mintKeypair.publicKey,
+          6,
+          wallet.publicKey,
+          null,
+          TOKEN_2022_PROGRAM_ID
+        )
+      ),
+      [payer, mintKeypair]
+    );
+
+    const badMint = mintKeypair.publicKey;
+    const pda = (seeds: Buffer[]) =>
+      PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+    const [badA, badB] = sortMints(badMint, tokenBMint);
+    const programOf = (mint: PublicKey) =>
+      mint.equals(badMint) ? TOKEN_2022_PROGRAM_ID : TOKEN_PROGRAM_ID;
+    const badPool = pda([
+      POOL_SEED,
+      ammConfigPda.toBuffer(),
+      badA.toBuffer(),
+      badB.toBuffer(),
+      new anchor.BN(FEE_BPS).toArrayLike(Buffer, "le", 2),
+      Buffer.from([CONSTANT_PRODUCT]),
+    ]);
+
+    let failed = false;
+
+    try {
+      await program.methods
+        .createPool(FEE_TIER_INDEX, { constantProduct: {} })
+        .accountsStrict({
+          payer: wallet.publicKey,
+          ammConfig: ammConfigPda,
+          treasury: treasuryPda,
+          tokenAMint: badA,
+          tokenBMint: badB,
+          pool: badPool,
+          vaultA: pda([VAULT_A_SEED, badPool.toBuffer()]),
+          vaultB: pda([VAULT_B_SEED, badPool.toBuffer()]),
+          lpMint: pda([LP_MINT_SEED, badPool.toBuffer()]),
+          lockedLpAuthority: pda([LOCKED_LP_AUTH_SEED, badPool.toBuffer()]),
+          lockedLp: pda([LOCKED_LP_SEED, badPool.toBuffer()]),
+          observationState: pda([OBSERVATION_SEED, badPool.toBuffer()]),
+          vaultAuthority: pda([VAULT_AUTH_SEED, badPool.toBuffer()]),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenAProgram: programOf(badA),
+          tokenBProgram: programOf(badB),
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+    } catch (err) {
+      expect(err.toString()).to.include("UnsupportedMint");
+      failed = true;
+    }
+
+    expect(failed).to.equal(true);
   });
 
   /* -------------------------------------------------- */
-  /* TEST 3: ADD LIQUIDITY                              */
+  /* TEST 5: ADD LIQUIDITY                              */
   /* -------------------------------------------------- */
 
   it("Adds liquidity", async () => {
@@ -222,7 +407,7 @@ describe("amm-capstone", () => {
     const amountB = new anchor.BN(500_000_000);
 
     await program.methods
-      .addLiquidity(amountA, amountB)
+      .addLiquidity(amountA, amountB, amountA, amountB, new anchor.BN(0))
       .accountsStrict({
         user: wallet.publicKey,
         pool: poolPda,
@@ -232,8 +417,13 @@ describe("amm-capstone", () => {
         vaultB,
         lpMint,
         userLp,
+        lockedLp,
         vaultAuthority: vaultAuthorityPda,
+        tokenAMint,
+        tokenBMint,
         tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
       })
       .rpc();
 
@@ -241,10 +431,69 @@ describe("amm-capstone", () => {
     const poolAccount = await program.account.pool.fetch(poolPda);
     console.log("Reserve A:", poolAccount.reserveA.toString());
     console.log("Reserve B:", poolAccount.reserveB.toString());
+
+    // First deposit mints sqrt(a * b), minus the locked minimum
+    const userLpBalance =
+      (await provider.connection.getTokenAccountBalance(userLp)).value.amount;
+    const lockedLpBalance =
+      (await provider.connection.getTokenAccountBalance(lockedLp)).value.amount;
+
+    expect(userLpBalance).to.equal("499999000");
+    expect(lockedLpBalance).to.equal("1000");
+  });
+
+  /* -------------------------------------------------- */
+  /* TEST 6: ADD LIQUIDITY AT POOL RATIO                */
+  /* -------------------------------------------------- */
+
+  it("Only takes the ratio-matching amount on later deposits", async () => {
+    const userBBefore = Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB)).value
+        .amount
+    );
+
+    // Pool is 1:1, so only 100_000_000 of the desired B is needed
+    await program.methods
+      .addLiquidity(
+        new anchor.BN(100_000_000),
+        new anchor.BN(300_000_000),
+        new anchor.BN(100_000_000),
+        new anchor.BN(100_000_000),
+        new anchor.BN(1)
+      )
+      .accountsStrict({
+        user: wallet.publicKey,
+        pool: poolPda,
+        userTokenA,
+        userTokenB,
+        vaultA,
+        vaultB,
+        lpMint,
+        userLp,
+        lockedLp,
+        vaultAuthority: vaultAuthorityPda,
+        tokenAMint,
+        tokenBMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const userBAfter = Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB)).value
+        .amount
+    );
+
+    expect(userBBefore - userBAfter).to.equal(100_000_000);
+
+    const poolAccount = await program.account.pool.fetch(poolPda);
+    expect(poolAccount.reserveA.toNumber()).to.equal(600_000_000);
+    expect(poolAccount.reserveB.toNumber()).to.equal(600_000_000);
   });
 
   /* -------------------------------------------------- */
-/* TEST 4: SWAP TOKEN A -> TOKEN B                    */
+/* TEST 7: SWAP TOKEN A -> TOKEN B                    */
 /* -------------------------------------------------- */
 
 it("Swaps token A → token B", async () => {
@@ -287,13 +536,17 @@ it("Swaps token A → token B", async () => {
     .accountsStrict({
       user: wallet.publicKey,
       pool: poolPda,
+      ammConfig: ammConfigPda,
+      observationState: observationPda,
       userInput: userTokenA,
       userOutput: userTokenB,
       vaultA,
       vaultB,
       vaultAuthority: vaultAuthorityPda,
-      treasury: treasuryPda,
-      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenAMint,
+      tokenBMint,
+      tokenAProgram: TOKEN_PROGRAM_ID,
+      tokenBProgram: TOKEN_PROGRAM_ID,
     })
     .rpc();
 
@@ -340,6 +593,12 @@ it("Swaps token A → token B", async () => {
   // Pool reserve B decreased
   expect(reserveBAfter).to.be.lessThan(reserveBBefore);
 
+  // First swap opens the price history
+  const observations =
+    await program.account.observationState.fetch(observationPda);
+  expect(observations.observationCount).to.equal(1);
+  expect(observations.observations[0].slot.toNumber()).to.be.greaterThan(0);
+
   // Constant product invariant check
   const kBefore = reserveABefore * reserveBBefore;
   const kAfter = reserveAAfter * reserveBAfter;
@@ -348,7 +607,325 @@ it("Swaps token A → token B", async () => {
   });
 
   /* -------------------------------------------------- */
-/* TEST 5: SLIPPAGE PROTECTION                        */
+/* TEST 8: SWAP TOKEN B -> TOKEN A                    */
+/* -------------------------------------------------- */
+
+it("Swaps token B → token A", async () => {
+
+  const poolBefore = await program.account.pool.fetch(poolPda);
+
+  const reserveABefore = poolBefore.reserveA.toNumber();
+  const reserveBBefore = poolBefore.reserveB.toNumber();
+
+  const userABefore =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenA))
+        .value.amount
+    );
+
+  const userBBefore =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB))
+        .value.amount
+    );
+
+  await program.methods
+    .swap(new anchor.BN(50_000_000), new anchor.BN(1))
+    .accountsStrict({
+      user: wallet.publicKey,
+      pool: poolPda,
+      ammConfig: ammConfigPda,
+      observationState: observationPda,
+      userInput: userTokenB,
+      userOutput: userTokenA,
+      vaultA,
+      vaultB,
+      vaultAuthority: vaultAuthorityPda,
+      tokenAMint,
+      tokenBMint,
+      tokenAProgram: TOKEN_PROGRAM_ID,
+      tokenBProgram: TOKEN_PROGRAM_ID,
+    })
+    .rpc();
+
+  const poolAfter = await program.account.pool.fetch(poolPda);
+
+  const reserveAAfter = poolAfter.reserveA.toNumber();
+  const reserveBAfter = poolAfter.reserveB.toNumber();
+
+  const userAAfter =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenA))
+        .value.amount
+    );
+
+  const userBAfter =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB))
+        .value.amount
+    );
+
+  // User spent token B and received token A
+  expect(userBAfter).to.be.lessThan(userBBefore);
+  expect(userAAfter).to.be.greaterThan(userABefore);
+
+  // Reserves moved in the opposite direction to the A → B swap
+  expect(reserveBAfter).to.be.greaterThan(reserveBBefore);
+  expect(reserveAAfter).to.be.lessThan(reserveABefore);
+
+  const kBefore = reserveABefore * reserveBBefore;
+  const kAfter = reserveAAfter * reserveBAfter;
+
+  expect(kAfter).to.be.greaterThanOrEqual(kBefore);
+  });
+
+  /* -------------------------------------------------- */
+/* TEST 9: EXACT-OUTPUT SWAP                          */
+/* -------------------------------------------------- */
+
+it("Buys an exact amount of token B with swapBaseOut", async () => {
+
+  const amountOut = 1_000_000;
+  const maxAmountIn = 2_000_000;
+
+  const userABefore =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenA))
+        .value.amount
+    );
+
+  const userBBefore =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB))
+        .value.amount
+    );
+
+  await program.methods
+    .swapBaseOut(new anchor.BN(maxAmountIn), new anchor.BN(amountOut))
+    .accountsStrict({
+      user: wallet.publicKey,
+      pool: poolPda,
+      ammConfig: ammConfigPda,
+      observationState: observationPda,
+      userInput: userTokenA,
+      userOutput: userTokenB,
+      vaultA,
+      vaultB,
+      vaultAuthority: vaultAuthorityPda,
+      tokenAMint,
+      tokenBMint,
+      tokenAProgram: TOKEN_PROGRAM_ID,
+      tokenBProgram: TOKEN_PROGRAM_ID,
+    })
+    .rpc();
+
+  const userAAfter =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenA))
+        .value.amount
+    );
+
+  const userBAfter =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB))
+        .value.amount
+    );
+
+  // Exactly amountOut received, no more than maxAmountIn spent
+  expect(userBAfter - userBBefore).to.equal(amountOut);
+  expect(userABefore - userAAfter).to.be.at.most(maxAmountIn);
+  expect(userABefore - userAAfter).to.be.greaterThan(0);
+  });
+
+  /* -------------------------------------------------- */
+/* TEST 10: TWAP                                      */
+/* -------------------------------------------------- */
+
+it("Reports the spot price as TWAP when reserves are unchanged", async () => {
+
+  const pool = await program.account.pool.fetch(poolPda);
+
+  await new Promise((resolve) => setTimeout(resolve, 2_000));
+
+  // The window reaches back to the last swap's entry in the ring
+  const twap = await program.methods
+    .observe(1)
+    .accountsStrict({ pool: poolPda, observationState: observationPda })
+    .view();
+
+  // Q64.64 spot prices at the reserves left by the last swap
+  const spotA = pool.reserveB.shln(64).div(pool.reserveA);
+  const spotB = pool.reserveA.shln(64).div(pool.reserveB);
+
+  expect(twap.priceA.toString()).to.equal(spotA.toString());
+  expect(twap.priceB.toString()).to.equal(spotB.toString());
+  expect(twap.start.timestamp.toString()).to.equal(pool.lastUpdateTs.toString());
+  expect(twap.current.timestamp.gt(pool.lastUpdateTs)).to.equal(true);
+  });
+
+  /* -------------------------------------------------- */
+/* TEST 11: COLLECT PROTOCOL FEES                     */
+/* -------------------------------------------------- */
+
+it("Collects accrued protocol fees to the treasury authority", async () => {
+
+  const poolBefore = await program.account.pool.fetch(poolPda);
+
+  const feesA = poolBefore.protocolFeesA.toNumber();
+  const feesB = poolBefore.protocolFeesB.toNumber();
+
+  // Both swap directions ran above, so both sides accrued fees
+  expect(feesA).to.be.greaterThan(0);
+  expect(feesB).to.be.greaterThan(0);
+
+  const userABefore =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenA))
+        .value.amount
+    );
+
+  const userBBefore =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB))
+        .value.amount
+    );
+
+  await program.methods
+    .collectFees()
+    .accountsStrict({
+      authority: wallet.publicKey,
+      treasury: treasuryPda,
+      pool: poolPda,
+      vaultA,
+      vaultB,
+      treasuryTokenA: userTokenA,
+      treasuryTokenB: userTokenB,
+      vaultAuthority: vaultAuthorityPda,
+      tokenAMint,
+      tokenBMint,
+      tokenAProgram: TOKEN_PROGRAM_ID,
+      tokenBProgram: TOKEN_PROGRAM_ID,
+    })
+    .rpc();
+
+  const poolAfter = await program.account.pool.fetch(poolPda);
+
+  const userAAfter =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenA))
+        .value.amount
+    );
+
+  const userBAfter =
+    Number(
+      (await provider.connection.getTokenAccountBalance(userTokenB))
+        .value.amount
+    );
+
+  expect(userAAfter - userABefore).to.equal(feesA);
+  expect(userBAfter - userBBefore).to.equal(feesB);
+
+  expect(poolAfter.protocolFeesA.toNumber()).to.equal(0);
+  expect(poolAfter.protocolFeesB.toNumber()).to.equal(0);
+
+  // Reserves are untouched by fee collection
+  expect(poolAfter.reserveA.toNumber()).to.equal(poolBefore.reserveA.toNumber());
+  expect(poolAfter.reserveB.toNumber()).to.equal(poolBefore.reserveB.toNumber());
+  });
+
+  /* -------------------------------------------------- */
+/* TEST 12: MINT MISMATCH                             */
+/* -------------------------------------------------- */
+
+it("Fails swap when input and output share a mint", async () => {
+
+  let failed = false;
+
+  try {
+    await program.methods
+      .swap(new anchor.BN(10_000_000), new anchor.BN(1))
+      .accountsStrict({
+        user: wallet.publicKey,
+        pool: poolPda,
+        ammConfig: ammConfigPda,
+        observationState: observationPda,
+        userInput: userTokenA,
+        userOutput: userTokenA,
+        vaultA,
+        vaultB,
+        vaultAuthority: vaultAuthorityPda,
+        tokenAMint,
+        tokenBMint,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+  } catch (err) {
+    console.log("Expected mint mismatch failure:", err.toString());
+    failed = true;
+  }
+
+  expect(failed).to.equal(true);
+  });
+
+  /* -------------------------------------------------- */
+/* TEST 13: POOL PAUSE                                */
+/* -------------------------------------------------- */
+
+it("Rejects swaps while the swap status bit is set", async () => {
+
+  const SWAP_DISABLED = 1 << 2;
+
+  await program.methods
+    .setPoolStatus(SWAP_DISABLED)
+    .accountsStrict({
+      admin: wallet.publicKey,
+      ammConfig: ammConfigPda,
+      pool: poolPda,
+    })
+    .rpc();
+
+  let failed = false;
+
+  try {
+    await program.methods
+      .swap(new anchor.BN(10_000_000), new anchor.BN(1))
+      .accountsStrict({
+        user: wallet.publicKey,
+        pool: poolPda,
+        ammConfig: ammConfigPda,
+        observationState: observationPda,
+        userInput: userTokenA,
+        userOutput: userTokenB,
+        vaultA,
+        vaultB,
+        vaultAuthority: vaultAuthorityPda,
+        tokenAMint,
+        tokenBMint,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+  } catch (err) {
+    expect(err.toString()).to.include("OperationDisabled");
+    failed = true;
+  }
+
+  expect(failed).to.equal(true);
+
+  // Re-enable everything for the remaining tests
+  await program.methods
+    .setPoolStatus(0)
+    .accountsStrict({
+      admin: wallet.publicKey,
+      ammConfig: ammConfigPda,
+      pool: poolPda,
+    })
+    .rpc();
+  });
+
+  /* -------------------------------------------------- */
+/* TEST 14: SLIPPAGE PROTECTION                       */
 /* -------------------------------------------------- */
 
 it("Fails swap when slippage exceeds limit", async () => {
@@ -366,13 +943,17 @@ it("Fails swap when slippage exceeds limit", async () => {
       .accountsStrict({
         user: wallet.publicKey,
         pool: poolPda,
+        ammConfig: ammConfigPda,
+        observationState: observationPda,
         userInput: userTokenA,
         userOutput: userTokenB,
         vaultA,
         vaultB,
         vaultAuthority: vaultAuthorityPda,
-        treasury: treasuryPda,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenAMint,
+        tokenBMint,
+        tokenAProgram: TOKEN_PROGRAM_ID,
+        tokenBProgram: TOKEN_PROGRAM_ID,
       })
       .rpc();
   } catch (err) {